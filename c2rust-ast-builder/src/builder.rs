@@ -371,6 +371,18 @@ impl Builder {
         }
     }
 
+    /// Add a doc comment attribute. The pretty-printer prints doc comments as is, so `line` has to
+    /// start with `///`.
+    pub fn doc_comment(self, line: &str) -> Self {
+        let attr = self.clone().doc_attr(line);
+        let mut attrs = self.attrs;
+        attrs.push(attr);
+        Builder {
+            attrs: attrs,
+            ..self
+        }
+    }
+
     pub fn single_attr<K>(self, key: K) -> Self
     where
         K: Make<PathSegment>,
@@ -773,6 +785,22 @@ impl Builder {
         })
     }
 
+    /// Half-open range `lo..hi`
+    pub fn range_expr<E1, E2>(self, lo: E1, hi: E2) -> P<Expr>
+    where
+        E1: Make<P<Expr>>,
+        E2: Make<P<Expr>>,
+    {
+        let lo = lo.make(&self);
+        let hi = hi.make(&self);
+        P(Expr {
+            id: self.id,
+            node: ExprKind::Range(Some(lo), Some(hi), RangeLimits::HalfOpen),
+            span: self.span,
+            attrs: self.attrs.into(),
+        })
+    }
+
     pub fn path_expr<Pa>(self, path: Pa) -> P<Expr>
     where
         Pa: Make<Path>,
@@ -1136,6 +1164,20 @@ impl Builder {
         })
     }
 
+    pub fn tuple_struct_pat<Pa, Pt>(self, path: Pa, pats: Vec<Pt>) -> P<Pat>
+    where
+        Pa: Make<Path>,
+        Pt: Make<P<Pat>>,
+    {
+        let path = path.make(&self);
+        let pats: Vec<P<Pat>> = pats.into_iter().map(|x| x.make(&self)).collect();
+        P(Pat {
+            id: self.id,
+            node: PatKind::TupleStruct(path, pats, None),
+            span: self.span,
+        })
+    }
+
     pub fn qpath_pat<Pa>(self, qself: Option<QSelf>, path: Pa) -> P<Pat>
     where
         Pa: Make<Path>,
@@ -1536,6 +1578,21 @@ impl Builder {
         )
     }
 
+    pub fn tuple_struct_item<I>(self, name: I, fields: Vec<StructField>) -> P<Item>
+    where
+        I: Make<Ident>,
+    {
+        let name = name.make(&self);
+        Self::item(
+            name,
+            self.attrs,
+            self.vis,
+            self.span,
+            self.id,
+            ItemKind::Struct(VariantData::Tuple(fields, DUMMY_NODE_ID), self.generics),
+        )
+    }
+
     pub fn union_item<I>(self, name: I, fields: Vec<StructField>) -> P<Item>
     where
         I: Make<Ident>,
@@ -1598,6 +1655,21 @@ impl Builder {
         }
     }
 
+    /// Build a `macro_rules!` definition. `tts` are the rules of the macro.
+    pub fn macro_rules_item<I, Ts>(self, name: I, tts: Ts) -> P<Item>
+    where
+        I: Make<Ident>,
+        Ts: Make<TokenStream>,
+    {
+        let name = name.make(&self);
+        let tokens = tts.make(&self);
+        let kind = ItemKind::MacroDef(MacroDef {
+            tokens,
+            legacy: true,
+        });
+        Self::item(name, self.attrs, self.vis, self.span, self.id, kind)
+    }
+
     pub fn mac_item<M>(self, mac: M) -> P<Item>
     where
         M: Make<Mac>,
@@ -1676,6 +1748,35 @@ impl Builder {
         )
     }
 
+    /// Build an `impl trait_ for ty` item
+    pub fn trait_impl_item<Pa, T>(self, trait_: Pa, ty: T, items: Vec<ImplItem>) -> P<Item>
+    where
+        Pa: Make<Path>,
+        T: Make<P<Ty>>,
+    {
+        let trait_ref = TraitRef {
+            path: trait_.make(&self),
+            ref_id: DUMMY_NODE_ID,
+        };
+        let ty = ty.make(&self);
+        Self::item(
+            keywords::Invalid.ident(),
+            self.attrs,
+            self.vis,
+            self.span,
+            self.id,
+            ItemKind::Impl(
+                self.unsafety,
+                ImplPolarity::Positive,
+                Defaultness::Final,
+                self.generics,
+                Some(trait_ref),
+                ty,
+                items,
+            ),
+        )
+    }
+
     pub fn extern_crate_item<I>(self, name: I, rename: Option<I>) -> P<Item>
     where
         I: Make<Ident>,
@@ -1811,6 +1912,53 @@ impl Builder {
         )
     }
 
+    pub fn fn_impl_item<I, D, B>(self, name: I, decl: D, block: B) -> ImplItem
+    where
+        I: Make<Ident>,
+        D: Make<P<FnDecl>>,
+        B: Make<P<Block>>,
+    {
+        let name = name.make(&self);
+        let decl = decl.make(&self);
+        let block = block.make(&self);
+        let header = FnHeader {
+            unsafety: self.unsafety,
+            asyncness: dummy_spanned(IsAsync::NotAsync),
+            constness: dummy_spanned(self.constness),
+            abi: self.abi,
+        };
+        let sig = MethodSig { header, decl };
+        Self::impl_item_(
+            name,
+            self.attrs,
+            self.vis,
+            Defaultness::Final,
+            self.generics,
+            self.span,
+            self.id,
+            ImplItemKind::Method(sig, block),
+        )
+    }
+
+    pub fn ty_impl_item<I, T>(self, name: I, ty: T) -> ImplItem
+    where
+        I: Make<Ident>,
+        T: Make<P<Ty>>,
+    {
+        let name = name.make(&self);
+        let ty = ty.make(&self);
+        Self::impl_item_(
+            name,
+            self.attrs,
+            self.vis,
+            Defaultness::Final,
+            self.generics,
+            self.span,
+            self.id,
+            ImplItemKind::Type(ty),
+        )
+    }
+
     // Trait Items
 
     /// Called `trait_item_` because `trait_item` is already used for "Item, of ItemKind::Trait".
@@ -2071,6 +2219,25 @@ impl Builder {
         }
     }
 
+    /// Build a doc comment attribute, see `doc_comment`
+    pub fn doc_attr(self, line: &str) -> Attribute {
+        let path: Path = vec!["doc"].make(&self);
+        let value = line.escape_default().to_string();
+        Attribute {
+            id: AttrId(0),
+            style: AttrStyle::Outer,
+            path,
+            tokens: vec![
+                Token::Eq,
+                Token::Literal(token::Lit::Str_(value.into_symbol()), None),
+            ]
+            .into_iter()
+            .collect(),
+            is_sugared_doc: true,
+            span: DUMMY_SP,
+        }
+    }
+
     pub fn meta_item_attr(mut self, style: AttrStyle, meta_item: MetaItem) -> Self {
         let mut attr = mk_attr_inner(DUMMY_SP, AttrId(0), meta_item);
         attr.style = style;
@@ -1,3 +1,4 @@
+#include <algorithm>
 #include <cstdlib>
 #include <fstream>
 #include <iostream>
@@ -284,6 +285,16 @@ class TypeEncoder final : public TypeVisitor<TypeEncoder> {
         VisitQualType(t);
     }
 
+    void VisitAtomicType(const AtomicType *T) {
+        auto t = T->getValueType();
+        auto qt = encodeQualType(t);
+
+        encodeType(T, TagAtomicType,
+                   [qt](CborEncoder *local) { cbor_encode_uint(local, qt); });
+
+        VisitQualType(t);
+    }
+
     void VisitBuiltinType(const BuiltinType *T) {
         TypeTag tag;
         auto kind = T->getKind();
@@ -431,6 +442,12 @@ class TranslateASTVisitor final
 
         /// Expressions that we have seen this macro expand to
         SmallPtrSet<Expr*, 10> Expressions;
+
+        /// For function-like macros, the expressions spelled as each argument
+        /// of an expansion, indexed by parameter. A parameter that occurs
+        /// several times in the replacement list has one expression per
+        /// occurrence.
+        std::unordered_map<Expr*, std::vector<std::vector<Expr*>>> Arguments;
     };
 
     ASTContext *Context;
@@ -631,6 +648,122 @@ class TranslateASTVisitor final
         return true;
     }
 
+    SourceLocation getImmediateExpansionBegin(SourceLocation loc) const {
+        auto &Mgr = Context->getSourceManager();
+#if CLANG_VERSION_MAJOR < 7
+        return Mgr.getImmediateExpansionRange(loc).first;
+#else // CLANG_VERSION_MAJOR >= 7
+        return Mgr.getImmediateExpansionRange(loc).getBegin();
+#endif
+    }
+
+    /// Tokens that were spelled as an argument of a function-like macro are
+    /// located at the macro call site. Look through them to the location of
+    /// the corresponding parameter in the macro replacement list.
+    SourceLocation skipMacroArgExpansions(SourceLocation loc) const {
+        auto &Mgr = Context->getSourceManager();
+        while (loc.isMacroID() && Mgr.isMacroArgExpansion(loc))
+            loc = getImmediateExpansionBegin(loc);
+        return loc;
+    }
+
+    /// Returns the parameter of `mac` that `loc` was spelled as an argument
+    /// for in the expansion of `mac` at `expansionLoc`, or -1 if `loc` did not
+    /// come from an argument of that expansion. If `paramLoc` is non-null, it
+    /// is set to the location of the parameter in the replacement list.
+    int getMacroArgIndex(SourceLocation loc, SourceLocation expansionLoc,
+                         const MacroInfo *mac, SourceLocation *paramLoc) const {
+        auto &Mgr = Context->getSourceManager();
+        if (!loc.isMacroID() || !Mgr.isMacroArgExpansion(loc))
+            return -1;
+        auto param = getImmediateExpansionBegin(loc);
+        if (!param.isMacroID() || getImmediateExpansionBegin(param) != expansionLoc)
+            return -1;
+
+        Token Result;
+        if (Lexer::getRawToken(Mgr.getSpellingLoc(param), Result, Mgr,
+                               Context->getLangOpts(), false) ||
+            !Result.is(tok::raw_identifier))
+            return -1;
+        auto *II = PP.LookUpIdentifierInfo(Result);
+        auto params = mac->params();
+        auto it = std::find(params.begin(), params.end(), II);
+        if (it == params.end())
+            return -1;
+        if (paramLoc)
+            *paramLoc = param;
+        return it - params.begin();
+    }
+
+    /// Find the outermost subexpressions of `S` that were spelled entirely
+    /// within one argument of the expansion of `mac` at `expansionLoc`.
+    void collectMacroArgs(Stmt *S, SourceLocation expansionLoc,
+                          const MacroInfo *mac,
+                          std::vector<std::vector<Expr *>> &args,
+                          std::vector<SourceLocation> &paramLocs) const {
+        if (!S)
+            return;
+        if (auto *E = dyn_cast<Expr>(S)) {
+            SourceLocation beginParam, endParam;
+            auto Range = E->getSourceRange();
+            auto index = getMacroArgIndex(Range.getBegin(), expansionLoc, mac, &beginParam);
+            if (index >= 0 &&
+                index == getMacroArgIndex(Range.getEnd(), expansionLoc, mac, &endParam) &&
+                beginParam == endParam) {
+                args[index].push_back(E);
+                paramLocs.push_back(beginParam);
+                return;
+            }
+        }
+        for (auto *child : S->children())
+            collectMacroArgs(child, expansionLoc, mac, args, paramLocs);
+    }
+
+    /// Record an expansion of a function-like macro, along with the
+    /// expressions spelled as its arguments. We only record expansions where
+    /// every occurrence of every parameter in the replacement list expanded to
+    /// exactly one complete expression, so that the translator can substitute
+    /// parameters for these expressions.
+    bool VisitFunctionMacro(StringRef name, SourceLocation loc, MacroInfo *mac,
+                            Expr *E) {
+        if (mac->isVariadic())
+            return false;
+
+        // Count the occurrences of each parameter in the replacement list.
+        // Stringizing and token pasting don't produce expressions we can
+        // substitute into.
+        auto params = mac->params();
+        std::vector<unsigned> occurrences(params.size());
+        for (auto &Tok : mac->tokens()) {
+            if (Tok.isOneOf(tok::hash, tok::hashhash, tok::hashat))
+                return false;
+            auto it = std::find(params.begin(), params.end(), Tok.getIdentifierInfo());
+            if (it != params.end())
+                occurrences[it - params.begin()]++;
+        }
+
+        std::vector<std::vector<Expr *>> args(params.size());
+        std::vector<SourceLocation> paramLocs;
+        collectMacroArgs(E, loc, mac, args, paramLocs);
+
+        // A parameter occurrence that expanded to several sibling expressions,
+        // e.g. `1 + 2` substituted into `x * 3`, isn't a complete argument.
+        std::set<unsigned> distinctParamLocs;
+        for (auto paramLoc : paramLocs)
+            distinctParamLocs.insert(paramLoc.getRawEncoding());
+        if (distinctParamLocs.size() != paramLocs.size())
+            return false;
+        for (unsigned i = 0; i < params.size(); ++i) {
+            if (args[i].size() != occurrences[i])
+                return false;
+        }
+
+        if (!VisitMacro(name, loc, mac, E))
+            return false;
+        macros[mac].Arguments[E] = std::move(args);
+        return true;
+    }
+
     static bool isScalarAsmType(QualType ty) {
         ty = ty.getCanonicalType();
         switch (ty->getTypeClass()) {
@@ -687,10 +820,42 @@ class TranslateASTVisitor final
             std::vector<void *> childIds(Info.Expressions.begin(),
                                          Info.Expressions.end());
 
-            encode_entry_raw(Mac, tag, Mac->getDefinitionLoc(), QualType(), false,
-                             false, false, childIds, [Name](CborEncoder *local) {
-                                 cbor_encode_string(local, Name.str());
-                             });
+            if (tag == TagMacroFunctionDef) {
+                std::vector<std::string> params;
+                for (auto *param : Mac->params())
+                    params.emplace_back(param->getName());
+
+                encode_entry_raw(Mac, tag, Mac->getDefinitionLoc(), QualType(), false,
+                                 false, false, childIds, [&](CborEncoder *local) {
+                                     cbor_encode_string(local, Name.str());
+                                     cbor_encode_string_array(local, ArrayRef<std::string>(params));
+
+                                     // Argument expressions of each expansion, in
+                                     // the same order as the children
+                                     CborEncoder expansionsEnc;
+                                     cbor_encoder_create_array(local, &expansionsEnc, childIds.size());
+                                     for (auto child : childIds) {
+                                         auto &args = Info.Arguments[static_cast<Expr *>(child)];
+                                         CborEncoder argsEnc;
+                                         cbor_encoder_create_array(&expansionsEnc, &argsEnc, args.size());
+                                         for (auto &occurrences : args) {
+                                             CborEncoder occurrencesEnc;
+                                             cbor_encoder_create_array(&argsEnc, &occurrencesEnc,
+                                                                       occurrences.size());
+                                             for (auto *arg : occurrences)
+                                                 cbor_encode_uint(&occurrencesEnc, uintptr_t(arg));
+                                             cbor_encoder_close_container(&argsEnc, &occurrencesEnc);
+                                         }
+                                         cbor_encoder_close_container(&expansionsEnc, &argsEnc);
+                                     }
+                                     cbor_encoder_close_container(local, &expansionsEnc);
+                                 });
+            } else {
+                encode_entry_raw(Mac, tag, Mac->getDefinitionLoc(), QualType(), false,
+                                 false, false, childIds, [Name](CborEncoder *local) {
+                                     cbor_encode_string(local, Name.str());
+                                 });
+            }
 
         }
     }
@@ -770,11 +935,9 @@ class TranslateASTVisitor final
     }
 
     bool VisitIndirectGotoStmt(IndirectGotoStmt *IGS) {
-        std::string msg =
-            "the GNU C labels-as-values extension is not supported. Aborting.";
-
-        printError(msg, IGS);
-        abort();
+        std::vector<void *> childIds = {IGS->getTarget()};
+        encode_entry(IGS, TagIndirectGotoStmt, childIds);
+        return true;
     }
 
     bool VisitLabelStmt(LabelStmt *LS) {
@@ -976,10 +1139,10 @@ class TranslateASTVisitor final
     bool VisitExpr(Expr *E) {
         curMacroExpansionStack.clear();
 
-        // We only translate constant macro objects to Rust consts, so this
-        // expression must be constant.
-        if (!E->isConstantInitializer(*Context, false))
-            return true;
+        // We only translate constant macro objects to Rust consts, so their
+        // expansions must be constant. Function-like macros may expand to
+        // arbitrary expressions.
+        bool isConstant = E->isConstantInitializer(*Context, false);
 
         auto &Mgr = Context->getSourceManager();
         auto Range = E->getSourceRange();
@@ -1000,6 +1163,8 @@ class TranslateASTVisitor final
         // starts with literal replacement and works it's way to the macro call
         // that was replaced.
         while (Begin.isMacroID()) {
+            Begin = skipMacroArgExpansions(Begin);
+            End = skipMacroArgExpansions(End);
 #if CLANG_VERSION_MAJOR < 7
             auto ExpansionRange = Mgr.getImmediateExpansionRange(Begin);
             auto ExpansionBegin = ExpansionRange.first;
@@ -1026,7 +1191,9 @@ class TranslateASTVisitor final
             Begin = ExpansionBegin;
             End = ExpansionEnd;
 
-            if (mac->isObjectLike() && VisitMacro(name, Begin, mac, E)) {
+            if (mac->isObjectLike() && isConstant && VisitMacro(name, Begin, mac, E)) {
+                curMacroExpansionStack.push_back(mac);
+            } else if (mac->isFunctionLike() && VisitFunctionMacro(name, Begin, mac, E)) {
                 curMacroExpansionStack.push_back(mac);
             }
         }
@@ -1040,8 +1207,63 @@ class TranslateASTVisitor final
         return true;
     }
 
+    /*
+     C11 generic selection, which Clang has already resolved.
+     Children:
+     - controlling expression (never evaluated)
+     - expression of the selected association
+     Extras:
+     - type of the selected association, or null for `default`
+     */
     bool VisitGenericSelectionExpr(GenericSelectionExpr *E) {
-        printWarning("Encountered unsupported generic selection expression", E);
+        if (E->isResultDependent()) {
+            printWarning("Encountered unsupported generic selection expression", E);
+            return true;
+        }
+
+        std::vector<void *> childIds{E->getControllingExpr(), E->getResultExpr()};
+        auto assocType = E->getAssocType(E->getResultIndex());
+        encode_entry(E, TagGenericSelectionExpr, childIds,
+                     [this, assocType](CborEncoder *extras) {
+                         if (assocType.isNull()) {
+                             cbor_encode_null(extras);
+                         } else {
+                             auto qt = typeEncoder.encodeQualType(assocType);
+                             cbor_encode_uint(extras, qt);
+                         }
+                     });
+        if (!assocType.isNull())
+            typeEncoder.VisitQualType(assocType);
+        return true;
+    }
+
+    /*
+     GNU `__builtin_types_compatible_p`, the only type trait available in C.
+     Children: (none)
+     Extras:
+     - first type argument
+     - second type argument
+     - whether the types are compatible
+     */
+    bool VisitTypeTraitExpr(TypeTraitExpr *E) {
+        if (E->getTrait() != BTT_TypeCompatible || E->isValueDependent()) {
+            printWarning("Encountered unsupported type trait expression", E);
+            return true;
+        }
+
+        std::vector<void *> childIds;
+        auto lhs = E->getArg(0)->getType();
+        auto rhs = E->getArg(1)->getType();
+        auto lhs_qt = typeEncoder.encodeQualType(lhs);
+        auto rhs_qt = typeEncoder.encodeQualType(rhs);
+        encode_entry(E, TagTypesCompatibleExpr, childIds,
+                     [E, lhs_qt, rhs_qt](CborEncoder *extras) {
+                         cbor_encode_uint(extras, lhs_qt);
+                         cbor_encode_uint(extras, rhs_qt);
+                         cbor_encode_boolean(extras, E->getValue());
+                     });
+        typeEncoder.VisitQualType(lhs);
+        typeEncoder.VisitQualType(rhs);
         return true;
     }
 
@@ -1495,14 +1717,61 @@ class TranslateASTVisitor final
     }
 #endif // CLANG_VERSION_MAJOR
 
+    /*
+     C11 or GNU atomic builtin such as `__atomic_load_n`.
+     Children (missing operands are null):
+     - pointer to the atomic object
+     - memory order
+     - first value operand
+     - memory order on failure (compare and exchange only)
+     - second value operand
+     - weak flag (GNU compare and exchange only)
+     Extras:
+     - name of the builtin
+     */
     bool VisitAtomicExpr(AtomicExpr *E) {
-        printWarning("Encountered unsupported atomic expression", E);
+        auto op = E->getOp();
+        auto numSubExprs = E->getNumSubExprs();
+        bool isInit = op == AtomicExpr::AO__c11_atomic_init;
+        bool hasVal2 = E->isCmpXChg() || op == AtomicExpr::AO__atomic_exchange;
+        bool hasWeak = op == AtomicExpr::AO__atomic_compare_exchange ||
+                       op == AtomicExpr::AO__atomic_compare_exchange_n;
+
+        std::vector<void *> childIds = {
+            E->getPtr(),
+            isInit ? nullptr : E->getOrder(),
+            isInit || numSubExprs > 2 ? E->getVal1() : nullptr,
+            E->isCmpXChg() ? E->getOrderFail() : nullptr,
+            hasVal2 ? E->getVal2() : nullptr,
+            hasWeak ? E->getWeak() : nullptr,
+        };
+
+        const char *name = nullptr;
+        switch (op) {
+#define BUILTIN(ID, TYPE, ATTRS)
+#define ATOMIC_BUILTIN(ID, TYPE, ATTRS)                                        \
+        case AtomicExpr::AO##ID:                                               \
+            name = #ID;                                                        \
+            break;
+#include "clang/Basic/Builtins.def"
+        }
+
+        if (!name) {
+            printWarning("Encountered unsupported atomic expression", E);
+            return true;
+        }
+
+        encode_entry(E, TagAtomicExpr, childIds,
+                     [name](CborEncoder *extras) {
+                         cbor_encode_text_stringz(extras, name);
+                     });
         return true;
     }
 
     bool VisitAddrLabelExpr(AddrLabelExpr *E) {
-        printError("Cannot translate GNU address of label expression", E);
-        abort();
+        std::vector<void *> childIds = {E->getLabel()->getStmt()};
+        encode_entry(E, TagAddrLabelExpr, childIds);
+        return true;
     }
 
     bool VisitChooseExpr(ChooseExpr *E) {
@@ -1749,17 +2018,22 @@ class TranslateASTVisitor final
                 // 2. Boolean true when definition present
                 cbor_encode_boolean(local, !!def);
 
+                // Layout attributes may only be given on the definition, e.g.
+                // `struct S; struct __attribute__((packed)) S { ... };`
+                auto layoutDecl = def ? def : D;
+
                 // 3. Attributes stored as an array of attribute names
                 CborEncoder attrs;
-                size_t attrs_n = D->hasAttrs() ? D->getAttrs().size() : 0;
+                size_t attrs_n =
+                    layoutDecl->hasAttrs() ? layoutDecl->getAttrs().size() : 0;
                 cbor_encoder_create_array(local, &attrs, attrs_n);
-                for (auto a : D->attrs()) {
+                for (auto a : layoutDecl->attrs()) {
                     cbor_encode_text_stringz(&attrs, a->getSpelling());
                 }
                 cbor_encoder_close_container(local, &attrs);
 
                 // 4. Encode manually specified alignment
-                auto align = D->getMaxAlignment();
+                auto align = layoutDecl->getMaxAlignment();
                 if (align == 0) {
                     cbor_encode_null(local);
                 } else {
@@ -1767,7 +2041,8 @@ class TranslateASTVisitor final
                 }
 
                 // 5. Encode pragma pack(n)
-                if (auto const mfaa = D->getAttr<MaxFieldAlignmentAttr>()) {
+                if (auto const mfaa =
+                        layoutDecl->getAttr<MaxFieldAlignmentAttr>()) {
                     cbor_encode_uint(local, mfaa->getAlignment() / 8);
                 } else {
                     cbor_encode_null(local);
@@ -1924,8 +2199,14 @@ class TranslateASTVisitor final
     }
 #endif // CLANG_VERSION_MAJOR
 
+    /*
+     GNU imaginary constant such as `2.0i`, as used by `_Complex_I`.
+     Children:
+     - the real-valued integer or floating literal
+     */
     bool VisitImaginaryLiteral(ImaginaryLiteral *L) {
-        printWarning("Encountered unsupported imaginary literal", L);
+        std::vector<void *> childIds{L->getSubExpr()};
+        encode_entry(L, TagImaginaryLiteral, childIds);
         return true;
     }
 
@@ -44,6 +44,7 @@ enum ASTEntryTag {
 
     TagAsmStmt,
     TagAttributedStmt,
+    TagIndirectGotoStmt,
 
     TagBinaryOperator = 200,
     TagUnaryOperator,
@@ -78,11 +79,20 @@ enum ASTEntryTag {
     // GNU extensions
     TagStmtExpr,
     TagChooseExpr,
+    TagTypesCompatibleExpr,
+    TagAddrLabelExpr,
+
+    // C11 generic selection
+    TagGenericSelectionExpr,
+
+    // C11 and GNU atomic builtins
+    TagAtomicExpr,
 
     TagIntegerLiteral = 300,
     TagStringLiteral,
     TagCharacterLiteral,
     TagFloatingLiteral,
+    TagImaginaryLiteral,
 };
 
 enum TypeTag {
@@ -134,6 +144,7 @@ enum TypeTag {
     TagBlockPointer,
     TagComplexType,
     TagHalf,
+    TagAtomicType,
 };
 
 enum StringTypeTag {
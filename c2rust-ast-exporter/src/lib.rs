@@ -29,18 +29,33 @@ pub fn get_untyped_ast(
     extra_args: &[&str],
     debug: bool,
 ) -> Result<clang_ast::AstContext, Error> {
-    let cbors = get_ast_cbors(file_path, cc_db, extra_args, debug);
-    let buffer = cbors.values().next().ok_or(Error::new(
-        ErrorKind::InvalidData,
-        "Could not parse input file",
-    ))?;
+    let buffer = get_ast_cbor(file_path, cc_db, extra_args, debug)?;
+    parse_untyped_ast(&buffer)
+}
 
-    // let cbor_path = file_path.with_extension("cbor");
-    // let mut cbor_file = File::create(&cbor_path)?;
-    // cbor_file.write_all(&buffer[..])?;
-    // eprintln!("Dumped CBOR to {}", cbor_path.to_string_lossy());
+/// Run the exporter on `file_path` and return the CBOR encoding of its AST, which can be
+/// saved and passed to `parse_untyped_ast` later.
+pub fn get_ast_cbor(
+    file_path: &Path,
+    cc_db: &Path,
+    extra_args: &[&str],
+    debug: bool,
+) -> Result<Vec<u8>, Error> {
+    let cbors = get_ast_cbors(file_path, cc_db, extra_args, debug);
+    cbors
+        .into_iter()
+        .map(|(_, cbor)| cbor)
+        .next()
+        .ok_or(Error::new(
+            ErrorKind::InvalidData,
+            "Could not parse input file",
+        ))
+}
 
-    let items: Value = from_slice(&buffer[..]).unwrap();
+/// Decode an AST exported by `get_ast_cbor`.
+pub fn parse_untyped_ast(buffer: &[u8]) -> Result<clang_ast::AstContext, Error> {
+    let items: Value =
+        from_slice(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}", e)))?;
 
     match clang_ast::process(items) {
         Ok(cxt) => Ok(cxt),
@@ -0,0 +1,815 @@
+//! A pure-Rust implementation of the x87 80-bit extended precision floating point type, which
+//! is what C's `long double` is on x86 and x86-64 Linux and macOS.
+//!
+//! [`f80`] has the same size, alignment and bit layout as the C type on x86-64, so structs and
+//! arrays containing `long double` values can be shared with C code. It is not passed or
+//! returned the same way as a C `long double` though, so it can't be used in the signatures of
+//! functions defined in C.
+//!
+//! All arithmetic is done in software and rounds to nearest, ties to even, like the x87 FPU in
+//! its default configuration. Floating point exceptions are not reported.
+
+#![allow(non_camel_case_types)]
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// An x87 80-bit extended precision floating point number.
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+pub struct f80 {
+    /// The significand, including the explicit integer bit
+    significand: u64,
+    /// The sign bit followed by the 15-bit biased exponent
+    sign_exponent: u16,
+}
+
+/// A binary floating point format, used to round exact results
+struct Format {
+    /// Bits in the significand, including the integer bit (which is implicit in some formats)
+    precision: u32,
+    bias: i32,
+    /// The biased exponent of infinities and NaNs
+    max_exponent: i32,
+}
+
+const F80: Format = Format {
+    precision: 64,
+    bias: 16383,
+    max_exponent: 0x7fff,
+};
+
+const F64: Format = Format {
+    precision: 53,
+    bias: 1023,
+    max_exponent: 0x7ff,
+};
+
+const F32: Format = Format {
+    precision: 24,
+    bias: 127,
+    max_exponent: 0xff,
+};
+
+/// The result of rounding a finite value to a `Format`
+enum Rounded {
+    /// A biased exponent (0 for subnormal numbers) and a significand with the integer bit
+    Finite(i32, u128),
+    Overflow,
+}
+
+impl Format {
+    /// Round `sig * 2^exp`, plus some positive amount smaller than `2^exp` if `sticky` is set.
+    fn round(&self, sig: u128, exp: i32, sticky: bool) -> Rounded {
+        if sig == 0 {
+            // Only reachable with `sticky` set for values far below the subnormal range
+            return Rounded::Finite(0, 0);
+        }
+
+        // Normalize so that the value is `sig * 2^exp` with the top bit of `sig` set
+        let zeros = sig.leading_zeros();
+        let sig = sig << zeros;
+        let exp = exp - zeros as i32;
+
+        let mut biased = exp + 127 + self.bias;
+        let mut shift = 128 - self.precision;
+        if biased < 1 {
+            shift += (1 - biased) as u32;
+            biased = 0;
+        }
+
+        let (mut mantissa, round_bit, sticky) = if shift > 128 {
+            (0, false, true)
+        } else if shift == 128 {
+            (0, true, sticky || sig << 1 != 0)
+        } else {
+            let rest = sig << (128 - shift);
+            (sig >> shift, rest >> 127 == 1, sticky || rest << 1 != 0)
+        };
+
+        if round_bit && (sticky || mantissa & 1 == 1) {
+            mantissa += 1;
+            if mantissa >> self.precision == 1 {
+                mantissa >>= 1;
+                biased += 1;
+            } else if biased == 0 && mantissa >> (self.precision - 1) == 1 {
+                // Rounded up from the largest subnormal number to the smallest normal one
+                biased = 1;
+            }
+        }
+
+        if biased >= self.max_exponent {
+            Rounded::Overflow
+        } else {
+            Rounded::Finite(biased, mantissa)
+        }
+    }
+}
+
+/// The value of an `f80`, taken apart
+#[derive(Clone, Copy)]
+enum Unpacked {
+    Nan,
+    Infinity(bool),
+    Zero(bool),
+    /// The sign and the value `sig * 2^exp`, with the top bit of `sig` set
+    Finite(bool, u64, i32),
+}
+
+impl f80 {
+    pub const ZERO: f80 = f80::from_bits(0);
+    pub const ONE: f80 = f80::from_bits(0x3fff_8000_0000_0000_0000);
+    pub const INFINITY: f80 = f80::from_bits(0x7fff_8000_0000_0000_0000);
+    pub const NEG_INFINITY: f80 = f80::from_bits(0xffff_8000_0000_0000_0000);
+    pub const NAN: f80 = f80::from_bits(0x7fff_c000_0000_0000_0000);
+
+    /// Build a value from its 80-bit representation, stored in the low bits of `bits`.
+    pub const fn from_bits(bits: u128) -> f80 {
+        f80 {
+            significand: bits as u64,
+            sign_exponent: (bits >> 64) as u16,
+        }
+    }
+
+    /// The 80-bit representation of the value, stored in the low bits of the result.
+    pub fn to_bits(self) -> u128 {
+        (self.sign_exponent as u128) << 64 | self.significand as u128
+    }
+
+    pub fn is_nan(self) -> bool {
+        match self.unpack() {
+            Unpacked::Nan => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_infinite(self) -> bool {
+        match self.unpack() {
+            Unpacked::Infinity(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_sign_negative(self) -> bool {
+        self.sign_exponent >> 15 == 1
+    }
+
+    fn unpack(self) -> Unpacked {
+        let sign = self.is_sign_negative();
+        let exponent = (self.sign_exponent & 0x7fff) as i32;
+        let sig = self.significand;
+
+        if exponent == 0x7fff {
+            // The integer bit is ignored, as the 8087 and 80287 did
+            if sig << 1 == 0 {
+                Unpacked::Infinity(sign)
+            } else {
+                Unpacked::Nan
+            }
+        } else if sig == 0 {
+            Unpacked::Zero(sign)
+        } else {
+            // Subnormal numbers have the same scale as the smallest normal ones
+            let exp = exponent.max(1) - F80.bias - 63;
+            let zeros = sig.leading_zeros();
+            Unpacked::Finite(sign, sig << zeros, exp - zeros as i32)
+        }
+    }
+
+    /// Round `sig * 2^exp` (see `Format::round`) to the nearest `f80`.
+    fn round(sign: bool, sig: u128, exp: i32, sticky: bool) -> f80 {
+        let sign_bit = (sign as u16) << 15;
+        match F80.round(sig, exp, sticky) {
+            Rounded::Finite(biased, mantissa) => f80 {
+                significand: mantissa as u64,
+                sign_exponent: sign_bit | biased as u16,
+            },
+            Rounded::Overflow => f80 {
+                significand: 1 << 63,
+                sign_exponent: sign_bit | 0x7fff,
+            },
+        }
+    }
+
+    fn zero(sign: bool) -> f80 {
+        f80 {
+            significand: 0,
+            sign_exponent: (sign as u16) << 15,
+        }
+    }
+
+    fn infinity(sign: bool) -> f80 {
+        if sign {
+            f80::NEG_INFINITY
+        } else {
+            f80::INFINITY
+        }
+    }
+
+    /// Convert an integer with the given sign and magnitude.
+    fn from_integer(sign: bool, magnitude: u128) -> f80 {
+        if magnitude == 0 {
+            f80::ZERO
+        } else {
+            f80::round(sign, magnitude, 0, false)
+        }
+    }
+
+    /// Convert a binary floating point number in format `fmt` with the given representation.
+    fn from_float(fmt: &Format, bits: u64) -> f80 {
+        let fraction_bits = fmt.precision - 1;
+        let total_bits = if fmt.precision == 53 { 64 } else { 32 };
+        let sign = (bits >> (total_bits - 1)) & 1 == 1;
+        let exponent = ((bits >> fraction_bits) & fmt.max_exponent as u64) as i32;
+        let fraction = bits & ((1 << fraction_bits) - 1);
+
+        if exponent == fmt.max_exponent {
+            if fraction == 0 {
+                f80::infinity(sign)
+            } else {
+                f80::NAN
+            }
+        } else if exponent == 0 && fraction == 0 {
+            f80::zero(sign)
+        } else if exponent == 0 {
+            let exp = 1 - fmt.bias - fraction_bits as i32;
+            f80::round(sign, fraction as u128, exp, false)
+        } else {
+            let sig = fraction | 1 << fraction_bits;
+            let exp = exponent - fmt.bias - fraction_bits as i32;
+            f80::round(sign, sig as u128, exp, false)
+        }
+    }
+
+    /// Round to a binary floating point number in format `fmt`, returning its representation.
+    fn to_float(self, fmt: &Format) -> u64 {
+        let fraction_bits = fmt.precision - 1;
+        let total_bits = if fmt.precision == 53 { 64 } else { 32 };
+        let sign_bit = |sign: bool| (sign as u64) << (total_bits - 1);
+        let infinity = |sign: bool| sign_bit(sign) | (fmt.max_exponent as u64) << fraction_bits;
+
+        match self.unpack() {
+            Unpacked::Nan => infinity(false) | 1 << (fraction_bits - 1),
+            Unpacked::Infinity(sign) => infinity(sign),
+            Unpacked::Zero(sign) => sign_bit(sign),
+            Unpacked::Finite(sign, sig, exp) => match fmt.round(sig as u128, exp, false) {
+                Rounded::Finite(biased, mantissa) => {
+                    let fraction = mantissa as u64 & ((1 << fraction_bits) - 1);
+                    sign_bit(sign) | (biased as u64) << fraction_bits | fraction
+                }
+                Rounded::Overflow => infinity(sign),
+            },
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        f64::from_bits(self.to_float(&F64))
+    }
+
+    pub fn to_f32(self) -> f32 {
+        f32::from_bits(self.to_float(&F32) as u32)
+    }
+
+    /// Truncate toward zero, returning the sign and the magnitude saturated to `u128::MAX`, or
+    /// `None` for NaN.
+    fn to_integer(self) -> Option<(bool, u128)> {
+        match self.unpack() {
+            Unpacked::Nan => None,
+            Unpacked::Infinity(sign) => Some((sign, u128::max_value())),
+            Unpacked::Zero(sign) => Some((sign, 0)),
+            Unpacked::Finite(sign, sig, exp) => {
+                let magnitude = if exp >= 64 {
+                    u128::max_value()
+                } else if exp >= 0 {
+                    (sig as u128) << exp
+                } else if exp > -64 {
+                    (sig >> -exp) as u128
+                } else {
+                    0
+                };
+                Some((sign, magnitude))
+            }
+        }
+    }
+
+    /// Convert to a signed 128-bit integer like an `as` cast from a primitive float would:
+    /// truncating toward zero and saturating, with NaN converting to 0.
+    pub fn to_i128(self) -> i128 {
+        match self.to_integer() {
+            None => 0,
+            Some((false, magnitude)) => magnitude.min(i128::max_value() as u128) as i128,
+            Some((true, magnitude)) if magnitude > i128::max_value() as u128 => i128::min_value(),
+            Some((true, magnitude)) => -(magnitude as i128),
+        }
+    }
+
+    /// Convert to an unsigned 128-bit integer like an `as` cast from a primitive float would:
+    /// truncating toward zero and saturating, with NaN converting to 0.
+    pub fn to_u128(self) -> u128 {
+        match self.to_integer() {
+            None | Some((true, _)) => 0,
+            Some((false, magnitude)) => magnitude,
+        }
+    }
+}
+
+macro_rules! to_int {
+    ($($name:ident: $ty:ident from $via:ident: $via_ty:ident,)*) => {
+        impl f80 {
+            $(
+                /// Convert like an `as` cast from a primitive float would: truncating toward
+                /// zero and saturating, with NaN converting to 0.
+                pub fn $name(self) -> $ty {
+                    let val = self.$via();
+                    if val > $ty::max_value() as $via_ty {
+                        $ty::max_value()
+                    } else if val < $ty::min_value() as $via_ty {
+                        $ty::min_value()
+                    } else {
+                        val as $ty
+                    }
+                }
+            )*
+        }
+    };
+}
+
+to_int! {
+    to_i8: i8 from to_i128: i128,
+    to_i16: i16 from to_i128: i128,
+    to_i32: i32 from to_i128: i128,
+    to_i64: i64 from to_i128: i128,
+    to_isize: isize from to_i128: i128,
+    to_u8: u8 from to_u128: u128,
+    to_u16: u16 from to_u128: u128,
+    to_u32: u32 from to_u128: u128,
+    to_u64: u64 from to_u128: u128,
+    to_usize: usize from to_u128: u128,
+}
+
+macro_rules! from_signed {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for f80 {
+                fn from(val: $ty) -> f80 {
+                    f80::from_integer(val < 0, (val as i128).wrapping_abs() as u128)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! from_unsigned {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for f80 {
+                fn from(val: $ty) -> f80 {
+                    f80::from_integer(false, val as u128)
+                }
+            }
+        )*
+    };
+}
+
+from_signed!(i8 i16 i32 i64 i128 isize);
+from_unsigned!(u8 u16 u32 u64 u128 usize);
+
+impl From<bool> for f80 {
+    fn from(val: bool) -> f80 {
+        f80::from(val as u8)
+    }
+}
+
+impl From<f64> for f80 {
+    fn from(val: f64) -> f80 {
+        f80::from_float(&F64, val.to_bits())
+    }
+}
+
+impl From<f32> for f80 {
+    fn from(val: f32) -> f80 {
+        f80::from_float(&F32, val.to_bits() as u64)
+    }
+}
+
+impl Default for f80 {
+    fn default() -> f80 {
+        f80::ZERO
+    }
+}
+
+impl Neg for f80 {
+    type Output = f80;
+
+    fn neg(self) -> f80 {
+        f80 {
+            significand: self.significand,
+            sign_exponent: self.sign_exponent ^ 0x8000,
+        }
+    }
+}
+
+impl Add for f80 {
+    type Output = f80;
+
+    fn add(self, rhs: f80) -> f80 {
+        use self::Unpacked::*;
+        match (self.unpack(), rhs.unpack()) {
+            (Nan, _) | (_, Nan) => f80::NAN,
+            (Infinity(a), Infinity(b)) if a != b => f80::NAN,
+            (Infinity(_), _) => self,
+            (_, Infinity(_)) => rhs,
+            (Zero(a), Zero(b)) => f80::zero(a && b),
+            (Zero(_), _) => rhs,
+            (_, Zero(_)) => self,
+            (Finite(sign_a, sig_a, exp_a), Finite(sign_b, sig_b, exp_b)) => {
+                let ((sign_a, sig_a, exp_a), (sign_b, sig_b, exp_b)) = if exp_a >= exp_b {
+                    ((sign_a, sig_a, exp_a), (sign_b, sig_b, exp_b))
+                } else {
+                    ((sign_b, sig_b, exp_b), (sign_a, sig_a, exp_a))
+                };
+
+                // Leave room for a carry, and keep the bits shifted out of the smaller operand
+                // as a sticky bit so that subtraction rounds correctly
+                let a = (sig_a as u128) << 62;
+                let shift = (exp_a - exp_b) as u32;
+                let b = (sig_b as u128) << 62;
+                let b = if shift == 0 {
+                    b
+                } else if shift >= 126 {
+                    1
+                } else {
+                    b >> shift | (b << (128 - shift) != 0) as u128
+                };
+                let exp = exp_a - 62;
+
+                if sign_a == sign_b {
+                    f80::round(sign_a, a + b, exp, false)
+                } else if a > b {
+                    f80::round(sign_a, a - b, exp, false)
+                } else if a < b {
+                    f80::round(sign_b, b - a, exp, false)
+                } else {
+                    f80::ZERO
+                }
+            }
+        }
+    }
+}
+
+impl Sub for f80 {
+    type Output = f80;
+
+    fn sub(self, rhs: f80) -> f80 {
+        self + -rhs
+    }
+}
+
+impl Mul for f80 {
+    type Output = f80;
+
+    fn mul(self, rhs: f80) -> f80 {
+        use self::Unpacked::*;
+        let sign = self.is_sign_negative() != rhs.is_sign_negative();
+        match (self.unpack(), rhs.unpack()) {
+            (Nan, _) | (_, Nan) => f80::NAN,
+            (Infinity(_), Zero(_)) | (Zero(_), Infinity(_)) => f80::NAN,
+            (Infinity(_), _) | (_, Infinity(_)) => f80::infinity(sign),
+            (Zero(_), _) | (_, Zero(_)) => f80::zero(sign),
+            (Finite(_, sig_a, exp_a), Finite(_, sig_b, exp_b)) => {
+                f80::round(sign, sig_a as u128 * sig_b as u128, exp_a + exp_b, false)
+            }
+        }
+    }
+}
+
+impl Div for f80 {
+    type Output = f80;
+
+    fn div(self, rhs: f80) -> f80 {
+        use self::Unpacked::*;
+        let sign = self.is_sign_negative() != rhs.is_sign_negative();
+        match (self.unpack(), rhs.unpack()) {
+            (Nan, _) | (_, Nan) => f80::NAN,
+            (Infinity(_), Infinity(_)) | (Zero(_), Zero(_)) => f80::NAN,
+            (Infinity(_), _) | (_, Zero(_)) => f80::infinity(sign),
+            (Zero(_), _) | (_, Infinity(_)) => f80::zero(sign),
+            (Finite(_, sig_a, exp_a), Finite(_, sig_b, exp_b)) => {
+                // Both significands have their top bit set, so the first quotient has 64 or 65
+                // bits, and the second one adds 32 more for rounding
+                let divisor = sig_b as u128;
+                let dividend = (sig_a as u128) << 64;
+                let (high, rem) = (dividend / divisor, dividend % divisor);
+                let dividend = rem << 32;
+                let (low, rem) = (dividend / divisor, dividend % divisor);
+
+                let sig = high << 32 | low;
+                f80::round(sign, sig, exp_a - exp_b - 96, rem != 0)
+            }
+        }
+    }
+}
+
+macro_rules! assign_op {
+    ($($trait:ident $method:ident $op:tt,)*) => {
+        $(
+            impl $trait for f80 {
+                fn $method(&mut self, rhs: f80) {
+                    *self = *self $op rhs;
+                }
+            }
+        )*
+    };
+}
+
+assign_op! {
+    AddAssign add_assign +,
+    SubAssign sub_assign -,
+    MulAssign mul_assign *,
+    DivAssign div_assign /,
+}
+
+impl PartialEq for f80 {
+    fn eq(&self, other: &f80) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for f80 {
+    fn partial_cmp(&self, other: &f80) -> Option<Ordering> {
+        use self::Unpacked::*;
+
+        // Order the magnitudes of non-NaN values: zero, finite values, then infinity
+        fn magnitude(val: Unpacked) -> (u8, i32, u64) {
+            match val {
+                Zero(_) => (0, 0, 0),
+                Finite(_, sig, exp) => (1, exp, sig),
+                Infinity(_) | Nan => (2, 0, 0),
+            }
+        }
+
+        // Zeros compare equal whatever their sign
+        fn sign(val: Unpacked) -> bool {
+            match val {
+                Infinity(sign) | Finite(sign, _, _) => sign,
+                Zero(_) | Nan => false,
+            }
+        }
+
+        let (a, b) = (self.unpack(), other.unpack());
+        match (a, b) {
+            (Nan, _) | (_, Nan) => None,
+            _ => {
+                let (sign_a, sign_b) = (sign(a), sign(b));
+                if sign_a != sign_b {
+                    return Some(if sign_a {
+                        Ordering::Less
+                    } else {
+                        Ordering::Greater
+                    });
+                }
+                let ord = magnitude(a).cmp(&magnitude(b));
+                Some(if sign_a { ord.reverse() } else { ord })
+            }
+        }
+    }
+}
+
+/// Formats the value rounded to an `f64`.
+impl fmt::Display for f80 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl fmt::Debug for f80 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} ({:#022x})", self.to_f64(), self.to_bits())
+    }
+}
+
+/// An error returned when parsing an `f80` fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseF80Error;
+
+impl fmt::Display for ParseF80Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid extended float literal")
+    }
+}
+
+/// Parses decimal numbers with an optional exponent, like C floating constants without a suffix,
+/// as well as `inf`, `infinity` and `nan`. The result is correctly rounded.
+impl FromStr for f80 {
+    type Err = ParseF80Error;
+
+    fn from_str(s: &str) -> Result<f80, ParseF80Error> {
+        let (sign, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        match s.to_ascii_lowercase().as_str() {
+            "inf" | "infinity" => return Ok(f80::infinity(sign)),
+            "nan" => return Ok(f80::NAN),
+            _ => {}
+        }
+
+        let (mantissa, exponent) = match s.find(|c| c == 'e' || c == 'E') {
+            Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+            None => (s, None),
+        };
+        let (int_part, frac_part) = match mantissa.find('.') {
+            Some(pos) => (&mantissa[..pos], &mantissa[pos + 1..]),
+            None => (mantissa, ""),
+        };
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(ParseF80Error);
+        }
+
+        let mut digits = BigUint::zero();
+        for c in int_part.bytes().chain(frac_part.bytes()) {
+            if !c.is_ascii_digit() {
+                return Err(ParseF80Error);
+            }
+            digits.mul_add_small(10, (c - b'0') as u32);
+        }
+
+        let mut exp10 = match exponent {
+            Some(exponent) => parse_exponent(exponent)?,
+            None => 0,
+        };
+        exp10 -= frac_part.len() as i64;
+
+        if digits.is_zero() {
+            return Ok(f80::zero(sign));
+        }
+
+        // The largest finite value is about 1.19e4932 and the smallest subnormal about 3.6e-4951
+        let magnitude = exp10 + digits.bit_len() as i64 * 3 / 10;
+        if magnitude > 5000 {
+            return Ok(f80::infinity(sign));
+        } else if magnitude < -5000 {
+            return Ok(f80::zero(sign));
+        }
+
+        if exp10 >= 0 {
+            for _ in 0..exp10 {
+                digits.mul_add_small(10, 0);
+            }
+            let (sig, exp, sticky) = digits.top_bits();
+            Ok(f80::round(sign, sig, exp, sticky))
+        } else {
+            let mut divisor = BigUint::from(1);
+            for _ in 0..-exp10 {
+                divisor.mul_add_small(10, 0);
+            }
+
+            // Scale so that the quotient has between 67 and 68 bits, enough to round correctly
+            let scale = divisor.bit_len() as i64 - digits.bit_len() as i64 + 67;
+            if scale >= 0 {
+                digits.shl(scale as usize);
+            } else {
+                divisor.shl(-scale as usize);
+            }
+
+            let mut quotient: u128 = 0;
+            for i in (0..69).rev() {
+                let mut shifted = divisor.clone();
+                shifted.shl(i);
+                if digits >= shifted {
+                    digits.sub(&shifted);
+                    quotient |= 1 << i;
+                }
+            }
+            Ok(f80::round(sign, quotient, -scale as i32, !digits.is_zero()))
+        }
+    }
+}
+
+fn parse_exponent(s: &str) -> Result<i64, ParseF80Error> {
+    let (negative, digits) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(ParseF80Error);
+    }
+
+    // Saturate huge exponents, they overflow or underflow either way
+    let mut exp: i64 = 0;
+    for c in digits.bytes() {
+        exp = (exp * 10 + (c - b'0') as i64).min(1 << 32);
+    }
+    Ok(if negative { -exp } else { exp })
+}
+
+/// Just enough of an arbitrary precision unsigned integer to parse decimal numbers.
+#[derive(Clone, PartialEq, Eq)]
+struct BigUint {
+    /// Little endian 32-bit digits, without trailing zeros
+    digits: Vec<u32>,
+}
+
+impl BigUint {
+    fn zero() -> BigUint {
+        BigUint { digits: vec![] }
+    }
+
+    fn from(val: u32) -> BigUint {
+        let mut res = BigUint::zero();
+        res.mul_add_small(1, val);
+        res
+    }
+
+    fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    fn bit_len(&self) -> usize {
+        match self.digits.last() {
+            Some(&top) => self.digits.len() * 32 - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// `self = self * mul + add`
+    fn mul_add_small(&mut self, mul: u32, add: u32) {
+        let mut carry = add as u64;
+        for digit in self.digits.iter_mut() {
+            let val = *digit as u64 * mul as u64 + carry;
+            *digit = val as u32;
+            carry = val >> 32;
+        }
+        if carry != 0 {
+            self.digits.push(carry as u32);
+        }
+    }
+
+    fn shl(&mut self, bits: usize) {
+        if self.is_zero() {
+            return;
+        }
+        let (words, bits) = (bits / 32, bits % 32);
+        if bits != 0 {
+            let mut carry = 0;
+            for digit in self.digits.iter_mut() {
+                let val = (*digit as u64) << bits | carry;
+                *digit = val as u32;
+                carry = val >> 32;
+            }
+            if carry != 0 {
+                self.digits.push(carry as u32);
+            }
+        }
+        let mut shifted = vec![0; words];
+        shifted.extend_from_slice(&self.digits);
+        self.digits = shifted;
+    }
+
+    /// `self = self - other`, where `other <= self`
+    fn sub(&mut self, other: &BigUint) {
+        let mut borrow = 0;
+        for i in 0..self.digits.len() {
+            let rhs = other.digits.get(i).cloned().unwrap_or(0) as i64 + borrow;
+            let val = self.digits[i] as i64 - rhs;
+            borrow = if val < 0 { 1 } else { 0 };
+            self.digits[i] = (val + (borrow << 32)) as u32;
+        }
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+    }
+
+    /// The top 128 bits `sig` and an exponent such that `self` is `sig * 2^exp` plus something
+    /// smaller than `2^exp`, which is nonzero if the sticky flag is set.
+    fn top_bits(&self) -> (u128, i32, bool) {
+        let len = self.bit_len();
+        let shift = len.saturating_sub(128);
+        let mut sig: u128 = 0;
+        for i in (shift..len).rev() {
+            sig = sig << 1 | self.bit(i) as u128;
+        }
+        let sticky = (0..shift).any(|i| self.bit(i));
+        (sig, shift as i32, sticky)
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        self.digits[i / 32] >> (i % 32) & 1 == 1
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        let ord = self
+            .digits
+            .len()
+            .cmp(&other.digits.len())
+            .then_with(|| self.digits.iter().rev().cmp(other.digits.iter().rev()));
+        Some(ord)
+    }
+}
@@ -0,0 +1,61 @@
+use c2rust_f80::f80;
+
+// Expected values are the results of the same operations on x86-64 `long double`s
+fn bits(sign_exponent: u16, significand: u64) -> u128 {
+    (sign_exponent as u128) << 64 | significand as u128
+}
+
+fn parse(s: &str) -> f80 {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_layout() {
+    assert_eq!(std::mem::size_of::<f80>(), 16);
+    assert_eq!(std::mem::align_of::<f80>(), 16);
+}
+
+#[test]
+fn test_arithmetic() {
+    let one = f80::ONE;
+    let three = f80::from(3);
+    let tenth = parse("0.1");
+
+    assert_eq!((one / three).to_bits(), bits(0x3ffd, 0xaaaaaaaaaaaaaaab));
+    assert_eq!((tenth * three).to_bits(), bits(0x3ffd, 0x999999999999999a));
+    assert_eq!((one / three - tenth).to_bits(), bits(0x3ffc, 0xeeeeeeeeeeeeeef0));
+    assert_eq!(-(one - one), f80::ZERO);
+    assert!((f80::INFINITY - f80::INFINITY).is_nan());
+    assert_eq!(one / f80::ZERO, f80::INFINITY);
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(parse("0.1").to_bits(), bits(0x3ffb, 0xcccccccccccccccd));
+    assert_eq!(parse("1e4000").to_bits(), bits(0x73e6, 0xd1ba8323fe558c61));
+    assert_eq!(parse("3.6e-4951").to_bits(), bits(0x0000, 0x0000000000000001));
+    assert_eq!(parse("1e5000"), f80::INFINITY);
+    assert_eq!(parse("-.5e1"), f80::from(-5));
+    assert!("1.2.3".parse::<f80>().is_err());
+}
+
+#[test]
+fn test_conversions() {
+    assert_eq!(f80::from(0.1f64).to_bits(), bits(0x3ffb, 0xccccccccccccd000));
+    assert_eq!(parse("0.1").to_f64(), 0.1);
+    assert_eq!(parse("0.1").to_f32(), 0.1);
+    assert_eq!(f80::from(u64::max_value()).to_bits(), bits(0x403e, 0xffffffffffffffff));
+    assert_eq!(f80::from(u64::max_value()).to_u64(), u64::max_value());
+    assert_eq!(parse("-2.9").to_i32(), -2);
+    assert_eq!(parse("1e30").to_i64(), i64::max_value());
+    assert_eq!(f80::NAN.to_u8(), 0);
+}
+
+#[test]
+fn test_comparisons() {
+    assert!(f80::from(-1) < f80::ZERO);
+    assert!(parse("0.1") < f80::from(0.1f64));
+    assert_eq!(-f80::ZERO, f80::ZERO);
+    assert!(f80::NAN != f80::NAN);
+    assert!(f80::NEG_INFINITY < f80::from(i64::min_value()));
+}
@@ -0,0 +1,140 @@
+//! Runtime support for `setjmp` and `longjmp` in code translated by C2Rust.
+//!
+//! A `setjmp` call is translated into a call to [`setjmp`] that runs the code protected by it as
+//! a closure. [`longjmp`] unwinds the stack back to the innermost running [`setjmp`] call for
+//! the same `jmp_buf`, which then returns the value passed to [`longjmp`] as an error.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::os::raw::{c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+
+thread_local! {
+    /// Addresses of the `jmp_buf`s of the `setjmp` calls that are currently running on this
+    /// thread, innermost last.
+    static ACTIVE: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+}
+
+/// Unwinding payload of a `longjmp`
+struct LongJmp {
+    env: usize,
+    val: c_int,
+}
+
+/// Pops the `jmp_buf` pushed by `setjmp`, even if the protected code unwinds.
+struct ActiveGuard;
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        ACTIVE.with(|active| active.borrow_mut().pop());
+    }
+}
+
+/// Run `f`, the code protected by a `setjmp(env)` call. Returns `Ok` with the result of `f` if
+/// it completes normally, or `Err` with the value passed to `longjmp(env, val)` if it jumps back.
+/// As in C, a `longjmp` value of 0 is returned as 1.
+///
+/// # Safety
+///
+/// `env` is only used as an identifier for the `setjmp` call and is never dereferenced, but it
+/// must not be reused by another `setjmp` call while `f` runs unless that call is nested in `f`.
+pub unsafe fn setjmp<R, F: FnOnce() -> R>(env: *mut c_void, f: F) -> Result<R, c_int> {
+    let env = env as usize;
+    ACTIVE.with(|active| active.borrow_mut().push(env));
+    let result = {
+        let _guard = ActiveGuard;
+        panic::catch_unwind(AssertUnwindSafe(f))
+    };
+    match result {
+        Ok(r) => Ok(r),
+        Err(payload) => Err(longjmp_value(env, payload)),
+    }
+}
+
+/// Extract the value of a `longjmp` to `env` from an unwinding payload, or continue unwinding if
+/// the payload belongs to a different `setjmp` or to a panic.
+fn longjmp_value(env: usize, payload: Box<dyn Any + Send>) -> c_int {
+    match payload.downcast::<LongJmp>() {
+        Ok(ref jmp) if jmp.env == env => {
+            if jmp.val == 0 {
+                1
+            } else {
+                jmp.val
+            }
+        }
+        Ok(jmp) => panic::resume_unwind(jmp),
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+/// Jump back to the running `setjmp(env)` call, making it return `val`.
+///
+/// # Safety
+///
+/// Every frame between here and the `setjmp` call must allow unwinding.
+///
+/// # Panics
+///
+/// Panics if no `setjmp` call for `env` is running on this thread.
+pub unsafe fn longjmp(env: *mut c_void, val: c_int) -> ! {
+    let env = env as usize;
+    let active = ACTIVE.with(|active| active.borrow().contains(&env));
+    if !active {
+        panic!("longjmp to a jmp_buf without an active setjmp");
+    }
+    panic::resume_unwind(Box::new(LongJmp { env, val }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    fn env_ptr(env: &mut [u64; 8]) -> *mut c_void {
+        env.as_mut_ptr() as *mut c_void
+    }
+
+    #[test]
+    fn no_jump() {
+        let mut env = [0u64; 8];
+        let res = unsafe { setjmp(env_ptr(&mut env), || 42) };
+        assert_eq!(res, Ok(42));
+    }
+
+    #[test]
+    fn jump() {
+        let mut env = [0u64; 8];
+        let env = env_ptr(&mut env);
+        let res: Result<(), _> = unsafe { setjmp(env, || longjmp(env, 7)) };
+        assert_eq!(res, Err(7));
+    }
+
+    #[test]
+    fn jump_zero_returns_one() {
+        let mut env = [0u64; 8];
+        let env = env_ptr(&mut env);
+        let res: Result<(), _> = unsafe { setjmp(env, || longjmp(env, 0)) };
+        assert_eq!(res, Err(1));
+    }
+
+    #[test]
+    fn jump_to_outer() {
+        let mut outer = [0u64; 8];
+        let mut inner = [0u64; 8];
+        let outer = env_ptr(&mut outer);
+        let inner = env_ptr(&mut inner);
+        let res = unsafe {
+            setjmp(outer, || {
+                let res: Result<(), _> = setjmp(inner, || longjmp(outer, 3));
+                res
+            })
+        };
+        assert_eq!(res, Err(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "longjmp to a jmp_buf without an active setjmp")]
+    fn inactive_jump() {
+        unsafe { longjmp(ptr::null_mut(), 1) }
+    }
+}
@@ -1,6 +1,7 @@
 extern crate handlebars;
 extern crate pathdiff;
 
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -10,9 +11,10 @@ use self::handlebars::Handlebars;
 use self::pathdiff::diff_paths;
 use serde_json::json;
 
-use super::TranspilerConfig;
+use super::{get_va_trampoline_path, TranspilerConfig, VaListMode};
 use crate::CrateSet;
 use crate::PragmaSet;
+use crate::compile_cmds::{CompileCmd, LinkCmd, LinkKind};
 use crate::convert_type::RESERVED_NAMES;
 
 #[derive(Debug, Copy, Clone)]
@@ -56,7 +58,47 @@ pub fn get_build_dir(tcfg: &TranspilerConfig, cc_db: &Path) -> PathBuf {
     }
 }
 
+/// A crate to emit build files for
+struct CrateConfig {
+    name: String,
+    /// The module holding the main function of a binary crate
+    main: Option<String>,
+    /// The `crate-type` of a library crate
+    crate_type: &'static str,
+    root_rs_file: &'static str,
+    /// Whether the crate is a workspace of its own rather than a member of one
+    standalone: bool,
+    /// Crates of the same workspace the crate depends on, by name and path
+    path_deps: Vec<(String, PathBuf)>,
+}
+
+/// A translated module, with what its crate needs to build it
+pub struct TranslatedModule {
+    /// The translation unit the module was translated from
+    pub input: PathBuf,
+    pub path: PathBuf,
+    pub pragmas: PragmaSet,
+    pub crates: CrateSet,
+    /// Whether the translation unit defines `main`
+    pub has_main: bool,
+}
+
+fn register_templates() -> Handlebars {
+    let mut reg = Handlebars::new();
+
+    reg.register_template_string("Cargo.toml", include_str!("Cargo.toml.hbs"))
+        .unwrap();
+    reg.register_template_string("lib.rs", include_str!("lib.rs.hbs"))
+        .unwrap();
+    reg.register_template_string("build.rs", include_str!("build.rs.hbs"))
+        .unwrap();
+    reg.register_template_string("workspace.toml", include_str!("workspace.toml.hbs"))
+        .unwrap();
+    reg
+}
+
 /// Emit `Cargo.toml` and `lib.rs` for a library or `main.rs` for a binary.
+/// The translation units of `c_cmds` are compiled from C by `build.rs`.
 /// Returns the path to `lib.rs` or `main.rs` (or `None` if the output file
 /// existed already).
 pub fn emit_build_files(
@@ -65,22 +107,258 @@ pub fn emit_build_files(
     modules: Vec<PathBuf>,
     pragmas: PragmaSet,
     crates: CrateSet,
+    c_cmds: &[CompileCmd],
 ) -> Option<PathBuf> {
-    let mut reg = Handlebars::new();
+    let reg = register_templates();
 
-    reg.register_template_string("Cargo.toml", include_str!("Cargo.toml.hbs"))
-        .unwrap();
-    reg.register_template_string("lib.rs", include_str!("lib.rs.hbs"))
-        .unwrap();
-    reg.register_template_string("build.rs", include_str!("build.rs.hbs"))
-        .unwrap();
+    let krate = CrateConfig {
+        name: tcfg
+            .output_dir
+            .as_ref()
+            .and_then(|x| x.file_name().map(|x| x.to_string_lossy().into_owned()))
+            .unwrap_or("c2rust".into()),
+        main: tcfg.main.clone(),
+        crate_type: "staticlib",
+        root_rs_file: get_root_rs_file_name(tcfg),
+        standalone: true,
+        path_deps: vec![],
+    };
+    if tcfg.translate_valist && tcfg.va_list == VaListMode::Nightly {
+        emit_rust_toolchain(tcfg, &build_dir);
+    }
+    emit_crate(
+        tcfg, &reg, build_dir, &krate, modules, pragmas, &crates, c_cmds,
+    )
+}
+
+/// Emit a Cargo workspace in `build_dir` with a crate for each executable and library linked by
+/// `link_cmds`. Translation units linked into more than one of them are moved into a library
+/// crate of their own, `common`, which the others depend on. Returns the directories and root
+/// files of the crates whose root file was emitted.
+pub fn emit_workspace(
+    tcfg: &TranspilerConfig,
+    build_dir: &Path,
+    link_cmds: &[LinkCmd],
+    modules: Vec<TranslatedModule>,
+    c_cmds: &[CompileCmd],
+) -> Vec<(PathBuf, PathBuf)> {
+    let reg = register_templates();
+
+    // The translation units each output needs, including those of the project's shared
+    // libraries it links against, which become part of its crate
+    let files_of: HashMap<&Path, &[PathBuf]> = link_cmds
+        .iter()
+        .map(|cmd| (cmd.output.as_path(), cmd.files.as_slice()))
+        .collect();
+    let target_files: Vec<Vec<&Path>> = link_cmds
+        .iter()
+        .map(|cmd| {
+            let mut files: Vec<&Path> = vec![];
+            let libs = cmd
+                .libraries
+                .iter()
+                .filter_map(|lib| files_of.get(lib.as_path()));
+            for file in cmd
+                .files
+                .iter()
+                .chain(libs.flat_map(|lib_files| lib_files.iter()))
+            {
+                if !files.contains(&file.as_path()) {
+                    files.push(file);
+                }
+            }
+            files
+        })
+        .collect();
 
-    emit_cargo_toml(tcfg, &reg, &build_dir, &crates);
-    if tcfg.translate_valist {
+    let mut uses: HashMap<&Path, usize> = HashMap::new();
+    for &file in target_files.iter().flatten() {
+        *uses.entry(file).or_insert(0) += 1;
+    }
+    let mut common_files: Vec<&Path> = uses
+        .iter()
+        .filter(|&(_, &n)| n > 1)
+        .map(|(&file, _)| file)
+        .collect();
+    common_files.sort();
+
+    let mut names = HashSet::new();
+    let mut unique_name = |name: String| {
+        let mut unique = name.clone();
+        let mut i = 2;
+        while !names.insert(unique.clone()) {
+            unique = format!("{}_{}", name, i);
+            i += 1;
+        }
+        unique
+    };
+
+    let common_name = unique_name("common".to_owned());
+    let mut members = vec![];
+    if !common_files.is_empty() {
+        let krate = CrateConfig {
+            name: common_name.clone(),
+            main: None,
+            crate_type: "rlib",
+            root_rs_file: "lib.rs",
+            standalone: false,
+            path_deps: vec![],
+        };
+        members.push((krate, common_files.clone()));
+    }
+    for (cmd, files) in link_cmds.iter().zip(&target_files) {
+        let main = match cmd.kind {
+            LinkKind::Executable => {
+                let main = modules
+                    .iter()
+                    .find(|m| m.has_main && files.contains(&m.input.as_path()))
+                    .map(|m| {
+                        let module = m.path.file_stem().unwrap().to_string_lossy();
+                        if common_files.contains(&m.input.as_path()) {
+                            format!("{}::{}", common_name, module)
+                        } else {
+                            module.into_owned()
+                        }
+                    });
+                if main.is_none() {
+                    warn!(
+                        "Could not find the translated main function of {}, emitting a library crate for it",
+                        cmd.output.display()
+                    );
+                }
+                main
+            }
+            _ => None,
+        };
+        let krate = CrateConfig {
+            name: unique_name(get_target_name(cmd)),
+            root_rs_file: if main.is_some() { "main.rs" } else { "lib.rs" },
+            main,
+            crate_type: match cmd.kind {
+                LinkKind::SharedLibrary => "cdylib",
+                _ => "staticlib",
+            },
+            standalone: false,
+            path_deps: if common_files.is_empty() {
+                vec![]
+            } else {
+                vec![(common_name.clone(), PathBuf::from("..").join(&common_name))]
+            },
+        };
+        let files = files
+            .iter()
+            .cloned()
+            .filter(|file| !common_files.contains(file))
+            .collect();
+        members.push((krate, files));
+    }
+
+    if tcfg.translate_valist && tcfg.va_list == VaListMode::Nightly {
         emit_rust_toolchain(tcfg, &build_dir);
     }
-    emit_build_rs(tcfg, &reg, &build_dir);
-    emit_lib_rs(tcfg, &reg, &build_dir, modules, pragmas, &crates)
+    let member_names: Vec<&str> = members
+        .iter()
+        .map(|(krate, _)| krate.name.as_str())
+        .collect();
+    let output = reg
+        .render("workspace.toml", &json!({ "members": member_names }))
+        .unwrap();
+    maybe_write_to_file(
+        &build_dir.join("Cargo.toml"),
+        output,
+        tcfg.overwrite_existing,
+    );
+
+    let mut root_files = vec![];
+    for (krate, files) in &members {
+        let crate_dir = build_dir.join(&krate.name);
+        if !crate_dir.exists() {
+            fs::create_dir(&crate_dir).expect(&format!(
+                "couldn't create crate directory: {}",
+                crate_dir.display()
+            ));
+        }
+
+        let crate_modules: Vec<&TranslatedModule> = modules
+            .iter()
+            .filter(|m| files.contains(&m.input.as_path()))
+            .collect();
+        let mut pragmas = PragmaSet::new();
+        let mut crates = CrateSet::new();
+        for module in &crate_modules {
+            pragmas.extend(module.pragmas.iter().cloned());
+            crates.extend(module.crates.iter().cloned());
+        }
+        pragmas.sort();
+        crates.sort();
+        let crate_c_cmds: Vec<CompileCmd> = c_cmds
+            .iter()
+            .filter(|cmd| files.contains(&cmd.abs_file().as_path()))
+            .cloned()
+            .collect();
+
+        let module_paths = crate_modules.iter().map(|m| m.path.clone()).collect();
+        let root_file = emit_crate(
+            tcfg,
+            &reg,
+            &crate_dir,
+            krate,
+            module_paths,
+            pragmas,
+            &crates,
+            &crate_c_cmds,
+        );
+        root_files.extend(root_file.map(|file| (crate_dir, file)));
+    }
+    root_files
+}
+
+/// Emit `Cargo.toml`, `build.rs` and the root file of `krate` in `crate_dir`. Returns the path
+/// to the root file, or `None` if it existed already.
+fn emit_crate(
+    tcfg: &TranspilerConfig,
+    reg: &Handlebars,
+    crate_dir: &Path,
+    krate: &CrateConfig,
+    modules: Vec<PathBuf>,
+    pragmas: PragmaSet,
+    crates: &CrateSet,
+    c_cmds: &[CompileCmd],
+) -> Option<PathBuf> {
+    let trampolines: Vec<PathBuf> = modules
+        .iter()
+        .map(|module| get_va_trampoline_path(module))
+        .filter(|path| path.exists())
+        .collect();
+    let build_c = !c_cmds.is_empty() || !trampolines.is_empty();
+    emit_cargo_toml(tcfg, reg, crate_dir, krate, crates, build_c);
+    emit_build_rs(tcfg, reg, crate_dir, c_cmds, &trampolines);
+    emit_lib_rs(tcfg, reg, crate_dir, krate, modules, pragmas, crates)
+}
+
+/// The crate name for the output of `cmd`, e.g. `foo` for `libfoo.so.1`
+fn get_target_name(cmd: &LinkCmd) -> String {
+    let file_name = cmd.output.file_name().unwrap().to_string_lossy();
+    let name = match cmd.kind {
+        LinkKind::Executable => &*file_name,
+        LinkKind::SharedLibrary | LinkKind::StaticLibrary => {
+            let name = file_name.split('.').next().unwrap();
+            if name.starts_with("lib") && name.len() > 3 {
+                &name[3..]
+            } else {
+                name
+            }
+        }
+    };
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
 }
 
 #[derive(Serialize)]
@@ -89,7 +367,16 @@ struct Module {
     name: String,
 }
 
-fn get_root_rs_file_name(tcfg: &TranspilerConfig) -> &str {
+/// A translation unit compiled from C with the `cc` crate. Paths and flags are Rust string
+/// literals.
+#[derive(Serialize)]
+struct CUnit {
+    file: String,
+    flags: Vec<String>,
+    lib_name: String,
+}
+
+fn get_root_rs_file_name(tcfg: &TranspilerConfig) -> &'static str {
     match (&tcfg.main, &tcfg.output_dir) {
         (Some(_), None) => "c2rust-main.rs",
         (None, None) => "c2rust-lib.rs",
@@ -120,20 +407,61 @@ fn get_module_name(main: &Option<String>) -> Option<String> {
     None
 }
 
-/// Emit `build.rs` to make it easier to link in native libraries
-fn emit_build_rs(tcfg: &TranspilerConfig, reg: &Handlebars, build_dir: &Path) -> Option<PathBuf> {
-    let json = json!({});
+/// Emit `build.rs` to make it easier to link in native libraries, and to compile the
+/// translation units of `c_cmds` and the trampolines of variadic functions
+fn emit_build_rs(
+    tcfg: &TranspilerConfig,
+    reg: &Handlebars,
+    build_dir: &Path,
+    c_cmds: &[CompileCmd],
+    trampolines: &[PathBuf],
+) -> Option<PathBuf> {
+    let c_units = c_cmds
+        .iter()
+        .enumerate()
+        .map(|(i, cmd)| {
+            let flags = cmd.build_flags().unwrap_or_else(|e| {
+                warn!("Compiling {} without its flags: {}", cmd.file.display(), e);
+                vec![]
+            });
+            // Each translation unit gets a library of its own, since they may need different
+            // flags
+            CUnit {
+                file: format!("{:?}", cmd.abs_file().display().to_string()),
+                flags: flags.iter().map(|flag| format!("{:?}", flag)).collect(),
+                lib_name: format!("{}_{}", get_lib_stem(&cmd.file), i),
+            }
+        })
+        .chain(trampolines.iter().map(|path| CUnit {
+            file: format!("{:?}", path.display().to_string()),
+            flags: vec![],
+            lib_name: format!("{}_va_trampolines", get_lib_stem(path)),
+        }))
+        .collect::<Vec<_>>();
+
+    let json = json!({
+        "c_units": c_units,
+    });
     let output = reg.render("build.rs", &json).unwrap();
     let output_path = build_dir.join("build.rs");
     maybe_write_to_file(&output_path, output, tcfg.overwrite_existing)
 }
 
+/// The file stem of `path`, usable in the name of a native library
+fn get_lib_stem(path: &Path) -> String {
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
 /// Emit lib.rs (main.rs) for a library (binary). Returns `Some(path)`
 /// to the generated file or `None` if the output file exists.
 fn emit_lib_rs(
     tcfg: &TranspilerConfig,
     reg: &Handlebars,
     build_dir: &Path,
+    krate: &CrateConfig,
     modules: Vec<PathBuf>,
     pragmas: PragmaSet,
     crates: &CrateSet,
@@ -156,7 +484,18 @@ fn emit_lib_rs(
         })
         .collect::<Vec<_>>();
 
-    let file_name = get_root_rs_file_name(tcfg);
+    let crates: Vec<String> = crates
+        .iter()
+        .map(|krate| krate.to_string())
+        .chain(
+            krate
+                .path_deps
+                .iter()
+                .map(|(name, _)| name.replace('-', "_")),
+        )
+        .collect();
+
+    let file_name = krate.root_rs_file;
     let rs_xcheck_backend = tcfg.cross_check_backend.replace("-", "_");
     let json = json!({
         "root_rs_file": file_name,
@@ -164,7 +503,7 @@ fn emit_lib_rs(
         "translate_valist": tcfg.translate_valist,
         "cross_checks": tcfg.cross_checks,
         "cross_check_backend": rs_xcheck_backend,
-        "main_module": get_module_name(&tcfg.main),
+        "main_module": get_module_name(&krate.main),
         "plugin_args": plugin_args,
         "modules": modules,
         "pragmas": pragmas,
@@ -185,19 +524,58 @@ fn emit_rust_toolchain(tcfg: &TranspilerConfig, build_dir: &Path) {
     maybe_write_to_file(&output_path, output, tcfg.overwrite_existing);
 }
 
-fn emit_cargo_toml(tcfg: &TranspilerConfig, reg: &Handlebars, build_dir: &Path, crates: &CrateSet) {
+/// Runtime support crates of C2Rust that translated code may use
+const RUNTIME_CRATES: &[&str] = &["c2rust-setjmp", "c2rust-f80", "c2rust-va-list"];
+
+/// The repository of C2Rust, which holds the runtime support crates
+const C2RUST_REPOSITORY: &str = "https://github.com/immunant/c2rust";
+
+/// The dependency on the runtime support crate `name`. These crates aren't published on
+/// crates.io, so they come from the source tree the transpiler was built from if it still
+/// exists, and from the C2Rust repository otherwise.
+fn runtime_dep(name: &str) -> serde_json::Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(name);
+    match path.canonicalize() {
+        Ok(path) => json!({ "name": name, "source": "path", "location": path }),
+        Err(_) => json!({ "name": name, "source": "git", "location": C2RUST_REPOSITORY }),
+    }
+}
+
+fn emit_cargo_toml(
+    tcfg: &TranspilerConfig,
+    reg: &Handlebars,
+    build_dir: &Path,
+    krate: &CrateConfig,
+    crates: &CrateSet,
+    build_c: bool,
+) {
+    let path_deps: Vec<_> = krate
+        .path_deps
+        .iter()
+        .map(|(name, path)| json!({ "name": name, "path": path }))
+        .collect();
+    let runtime_deps: Vec<_> = RUNTIME_CRATES
+        .iter()
+        .filter(|name| crates.contains(name.replace('-', "_").as_str()))
+        .map(|name| runtime_dep(name))
+        .collect();
+
     // rust_checks_path is gone because we don't want to refer to the source
     // path but instead want the cross-check libs to be installed via cargo.
     let json = json!({
-        "crate_name": tcfg.output_dir.as_ref().and_then(
-            |x| x.file_name().map(|x| x.to_string_lossy())
-        ).unwrap_or("c2rust".into()),
-        "root_rs_file": get_root_rs_file_name(tcfg),
-        "main_module": tcfg.main.is_some(),
+        "crate_name": krate.name,
+        "root_rs_file": krate.root_rs_file,
+        "main_module": krate.main.is_some(),
+        "crate_type": krate.crate_type,
+        "standalone": krate.standalone,
+        "path_deps": path_deps,
+        "runtime_deps": runtime_deps,
         "cross_checks": tcfg.cross_checks,
         "cross_check_backend": tcfg.cross_check_backend,
         "c2rust_bitfields": crates.contains("c2rust_bitfields"),
         "f128": crates.contains("f128"),
+        "num_complex": crates.contains("num_complex"),
+        "cc": build_c,
     });
     let file_name = "Cargo.toml";
     let output_path = build_dir.join(file_name);
@@ -222,3 +600,111 @@ fn maybe_write_to_file(output_path: &Path, output: String, overwrite: bool) -> O
 
     Some(PathBuf::from(output_path))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runtime_deps() {
+        // Tests run in the source tree, so the runtime crates are next to this one
+        let dep = runtime_dep("c2rust-setjmp");
+        assert_eq!(dep["source"], "path");
+        let path = PathBuf::from(dep["location"].as_str().unwrap());
+        assert!(path.join("Cargo.toml").is_file());
+
+        let dep = runtime_dep("c2rust-unknown");
+        assert_eq!(dep["source"], "git");
+        assert_eq!(dep["location"], C2RUST_REPOSITORY);
+    }
+
+    #[test]
+    fn workspace() {
+        let build_dir =
+            std::env::temp_dir().join(format!("c2rust-transpile-workspace-{}", std::process::id()));
+        fs::create_dir_all(&build_dir).unwrap();
+        let src = build_dir.join("src");
+        let module = |name: &str, has_main| TranslatedModule {
+            input: src.join(format!("{}.c", name)),
+            path: src.join(format!("{}.rs", name)),
+            pragmas: PragmaSet::new(),
+            crates: CrateSet::new(),
+            has_main,
+        };
+        let modules = vec![module("util", false), module("a", true), module("b", true)];
+        // Both programs link `util.c`, which goes into the common crate
+        let link_cmds: Vec<LinkCmd> = serde_json::from_value(json!([
+            {
+                "output": build_dir.join("prog-a"),
+                "kind": "executable",
+                "files": [src.join("util.c"), src.join("a.c")],
+            },
+            {
+                "output": build_dir.join("prog-b"),
+                "kind": "executable",
+                "files": [src.join("b.c"), src.join("util.c")],
+            },
+        ]))
+        .unwrap();
+
+        let tcfg = TranspilerConfig::default();
+        let root_files = emit_workspace(&tcfg, &build_dir, &link_cmds, modules, &[]);
+        let read = |path: &str| fs::read_to_string(build_dir.join(path)).unwrap();
+
+        let expected: Vec<(PathBuf, PathBuf)> = vec![
+            ("common", "lib.rs"),
+            ("prog-a", "main.rs"),
+            ("prog-b", "main.rs"),
+        ]
+        .into_iter()
+        .map(|(name, root)| (build_dir.join(name), build_dir.join(name).join(root)))
+        .collect();
+        assert_eq!(root_files, expected);
+
+        let workspace = read("Cargo.toml");
+        for member in &["common", "prog-a", "prog-b"] {
+            assert!(
+                workspace.contains(&format!("\"{}\",", member)),
+                "{}",
+                workspace
+            );
+        }
+
+        let common = read("common/lib.rs");
+        assert!(common.contains("pub mod util;"), "{}", common);
+        assert!(!common.contains("pub mod a;") && !common.contains("pub mod b;"));
+        assert!(read("common/Cargo.toml").contains("crate-type = [\"rlib\"]"));
+
+        for (name, own, other) in &[("prog-a", "a", "b"), ("prog-b", "b", "a")] {
+            let cargo_toml = read(&format!("{}/Cargo.toml", name));
+            assert!(cargo_toml.contains("[[bin]]"), "{}", cargo_toml);
+            assert!(
+                cargo_toml.contains("common = { path = \"../common\" }"),
+                "{}",
+                cargo_toml
+            );
+
+            let main_rs = read(&format!("{}/main.rs", name));
+            assert!(main_rs.contains("extern crate common;"), "{}", main_rs);
+            assert!(
+                main_rs.contains(&format!("pub mod {};", own)),
+                "{}",
+                main_rs
+            );
+            assert!(
+                !main_rs.contains(&format!("pub mod {};", other)),
+                "{}",
+                main_rs
+            );
+            assert!(!main_rs.contains("pub mod util;"), "{}", main_rs);
+            // The main function is taken from the program's own module, not from `util`
+            assert!(
+                main_rs.contains(&format!("fn main() {{ {}::main() }}", own)),
+                "{}",
+                main_rs
+            );
+        }
+
+        fs::remove_dir_all(&build_dir).unwrap();
+    }
+}
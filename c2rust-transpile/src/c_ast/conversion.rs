@@ -145,6 +145,7 @@ fn parse_cast_kind(kind: &str) -> CastKind {
         "FloatingComplexToReal" => CastKind::FloatingComplexToReal,
         "FloatingComplexCast" => CastKind::FloatingComplexCast,
         "FloatingComplexToIntegralComplex" => CastKind::FloatingComplexToIntegralComplex,
+        "FloatingComplexToBoolean" => CastKind::FloatingComplexToBoolean,
         "IntegralRealToComplex" => CastKind::IntegralRealToComplex,
         "IntegralComplexToReal" => CastKind::IntegralComplexToReal,
         "IntegralComplexToBoolean" => CastKind::IntegralComplexToBoolean,
@@ -153,6 +154,8 @@ fn parse_cast_kind(kind: &str) -> CastKind {
         "BuiltinFnToFnPtr" => CastKind::BuiltinFnToFnPtr,
         "ConstCast" => CastKind::ConstCast,
         "VectorSplat" => CastKind::VectorSplat,
+        "AtomicToNonAtomic" => CastKind::AtomicToNonAtomic,
+        "NonAtomicToAtomic" => CastKind::NonAtomicToAtomic,
         k => panic!("Unsupported implicit cast: {}", k),
     }
 }
@@ -527,6 +530,15 @@ impl ConversionContext {
                     self.processed_nodes.insert(new_id, OTHER_TYPE);
                 }
 
+                TypeTag::TagAtomicType if expected_ty & OTHER_TYPE != 0 => {
+                    let value = ty_node.extras[0].as_u64().expect("Atomic child not found");
+                    let value_new = self.visit_type(value);
+
+                    let atomic_ty = CTypeKind::Atomic(value_new);
+                    self.add_type(new_id, not_located(atomic_ty));
+                    self.processed_nodes.insert(new_id, OTHER_TYPE);
+                }
+
                 TypeTag::TagStructType if expected_ty & OTHER_TYPE != 0 => {
                     let decl = ty_node.extras[0].as_u64().expect("Struct decl not found");
                     let decl_new = CDeclId(self.visit_node_type(decl, RECORD_DECL));
@@ -807,6 +819,16 @@ impl ConversionContext {
                     self.processed_nodes.insert(new_id, OTHER_STMT);
                 }
 
+                ASTEntryTag::TagIndirectGotoStmt if expected_ty & OTHER_STMT != 0 => {
+                    let target_old = node.children[0].expect("IndirectGoto target not found");
+                    let target = self.visit_expr(target_old);
+
+                    let goto_stmt = CStmtKind::IndirectGoto(target);
+
+                    self.add_stmt(new_id, located(node, goto_stmt));
+                    self.processed_nodes.insert(new_id, OTHER_STMT);
+                }
+
                 ASTEntryTag::TagNullStmt if expected_ty & OTHER_STMT != 0 => {
                     let null_stmt = CStmtKind::Empty;
 
@@ -1071,6 +1093,16 @@ impl ConversionContext {
                     self.expr_possibly_as_stmt(expected_ty, new_id, node, floating_literal);
                 }
 
+                ASTEntryTag::TagImaginaryLiteral if expected_ty & (EXPR | STMT) != 0 => {
+                    let real = node.children[0].expect("Expected real part of imaginary literal");
+                    let ty_old = node.type_id.expect("Expected expression to have type");
+                    let ty = self.visit_qualified_type(ty_old);
+
+                    let imaginary_literal = CExprKind::ImaginaryLiteral(ty, self.visit_expr(real));
+
+                    self.expr_possibly_as_stmt(expected_ty, new_id, node, imaginary_literal);
+                }
+
                 ASTEntryTag::TagUnaryOperator if expected_ty & (EXPR | STMT) != 0 => {
                     let prefix = node.extras[1]
                         .as_boolean()
@@ -1548,7 +1580,7 @@ impl ConversionContext {
                     let true_expr = self.visit_expr(true_expr);
 
                     let false_expr =
-                        node.children[1].expect("ChooseExpr false expression not found");
+                        node.children[2].expect("ChooseExpr false expression not found");
                     let false_expr = self.visit_expr(false_expr);
 
                     let ty = node.type_id.expect("Expected expression to have type");
@@ -1570,6 +1602,91 @@ impl ConversionContext {
                     self.expr_possibly_as_stmt(expected_ty, new_id, node, e)
                 }
 
+                ASTEntryTag::TagGenericSelectionExpr => {
+                    let controlling = node.children[0]
+                        .expect("GenericSelectionExpr controlling expression not found");
+                    let controlling = self.visit_expr(controlling);
+
+                    let selected = node.children[1]
+                        .expect("GenericSelectionExpr selected expression not found");
+                    let selected = self.visit_expr(selected);
+
+                    let assoc_ty = node.extras[0]
+                        .as_u64()
+                        .map(|assoc_ty| self.visit_qualified_type(assoc_ty));
+
+                    let ty = node.type_id.expect("Expected expression to have type");
+                    let ty = self.visit_qualified_type(ty);
+
+                    let e = CExprKind::GenericSelection(ty, controlling, assoc_ty, selected);
+
+                    self.expr_possibly_as_stmt(expected_ty, new_id, node, e)
+                }
+
+                ASTEntryTag::TagTypesCompatibleExpr => {
+                    let lhs = node.extras[0].as_u64().expect("Expected first type argument");
+                    let lhs = self.visit_qualified_type(lhs);
+
+                    let rhs = node.extras[1].as_u64().expect("Expected second type argument");
+                    let rhs = self.visit_qualified_type(rhs);
+
+                    let compatible = node.extras[2]
+                        .as_boolean()
+                        .expect("Expected type compatibility");
+
+                    let ty = node.type_id.expect("Expected expression to have type");
+                    let ty = self.visit_qualified_type(ty);
+
+                    let e = CExprKind::TypesCompatible(ty, lhs, rhs, compatible);
+
+                    self.expr_possibly_as_stmt(expected_ty, new_id, node, e)
+                }
+
+                ASTEntryTag::TagAddrLabelExpr => {
+                    let label_old = node.children[0].expect("AddrLabelExpr label not found");
+                    let label = CStmtId(self.visit_node_type(label_old, LABEL_STMT));
+                    self.typed_context.address_taken_labels.insert(label);
+
+                    let ty = node.type_id.expect("Expected expression to have type");
+                    let ty = self.visit_qualified_type(ty);
+
+                    let e = CExprKind::AddrLabel(ty, label);
+
+                    self.expr_possibly_as_stmt(expected_ty, new_id, node, e)
+                }
+
+                ASTEntryTag::TagAtomicExpr => {
+                    let ptr = node.children[0].expect("AtomicExpr pointer not found");
+                    let ptr = self.visit_expr(ptr);
+
+                    let order = node.children[1].map(|id| self.visit_expr(id));
+                    let val1 = node.children[2].map(|id| self.visit_expr(id));
+                    let order_fail = node.children[3].map(|id| self.visit_expr(id));
+                    let val2 = node.children[4].map(|id| self.visit_expr(id));
+                    let weak = node.children[5].map(|id| self.visit_expr(id));
+
+                    let name = node.extras[0]
+                        .as_string()
+                        .expect("Expected atomic builtin name")
+                        .to_owned();
+
+                    let ty = node.type_id.expect("Expected expression to have type");
+                    let typ = self.visit_qualified_type(ty);
+
+                    let e = CExprKind::Atomic {
+                        typ,
+                        name,
+                        ptr,
+                        order,
+                        val1,
+                        order_fail,
+                        val2,
+                        weak,
+                    };
+
+                    self.expr_possibly_as_stmt(expected_ty, new_id, node, e)
+                }
+
                 // Declarations
                 ASTEntryTag::TagFunctionDecl if expected_ty & OTHER_DECL != 0 => {
                     let name = node.extras[0]
@@ -1819,6 +1936,16 @@ impl ConversionContext {
                     let has_def = node.extras[1]
                         .as_boolean()
                         .expect("Expected has_def flag on struct");
+                    let attrs = node.extras[2]
+                        .as_array()
+                        .expect("Expected attribute array on record");
+                    let manual_alignment =
+                        expect_opt_u64(&node.extras[3]).expect("Expected union alignment");
+                    let max_field_alignment =
+                        expect_opt_u64(&node.extras[4]).expect("Expected union field align");
+                    let platform_byte_size = node.extras[5].as_u64().expect("Expected union size");
+                    let platform_alignment =
+                        node.extras[6].as_u64().expect("Expected union alignment");
                     let fields: Option<Vec<CDeclId>> = if has_def {
                         Some(
                             node.children
@@ -1835,7 +1962,19 @@ impl ConversionContext {
                         None
                     };
 
-                    let record = CDeclKind::Union { name, fields };
+                    let is_packed = attrs.iter().any(|attr| {
+                        attr.as_string().expect("Records attributes should be strings") == "packed"
+                    });
+
+                    let record = CDeclKind::Union {
+                        name,
+                        fields,
+                        is_packed,
+                        manual_alignment,
+                        max_field_alignment,
+                        platform_byte_size,
+                        platform_alignment,
+                    };
 
                     self.add_decl(new_id, located(node, record));
                     self.processed_nodes.insert(new_id, RECORD_DECL);
@@ -1893,6 +2032,69 @@ impl ConversionContext {
                     self.typed_context.c_decls_top.push(CDeclId(new_id));
                 }
 
+                ASTEntryTag::TagMacroFunctionDef if expected_ty & MACRO_DECL != 0 => {
+                    let name = node.extras[0]
+                        .as_string()
+                        .expect("Macros must have a name")
+                        .to_owned();
+
+                    let params = node.extras[1]
+                        .as_array()
+                        .expect("Expected macro parameters")
+                        .iter()
+                        .map(|param| {
+                            param
+                                .as_string()
+                                .expect("Macro parameters must have a name")
+                                .to_owned()
+                        })
+                        .collect();
+
+                    let arguments = node.extras[2]
+                        .as_array()
+                        .expect("Expected macro expansion arguments");
+
+                    let expansions = node
+                        .children
+                        .iter()
+                        .zip(arguments)
+                        .map(|(id, args)| {
+                            let expr_id = id.expect("Macro replacement expr not found");
+                            let replacement = self.visit_expr(expr_id);
+                            let args = args
+                                .as_array()
+                                .expect("Expected macro arguments")
+                                .iter()
+                                .map(|occurrences| {
+                                    occurrences
+                                        .as_array()
+                                        .expect("Expected macro argument occurrences")
+                                        .iter()
+                                        .map(|arg| {
+                                            let arg_id = arg
+                                                .as_u64()
+                                                .expect("Macro argument expr not found");
+                                            self.visit_expr(arg_id)
+                                        })
+                                        .collect()
+                                })
+                                .collect();
+                            CMacroExpansion { replacement, args }
+                        })
+                        .collect();
+
+                    let mac_function = CDeclKind::MacroFunction {
+                        name,
+                        params,
+                        expansions,
+                    };
+                    self.add_decl(new_id, located(node, mac_function));
+                    self.processed_nodes.insert(new_id, MACRO_DECL);
+
+                    // See above
+                    self.typed_context.c_decls_top.push(CDeclId(new_id));
+                }
+
                 t => panic!("Could not translate node {:?} as type {}", t, expected_ty),
             }
         }
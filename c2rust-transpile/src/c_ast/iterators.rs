@@ -1,6 +1,6 @@
 use crate::c_ast::*;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum SomeId {
     Stmt(CStmtId),
     Expr(CExprId),
@@ -36,14 +36,21 @@ macro_rules! intos {
     ( $( $x:expr ),* ) => { vec![ $( $x.into(), )* ] };
 }
 
+fn atomic_children(ptr: CExprId, operands: &[Option<CExprId>]) -> Vec<SomeId> {
+    let mut res = intos![ptr];
+    res.extend(operands.iter().filter_map(|&x| x).map(SomeId::from));
+    res
+}
+
 fn immediate_expr_children(kind: &CExprKind) -> Vec<SomeId> {
     use crate::c_ast::CExprKind::*;
     match *kind {
         BadExpr => vec![],
         DesignatedInitExpr(..) => vec![], // the relevant information will be found in the semantic initializer
         ShuffleVector(..) | ConvertVector(..) => vec![],
-        OffsetOf(..) | Literal(..) | ImplicitValueInit(..) => vec![],
-        DeclRef(..) => vec![], // don't follow references back!
+        OffsetOf(..) | Literal(..) | ImplicitValueInit(..) | TypesCompatible(..) => vec![],
+        DeclRef(..) => vec![],   // don't follow references back!
+        AddrLabel(..) => vec![], // don't follow the reference to the label
         Unary(_ty, _op, subexpr, _) => intos![subexpr],
         UnaryType(_ty, _op, opt_expr_id, _) => opt_expr_id.iter().map(|&x| x.into()).collect(),
         Binary(_ty, _op, lhs, rhs, _, _) => intos![lhs, rhs],
@@ -57,13 +64,25 @@ fn immediate_expr_children(kind: &CExprKind) -> Vec<SomeId> {
         ArraySubscript(_, l, r, _) => intos![l, r],
         Conditional(_, c, t, e)
         | Choose(_, c, t, e, _) => intos![c, t, e],
+        // The controlling expression is never evaluated
+        GenericSelection(_, _, _, e) => intos![e],
         BinaryConditional(_, c, t) => intos![c, t],
         InitList(_, ref xs, _, _) => xs.iter().map(|&x| x.into()).collect(),
+        Atomic {
+            ptr,
+            order,
+            val1,
+            order_fail,
+            val2,
+            weak,
+            ..
+        } => atomic_children(ptr, &[order, val1, order_fail, val2, weak]),
         ImplicitCast(_, e, _, _, _)
         | ExplicitCast(_, e, _, _, _)
         | Member(_, e, _, _, _)
         | Paren(_, e)
         | CompoundLiteral(_, e)
+        | ImaginaryLiteral(_, e)
         | Predefined(_, e)
         | VAArg(_, e) => intos![e],
         Statements(_, s) => vec![s.into()],
@@ -81,8 +100,9 @@ fn immediate_expr_children_all_types(kind: &CExprKind) -> Vec<SomeId> {
         // We need to iterate the struct type if this offsetof is variable,
         // since it may not get instantiated
         OffsetOf(_, OffsetOfKind::Variable(qty, _, _)) => intos![qty.ctype],
-        OffsetOf(..) | Literal(..) | ImplicitValueInit(..) => vec![],
-        DeclRef(..) => vec![], // don't follow references back!
+        OffsetOf(..) | Literal(..) | ImplicitValueInit(..) | TypesCompatible(..) => vec![],
+        DeclRef(..) => vec![],   // don't follow references back!
+        AddrLabel(..) => vec![], // don't follow the reference to the label
         Unary(_ty, _op, subexpr, _) => intos![subexpr],
         UnaryType(_ty, _op, opt_expr_id, qty) => {
             let mut res = intos![qty.ctype];
@@ -102,8 +122,19 @@ fn immediate_expr_children_all_types(kind: &CExprKind) -> Vec<SomeId> {
         ArraySubscript(_, l, r, _) => intos![l, r],
         Conditional(_, c, t, e)
         | Choose(_, c, t, e, _) => intos![c, t, e],
+        // The controlling expression is never evaluated
+        GenericSelection(_, _, _, e) => intos![e],
         BinaryConditional(_, c, t) => intos![c, t],
         InitList(_, ref xs, _, _) => xs.iter().map(|&x| x.into()).collect(),
+        Atomic {
+            ptr,
+            order,
+            val1,
+            order_fail,
+            val2,
+            weak,
+            ..
+        } => atomic_children(ptr, &[order, val1, order_fail, val2, weak]),
         Member(_, e, _, _, _) | Predefined(_, e) => intos![e],
         // Normally we don't step into the result type annotation field, because it's not really
         // part of the expression.  But for `ExplicitCast`, the result type is actually the cast's
@@ -112,6 +143,7 @@ fn immediate_expr_children_all_types(kind: &CExprKind) -> Vec<SomeId> {
         | ImplicitCast(qty, e, _, _, _)
         | Paren(qty, e)
         | CompoundLiteral(qty, e)
+        | ImaginaryLiteral(qty, e)
         | VAArg(qty, e) => {
             intos![qty.ctype, e]
         }
@@ -161,6 +193,16 @@ fn immediate_decl_children(kind: &CDeclKind) -> Vec<SomeId> {
         MacroObject {
             ref replacements, ..
         } => replacements.iter().map(|&x| x.into()).collect(),
+        MacroFunction {
+            ref expansions, ..
+        } => {
+            let mut res = vec![];
+            for expansion in expansions {
+                res.push(expansion.replacement.into());
+                res.extend(expansion.args.iter().flatten().map(|&x| -> SomeId { x.into() }));
+            }
+            res
+        }
     }
 }
 
@@ -219,6 +261,7 @@ fn immediate_stmt_children(kind: &CStmtKind) -> Vec<SomeId> {
             res
         }
         Goto(_) => vec![], // Don't follow the reference to the label
+        IndirectGoto(e) => intos![e],
         Break => vec![],
         Continue => vec![],
         Return(ref opt_e) => opt_e.iter().map(|&x| x.into()).collect(),
@@ -259,6 +302,7 @@ fn immediate_type_children(kind: &CTypeKind) -> Vec<SomeId> {
         | Paren(ctype)
         | TypeOf(ctype)
         | Complex(ctype)
+        | Atomic(ctype)
         | ConstantArray(ctype, _)
         | IncompleteArray(ctype) => intos![ctype],
 
@@ -58,12 +58,15 @@ pub struct TypedAstContext {
     // The key is the typedef decl being squashed away,
     // and the value is the decl id to the corresponding structure
     pub prenamed_decls: IndexMap<CDeclId, CDeclId>,
+
+    // Labels whose address is taken with the GNU `&&label` extension
+    pub address_taken_labels: IndexSet<CLabelId>,
 }
 
 /// Comments associated with a typed AST context
 #[derive(Debug, Clone)]
 pub struct CommentContext {
-    decl_comments: HashMap<CDeclId, Vec<String>>,
+    decl_comments: HashMap<CDeclId, Vec<(SrcLoc, String)>>,
     stmt_comments: HashMap<CStmtId, Vec<String>>,
 }
 
@@ -83,6 +86,7 @@ impl TypedAstContext {
 
             comments: vec![],
             prenamed_decls: IndexMap::new(),
+            address_taken_labels: IndexSet::new(),
         }
     }
 
@@ -94,10 +98,22 @@ impl TypedAstContext {
         self.c_decls.iter_mut()
     }
 
+    pub fn iter_exprs(&self) -> std::collections::hash_map::Iter<CExprId, CExpr> {
+        self.c_exprs.iter()
+    }
+
     pub fn get_decl(&self, key: &CDeclId) -> Option<&CDecl> {
         self.c_decls.get(key)
     }
 
+    /// The integer that stands in for the address of a label taken with `&&label`. Tags start at
+    /// 1 so that they never compare equal to a null pointer.
+    pub fn label_address_tag(&self, label_id: CLabelId) -> Option<u64> {
+        self.address_taken_labels
+            .get_full(&label_id)
+            .map(|(index, _)| index as u64 + 1)
+    }
+
     pub fn is_null_expr(&self, expr_id: CExprId) -> bool {
         match self[expr_id].kind {
             CExprKind::ExplicitCast(_, _, CastKind::NullToPointer, _, _)
@@ -156,6 +172,66 @@ impl TypedAstContext {
         }
     }
 
+    /// Get the packing of a record in bytes, if it has any effect. This is 1 for
+    /// `__attribute__((packed))` and N for `#pragma pack(N)`.
+    pub fn record_packing(&self, decl: CRecordId) -> Option<u64> {
+        match self.index(decl).kind {
+            CDeclKind::Struct {
+                is_packed,
+                max_field_alignment,
+                platform_alignment,
+                ..
+            }
+            | CDeclKind::Union {
+                is_packed,
+                max_field_alignment,
+                platform_alignment,
+                ..
+            } => {
+                if is_packed {
+                    Some(1)
+                } else {
+                    // `#pragma pack(N)` doesn't change the layout of records that are already
+                    // aligned to at most N bytes.
+                    max_field_alignment.filter(|&pack| pack <= platform_alignment)
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Is the given record packed? Rust must not take references to the fields of such records
+    /// since they might not be aligned.
+    pub fn is_packed_record(&self, decl: CRecordId) -> bool {
+        self.record_packing(decl).is_some()
+    }
+
+    /// Is the given field declared in a packed record?
+    pub fn is_packed_field(&self, field: CFieldId) -> bool {
+        self.parents
+            .get(&field)
+            .map_or(false, |&record| self.is_packed_record(record))
+    }
+
+    /// Does the given record need to be both packed and over-aligned? Rust doesn't allow
+    /// `#[repr(packed)]` and `#[repr(align)]` on the same type, so we translate these records
+    /// into an aligned wrapper around a packed inner record.
+    pub fn has_inner_struct_decl(&self, decl: CRecordId) -> bool {
+        let manual_alignment = match self.index(decl).kind {
+            CDeclKind::Struct {
+                manual_alignment, ..
+            }
+            | CDeclKind::Union {
+                manual_alignment, ..
+            } => manual_alignment,
+            _ => None,
+        };
+        match (self.record_packing(decl), manual_alignment) {
+            (Some(packing), Some(alignment)) => alignment > packing,
+            _ => false,
+        }
+    }
+
     pub fn get_pointee_qual_type(&self, typ: CTypeId) -> Option<CQualTypeId> {
         let resolved_ctype = self.resolve_type(typ);
         if let CTypeKind::Pointer(p) = resolved_ctype.kind {
@@ -170,6 +246,7 @@ impl TypedAstContext {
             CTypeKind::Attributed(ty, _) => self.resolve_type_id(ty.ctype),
             CTypeKind::Elaborated(ty) => self.resolve_type_id(ty),
             CTypeKind::Decayed(ty) => self.resolve_type_id(ty),
+            CTypeKind::Atomic(ty) => self.resolve_type_id(ty),
             CTypeKind::TypeOf(ty) => self.resolve_type_id(ty),
             CTypeKind::Paren(ty) => self.resolve_type_id(ty),
             CTypeKind::Typedef(decl) => match self.index(decl).kind {
@@ -180,6 +257,22 @@ impl TypedAstContext {
         }
     }
 
+    /// Is `typ` an `_Atomic` qualified type, possibly behind typedefs?
+    pub fn is_atomic_type(&self, typ: CTypeId) -> bool {
+        match self.index(typ).kind {
+            CTypeKind::Atomic(_) => true,
+            CTypeKind::Attributed(ty, _) => self.is_atomic_type(ty.ctype),
+            CTypeKind::Elaborated(ty) | CTypeKind::TypeOf(ty) | CTypeKind::Paren(ty) => {
+                self.is_atomic_type(ty)
+            }
+            CTypeKind::Typedef(decl) => match self.index(decl).kind {
+                CDeclKind::Typedef { typ: ty, .. } => self.is_atomic_type(ty.ctype),
+                _ => panic!("Typedef decl did not point to a typedef"),
+            },
+            _ => false,
+        }
+    }
+
     pub fn resolve_type(&self, typ: CTypeId) -> &CType {
         let resolved_typ_id = self.resolve_type_id(typ);
         self.index(resolved_typ_id)
@@ -202,12 +295,15 @@ impl TypedAstContext {
             CExprKind::ImplicitValueInit { .. } |
             CExprKind::Predefined(..) |
             CExprKind::Statements(..) | // TODO: more precision
-            CExprKind::VAArg(..) => false,
+            CExprKind::VAArg(..) |
+            CExprKind::Atomic { .. } => false,
 
             CExprKind::Literal(_, _) |
             CExprKind::DeclRef(_, _, _) |
             CExprKind::UnaryType(_, _, _, _) |
-            CExprKind::OffsetOf(..) => true,
+            CExprKind::OffsetOf(..) |
+            CExprKind::TypesCompatible(..) |
+            CExprKind::AddrLabel(..) => true,
 
             CExprKind::DesignatedInitExpr(_,_,e) |
             CExprKind::ImplicitCast(_, e, _, _, _) |
@@ -215,6 +311,7 @@ impl TypedAstContext {
             CExprKind::Member(_, e, _, _, _) |
             CExprKind::Paren(_, e) |
             CExprKind::CompoundLiteral(_, e) |
+            CExprKind::ImaginaryLiteral(_, e) |
             CExprKind::Unary(_, _, e, _) => self.is_expr_pure(e),
 
             CExprKind::Binary(_, op, _, _, _, _) if op.underlying_assignment().is_some() => false,
@@ -224,6 +321,8 @@ impl TypedAstContext {
             CExprKind::Conditional(_, c, lhs, rhs) => self.is_expr_pure(c) && self.is_expr_pure(lhs) && self.is_expr_pure(rhs),
             CExprKind::BinaryConditional(_, c, rhs) => self.is_expr_pure(c) && self.is_expr_pure(rhs),
             CExprKind::Choose(_, c, lhs, rhs, _) => self.is_expr_pure(c) && self.is_expr_pure(lhs) && self.is_expr_pure(rhs),
+
+            CExprKind::GenericSelection(_, _, _, e) => self.is_expr_pure(e),
         }
     }
 
@@ -471,7 +570,7 @@ impl CommentContext {
         // Flatten out the nested comment maps
         let decl_comments = decl_comments_map
             .into_iter()
-            .map(|(decl_id, map)| (decl_id, map.into_iter().map(|(_, v)| v).collect()))
+            .map(|(decl_id, map)| (decl_id, map.into_iter().collect()))
             .collect();
         let stmt_comments = stmt_comments_map
             .into_iter()
@@ -486,7 +585,39 @@ impl CommentContext {
 
     // Extract the comment for a given declaration
     pub fn remove_decl_comment(&mut self, decl_id: CDeclId) -> Vec<String> {
-        self.decl_comments.remove(&decl_id).unwrap_or(vec![])
+        self.decl_comments
+            .remove(&decl_id)
+            .map_or(vec![], |comments| {
+                comments.into_iter().map(|(_, v)| v).collect()
+            })
+    }
+
+    /// Extract the comments that document the declaration `decl_id` at `decl_loc`: those that
+    /// directly precede it, each ending on the line before the next one, and those that follow it
+    /// on its first line. Comments separated from the declaration by a blank line are left for
+    /// `remove_decl_comment`.
+    pub fn remove_decl_doc_comment(&mut self, decl_id: CDeclId, decl_loc: &SrcLoc) -> Vec<String> {
+        let comments = match self.decl_comments.get_mut(&decl_id) {
+            Some(comments) => comments,
+            None => return vec![],
+        };
+
+        let mut next_line = decl_loc.line;
+        let mut first_doc = comments.len();
+        while first_doc > 0 {
+            let (ref loc, ref comment) = comments[first_doc - 1];
+            let end_line = loc.line + comment.matches('\n').count() as u64;
+            if end_line + 1 < next_line {
+                break;
+            }
+            next_line = loc.line;
+            first_doc -= 1;
+        }
+        comments
+            .split_off(first_doc)
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect()
     }
 
     // Extract the comment for a given statement
@@ -632,6 +763,11 @@ pub enum CDeclKind {
     Union {
         name: Option<String>,
         fields: Option<Vec<CFieldId>>,
+        is_packed: bool,
+        manual_alignment: Option<u64>,
+        max_field_alignment: Option<u64>,
+        platform_byte_size: u64,
+        platform_alignment: u64,
     },
 
     // Field
@@ -647,6 +783,22 @@ pub enum CDeclKind {
         name: String,
         replacements: Vec<CExprId>,
     },
+
+    MacroFunction {
+        name: String,
+        params: Vec<String>,
+        expansions: Vec<CMacroExpansion>,
+    },
+}
+
+/// A single expansion of a function-like macro
+#[derive(Debug, Clone)]
+pub struct CMacroExpansion {
+    /// The expression the macro expanded to
+    pub replacement: CExprId,
+    /// For each macro parameter, the expressions spelled as its argument. There is one
+    /// expression for each occurrence of the parameter in the macro definition.
+    pub args: Vec<Vec<CExprId>>,
 }
 
 impl CDeclKind {
@@ -667,6 +819,7 @@ impl CDeclKind {
             } => Some(i),
             &CDeclKind::Field { name: ref i, .. } => Some(i),
             &CDeclKind::MacroObject { ref name, .. } => Some(name),
+            &CDeclKind::MacroFunction { ref name, .. } => Some(name),
             _ => None,
         }
     }
@@ -752,6 +905,9 @@ pub enum CExprKind {
     // Compound literal
     CompoundLiteral(CQualTypeId, CExprId),
 
+    // GNU imaginary literal, wrapping the real-valued literal
+    ImaginaryLiteral(CQualTypeId, CExprId),
+
     // Predefined expr
     Predefined(CQualTypeId, CExprId),
 
@@ -771,6 +927,29 @@ pub enum CExprKind {
     // GNU choose expr. Condition, true expr, false expr, was condition true?
     Choose(CQualTypeId, CExprId, CExprId, CExprId, bool),
 
+    // C11 generic selection, already resolved by Clang. Controlling expression (which is never
+    // evaluated), type of the selected association (`None` for `default`), selected expression
+    GenericSelection(CQualTypeId, CExprId, Option<CQualTypeId>, CExprId),
+
+    // GNU `__builtin_types_compatible_p`. Argument types, are they compatible?
+    TypesCompatible(CQualTypeId, CQualTypeId, CQualTypeId, bool),
+
+    // GNU address of label `&&label`
+    AddrLabel(CQualTypeId, CLabelId),
+
+    // C11 or GNU atomic builtin, e.g. `__atomic_fetch_add(ptr, val1, order)`. The operands are
+    // named after Clang's `AtomicExpr`, and are missing when the builtin doesn't take them.
+    Atomic {
+        typ: CQualTypeId,
+        name: String,
+        ptr: CExprId,
+        order: Option<CExprId>,
+        val1: Option<CExprId>,
+        order_fail: Option<CExprId>,
+        val2: Option<CExprId>,
+        weak: Option<CExprId>,
+    },
+
     BadExpr,
 }
 
@@ -813,6 +992,7 @@ impl CExprKind {
             | CExprKind::ImplicitValueInit(ty)
             | CExprKind::Paren(ty, _)
             | CExprKind::CompoundLiteral(ty, _)
+            | CExprKind::ImaginaryLiteral(ty, _)
             | CExprKind::Predefined(ty, _)
             | CExprKind::Statements(ty, _)
             | CExprKind::VAArg(ty, _)
@@ -820,6 +1000,10 @@ impl CExprKind {
             | CExprKind::ConvertVector(ty, _)
             | CExprKind::DesignatedInitExpr(ty, _, _) => Some(ty),
             | CExprKind::Choose(ty, _, _, _, _) => Some(ty),
+            CExprKind::GenericSelection(ty, _, _, _) => Some(ty),
+            CExprKind::TypesCompatible(ty, _, _, _) => Some(ty),
+            CExprKind::AddrLabel(ty, _) => Some(ty),
+            CExprKind::Atomic { typ, .. } => Some(typ),
         }
     }
 
@@ -861,6 +1045,7 @@ pub enum CastKind {
     FloatingComplexToReal,
     FloatingComplexCast,
     FloatingComplexToIntegralComplex,
+    FloatingComplexToBoolean,
     IntegralRealToComplex,
     IntegralComplexToReal,
     IntegralComplexToBoolean,
@@ -869,6 +1054,8 @@ pub enum CastKind {
     BuiltinFnToFnPtr,
     ConstCast,
     VectorSplat,
+    AtomicToNonAtomic,
+    NonAtomicToAtomic,
 }
 
 /// Represents a unary operator in C (6.5.3 Unary operators) and GNU C extensions
@@ -1068,6 +1255,7 @@ pub enum CStmtKind {
 
     // Jump statements (6.8.6)
     Goto(CLabelId),
+    IndirectGoto(CExprId), // GNU computed goto, `goto *expr`
     Break,
     Continue,
     Return(Option<CExprId>),
@@ -1177,6 +1365,9 @@ pub enum CTypeKind {
 
     Complex(CTypeId),
 
+    // Atomic types (6.7.2.4)
+    Atomic(CTypeId),
+
     // Pointer types (6.7.5.1)
     Pointer(CQualTypeId),
 
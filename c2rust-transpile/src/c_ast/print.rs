@@ -212,6 +212,10 @@ impl<W: Write> Printer<W> {
                 self.writer.write_all(b")")?;
                 self.print_expr(val, context)
             }
+            Some(&CExprKind::ImaginaryLiteral(_, val)) => {
+                self.print_expr(val, context)?;
+                self.writer.write_all(b"i")
+            }
             Some(&CExprKind::Predefined(_, val)) => self.print_expr(val, context),
 
             Some(&CExprKind::VAArg(_, val)) => self.print_expr(val, context),
@@ -226,6 +230,52 @@ impl<W: Write> Printer<W> {
                 self.writer.write_all(b")")
             }
 
+            Some(&CExprKind::GenericSelection(_, controlling, assoc_ty, selected)) => {
+                self.writer.write_all(b"_Generic(")?;
+                self.print_expr(controlling, context)?;
+                self.writer.write_all(b", ")?;
+                match assoc_ty {
+                    Some(assoc_ty) => self.print_qtype(assoc_ty, None, context)?,
+                    None => self.writer.write_all(b"default")?,
+                }
+                self.writer.write_all(b": ")?;
+                self.print_expr(selected, context)?;
+                self.writer.write_all(b")")
+            }
+
+            Some(&CExprKind::TypesCompatible(_, lhs, rhs, _)) => {
+                self.writer.write_all(b"__builtin_types_compatible_p(")?;
+                self.print_qtype(lhs, None, context)?;
+                self.writer.write_all(b", ")?;
+                self.print_qtype(rhs, None, context)?;
+                self.writer.write_all(b")")
+            }
+
+            Some(&CExprKind::AddrLabel(_, label)) => {
+                self.writer.write_fmt(format_args!("&&label_{}", label.0))
+            }
+
+            Some(&CExprKind::Atomic {
+                ref name,
+                ptr,
+                order,
+                val1,
+                order_fail,
+                val2,
+                weak,
+                ..
+            }) => {
+                self.writer.write_fmt(format_args!("{}(", name))?;
+                self.print_expr(ptr, context)?;
+                for &arg in [val1, val2, weak, order, order_fail].iter() {
+                    if let Some(arg) = arg {
+                        self.writer.write_all(b", ")?;
+                        self.print_expr(arg, context)?;
+                    }
+                }
+                self.writer.write_all(b")")
+            }
+
             None => panic!("Could not find expression with ID {:?}", expr_id),
             // _ => unimplemented!("Printer::print_expr"),
         }
@@ -702,6 +752,20 @@ impl<W: Write> Printer<W> {
                 Ok(())
             }
 
+            Some(&CDeclKind::MacroFunction {
+                ref name,
+                ref params,
+                ref expansions,
+            }) => {
+                self.writer
+                    .write_fmt(format_args!("#define {}({}) ", name, params.join(", ")))?;
+                if let Some(expansion) = expansions.first() {
+                    self.print_expr(expansion.replacement, context)?;
+                }
+
+                Ok(())
+            }
+
             None => panic!("Could not find declaration with ID {:?}", decl_id),
             // _ => unimplemented!("Printer::print_decl"),
         }
@@ -748,6 +812,16 @@ impl<W: Write> Printer<W> {
 
             Some(&CTypeKind::Elaborated(ref ctype)) => self.print_type(*ctype, ident, context),
             Some(&CTypeKind::Decayed(ref ctype)) => self.print_type(*ctype, ident, context),
+            Some(&CTypeKind::Atomic(ref ctype)) => {
+                self.writer.write_all(b"_Atomic(")?;
+                self.print_type(*ctype, None, context)?;
+                self.writer.write_all(b")")?;
+                if let Some(i) = ident {
+                    self.writer.write_fmt(format_args!(" {}", i))?;
+                }
+
+                Ok(())
+            }
             Some(&CTypeKind::Paren(ref ctype)) => {
                 self.parenthesize(true, |slf| slf.print_type(*ctype, ident, context))
             }
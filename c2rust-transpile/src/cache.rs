@@ -0,0 +1,268 @@
+//! Cache of translation results, used to skip translation units that haven't changed since the
+//! last run.
+//!
+//! For each compile command we store a hash of the preprocessed source together with every
+//! option that affects the translation, the path of the translated file, the pragmas and crates
+//! it needs, and the report of its declarations. If the hash still matches, the existing
+//! translation is reused as is.
+//!
+//! Hashes are written to disk and compared across runs and builds of the transpiler, so they use
+//! FNV-1a rather than `std`'s hashers, whose algorithm may change between Rust releases.
+
+use std::fmt::Debug;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use failure::Error;
+
+use crate::compile_cmds::CompileCmd;
+use crate::report::DeclReport;
+use crate::saved_ast;
+use crate::{CrateSet, PragmaVec, TranspilerConfig};
+
+/// The version of the cache entry format and of the way hashes are computed. Entries written
+/// with another version are ignored.
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CacheEntry {
+    /// Missing from entries written before the format was versioned
+    #[serde(default)]
+    version: u32,
+    hash: String,
+    output: PathBuf,
+    pragmas: Vec<(String, Vec<String>)>,
+    crates: Vec<String>,
+    /// Missing from entries written before reports were cached
+    #[serde(default)]
+    decls: Option<Vec<DeclReport>>,
+}
+
+/// The cache entry of a single compile command
+pub struct TranslationCache {
+    path: PathBuf,
+    hash: Option<String>,
+    entry: Option<CacheEntry>,
+}
+
+impl TranslationCache {
+    pub fn open(
+        cache_dir: &Path,
+        cmd: &CompileCmd,
+        tcfg: &TranspilerConfig,
+        extra_clang_args: &[&str],
+    ) -> TranslationCache {
+        let input_path = cmd.abs_file();
+        let path = entry_path(cache_dir, &input_path, "json");
+
+        let hash = match translation_hash(cmd, tcfg, extra_clang_args) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                warn!("Not caching {}: {}", input_path.display(), e);
+                None
+            }
+        };
+
+        let entry = File::open(&path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, CacheEntry>(file).ok())
+            .filter(|entry| entry.version == CACHE_VERSION);
+
+        TranslationCache { path, hash, entry }
+    }
+
+    /// Whether `output_path` holds the translation of the current source and options
+    pub fn is_fresh(&self, output_path: &Path) -> bool {
+        match (&self.hash, &self.entry) {
+            (Some(hash), Some(entry)) => {
+                entry.hash == *hash && entry.output == output_path && output_path.exists()
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `output_path` was written by a previous run, so it is safe to overwrite
+    pub fn produced(&self, output_path: &Path) -> bool {
+        self.entry
+            .as_ref()
+            .map_or(false, |entry| entry.output == output_path)
+    }
+
+    /// The pragmas and crates required by the cached translation, and its declaration reports
+    pub fn cached_result(&self) -> Option<(PragmaVec, CrateSet, Option<Vec<DeclReport>>)> {
+        let entry = self.entry.as_ref()?;
+        let pragmas = entry
+            .pragmas
+            .iter()
+            .map(|(key, vals)| (leak(key), vals.iter().map(|val| leak(val)).collect()))
+            .collect();
+        let crates = entry.crates.iter().map(|krate| leak(krate)).collect();
+        Some((pragmas, crates, entry.decls.clone()))
+    }
+
+    /// Record the translation of the current source and options
+    pub fn store(
+        &self,
+        output_path: &Path,
+        pragmas: &PragmaVec,
+        crates: &CrateSet,
+        decls: &[DeclReport],
+    ) -> Result<(), Error> {
+        let hash = match &self.hash {
+            Some(hash) => hash.clone(),
+            None => return Ok(()),
+        };
+
+        let entry = CacheEntry {
+            version: CACHE_VERSION,
+            hash,
+            output: output_path.to_path_buf(),
+            pragmas: pragmas
+                .iter()
+                .map(|(key, vals)| {
+                    (
+                        key.to_string(),
+                        vals.iter().map(|val| val.to_string()).collect(),
+                    )
+                })
+                .collect(),
+            crates: crates.iter().map(|krate| krate.to_string()).collect(),
+            decls: Some(decls.to_vec()),
+        };
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        serde_json::to_writer(File::create(&self.path)?, &entry)?;
+        Ok(())
+    }
+}
+
+/// Pragmas and crates are `&'static str`s everywhere else, and the transpiler only runs once
+fn leak(s: &str) -> &'static str {
+    Box::leak(s.to_owned().into_boxed_str())
+}
+
+/// The 128-bit FNV-1a hash, as specified in
+/// https://tools.ietf.org/html/draft-eastlake-fnv
+struct StableHasher(u128);
+
+impl StableHasher {
+    const OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+    fn new() -> StableHasher {
+        StableHasher(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u128::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Hash one of several fields, prefixed by its length so that the boundaries between fields
+    /// are part of the hash
+    fn write_field(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    fn finish(&self) -> String {
+        format!("{:032x}", self.0)
+    }
+}
+
+/// Files stored per translation unit, like cache entries, are named after the input file with a
+/// hash of `key_path` to tell apart files with the same name in different directories.
+pub fn entry_path(dir: &Path, key_path: &Path, extension: &str) -> PathBuf {
+    let mut hasher = StableHasher::new();
+    hasher.write_field(key_path.to_string_lossy().as_bytes());
+
+    let stem = key_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    dir.join(format!("{}-{}.{}", stem, hasher.finish(), extension))
+}
+
+fn translation_hash(
+    cmd: &CompileCmd,
+    tcfg: &TranspilerConfig,
+    extra_clang_args: &[&str],
+) -> Result<String, Error> {
+    let mut hasher = StableHasher::new();
+    hasher.write(&CACHE_VERSION.to_le_bytes());
+    hasher.write_field(env!("CARGO_PKG_VERSION").as_bytes());
+    // Translations of saved ASTs don't need the sources, so they are keyed on the ASTs
+    match &tcfg.load_asts {
+        Some(dir) => {
+            for ast in saved_ast::read_all(dir, cmd, &tcfg.targets)? {
+                hasher.write_field(&ast);
+            }
+        }
+        None => hasher.write_field(&cmd.preprocess()?),
+    }
+    hasher.write(&(extra_clang_args.len() as u64).to_le_bytes());
+    for arg in extra_clang_args {
+        hasher.write_field(arg.as_bytes());
+    }
+
+    // Everything that can change the translated file, but not options that only control
+    // debugging output or which files get translated.
+    let options: &[&dyn Debug] = &[
+        &tcfg.incremental_relooper,
+        &tcfg.fail_on_multiple,
+        &tcfg.debug_relooper_labels,
+        &tcfg.cross_checks,
+        &tcfg.cross_check_backend,
+        &tcfg.cross_check_configs,
+        &tcfg.prefix_function_names,
+        &tcfg.translate_asm,
+        &tcfg.use_c_loop_info,
+        &tcfg.use_c_multiple_info,
+        &tcfg.simplify_structures,
+        &tcfg.panic_on_translator_failure,
+        &tcfg.emit_modules,
+        &tcfg.fail_on_error,
+        &tcfg.replace_unsupported_decls,
+        &tcfg.translate_valist,
+        &tcfg.va_list,
+        &tcfg.static_init,
+        &tcfg.overflow,
+        &tcfg.source_map,
+        &tcfg.reduce_type_annotations,
+        &tcfg.reorganize_definitions,
+        &tcfg.emit_no_std,
+        &tcfg.translate_const_macros,
+        &tcfg.translate_fn_macros,
+        &tcfg.translate_setjmp,
+        &tcfg.closed_enums,
+        &tcfg.long_double,
+        &tcfg.targets,
+        &tcfg.emit_build_files,
+        &tcfg.main,
+    ];
+    for option in options {
+        hasher.write_field(format!("{:?}", option).as_bytes());
+    }
+
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_test_vectors() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = StableHasher::new();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(hash(b""), "6c62272e07bb014262b821756295c58d");
+        assert_eq!(hash(b"a"), "d228cb696f1a8caf78912b704e4a8964");
+    }
+}
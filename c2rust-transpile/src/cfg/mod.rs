@@ -573,20 +573,32 @@ impl Cfg<Label, StmtOrDecl> {
         stmt_ids: &[CStmtId],
         ret: ImplicitReturnType,
     ) -> Result<(Self, DeclStmtStore), TranslationError> {
-        let mut c_label_to_goto: IndexMap<CLabelId, IndexSet<CStmtId>> = IndexMap::new();
-        for (target, x) in stmt_ids
+        let stmts: Vec<CStmtId> = stmt_ids
             .iter()
             .flat_map(|&stmt_id| DFExpr::new(&translator.ast_context, stmt_id.into()))
             .flat_map(SomeId::stmt)
-            .flat_map(|x| match translator.ast_context[x].kind {
-                CStmtKind::Goto(target) => Some((target, x)),
-                _ => None,
-            })
-        {
-            c_label_to_goto
-                .entry(target)
-                .or_insert(IndexSet::new())
-                .insert(x);
+            .collect();
+
+        // A computed goto can jump to any label in the function whose address is taken
+        let address_taken_labels: Vec<CLabelId> = stmts
+            .iter()
+            .cloned()
+            .filter(|x| translator.ast_context.address_taken_labels.contains(x))
+            .collect();
+
+        let mut c_label_to_goto: IndexMap<CLabelId, IndexSet<CStmtId>> = IndexMap::new();
+        for &x in &stmts {
+            let targets: Vec<CLabelId> = match translator.ast_context[x].kind {
+                CStmtKind::Goto(target) => vec![target],
+                CStmtKind::IndirectGoto(_) => address_taken_labels.clone(),
+                _ => vec![],
+            };
+            for target in targets {
+                c_label_to_goto
+                    .entry(target)
+                    .or_insert(IndexSet::new())
+                    .insert(x);
+            }
         }
 
         let mut cfg_builder = CfgBuilder::new(c_label_to_goto);
@@ -1345,8 +1357,22 @@ impl CfgBuilder {
                 let last = stmt_ids.last();
 
                 // We feed the optional output label into the entry label of the next block
-                for stmt in stmt_ids {
+                for (i, stmt) in stmt_ids.iter().enumerate() {
                     let new_label: Label = lbl.unwrap_or(slf.fresh_label());
+
+                    if let Some(setjmp_if) = translator.setjmp_if(*stmt) {
+                        let rest = &stmt_ids[i + 1..];
+                        lbl = slf.convert_setjmp_if(
+                            translator, ctx, &setjmp_if, rest, in_tail, new_label,
+                        )?;
+
+                        // Without a branch for `setjmp` returning 0, `rest` has been translated
+                        if setjmp_if.direct.is_none() {
+                            return Ok(lbl);
+                        }
+                        continue;
+                    }
+
                     let sub_in_tail = in_tail.filter(|_| Some(stmt) == last);
                     lbl = slf.convert_stmt_help(translator, ctx, *stmt, sub_in_tail, new_label)?;
                 }
@@ -1356,6 +1382,96 @@ impl CfgBuilder {
         )
     }
 
+    /// Translate an `if` statement whose condition calls `setjmp` (see `translator::setjmp`).
+    /// `rest` are the statements following it in the enclosing block. They are the code protected
+    /// by the `setjmp` unless the `if` statement has a branch for `setjmp` returning 0.
+    fn convert_setjmp_if(
+        &mut self,
+        translator: &Translation,
+        ctx: ExprContext,
+        setjmp_if: &SetjmpIf,
+        rest: &[CStmtId],
+        in_tail: Option<ImplicitReturnType>,
+        entry: Label,
+    ) -> Result<Option<Label>, TranslationError> {
+        let mut wip = self.new_wip_block(entry);
+        for cmmt in translator
+            .comment_context
+            .borrow_mut()
+            .remove_stmt_comment(setjmp_if.stmt)
+        {
+            wip.push_comment(cmmt);
+        }
+        wip.extend(translator.convert_setjmp_init(ctx, setjmp_if)?);
+
+        // The protected code may only return if it is the rest of the function body
+        let (region, ret) = match setjmp_if.direct {
+            Some(direct) => (vec![direct], None),
+            None => {
+                let ret = in_tail.filter(|ret| match ret {
+                    ImplicitReturnType::StmtExpr(..) => false,
+                    _ => true,
+                });
+                (rest.to_vec(), ret)
+            }
+        };
+        if let Some(reason) =
+            translator.setjmp_region_escape(&region, ret.is_some(), &self.c_label_to_goto)
+        {
+            return Err(translator.unsupported_setjmp(setjmp_if.call, reason));
+        }
+
+        let call_entry = self.fresh_label();
+        let next_entry = self.fresh_label();
+        let jumped_entry = if setjmp_if.jumped.is_some() {
+            self.fresh_label()
+        } else {
+            next_entry
+        };
+        self.add_wip_block(wip, Jump(call_entry));
+
+        // Run the protected code
+        let body = translator.convert_setjmp_region(
+            ctx,
+            &region,
+            ret.unwrap_or(ImplicitReturnType::Void),
+        )?;
+        let (stmts, call) = translator
+            .convert_setjmp_call(ctx, setjmp_if, body)?
+            .discard_unsafe();
+        let mut call_wip = self.new_wip_block(call_entry);
+        call_wip.extend(stmts);
+        if ret.is_some() {
+            let call = translator.convert_setjmp_match(ctx, setjmp_if, call, true)?;
+            call_wip.push_stmt(mk().semi_stmt(call));
+            self.add_wip_block(call_wip, Jump(jumped_entry));
+        } else {
+            let cond = translator.convert_setjmp_match(ctx, setjmp_if, call, false)?;
+            self.add_wip_block(call_wip, Branch(cond, jumped_entry, next_entry));
+        }
+
+        // Continue after a `longjmp`. The `jmp_buf` is still valid if the jumped branch falls
+        // through to the protected code, so we run that with `setjmp` again.
+        if let Some(jumped) = setjmp_if.jumped {
+            let jumped_next = if setjmp_if.direct.is_none() {
+                call_entry
+            } else {
+                next_entry
+            };
+            let jumped_end = self.convert_stmt_help(translator, ctx, jumped, None, jumped_entry)?;
+            if let Some(jumped_end) = jumped_end {
+                let wip_jumped = self.new_wip_block(jumped_end);
+                self.add_wip_block(wip_jumped, Jump(jumped_next));
+            }
+        }
+
+        if ret.is_some() {
+            Ok(None)
+        } else {
+            Ok(Some(next_entry))
+        }
+    }
+
     /// Translate a C statement, inserting it into the CFG under the label key passed in.
     ///
     /// If the input C statement naturally passes control to the statement that follows it, the
@@ -1393,6 +1509,9 @@ impl CfgBuilder {
         {
             wip.push_comment(cmmt);
         }
+        if let Some(marker) = translator.source_marker(&translator.ast_context[stmt_id].loc) {
+            wip.push_comment(marker);
+        }
 
         let out_wip: Result<Option<WipBlock>, TranslationError> =
             match translator.ast_context.index(stmt_id).kind {
@@ -1414,6 +1533,10 @@ impl CfgBuilder {
                         {
                             wip.push_comment(cmmt);
                         }
+                        let decl_loc = &translator.ast_context[*decl].loc;
+                        if let Some(marker) = translator.source_marker(decl_loc) {
+                            wip.push_comment(marker);
+                        }
 
                         wip.push_decl(*decl);
                         wip.defined.insert(*decl);
@@ -1699,6 +1822,54 @@ impl CfgBuilder {
                     Ok(None)
                 }
 
+                CStmtKind::IndirectGoto(target) => {
+                    // `&&label` evaluates to an integer tag, so a computed goto is a multi-way
+                    // branch on that tag over the labels whose address is taken
+                    let (stmts, val) = translator
+                        .convert_expr(ctx.used(), target)?
+                        .discard_unsafe();
+                    wip.extend(stmts);
+                    let val = mk().cast_expr(val, mk().path_ty(vec!["usize"]));
+
+                    let label_ids: Vec<CLabelId> = self
+                        .c_label_to_goto
+                        .iter()
+                        .filter(|&(_, gotos)| gotos.contains(&stmt_id))
+                        .map(|(&label_id, _)| label_id)
+                        .collect();
+
+                    let mut cases = vec![];
+                    for label_id in label_ids {
+                        let tag = translator
+                            .ast_context
+                            .label_address_tag(label_id)
+                            .ok_or_else(|| format_err!("Label {:?} has no address", label_id))?;
+                        let pat = mk().lit_pat(
+                            mk().lit_expr(mk().int_lit(tag as u128, LitIntType::Unsuffixed)),
+                        );
+                        cases.push((vec![pat], Label::FromC(label_id)));
+
+                        self.last_per_stmt_mut()
+                            .c_labels_used
+                            .entry(label_id)
+                            .or_insert(IndexSet::new())
+                            .insert(stmt_id);
+                    }
+
+                    // Jumping anywhere else is undefined behavior
+                    let bad_target = self.fresh_label();
+                    let mut bad_target_wip = self.new_wip_block(bad_target);
+                    bad_target_wip.push_stmt(
+                        mk().semi_stmt(translator.panic("Computed goto to an unknown label")),
+                    );
+                    self.add_wip_block(bad_target_wip, End);
+                    cases.push((vec![mk().wild_pat()], bad_target));
+
+                    self.add_wip_block(wip, Switch { expr: val, cases });
+
+                    Ok(None)
+                }
+
                 CStmtKind::Compound(ref comp_stmts) => {
                     let comp_entry = self.fresh_label();
                     self.add_wip_block(wip, Jump(comp_entry));
@@ -1887,6 +2058,7 @@ impl CfgBuilder {
                     wip.extend(translator.convert_asm(
                         ctx,
                         DUMMY_SP,
+                        &translator.ast_context.index(stmt_id).loc,
                         is_volatile,
                         asm,
                         inputs,
@@ -3,6 +3,7 @@
 use super::*;
 
 use crate::rust_ast::comment_store;
+use crate::source_map;
 
 /// Convert a sequence of structures produced by Relooper back into Rust statements
 pub fn structured_cfg(
@@ -23,6 +24,9 @@ pub fn structured_cfg(
     let mut queued = vec![];
     let mut stmts = vec![];
     s.into_stmt(ast, comment_store, &mut queued, &mut stmts);
+    // Source map markers of C statements that translated to nothing at the end of a block are
+    // expected to be left over
+    queued.retain(|c| !source_map::is_marker(c));
     if !queued.is_empty() {
         eprintln!("Did not find a statement for comments {:?}", queued);
     }
@@ -2,11 +2,12 @@ use std::collections::HashSet;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use failure::Error;
 use regex::Regex;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct CompileCmd {
     /// The working directory of the compilation. All paths specified in the command
     /// or file fields must be either absolute or relative to this directory.
@@ -34,14 +35,128 @@ impl CompileCmd {
         match self.file.is_absolute() {
             true => self.file.clone(),
             false => {
+                // The source may be missing when translating saved ASTs
                 let path = self.directory.join(&self.file);
-                let e = format!("could not canonicalize {}", path.display());
-                path.canonicalize().expect(&e)
+                path.canonicalize().unwrap_or(path)
             },
         }
     }
+
+    /// The path of the source relative to the directory of the compilation, which stays the same
+    /// when the whole project is moved. Sources outside of that directory keep their absolute
+    /// path.
+    pub fn relative_file(&self) -> &Path {
+        self.file
+            .strip_prefix(&self.directory)
+            .unwrap_or(&self.file)
+    }
+
+    /// The compile command as a list of arguments, starting with the compiler
+    fn args(&self) -> Vec<String> {
+        match &self.command {
+            Some(command) if self.arguments.is_empty() => split_command(command),
+            _ => self.arguments.clone(),
+        }
+    }
+
+    /// The compiler of this command, and the options it passes to it other than the input file
+    /// and those that write the object file or dependency files
+    fn compiler_and_flags(&self) -> Result<(String, Vec<String>), Error> {
+        let args = self.args();
+        let (compiler, args) = args
+            .split_first()
+            .ok_or_else(|| format_err!("Empty compile command for {}", self.file.display()))?;
+
+        let mut flags = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-c" | "-M" | "-MM" | "-MD" | "-MMD" | "-MG" | "-MP" => {}
+                "-o" | "-MF" | "-MT" | "-MQ" => {
+                    args.next();
+                }
+                arg if arg.starts_with("-o") || arg.starts_with("-MF") => {}
+                arg if self.directory.join(arg) == self.directory.join(&self.file) => {}
+                arg => flags.push(arg.to_owned()),
+            }
+        }
+        Ok((compiler.to_owned(), flags))
+    }
+
+    /// The options of this command needed to compile its input file from any directory, i.e.
+    /// with relative include paths made absolute
+    pub fn build_flags(&self) -> Result<Vec<String>, Error> {
+        let (_, flags) = self.compiler_and_flags()?;
+
+        let mut build_flags = vec![];
+        let mut flags = flags.into_iter();
+        while let Some(flag) = flags.next() {
+            let path_option = PATH_OPTIONS.iter().find(|option| flag.starts_with(*option));
+            match path_option {
+                Some(option) if flag.len() > option.len() => {
+                    let path = self.directory.join(&flag[option.len()..]);
+                    build_flags.push(format!("{}{}", option, path.display()));
+                }
+                Some(_) => {
+                    build_flags.push(flag);
+                    build_flags.extend(
+                        flags
+                            .next()
+                            .map(|path| self.directory.join(path).display().to_string()),
+                    );
+                }
+                None => build_flags.push(flag),
+            }
+        }
+        Ok(build_flags)
+    }
+
+    /// Run the compiler of this command with `-E` and return the preprocessed source. Comments
+    /// are kept since they are translated too.
+    pub fn preprocess(&self) -> Result<Vec<u8>, Error> {
+        let (compiler, flags) = self.compiler_and_flags()?;
+
+        let output = Command::new(compiler)
+            .args(&flags)
+            .arg(&self.file)
+            .args(&["-E", "-C"])
+            .current_dir(&self.directory)
+            .output()?;
+        if !output.status.success() {
+            return Err(format_err!(
+                "Could not preprocess {}: {}",
+                self.file.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// Split a `command` from the compilation database into arguments. Only `"` and `\` are special.
+fn split_command(command: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut arg = None;
+    let mut quoted = false;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => arg.get_or_insert_with(String::new).extend(chars.next()),
+            '"' => {
+                quoted = !quoted;
+                arg.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => args.extend(arg.take()),
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+    args
 }
 
+/// Options that take a path, which is relative to the directory of the compile command
+const PATH_OPTIONS: [&str; 5] = ["-isystem", "-iquote", "-include", "-idirafter", "-I"];
+
 ///GNU GCC treats all of the following extensions as C++
 const CPP_EXTS: [&str; 7] = ["C", "cc", "cpp", "CPP", "c++", "cp", "cxx"];
 
@@ -102,3 +217,54 @@ pub fn get_compile_commands(
 
     Ok(v)
 }
+
+/// What a link command produces
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    Executable,
+    SharedLibrary,
+    StaticLibrary,
+}
+
+/// A command linking translation units into an executable or library, as recorded in
+/// `link_commands.json` by `c2rust intercept`. The file may also be written by hand, in which
+/// case only `output`, `kind` and `files` are needed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LinkCmd {
+    /// The working directory of the link. Relative paths in the other fields are relative to
+    /// it, or to the directory of the link commands file if there is none.
+    directory: Option<PathBuf>,
+    /// The executable or library produced
+    pub output: PathBuf,
+    pub kind: LinkKind,
+    /// Translation units linked into the output, directly or through static libraries
+    pub files: Vec<PathBuf>,
+    /// Shared libraries of the project the output links against
+    #[serde(default)]
+    pub libraries: Vec<PathBuf>,
+}
+
+/// Read the link commands of `link_commands`, with all paths made absolute
+pub fn get_link_commands(link_commands: &Path) -> Result<Vec<LinkCmd>, Error> {
+    let f = File::open(link_commands)?;
+    let mut cmds: Vec<LinkCmd> = serde_json::from_reader(f)?;
+
+    let base_dir = link_commands.parent().unwrap_or(Path::new("."));
+    for cmd in &mut cmds {
+        let dir = base_dir.join(
+            cmd.directory
+                .as_ref()
+                .map_or(Path::new(""), PathBuf::as_path),
+        );
+        let abs = |path: &Path| {
+            let path = dir.join(path);
+            path.canonicalize().unwrap_or(path)
+        };
+        cmd.output = abs(&cmd.output);
+        cmd.files = cmd.files.iter().map(|file| abs(file)).collect();
+        cmd.libraries = cmd.libraries.iter().map(|lib| abs(lib)).collect();
+    }
+
+    Ok(cmds)
+}
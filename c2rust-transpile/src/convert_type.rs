@@ -2,17 +2,24 @@ use crate::c_ast::CDeclId;
 use crate::c_ast::*;
 use crate::renamer::*;
 use crate::diagnostics::TranslationError;
+use crate::translator::{LongDoubleMode, VaListMode};
 use c2rust_ast_builder::mk;
+use indexmap::IndexSet;
 use std::collections::{HashMap, HashSet};
+use std::mem;
 use std::ops::Index;
 use syntax::ast::*;
 use syntax::ptr::P;
 
 pub struct TypeConverter {
     pub translate_valist: bool,
+    pub long_double: LongDoubleMode,
+    pub va_list: VaListMode,
     renamer: Renamer<CDeclId>,
     fields: HashMap<CDeclId, Renamer<CFieldId>>,
+    suffix_names: HashMap<(CDeclId, &'static str), String>,
     features: HashSet<&'static str>,
+    extern_crates: IndexSet<&'static str>,
     emit_no_std: bool,
 }
 
@@ -129,9 +136,13 @@ impl TypeConverter {
     pub fn new(emit_no_std: bool) -> TypeConverter {
         TypeConverter {
             translate_valist: false,
+            long_double: LongDoubleMode::F128,
+            va_list: VaListMode::Nightly,
             renamer: Renamer::new(&RESERVED_NAMES),
             fields: HashMap::new(),
+            suffix_names: HashMap::new(),
             features: HashSet::new(),
+            extern_crates: IndexSet::new(),
             emit_no_std,
         }
     }
@@ -140,6 +151,40 @@ impl TypeConverter {
         &self.features
     }
 
+    pub fn crates_used(&self) -> &IndexSet<&'static str> {
+        &self.extern_crates
+    }
+
+    /// Reset the features and crates used so far, returning them
+    pub fn take_usage(&mut self) -> (HashSet<&'static str>, IndexSet<&'static str>) {
+        (
+            mem::replace(&mut self.features, HashSet::new()),
+            mem::replace(&mut self.extern_crates, IndexSet::new()),
+        )
+    }
+
+    /// Record features and crates as used, e.g. to restore the ones returned by `take_usage`
+    pub fn add_usage(&mut self, features: &HashSet<&'static str>, crates: &IndexSet<&'static str>) {
+        self.features.extend(features.iter().cloned());
+        self.extern_crates.extend(crates.iter().cloned());
+    }
+
+    /// The Rust type of a `va_list` argument
+    pub fn va_list_ty(&mut self) -> P<Ty> {
+        match self.va_list {
+            VaListMode::Nightly => {
+                self.features.insert("c_variadic");
+
+                let std_or_core = if self.emit_no_std { "core" } else { "std" };
+                mk().path_ty(vec!["", std_or_core, "ffi", "VaList"])
+            }
+            VaListMode::Runtime => {
+                self.extern_crates.insert("c2rust_va_list");
+                mk().path_ty(vec!["c2rust_va_list", "VaList"])
+            }
+        }
+    }
+
     pub fn declare_decl_name(&mut self, decl_id: CDeclId, name: &str) -> String {
         self.renamer
             .insert(decl_id, name)
@@ -154,6 +199,22 @@ impl TypeConverter {
         self.renamer.get(&decl_id)
     }
 
+    /// Resolve the name of an item derived from the declaration `decl_id`, such as the packed
+    /// inner struct of a record that is also over-aligned. A fresh name is picked the first
+    /// time a given suffix is requested.
+    pub fn resolve_decl_suffix_name(&mut self, decl_id: CDeclId, suffix: &'static str) -> String {
+        if let Some(name) = self.suffix_names.get(&(decl_id, suffix)) {
+            return name.clone();
+        }
+
+        let base = self
+            .resolve_decl_name(decl_id)
+            .expect("Declaration should already be named");
+        let name = self.renamer.pick_name_root(&format!("{}{}", base, suffix));
+        self.suffix_names.insert((decl_id, suffix), name.clone());
+        name
+    }
+
     pub fn declare_field_name(
         &mut self,
         record_id: CRecordId,
@@ -265,12 +326,7 @@ impl TypeConverter {
                     } = ctxt[struct_id].kind
                     {
                         if struct_name == "__va_list_tag" {
-                            self.features.insert("c_variadic");
-
-                            let std_or_core = if self.emit_no_std { "core" } else { "std" };
-                            let path = vec!["", std_or_core, "ffi", "VaList"];
-                            let ty = mk().path_ty(path);
-                            return Ok(ty);
+                            return Ok(self.va_list_ty());
                         }
                     }
                 }
@@ -329,7 +385,17 @@ impl TypeConverter {
             CTypeKind::UChar => Ok(mk().path_ty(mk().path(vec!["libc", "c_uchar"]))),
             CTypeKind::Char => Ok(mk().path_ty(mk().path(vec!["libc", "c_char"]))),
             CTypeKind::Double => Ok(mk().path_ty(mk().path(vec!["libc", "c_double"]))),
-            CTypeKind::LongDouble => Ok(mk().path_ty(mk().path(vec!["f128", "f128"]))),
+            CTypeKind::LongDouble => match self.long_double {
+                LongDoubleMode::F128 => {
+                    self.extern_crates.insert("f128");
+                    Ok(mk().path_ty(mk().path(vec!["f128", "f128"])))
+                }
+                LongDoubleMode::F64 => Ok(mk().path_ty(mk().path(vec!["f64"]))),
+                LongDoubleMode::F80 => {
+                    self.extern_crates.insert("c2rust_f80");
+                    Ok(mk().path_ty(mk().path(vec!["c2rust_f80", "f80"])))
+                }
+            },
             CTypeKind::Float => Ok(mk().path_ty(mk().path(vec!["libc", "c_float"]))),
             CTypeKind::Int128 => Ok(mk().path_ty(mk().path(vec!["i128"]))),
             CTypeKind::UInt128 => Ok(mk().path_ty(mk().path(vec!["u128"]))),
@@ -370,6 +436,16 @@ impl TypeConverter {
                 ))
             }
 
+            // Vectors other than the x86 and NEON types are translated to arrays, see
+            // `Translation::portable_vector_type`
+            CTypeKind::Vector(element, count) => {
+                let ty = self.convert(ctxt, element.ctype)?;
+                Ok(mk().array_ty(
+                    ty,
+                    mk().lit_expr(mk().int_lit(count as u128, LitIntType::Unsuffixed)),
+                ))
+            }
+
             CTypeKind::IncompleteArray(element) => {
                 let ty = self.convert(ctxt, element)?;
                 let zero_lit = mk().int_lit(0, LitIntType::Unsuffixed);
@@ -401,6 +477,21 @@ impl TypeConverter {
 
             CTypeKind::TypeOf(ty) => self.convert(ctxt, ty),
 
+            // Atomic accesses are translated into atomic intrinsics on the value type
+            CTypeKind::Atomic(ty) => self.convert(ctxt, ty),
+
+            // `num_complex::Complex` is `#[repr(C)]` with the real part first, which
+            // matches the layout of C's `_Complex` types
+            CTypeKind::Complex(ctype) => {
+                let child_ty = self.convert(ctxt, ctype)?;
+                self.extern_crates.insert("num_complex");
+                let param = mk().angle_bracketed_args(vec![child_ty]);
+                Ok(mk().path_ty(vec![
+                    mk().path_segment("num_complex"),
+                    mk().path_segment_with_args("Complex", param),
+                ]))
+            }
+
             ref t => Err(format_err!("Unsupported type {:?}", t).into()),
         }
     }
@@ -11,12 +11,21 @@ use std::sync::Arc;
 use crate::c_ast::SrcLoc;
 use c2rust_ast_exporter::get_clang_major_version;
 
-const DEFAULT_WARNINGS: &[Diagnostic] = &[];
+const DEFAULT_WARNINGS: &[Diagnostic] = &[
+    Diagnostic::Setjmp,
+    Diagnostic::LongDouble,
+    Diagnostic::Asm,
+    Diagnostic::StaticInit,
+];
 
 #[derive(PartialEq, Eq, Hash, Debug, Display, EnumString, Clone)]
 #[strum(serialize_all = "kebab_case")]
 pub enum Diagnostic {
     Comments,
+    Setjmp,
+    LongDouble,
+    Asm,
+    StaticInit,
 }
 
 macro_rules! diag {
@@ -58,7 +67,8 @@ pub fn init(mut enabled_warnings: HashSet<Diagnostic>) {
         })
         .chain(io::stderr())
         .apply()
-        .expect("Could not set up diagnostics");
+        // A program that transpiles more than once keeps the logger of the first time
+        .unwrap_or(());
 }
 
 
@@ -172,6 +182,22 @@ impl TranslationError {
         }
     }
 
+    /// The messages of the error and its causes, outermost first
+    pub fn messages(&self) -> Vec<String> {
+        let error: &dyn Fail = self;
+        let mut messages: Vec<String> = error.iter_causes().map(|e| e.to_string()).collect();
+        let kind = self.inner.get_context().to_string();
+        if !kind.is_empty() {
+            messages.push(kind);
+        }
+        messages
+    }
+
+    /// The locations the error was reported at, innermost first
+    pub fn locations(&self) -> &[SrcLoc] {
+        &self.loc
+    }
+
     pub fn add_loc(mut self, loc: &Option<SrcLoc>) -> Self {
         if let Some(loc) = loc {
             self.loc.push(loc.clone());
@@ -0,0 +1,402 @@
+//! Translation of C comments into rustdoc comments.
+//!
+//! The comments that directly precede a declaration document its translation as `///` doc
+//! comments. Comment delimiters and the leading `*` of the lines of block comments are removed,
+//! and the common Doxygen commands are translated into rustdoc conventions:
+//!
+//! - `@brief` is dropped, since rustdoc already takes the first paragraph as the summary
+//! - `@param name text` goes into an `# Arguments` section as ``* `name` - text``
+//! - `@return text` and `@returns text` go into a `# Returns` section, and `@retval value text`
+//!   into the same section as ``* `value` - text``
+//!
+//! Commands may start with `\` instead of `@`. Other commands are kept as they are.
+//!
+//! rustdoc would compile the code blocks of the comments as Rust doctests, so fenced blocks are
+//! given the `text` language and indented blocks are fenced as `text`.
+
+/// The part of the doc comment that lines without a command continue
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Section {
+    Description,
+    Arguments,
+    Returns,
+}
+
+struct DocComment {
+    description: Vec<String>,
+    arguments: Vec<String>,
+    returns: Vec<String>,
+    section: Section,
+}
+
+impl DocComment {
+    fn new() -> Self {
+        DocComment {
+            description: vec![],
+            arguments: vec![],
+            returns: vec![],
+            section: Section::Description,
+        }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            // A blank line ends the paragraph of a command
+            self.section = Section::Description;
+            self.description.push(String::new());
+            return;
+        }
+
+        match command(trimmed) {
+            Some(("brief", text)) | Some(("short", text)) => {
+                self.section = Section::Description;
+                if !text.is_empty() {
+                    self.description.push(text.to_string());
+                }
+            }
+            Some(("param", text)) => {
+                self.section = Section::Arguments;
+                self.arguments.push(bullet(text));
+            }
+            Some(("return", text)) | Some(("returns", text)) => {
+                self.section = Section::Returns;
+                if !text.is_empty() {
+                    self.returns.push(text.to_string());
+                }
+            }
+            Some(("retval", text)) => {
+                self.section = Section::Returns;
+                self.returns.push(bullet(text));
+            }
+            _ => match self.section {
+                Section::Description => self.description.push(line.to_string()),
+                Section::Arguments => {
+                    let argument = self.arguments.last_mut().unwrap();
+                    argument.push(' ');
+                    argument.push_str(trimmed);
+                }
+                Section::Returns => self.returns.push(trimmed.to_string()),
+            },
+        }
+    }
+
+    fn into_lines(self) -> Vec<String> {
+        let mut lines = vec![];
+        for line in self.description {
+            // Collapse runs of blank lines
+            if !line.is_empty() || lines.last().map_or(false, |l: &String| !l.is_empty()) {
+                lines.push(line);
+            }
+        }
+        if lines.last().map_or(false, |l| l.is_empty()) {
+            lines.pop();
+        }
+        let mut lines = fence_code_blocks(lines);
+
+        for (title, section) in &[("# Arguments", self.arguments), ("# Returns", self.returns)] {
+            if section.is_empty() {
+                continue;
+            }
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(title.to_string());
+            lines.push(String::new());
+            lines.extend(section.iter().cloned());
+        }
+
+        lines
+            .into_iter()
+            .map(|line| {
+                if line.is_empty() {
+                    "///".to_string()
+                } else {
+                    format!("/// {}", line)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Whether markdown takes `line` for part of an indented code block, if a blank line precedes it
+fn is_indented(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+/// Mark the code blocks of the markdown `lines` as `text`: fenced blocks get `text` for their
+/// language, and indented blocks are fenced
+fn fence_code_blocks(lines: Vec<String>) -> Vec<String> {
+    // Closes an indented block before the blank lines that end it
+    fn close_indented(fenced: &mut Vec<String>) {
+        let blanks = fenced.iter().rev().take_while(|l| l.is_empty()).count();
+        let at = fenced.len() - blanks;
+        fenced.insert(at, "```".to_string());
+    }
+
+    let mut fenced = vec![];
+    let mut fence: Option<&'static str> = None;
+    let mut in_indented = false;
+    let mut after_blank = true;
+    for line in lines {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            fenced.push(line);
+            continue;
+        }
+
+        if in_indented && !line.is_empty() && !is_indented(&line) {
+            close_indented(&mut fenced);
+            in_indented = false;
+        }
+        if !in_indented {
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                let marker = if trimmed.starts_with("```") {
+                    "```"
+                } else {
+                    "~~~"
+                };
+                fence = Some(marker);
+                fenced.push(format!("{}text", marker));
+                after_blank = false;
+                continue;
+            }
+            if after_blank && is_indented(&line) {
+                fenced.push("```text".to_string());
+                in_indented = true;
+            }
+        }
+        after_blank = line.is_empty();
+        fenced.push(line);
+    }
+
+    if in_indented {
+        close_indented(&mut fenced);
+    }
+    if let Some(marker) = fence {
+        fenced.push(marker.to_string());
+    }
+    fenced
+}
+
+/// The Doxygen command `line` starts with, if any, and the text after it. The direction of a
+/// `@param[in]` is dropped.
+fn command(line: &str) -> Option<(&str, &str)> {
+    if !line.starts_with('@') && !line.starts_with('\\') {
+        return None;
+    }
+    let line = &line[1..];
+    let end = line
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(line.len());
+    let (name, mut rest) = line.split_at(end);
+    if name.is_empty() {
+        return None;
+    }
+    if rest.starts_with('[') {
+        rest = &rest[rest.find(']').map_or(rest.len(), |i| i + 1)..];
+    } else if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((name, rest.trim()))
+}
+
+/// A list item for the text of a `@param` or `@retval` whose first word names what it describes
+fn bullet(text: &str) -> String {
+    let mut words = text.splitn(2, char::is_whitespace);
+    let name = words.next().unwrap_or("");
+    match words.next().map(str::trim_start) {
+        Some(desc) if !desc.is_empty() => format!("* `{}` - {}", name, desc),
+        _ => format!("* `{}`", name),
+    }
+}
+
+/// The lines of text of the raw C comment `comment`, without its delimiters
+fn comment_text(comment: &str) -> Vec<String> {
+    let mut lines: Vec<String> = if comment.starts_with("//") {
+        comment
+            .lines()
+            .map(|line| {
+                let line = line.trim_start();
+                let line = line.trim_start_matches('/');
+                let line = line.trim_start_matches('!');
+                line.trim_start_matches('<').to_string()
+            })
+            .collect()
+    } else {
+        let body = comment.trim_start_matches("/*");
+        let body = body.trim_end_matches("*/");
+        let body = body.trim_start_matches(|c| c == '*' || c == '!');
+        let body = body.trim_start_matches('<');
+        body.lines()
+            .enumerate()
+            .map(|(i, line)| {
+                if i == 0 {
+                    return line.to_string();
+                }
+                // Continuation lines often start with a `*`
+                let trimmed = line.trim_start();
+                if trimmed.starts_with('*') {
+                    trimmed[1..].to_string()
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect()
+    };
+
+    for line in &mut lines {
+        // Drop the rules that frame some comments, like `*****` or `-----`
+        let is_rule = line.len() > 2
+            && line
+                .trim()
+                .chars()
+                .all(|c| c == '*' || c == '/' || c == '-' || c == '=');
+        if is_rule {
+            line.clear();
+        }
+        let len = line.trim_end().len();
+        line.truncate(len);
+    }
+
+    // Remove the indentation common to all lines
+    let indent = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start_matches(|c| c == ' ' || c == '\t').len())
+        .min()
+        .unwrap_or(0);
+    for line in &mut lines {
+        if !line.is_empty() {
+            *line = line[indent..].to_string();
+        }
+    }
+
+    while lines.first().map_or(false, |l| l.is_empty()) {
+        lines.remove(0);
+    }
+    while lines.last().map_or(false, |l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Translate the raw C comments `comments`, in source order, into the lines of a doc comment,
+/// each starting with `///`
+pub fn doc_comment_lines(comments: &[String]) -> Vec<String> {
+    let mut doc = DocComment::new();
+    for comment in comments {
+        for line in comment_text(comment) {
+            doc.push_line(&line);
+        }
+    }
+    doc.into_lines()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(comments: &[&str]) -> Vec<String> {
+        let comments: Vec<String> = comments.iter().map(|c| c.to_string()).collect();
+        doc_comment_lines(&comments)
+    }
+
+    #[test]
+    fn plain_comments() {
+        assert_eq!(doc(&["// The length"]), vec!["/// The length"]);
+        assert_eq!(doc(&["/* The length */"]), vec!["/// The length"]);
+        assert_eq!(doc(&["///< The length"]), vec!["/// The length"]);
+        assert_eq!(
+            doc(&["/**\n * First line\n *\n *     indented\n */"]),
+            vec![
+                "/// First line",
+                "///",
+                "/// ```text",
+                "///     indented",
+                "/// ```"
+            ]
+        );
+        assert_eq!(
+            doc(&["/*********\n * Banner\n *********/"]),
+            vec!["/// Banner"]
+        );
+    }
+
+    #[test]
+    fn code_blocks() {
+        let comment = "/*
+         * Usage:
+         *
+         *     int n = count(list);
+         *
+         *     free(list);
+         *
+         * Or:
+         * ```
+         * count(NULL);
+         * ```
+         * ~~~c
+         * count(0);
+         */";
+        assert_eq!(
+            doc(&[comment]),
+            vec![
+                "/// Usage:",
+                "///",
+                "/// ```text",
+                "///     int n = count(list);",
+                "///",
+                "///     free(list);",
+                "/// ```",
+                "///",
+                "/// Or:",
+                "/// ```text",
+                "/// count(NULL);",
+                "/// ```",
+                "/// ~~~text",
+                "/// count(0);",
+                "/// ~~~",
+            ]
+        );
+        assert_eq!(
+            doc(&["// Not code:\n//     continued"]),
+            vec!["/// Not code:", "///     continued"]
+        );
+    }
+
+    #[test]
+    fn doxygen_commands() {
+        let comment = "/**
+         * @brief Copy a buffer.
+         *
+         * Copies at most @p n bytes.
+         * @param[out] dst the destination,
+         *     which must not overlap @p src
+         * @param src the source
+         * \\return the number of bytes copied
+         * @retval -1 on errors
+         */";
+        assert_eq!(
+            doc(&[comment]),
+            vec![
+                "/// Copy a buffer.",
+                "///",
+                "/// Copies at most @p n bytes.",
+                "///",
+                "/// # Arguments",
+                "///",
+                "/// * `dst` - the destination, which must not overlap @p src",
+                "/// * `src` - the source",
+                "///",
+                "/// # Returns",
+                "///",
+                "/// the number of bytes copied",
+                "/// * `-1` - on errors",
+            ]
+        );
+    }
+}
@@ -18,6 +18,7 @@ extern crate c2rust_ast_exporter;
 extern crate clap;
 extern crate itertools;
 extern crate libc;
+extern crate rayon;
 extern crate regex;
 extern crate serde_json;
 #[macro_use]
@@ -34,11 +35,16 @@ mod diagnostics;
 
 pub mod build_files;
 pub mod c_ast;
+mod cache;
 pub mod cfg;
 mod compile_cmds;
 pub mod convert_type;
+mod doc_comments;
 pub mod renamer;
+mod report;
 pub mod rust_ast;
+mod saved_ast;
+mod source_map;
 pub mod translator;
 pub mod with_stmts;
 
@@ -48,8 +54,10 @@ use std::io;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::Mutex;
 
 use failure::Error;
+use rayon::prelude::*;
 use regex::Regex;
 
 use crate::c_ast::Printer;
@@ -57,15 +65,27 @@ use crate::c_ast::*;
 pub use crate::diagnostics::Diagnostic;
 use c2rust_ast_exporter as ast_exporter;
 
-use crate::build_files::{emit_build_files, get_build_dir};
-use crate::compile_cmds::get_compile_commands;
-pub use crate::translator::ReplaceMode;
+use crate::build_files::{emit_build_files, emit_workspace, get_build_dir, TranslatedModule};
+use crate::cache::TranslationCache;
+use crate::compile_cmds::{get_compile_commands, get_link_commands, CompileCmd};
+use crate::report::{write_report, DeclReport, DeclStatus};
+pub use crate::translator::{
+    LongDoubleMode, OverflowMode, ReplaceMode, StaticInitMode, VaListMode,
+};
 use std::prelude::v1::Vec;
 
 type PragmaVec = Vec<(&'static str, Vec<&'static str>)>;
 type PragmaSet = indexmap::IndexSet<(&'static str, &'static str)>;
 type CrateSet = indexmap::IndexSet<&'static str>;
-type TranspileResult = (PathBuf, Option<PragmaVec>, Option<CrateSet>);
+type TranspileResult = (
+    PathBuf,
+    Option<PragmaVec>,
+    Option<CrateSet>,
+    Option<Vec<DeclReport>>,
+);
+
+/// Worker threads recurse as deeply over the C AST as the main thread does
+const TRANSLATOR_STACK_SIZE: usize = 64 * 1024 * 1024;
 
 /// Configuration settings for the translation process
 #[derive(Debug)]
@@ -99,19 +119,111 @@ pub struct TranspilerConfig {
     pub fail_on_error: bool,
     pub replace_unsupported_decls: ReplaceMode,
     pub translate_valist: bool,
+    pub va_list: VaListMode,
+    pub static_init: StaticInitMode,
+    pub overflow: OverflowMode,
     pub overwrite_existing: bool,
+    /// Directory caching translations, to skip translation units that didn't change
+    pub cache_dir: Option<PathBuf>,
+    /// Directory to save the exported ASTs to, so they can be translated with `load_asts`
+    pub save_asts: Option<PathBuf>,
+    /// Directory to load ASTs saved with `save_asts` from, instead of exporting them with Clang
+    pub load_asts: Option<PathBuf>,
     pub reduce_type_annotations: bool,
     pub reorganize_definitions: bool,
+    /// Number of translation units to transpile in parallel, defaults to the number of CPUs
+    pub jobs: Option<usize>,
+    /// Write a JSON report of the outcome of every top-level declaration to this file
+    pub report: Option<PathBuf>,
+    /// Write a map from the translated Rust code back to the C source next to each module
+    pub source_map: bool,
     pub enabled_warnings: HashSet<Diagnostic>,
     pub emit_no_std: bool,
     pub output_dir: Option<PathBuf>,
     pub translate_const_macros: bool,
+    pub translate_fn_macros: bool,
+    pub translate_setjmp: bool,
+    /// Translate enums that are only ever assigned their enumerators into Rust `enum`s
+    pub closed_enums: bool,
+    pub long_double: LongDoubleMode,
+    /// Clang target triples to translate for, merging the results behind `#[cfg]` attributes
+    pub targets: Vec<String>,
 
     // Options that control build files
     /// Emit `Cargo.toml` and one of `main.rs`, `lib.rs`
     pub emit_build_files: bool,
     /// Names the translation unit containing the main function
     pub main: Option<String>,
+    /// Compile the translation units that are not translated, or not translated completely,
+    /// from C in the emitted `build.rs`
+    pub hybrid_build: bool,
+    /// Translation units to keep in C in a hybrid build
+    pub keep_c: Option<Regex>,
+    /// Link commands of the project, to emit a Cargo workspace with a crate for each executable
+    /// and library they produce
+    pub link_commands: Option<PathBuf>,
+}
+
+/// The configuration of `c2rust transpile` without any options
+impl Default for TranspilerConfig {
+    fn default() -> Self {
+        TranspilerConfig {
+            dump_untyped_context: false,
+            dump_typed_context: false,
+            pretty_typed_context: false,
+            dump_function_cfgs: false,
+            json_function_cfgs: false,
+            dump_cfg_liveness: false,
+            dump_structures: false,
+            verbose: false,
+            debug_ast_exporter: false,
+
+            incremental_relooper: true,
+            fail_on_multiple: false,
+            filter: None,
+            debug_relooper_labels: false,
+            cross_checks: false,
+            cross_check_backend: "zstd-logging".to_string(),
+            cross_check_configs: vec![],
+            prefix_function_names: None,
+            translate_asm: true,
+            use_c_loop_info: true,
+            use_c_multiple_info: true,
+            simplify_structures: true,
+            panic_on_translator_failure: false,
+            emit_modules: false,
+            fail_on_error: false,
+            replace_unsupported_decls: ReplaceMode::Extern,
+            translate_valist: true,
+            va_list: VaListMode::Nightly,
+            static_init: StaticInitMode::Sections,
+            overflow: OverflowMode::Plain,
+            overwrite_existing: false,
+            cache_dir: None,
+            save_asts: None,
+            load_asts: None,
+            reduce_type_annotations: false,
+            reorganize_definitions: false,
+            jobs: None,
+            report: None,
+            source_map: false,
+            enabled_warnings: HashSet::new(),
+            emit_no_std: false,
+            output_dir: None,
+            translate_const_macros: false,
+            translate_fn_macros: false,
+            translate_setjmp: false,
+            closed_enums: false,
+            long_double: LongDoubleMode::F128,
+            targets: vec![],
+
+            emit_build_files: false,
+            main: None,
+            hybrid_build: false,
+            keep_c: None,
+            link_commands: None,
+        }
+    }
 }
 
 /// Main entry point to transpiler. Called from CLI tools with the result of
@@ -119,45 +231,128 @@ pub struct TranspilerConfig {
 pub fn transpile(tcfg: TranspilerConfig, cc_db: &Path, extra_clang_args: &[&str]) {
     diagnostics::init(tcfg.enabled_warnings.clone());
 
-    let cmds = get_compile_commands(cc_db, &tcfg.filter).expect(&format!(
+    if let Err(e) = translator::check_targets(&tcfg.targets) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+    if tcfg.source_map && !tcfg.targets.is_empty() {
+        warn!("Source maps are not written when translating with --target");
+    }
+
+    let mut cmds = get_compile_commands(cc_db, &tcfg.filter).expect(&format!(
         "Could not parse compile commands from {}",
         cc_db.to_string_lossy()
     ));
 
+    // Translation units that are compiled from C in a hybrid build
+    let mut c_cmds = vec![];
+    if tcfg.hybrid_build {
+        let all_cmds = get_compile_commands(cc_db, &None).expect(&format!(
+            "Could not parse compile commands from {}",
+            cc_db.to_string_lossy()
+        ));
+        let filtered: HashSet<PathBuf> = cmds.iter().map(CompileCmd::abs_file).collect();
+        c_cmds.extend(
+            all_cmds
+                .into_iter()
+                .filter(|cmd| !filtered.contains(&cmd.abs_file())),
+        );
+
+        if let Some(keep_c) = &tcfg.keep_c {
+            let (kept, translated): (Vec<_>, Vec<_>) = cmds
+                .into_iter()
+                .partition(|cmd| keep_c.is_match(cmd.file.to_str().unwrap()));
+            c_cmds.extend(kept);
+            cmds = translated;
+        }
+    }
+
     // we may need to specify path to system include dir on macOS
     let clang_args: Vec<String> = get_isystem_args();
     let mut clang_args: Vec<&str> = clang_args.iter().map(AsRef::as_ref).collect();
     clang_args.extend_from_slice(extra_clang_args);
 
-    let results = cmds
-        .iter()
-        .map(|cmd| transpile_single(&tcfg, cmd.abs_file().as_path(), cc_db, extra_clang_args))
-        .collect::<Vec<TranspileResult>>();
+    // The AST exporter parses its arguments into global LLVM options, so only one translation
+    // unit can be exported at a time. Everything after that runs in parallel.
+    let exporter_lock = Mutex::new(());
+    let mut pool = rayon::ThreadPoolBuilder::new().stack_size(TRANSLATOR_STACK_SIZE);
+    if let Some(jobs) = tcfg.jobs {
+        pool = pool.num_threads(jobs);
+    }
+    let pool = pool.build().expect("Could not create transpiler thread pool");
+
+    // `collect` keeps the results in the order of the compile commands, so the module list and
+    // the collected pragmas and crates don't depend on scheduling.
+    let results = pool.install(|| {
+        cmds.par_iter()
+            .map(|cmd| transpile_single(&tcfg, cmd, cc_db, extra_clang_args, &exporter_lock))
+            .collect::<Vec<TranspileResult>>()
+    });
     let mut modules = vec![];
     let mut modules_skipped = false;
     let mut pragmas = PragmaSet::new();
     let mut crates = CrateSet::new();
-    for res in results {
-        let (module, pragma_vec, crate_set) = res;
-        modules.push(module);
+    let mut reports = vec![];
+    for (cmd, res) in cmds.into_iter().zip(results) {
+        let (module, pragma_vec, crate_set, decls) = res;
 
+        let mut module_pragmas = PragmaSet::new();
         if let Some(pv) = pragma_vec {
             for (key, vals) in pv {
                 for val in vals {
-                    pragmas.insert((key, val));
+                    module_pragmas.insert((key, val));
                 }
             }
         } else {
             modules_skipped = true;
         }
-
-        if let Some(cs) = crate_set {
-            crates.extend(cs);
+        pragmas.extend(module_pragmas.iter().cloned());
+
+        let module_crates = crate_set.unwrap_or_default();
+        crates.extend(module_crates.iter().cloned());
+
+        // A module missing some of the functions and variables its C defines would leave
+        // undefined symbols, so the C is kept. Declarations it only uses don't matter.
+        let incomplete = decls.as_ref().map_or(false, |decls| {
+            decls
+                .iter()
+                .any(|decl| decl.main_file_definition && decl.status != DeclStatus::Translated)
+        });
+        let has_main = decls.as_ref().map_or(false, |decls| {
+            decls.iter().any(|decl| {
+                decl.kind == "function" && decl.name.as_ref().map_or(false, |name| name == "main")
+            })
+        });
+        reports.push((cmd.abs_file(), module.clone(), decls));
+        if tcfg.hybrid_build && incomplete {
+            warn!(
+                "Compiling {} from C, as it could not be translated completely",
+                cmd.file.display()
+            );
+            c_cmds.push(cmd);
+        } else {
+            modules.push(TranslatedModule {
+                input: cmd.abs_file(),
+                path: module,
+                pragmas: module_pragmas,
+                crates: module_crates,
+                has_main,
+            });
         }
     }
     pragmas.sort();
     crates.sort();
 
+    if let Some(report_path) = &tcfg.report {
+        if let Err(e) = write_report(report_path, &reports) {
+            warn!(
+                "Could not write the translation report to {}: {}",
+                report_path.display(),
+                e
+            );
+        }
+    }
+
     if tcfg.emit_build_files {
         if modules_skipped {
             // If we skipped a file, we may not have collected all required pragmas
@@ -165,11 +360,26 @@ pub fn transpile(tcfg: TranspilerConfig, cc_db: &Path, extra_clang_args: &[&str]
             return;
         }
         let build_dir = get_build_dir(&tcfg, cc_db);
-        let crate_file = emit_build_files(&tcfg, &build_dir, modules, pragmas, crates);
+        let crate_files = match &tcfg.link_commands {
+            Some(link_commands) => {
+                let link_cmds = get_link_commands(link_commands).expect(&format!(
+                    "Could not parse link commands from {}",
+                    link_commands.display()
+                ));
+                emit_workspace(&tcfg, &build_dir, &link_cmds, modules, &c_cmds)
+            }
+            None => {
+                let modules = modules.into_iter().map(|m| m.path).collect();
+                emit_build_files(&tcfg, &build_dir, modules, pragmas, crates, &c_cmds)
+                    .map(|file| (build_dir.clone(), file))
+                    .into_iter()
+                    .collect()
+            }
+        };
         // We only run the reorganization refactoring if we emitted a fresh crate file
-        if let Some(output_file) = crate_file {
+        for (crate_dir, output_file) in crate_files {
             if tcfg.reorganize_definitions {
-                reorganize_definitions(&build_dir, &output_file).unwrap_or_else(|e| {
+                reorganize_definitions(&crate_dir, &output_file).unwrap_or_else(|e| {
                     warn!("Failed to reorganize definitions. {}", e.as_fail());
                 })
             }
@@ -265,19 +475,40 @@ fn reorganize_definitions(build_dir: &PathBuf, crate_path: &PathBuf) -> Result<(
 
 fn transpile_single(
     tcfg: &TranspilerConfig,
-    input_path: &Path,
+    cmd: &CompileCmd,
     cc_db: &Path,
     extra_clang_args: &[&str],
+    exporter_lock: &Mutex<()>,
 ) -> TranspileResult {
+    let input_path = cmd.abs_file();
+    let input_path = input_path.as_path();
     let output_path = get_output_path(tcfg, input_path);
-    if output_path.exists() && !tcfg.overwrite_existing {
+
+    let cache = tcfg
+        .cache_dir
+        .as_ref()
+        .map(|cache_dir| TranslationCache::open(cache_dir, cmd, tcfg, extra_clang_args));
+    if let Some(cache) = &cache {
+        if cache.is_fresh(&output_path) {
+            if let Some((pragmas, crates, decls)) = cache.cached_result() {
+                println!("Skipping unchanged file {}", output_path.display());
+                return (output_path, Some(pragmas), Some(crates), decls);
+            }
+        }
+    }
+
+    // Translations from earlier runs are overwritten when they are out of date
+    let produced = cache
+        .as_ref()
+        .map_or(false, |cache| cache.produced(&output_path));
+    if output_path.exists() && !tcfg.overwrite_existing && !produced {
         println!("Skipping existing file {}", output_path.display());
-        return (output_path, None, None);
+        return (output_path, None, None, None);
     }
 
     let file = input_path.file_name().unwrap().to_str().unwrap();
     println!("Transpiling {}", file);
-    if !input_path.exists() {
+    if !input_path.exists() && tcfg.load_asts.is_none() {
         warn!(
             "Input C file {} does not exist, skipping!",
             input_path.display()
@@ -288,13 +519,107 @@ fn transpile_single(
         println!("Additional Clang arguments: {}", extra_clang_args.join(" "));
     }
 
+    // Perform the translation
+    let translation = if tcfg.targets.is_empty() {
+        let typed_context = export_ast(tcfg, cmd, None, cc_db, extra_clang_args, exporter_lock);
+        translator::translate(typed_context, &tcfg, input_path.to_path_buf())
+    } else {
+        let translations = tcfg
+            .targets
+            .iter()
+            .map(|target| {
+                let target_arg = format!("--target={}", target);
+                let mut clang_args = extra_clang_args.to_vec();
+                clang_args.push(&target_arg);
+
+                let typed_context =
+                    export_ast(tcfg, cmd, Some(target), cc_db, &clang_args, exporter_lock);
+                translator::translate_target(target, typed_context, tcfg, input_path.to_path_buf())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            });
+        let (translated_string, pragmas, crates, decls, trampolines) =
+            translator::merge_target_translations(tcfg, translations).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            });
+        (translated_string, pragmas, crates, decls, trampolines, None)
+    };
+    let (translated_string, pragmas, crates, decls, trampolines, source_map) = translation;
+
+    let mut file = match File::create(&output_path) {
+        Ok(file) => file,
+        Err(e) => panic!("Unable to open file for writing: {}", e),
+    };
+
+    match file.write_all(translated_string.as_bytes()) {
+        Ok(()) => (),
+        Err(e) => panic!("Unable to write translation to file: {}", e),
+    };
+
+    // Trampolines left over from an earlier translation would define functions twice
+    let trampoline_path = get_va_trampoline_path(&output_path);
+    let written = match trampolines {
+        Some(trampolines) => fs::write(&trampoline_path, trampolines),
+        None if trampoline_path.exists() => fs::remove_file(&trampoline_path),
+        None => Ok(()),
+    };
+    if let Err(e) = written {
+        panic!("Unable to write {}: {}", trampoline_path.display(), e);
+    }
+
+    if let Some(mappings) = source_map {
+        let source_map_path = get_source_map_path(&output_path);
+        if let Err(e) = source_map::write_source_map(&source_map_path, &output_path, &mappings) {
+            panic!("Unable to write {}: {}", source_map_path.display(), e);
+        }
+    }
+
+    if let Some(cache) = &cache {
+        if let Err(e) = cache.store(&output_path, &pragmas, &crates, &decls) {
+            warn!("Could not cache the translation of {}: {}", input_path.display(), e);
+        }
+    }
+
+    (output_path, Some(pragmas), Some(crates), Some(decls))
+}
+
+/// Run the AST exporter on the translation unit of `cmd` for `target`, or load the AST it saved
+/// earlier with `--load-asts`, and convert the result into a typed AST
+fn export_ast(
+    tcfg: &TranspilerConfig,
+    cmd: &CompileCmd,
+    target: Option<&str>,
+    cc_db: &Path,
+    extra_clang_args: &[&str],
+    exporter_lock: &Mutex<()>,
+) -> TypedAstContext {
+    let input_path = cmd.abs_file();
+    let input_path = input_path.as_path();
+    let cbor = match &tcfg.load_asts {
+        Some(dir) => saved_ast::load(dir, cmd, target),
+        None => {
+            let _exporter = exporter_lock.lock().unwrap();
+            ast_exporter::get_ast_cbor(input_path, cc_db, extra_clang_args, tcfg.debug_ast_exporter)
+                .map_err(Error::from)
+        }
+    };
+    let cbor = cbor.unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+
+    if let Some(dir) = &tcfg.save_asts {
+        if let Err(e) = saved_ast::save(dir, cmd, target, &cbor) {
+            warn!("Could not save the AST of {}: {}", input_path.display(), e);
+        }
+    }
+
     // Extract the untyped AST from the CBOR file
-    let untyped_context = match ast_exporter::get_untyped_ast(
-        input_path,
-        cc_db,
-        extra_clang_args,
-        tcfg.debug_ast_exporter,
-    ) {
+    let untyped_context = match ast_exporter::parse_untyped_ast(&cbor) {
         Err(e) => {
             eprintln!("Error: {:}", e);
             process::exit(1);
@@ -324,21 +649,18 @@ fn transpile_single(
         println!("{:#?}", Printer::new(io::stdout()).print(&typed_context));
     }
 
-    // Perform the translation
-    let (translated_string, pragmas, crates) =
-        translator::translate(typed_context, &tcfg, input_path.to_path_buf());
-
-    let mut file = match File::create(&output_path) {
-        Ok(file) => file,
-        Err(e) => panic!("Unable to open file for writing: {}", e),
-    };
+    typed_context
+}
 
-    match file.write_all(translated_string.as_bytes()) {
-        Ok(()) => (),
-        Err(e) => panic!("Unable to write translation to file: {}", e),
-    };
+/// The C trampolines of the variadic functions translated into `output_path` with
+/// `--va-list runtime` are written to this file
+fn get_va_trampoline_path(output_path: &Path) -> PathBuf {
+    output_path.with_extension("va_trampolines.c")
+}
 
-    (output_path, Some(pragmas), Some(crates))
+/// The source map of `output_path` is written to this file with `--source-map`
+fn get_source_map_path(output_path: &Path) -> PathBuf {
+    output_path.with_extension("source_map.json")
 }
 
 fn get_output_path(tcfg: &TranspilerConfig, input_path: &Path) -> PathBuf {
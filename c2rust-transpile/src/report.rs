@@ -0,0 +1,130 @@
+//! Machine-readable report of the outcome of a translation, written with `--report`.
+//!
+//! The report lists every top-level declaration the translator tried to convert, where it came
+//! from, whether it made it into the output and, if not, why. Translation units skipped because
+//! their translation was up to date or already existed are listed without declarations.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use failure::Error;
+
+use crate::c_ast::SrcLoc;
+use crate::diagnostics::TranslationError;
+
+/// What became of a declaration
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeclStatus {
+    /// Translated in full
+    Translated,
+    /// Replaced by an `extern` declaration after the definition failed to translate
+    Stubbed,
+    /// Left out of the translation because of an error
+    Skipped,
+}
+
+/// A position in a C source file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Location {
+    pub file: Option<PathBuf>,
+    pub line: u64,
+    pub column: u64,
+}
+
+impl From<&SrcLoc> for Location {
+    fn from(loc: &SrcLoc) -> Location {
+        Location {
+            file: loc.file_path.clone(),
+            line: loc.line,
+            column: loc.column,
+        }
+    }
+}
+
+/// The outcome of translating a single top-level declaration
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeclReport {
+    /// The `CDeclId` of the declaration, which is only unique within its translation unit
+    pub id: u64,
+    pub name: Option<String>,
+    /// The kind of declaration, e.g. `function` or `struct`
+    pub kind: String,
+    pub loc: Option<Location>,
+    /// Whether the declaration is a function or variable definition in the main file of its
+    /// translation unit, rather than one it only declares or gets from a header
+    #[serde(default)]
+    pub main_file_definition: bool,
+    /// The target triple the declaration was translated for, when translating for several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub status: DeclStatus,
+    /// The error that stopped the declaration from being translated, outermost message first
+    pub errors: Vec<String>,
+    /// Locations the error was reported at, innermost first
+    pub error_locs: Vec<Location>,
+    /// Crate features needed by the translation of the declaration
+    pub features: Vec<String>,
+    /// Crates needed by the translation of the declaration
+    pub crates: Vec<String>,
+}
+
+impl DeclReport {
+    pub fn set_error(&mut self, e: &TranslationError) {
+        self.errors = e.messages();
+        self.error_locs = e.locations().iter().map(Location::from).collect();
+    }
+}
+
+#[derive(Serialize, Default)]
+struct Summary {
+    translated: usize,
+    stubbed: usize,
+    skipped: usize,
+}
+
+#[derive(Serialize)]
+struct FileReport<'a> {
+    input: &'a Path,
+    output: &'a Path,
+    /// `None` if the translation unit was not translated in this run
+    decls: Option<&'a [DeclReport]>,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    summary: Summary,
+    files: Vec<FileReport<'a>>,
+}
+
+/// Write the report for the translation units in `files`, given as the input file, the
+/// translated file and, if the translation unit was translated, its declarations.
+pub fn write_report(
+    path: &Path,
+    files: &[(PathBuf, PathBuf, Option<Vec<DeclReport>>)],
+) -> Result<(), Error> {
+    let mut summary = Summary::default();
+    for decl in files
+        .iter()
+        .flat_map(|(_, _, decls)| decls.iter().flatten())
+    {
+        match decl.status {
+            DeclStatus::Translated => summary.translated += 1,
+            DeclStatus::Stubbed => summary.stubbed += 1,
+            DeclStatus::Skipped => summary.skipped += 1,
+        }
+    }
+
+    let files = files
+        .iter()
+        .map(|(input, output, decls)| FileReport {
+            input,
+            output,
+            decls: decls.as_ref().map(Vec::as_slice),
+        })
+        .collect();
+
+    let report = Report { summary, files };
+    serde_json::to_writer_pretty(File::create(path)?, &report)?;
+    Ok(())
+}
@@ -26,6 +26,7 @@
 use crate::rust_ast::traverse;
 use itertools::Itertools;
 use std::collections::BTreeMap;
+use std::mem;
 use syntax::ast::*;
 use syntax::parse::lexer::comments;
 use syntax_pos::hygiene::SyntaxContext;
@@ -127,6 +128,15 @@ impl CommentTraverser {
         }
     }
 
+    /// Take the comments reinserted since the last call, in order. Printing each item of a
+    /// translation separately with the comments taken after traversing it keeps them in place.
+    pub fn take_comments(&mut self) -> Vec<comments::Comment> {
+        mem::replace(&mut self.store.output_comments, BTreeMap::new())
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect()
+    }
+
     /// Turn the traverser back into a `CommentStore`.
     pub fn into_comment_store(self) -> CommentStore {
         //        assert!(old_comments.is_empty());
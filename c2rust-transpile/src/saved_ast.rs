@@ -0,0 +1,132 @@
+//! ASTs saved with `--save-asts` and translated with `--load-asts`.
+//!
+//! Exporting the AST of a translation unit needs Clang, and the headers and toolchain the
+//! translation unit is built with. Saving the ASTs lets them be exported once on a machine that
+//! has all of those, and translated anywhere else. Each translation unit is saved as the CBOR
+//! produced by the AST exporter, with one file per target when translating with `--target`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::Error;
+
+use crate::cache::entry_path;
+use crate::compile_cmds::CompileCmd;
+
+/// Saved ASTs are keyed on the path of their translation unit relative to the directory it is
+/// compiled in, so that they can be loaded from a checkout in another place, whether its compile
+/// commands are regenerated there or still list the original directory.
+fn ast_path(dir: &Path, cmd: &CompileCmd, target: Option<&str>) -> PathBuf {
+    let key = cmd.relative_file();
+    match target {
+        Some(target) => entry_path(dir, key, &format!("{}.cbor", target)),
+        None => entry_path(dir, key, "cbor"),
+    }
+}
+
+/// Save the AST of the translation unit of `cmd` exported for `target`
+pub fn save(dir: &Path, cmd: &CompileCmd, target: Option<&str>, cbor: &[u8]) -> Result<(), Error> {
+    fs::create_dir_all(dir)?;
+    fs::write(ast_path(dir, cmd, target), cbor)?;
+    Ok(())
+}
+
+/// Load the AST of the translation unit of `cmd` exported for `target`
+pub fn load(dir: &Path, cmd: &CompileCmd, target: Option<&str>) -> Result<Vec<u8>, Error> {
+    let path = ast_path(dir, cmd, target);
+    fs::read(&path).map_err(|e| {
+        format_err!(
+            "Could not load the AST of {} from {}: {}",
+            cmd.abs_file().display(),
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Load every AST of the translation unit of `cmd` needed to translate it for `targets`, or for
+/// the default target if there are none.
+pub fn read_all(dir: &Path, cmd: &CompileCmd, targets: &[String]) -> Result<Vec<Vec<u8>>, Error> {
+    if targets.is_empty() {
+        return Ok(vec![load(dir, cmd, None)?]);
+    }
+    targets
+        .iter()
+        .map(|target| load(dir, cmd, Some(target)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::process;
+
+    use crate::compile_cmds::get_compile_commands;
+
+    /// The compile commands of `db`, a `compile_commands.json` written to `dir`
+    fn compile_cmds(dir: &Path, db: serde_json::Value) -> Vec<CompileCmd> {
+        fs::create_dir_all(dir).unwrap();
+        let cc_db = dir.join("compile_commands.json");
+        fs::write(&cc_db, db.to_string()).unwrap();
+        get_compile_commands(&cc_db, &None).unwrap()
+    }
+
+    #[test]
+    fn load_relocated() {
+        let root = env::temp_dir().join(format!("c2rust-saved-ast-{}", process::id()));
+        let asts = root.join("asts");
+        let original = Path::new("/nonexistent/project");
+
+        let db = serde_json::json!([
+            {
+                "directory": original,
+                "arguments": ["cc", "-c", "src/main.c"],
+                "file": original.join("src/main.c"),
+            },
+            {
+                "directory": original,
+                "arguments": ["cc", "-c", "util/main.c"],
+                "file": "util/main.c",
+            },
+        ]);
+        let cmds = compile_cmds(&root.join("original"), db.clone());
+        save(&asts, &cmds[0], None, b"main").unwrap();
+        save(&asts, &cmds[1], Some("x86_64-linux-gnu"), b"util").unwrap();
+
+        // The project and its original compile commands moved elsewhere
+        let moved = compile_cmds(&root.join("moved"), db);
+        assert_eq!(load(&asts, &moved[0], None).unwrap(), b"main");
+
+        // The compile commands regenerated in the new place
+        let new = root.join("new");
+        let regenerated = compile_cmds(
+            &new,
+            serde_json::json!([
+                {
+                    "directory": new,
+                    "arguments": ["cc", "-c", "src/main.c"],
+                    "file": new.join("src/main.c"),
+                },
+                {
+                    "directory": new,
+                    "arguments": ["cc", "-c", "util/main.c"],
+                    "file": "util/main.c",
+                },
+            ]),
+        );
+        let targets = vec!["x86_64-linux-gnu".to_string()];
+        assert_eq!(
+            read_all(&asts, &regenerated[0], &[]).unwrap(),
+            vec![b"main"]
+        );
+        assert_eq!(
+            read_all(&asts, &regenerated[1], &targets).unwrap(),
+            vec![b"util"]
+        );
+        assert!(load(&asts, &regenerated[1], None).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
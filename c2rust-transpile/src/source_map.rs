@@ -0,0 +1,322 @@
+//! Map from the translated Rust code back to the C code it was translated from, written with
+//! `--source-map`.
+//!
+//! Spans in the Rust AST don't survive pretty-printing, so the translator records the location
+//! of each item and statement it translates as a marker comment instead (see
+//! `Translation::source_marker`). Marker comments go through the `CommentStore` like any other
+//! comment, and the pretty-printer puts each of them right before the node it annotates, usually
+//! on a line of its own but sometimes within a line, like in a block printed on one line.
+//! `extract_source_map` then removes the markers from the printed code and records where each
+//! annotated node ended up.
+
+use std::cmp::Reverse;
+use std::fs::File;
+use std::path::Path;
+
+use failure::Error;
+
+use crate::c_ast::SrcLoc;
+use crate::report::Location;
+
+const MARKER_PREFIX: &str = "/*c2rust-source-map ";
+const MARKER_SUFFIX: &str = "*/";
+
+/// A range of the translated Rust code and the start of the C code it was translated from
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SourceMapping {
+    /// Byte offset of the start of the Rust code
+    pub start: usize,
+    /// Byte offset just past the end of the Rust code
+    pub end: usize,
+    /// First line of the Rust code, starting at 1
+    pub line: usize,
+    /// Last line of the Rust code, starting at 1
+    pub end_line: usize,
+    pub source: Location,
+}
+
+#[derive(Serialize)]
+struct SourceMap<'a> {
+    file: &'a Path,
+    mappings: &'a [SourceMapping],
+}
+
+/// The marker comment for the `index`th recorded source location
+pub fn marker(index: usize) -> String {
+    format!("{}{}{}", MARKER_PREFIX, index, MARKER_SUFFIX)
+}
+
+pub fn is_marker(comment: &str) -> bool {
+    parse_marker(comment).is_some()
+}
+
+fn parse_marker(line: &str) -> Option<usize> {
+    let line = line.trim();
+    if line.starts_with(MARKER_PREFIX) && line.ends_with(MARKER_SUFFIX) {
+        line[MARKER_PREFIX.len()..line.len() - MARKER_SUFFIX.len()]
+            .parse()
+            .ok()
+    } else {
+        None
+    }
+}
+
+/// Remove the markers from `line`. Returns the line without them, and the index of each marker
+/// along with the offset in the returned line of the code it precedes.
+fn strip_markers(line: &str) -> (String, Vec<(usize, usize)>) {
+    let mut stripped = String::with_capacity(line.len());
+    let mut markers = vec![];
+    let mut rest = line;
+    while let Some(start) = rest.find(MARKER_PREFIX) {
+        let after_prefix = &rest[start + MARKER_PREFIX.len()..];
+        let index = after_prefix.find(MARKER_SUFFIX).and_then(|end| {
+            let index = after_prefix[..end].parse().ok()?;
+            Some((index, end + MARKER_SUFFIX.len()))
+        });
+        match index {
+            Some((index, len)) => {
+                stripped.push_str(&rest[..start]);
+                markers.push((index, stripped.len()));
+                let after = &after_prefix[len..];
+                // The printer separates the marker from the code it precedes with a space
+                rest = if after.starts_with(' ') {
+                    &after[1..]
+                } else {
+                    after
+                };
+            }
+            None => {
+                let end = start + MARKER_PREFIX.len();
+                stripped.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+        }
+    }
+    stripped.push_str(rest);
+    (stripped, markers)
+}
+
+/// The length of the node at the start of `code`, which is part of a single line: up to a
+/// delimiter that closes one opened before it, a `,` outside of any delimiters, or just past a
+/// `;` outside of any delimiters
+fn inline_node_len(code: &str) -> usize {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in code.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth == 0 => return code[..i].trim_end().len(),
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => return i,
+            ';' if depth == 0 => return i + 1,
+            _ => {}
+        }
+    }
+    code.trim_end().len()
+}
+
+/// Remove the markers from the pretty-printed `code` and map each node they annotate to the
+/// entry of `locs` its marker refers to. When several markers annotate the same node, the last
+/// one wins, since it comes from the innermost C statement.
+///
+/// Nodes whose marker starts their line end at the first line that is indented less than their
+/// first line, or as much but doesn't start with a closing delimiter. Nodes whose marker is
+/// within a line end on the same line.
+pub fn extract_source_map(code: &str, locs: &[SrcLoc]) -> (String, Vec<SourceMapping>) {
+    let mut output = String::with_capacity(code.len());
+    let mut mappings = vec![];
+
+    // Start offset, line and indentation of the nodes that haven't ended yet, innermost last
+    let mut open: Vec<(usize, usize, usize, &SrcLoc)> = vec![];
+    let mut marker = None;
+    let mut line_no = 0;
+    // End offset and number of the last line that wasn't blank
+    let mut last_line = (0, 0);
+
+    for line in code.split('\n') {
+        let (line, markers) = strip_markers(line);
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        // Markers before any code annotate the node that starts the line, or the next line
+        let (leading, inline): (Vec<_>, Vec<_>) = markers
+            .into_iter()
+            .partition(|&(_, offset)| offset <= indent);
+        marker = leading.last().map(|&(index, _)| index).or(marker);
+        if trimmed.is_empty() && !leading.is_empty() {
+            continue;
+        }
+        line_no += 1;
+
+        if !trimmed.is_empty() {
+            let closes = trimmed.starts_with(|c| c == '}' || c == ')' || c == ']');
+            while let Some(&(start, line, node_indent, loc)) = open.last() {
+                if indent > node_indent || indent == node_indent && closes {
+                    break;
+                }
+                open.pop();
+                mappings.push(SourceMapping {
+                    start,
+                    end: last_line.0,
+                    line,
+                    end_line: last_line.1,
+                    source: loc.into(),
+                });
+            }
+        }
+
+        if let Some(loc) = marker.take().and_then(|index| locs.get(index)) {
+            open.push((output.len() + indent, line_no, indent, loc));
+        }
+        for (index, offset) in inline {
+            if let Some(loc) = locs.get(index) {
+                let start = output.len() + offset;
+                mappings.push(SourceMapping {
+                    start,
+                    end: start + inline_node_len(&line[offset..]),
+                    line: line_no,
+                    end_line: line_no,
+                    source: loc.into(),
+                });
+            }
+        }
+
+        output.push_str(&line);
+        if !trimmed.is_empty() {
+            last_line = (output.len(), line_no);
+        }
+        output.push('\n');
+    }
+    // `split` yields the text after the last newline as well
+    output.pop();
+
+    for (start, line, _, loc) in open.into_iter().rev() {
+        mappings.push(SourceMapping {
+            start,
+            end: last_line.0,
+            line,
+            end_line: last_line.1,
+            source: loc.into(),
+        });
+    }
+    mappings.sort_by_key(|m| (m.start, Reverse(m.end)));
+
+    (output, mappings)
+}
+
+/// Write the source map of the translated file `file`
+pub fn write_source_map(path: &Path, file: &Path, mappings: &[SourceMapping]) -> Result<(), Error> {
+    let source_map = SourceMap { file, mappings };
+    serde_json::to_writer_pretty(File::create(path)?, &source_map)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(line: u64) -> SrcLoc {
+        SrcLoc {
+            fileid: 0,
+            line,
+            column: 1,
+            file_path: None,
+        }
+    }
+
+    #[test]
+    fn nested_nodes() {
+        let code = [
+            &marker(0),
+            "pub unsafe extern \"C\" fn f(mut x: libc::c_int) -> libc::c_int {",
+            &format!("    {}", marker(1)),
+            &format!("    {}", marker(2)),
+            "    if x != 0 {",
+            &format!("        {}", marker(3)),
+            "        x += 1",
+            "    } else { x -= 1 }",
+            "",
+            &format!("    {}", marker(4)),
+            "    return x;",
+            "}",
+            "",
+        ]
+        .join("\n");
+        let locs: Vec<_> = (1..=5).map(loc).collect();
+        let (output, mappings) = extract_source_map(&code, &locs);
+
+        assert!(!output.contains("c2rust-source-map"));
+        assert!(output.ends_with("}\n"));
+        let ranges: Vec<_> = mappings
+            .iter()
+            .map(|m| (&output[m.start..m.end], m.line, m.end_line, m.source.line))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (output.trim_end(), 1, 7, 1),
+                (
+                    "if x != 0 {\n        x += 1\n    } else { x -= 1 }",
+                    2,
+                    4,
+                    3
+                ),
+                ("x += 1", 3, 3, 4),
+                ("return x;", 6, 6, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_markers() {
+        let code = [
+            &marker(0),
+            "fn f(mut x: i32) -> i32 {",
+            &format!(
+                "    if x != 0 {{ {}x += 1 }} else {{ {} x -= 1; }}",
+                marker(1),
+                marker(2)
+            ),
+            &format!("    {} g(x, {} h(\"(\", x));", marker(3), marker(4)),
+            &format!("    {} return x;", marker(5)),
+            "}",
+        ]
+        .join("\n");
+        let locs: Vec<_> = (1..=6).map(loc).collect();
+        let (output, mappings) = extract_source_map(&code, &locs);
+
+        assert_eq!(
+            output,
+            "fn f(mut x: i32) -> i32 {\n\
+             \x20   if x != 0 { x += 1 } else { x -= 1; }\n\
+             \x20   g(x, h(\"(\", x));\n\
+             \x20   return x;\n\
+             }"
+        );
+        let ranges: Vec<_> = mappings
+            .iter()
+            .map(|m| (&output[m.start..m.end], m.line, m.end_line, m.source.line))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (output.as_str(), 1, 5, 1),
+                ("x += 1", 2, 2, 2),
+                ("x -= 1;", 2, 2, 3),
+                ("g(x, h(\"(\", x));", 3, 3, 4),
+                ("h(\"(\", x)", 3, 3, 5),
+                ("return x;", 4, 4, 6),
+            ]
+        );
+    }
+}
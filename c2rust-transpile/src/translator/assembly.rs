@@ -1,20 +1,95 @@
 #![deny(missing_docs)]
-//! This module provides basic support for converting inline assembly statements.
+//! This module provides support for converting inline assembly statements.
+//!
+//! Clang hands us the assembly template in LLVM's syntax, where operands are referred to as `$N`
+//! or `${N:modifier}`, together with constraints that the exporter has already simplified. Since
+//! `asm!` passes both on to LLVM as they are, they mean the same thing in Rust as they did to
+//! Clang. We only check that the constraints are ones we know how to pass, and make up for the
+//! differences in how `asm!` takes its operands.
 
 use super::*;
 
+/// How an operand of an inline assembly statement is passed to `asm!`
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum OperandKind {
+    /// A value in a register, possibly a specific one
+    Reg,
+    /// A constant
+    Const,
+    /// An operand in memory, passed to the assembly by address
+    Memory,
+    /// An input tied to the output with the given index
+    Tied(usize),
+}
+
+/// An operand constraint, as simplified by the AST exporter
+struct Constraint {
+    /// The output is read as well as written (`+`)
+    read_write: bool,
+    kind: OperandKind,
+}
+
+/// Parse a constraint, returning it back if it isn't supported.
+fn parse_constraint(constraints: &str) -> Result<Constraint, String> {
+    let mut read_write = false;
+    let mut is_memory = false;
+
+    let mut body = constraints;
+    loop {
+        match body.chars().next() {
+            Some('=') | Some('&') => {}
+            Some('+') => read_write = true,
+            // The exporter marks operands that have to be passed by address with `*`
+            Some('*') => is_memory = true,
+            _ => break,
+        }
+        body = &body[1..];
+    }
+
+    let kind = if is_memory {
+        OperandKind::Memory
+    } else if !body.is_empty() && body.bytes().all(|b| b.is_ascii_digit()) {
+        OperandKind::Tied(body.parse().map_err(|_| constraints.to_string())?)
+    } else if body.starts_with('{') && body.ends_with('}') {
+        OperandKind::Reg
+    } else if !body.is_empty() && body.chars().all(|c| "rgqQRlxv".contains(c)) {
+        OperandKind::Reg
+    } else if !body.is_empty() && body.chars().all(|c| "inIJKLMNeZ".contains(c)) {
+        OperandKind::Const
+    } else {
+        return Err(constraints.to_string());
+    };
+
+    Ok(Constraint { read_write, kind })
+}
+
+fn push_expr(tokens: &mut Vec<Token>, expr: P<Expr>) {
+    tokens.push(Token::Interpolated(Lrc::new(Nonterminal::NtExpr(expr))));
+}
+
+/// Push a comma-separated list of `"constraint"(expr)` operands.
+fn push_operands(tokens: &mut Vec<Token>, operands: Vec<(String, P<Expr>)>) {
+    for (i, (constraint, expr)) in operands.into_iter().enumerate() {
+        if i > 0 {
+            tokens.push(Token::Comma);
+        }
+        push_expr(tokens, mk().lit_expr(mk().str_lit(&constraint)));
+        push_expr(tokens, mk().paren_expr(expr));
+    }
+}
+
 impl<'c> Translation<'c> {
-    /// Convert an inline-assembly statement into one or more Rust statements.
-    /// If inline assembly translation is not enabled this will result in an
-    /// error message instead of a conversion. Because the inline assembly syntax
-    /// used in C is different than the one used in Rust (Rust uses the LLVM syntax
-    /// directly) the resulting translated assembly statements will be unlikely to work
-    /// without further manual translation. The translator will properly translate
-    /// the arguments to the assembly statement, however.
+    /// Convert a GCC inline assembly statement into an `asm!` invocation, along with any
+    /// statements needed to compute its operands. If inline assembly translation is not
+    /// enabled, or the statement uses constraints that we don't know how to pass to `asm!`,
+    /// this results in an error instead.
+    ///
+    /// Only x86 and x86_64 constraints are supported.
     pub fn convert_asm(
         &self,
         ctx: ExprContext,
         span: Span,
+        src_loc: &Option<SrcLoc>,
         is_volatile: bool,
         asm: &str,
         inputs: &[AsmOperand],
@@ -27,70 +102,110 @@ impl<'c> Translation<'c> {
             ));
         }
 
-        self.use_feature("asm");
+        let parse_constraints = |operands: &[AsmOperand]| {
+            operands
+                .iter()
+                .map(|operand| parse_constraint(&operand.constraints))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|constraint| {
+                    self.unsupported_asm(src_loc, &format!("constraint \"{}\"", constraint))
+                })
+        };
+        let output_constraints = parse_constraints(outputs)?;
+        let input_constraints = parse_constraints(inputs)?;
 
-        fn push_expr(tokens: &mut Vec<Token>, expr: P<Expr>) {
-            tokens.push(Token::Interpolated(Lrc::new(Nonterminal::NtExpr(expr))));
+        // Inputs can only share a register with an output that doesn't already take an input
+        let mut tied_outputs = vec![];
+        for constraint in &input_constraints {
+            if let OperandKind::Tied(output_idx) = constraint.kind {
+                let tied_to_register = output_constraints.get(output_idx).map_or(false, |output| {
+                    !output.read_write && output.kind == OperandKind::Reg
+                });
+                if !tied_to_register || tied_outputs.contains(&output_idx) {
+                    return Err(self.unsupported_asm(
+                        src_loc,
+                        &format!("input tied to operand {}", output_idx),
+                    ));
+                }
+                tied_outputs.push(output_idx);
+            }
         }
 
+        self.use_feature("asm");
+
         let mut stmts: Vec<Stmt> = vec![];
-        let mut tokens: Vec<Token> = vec![];
-        let mut first;
+        let mut output_args = vec![];
+        let mut input_args = vec![];
+        // Inputs that `asm!` doesn't add for us go after the ones from C, so that the template's
+        // operand numbers still refer to the same operands
+        let mut extra_input_args = vec![];
 
-        // Assembly template
-        push_expr(&mut tokens, mk().lit_expr(mk().str_lit(asm)));
+        for (operand, constraint) in outputs.iter().zip(&output_constraints) {
+            let mut result = self.convert_expr(ctx.used(), operand.expression)?;
+            stmts.append(result.stmts_mut());
+            let place = result.into_value();
 
-        // Outputs and Inputs
-        for &(list, is_output) in &[(outputs, true), (inputs, false)] {
-            first = true;
-            tokens.push(Token::Colon); // Always emitted, even if list is empty
-
-            for &AsmOperand {
-                ref constraints,
-                expression,
-            } in list
-            {
-                if first {
-                    first = false
-                } else {
-                    tokens.push(Token::Comma)
-                }
+            if constraint.kind != OperandKind::Memory {
+                // `asm!` reads read-write register operands itself
+                output_args.push((operand.constraints.clone(), place));
+                continue;
+            }
 
-                let mut result = self.convert_expr(ctx.used(), expression)?;
-                stmts.append(result.stmts_mut());
-
-                let mut result = result.into_value();
-                if constraints.contains('*') {
-                    // If the constraint string contains `*`, then
-                    // c2rust-ast-exporter added it (there's no gcc equivalent);
-                    // in this case, we need to do what clang does and pass in
-                    // the operand by-address instead of by-value
-                    if is_output {
-                        result = mk().mutbl().addr_of_expr(result);
-                    } else {
-                        result = mk().addr_of_expr(result);
-                    }
-                }
+            // Memory operands are passed by address. `asm!` would tie the input of a read-write
+            // one to the output, so we pass the address in again as a memory input like Clang
+            // does, computing it only once.
+            let addr = mk().mutbl().addr_of_expr(place);
+            if constraint.read_write {
+                let addr_name = self.renamer.borrow_mut().fresh();
+                stmts.push(mk().local_stmt(P(mk().local(
+                    mk().ident_pat(&addr_name),
+                    None as Option<P<Ty>>,
+                    Some(mk().cast_expr(addr, mk().mutbl().ptr_ty(mk().infer_ty()))),
+                ))));
+                let memory = operand
+                    .constraints
+                    .trim_start_matches(|c| c == '+' || c == '&');
+                output_args.push((format!("={}", memory), mk().ident_expr(&addr_name)));
+                extra_input_args.push((memory.to_string(), mk().ident_expr(&addr_name)));
+            } else {
+                output_args.push((operand.constraints.clone(), addr));
+            }
+        }
 
-                push_expr(&mut tokens, mk().lit_expr(mk().str_lit(constraints)));
-                push_expr(&mut tokens, mk().paren_expr(result));
+        for (operand, constraint) in inputs.iter().zip(&input_constraints) {
+            let mut result = self.convert_expr(ctx.used(), operand.expression)?;
+            stmts.append(result.stmts_mut());
+            let mut value = result.into_value();
+            if constraint.kind == OperandKind::Memory {
+                value = mk().addr_of_expr(value);
             }
+            input_args.push((operand.constraints.clone(), value));
         }
+        input_args.extend(extra_input_args);
+
+        let mut tokens: Vec<Token> = vec![];
 
-        // Clobbers
-        first = true;
+        // Assembly template
+        push_expr(&mut tokens, mk().lit_expr(mk().str_lit(asm)));
+
+        // Outputs and inputs, whose lists are always emitted, even if empty
         tokens.push(Token::Colon);
-        for clobber in clobbers {
-            if first {
-                first = false
-            } else {
-                tokens.push(Token::Comma)
+        push_operands(&mut tokens, output_args);
+        tokens.push(Token::Colon);
+        push_operands(&mut tokens, input_args);
+
+        // Clobbers, which GCC allows to name registers with a `%`
+        tokens.push(Token::Colon);
+        for (i, clobber) in clobbers.iter().enumerate() {
+            if i > 0 {
+                tokens.push(Token::Comma);
             }
+            let clobber = clobber.trim_start_matches('%');
             push_expr(&mut tokens, mk().lit_expr(mk().str_lit(clobber)));
         }
 
-        // Options
-        if is_volatile {
+        // Options. GCC treats statements without outputs as volatile.
+        if is_volatile || outputs.is_empty() {
             tokens.push(Token::Colon);
             push_expr(&mut tokens, mk().lit_expr(mk().str_lit("volatile")));
         }
@@ -101,9 +216,23 @@ impl<'c> Translation<'c> {
             MacDelimiter::Parenthesis,
         );
         let mac = mk().mac_expr(mac);
-        let mac = mk().span(span).expr_stmt(mac);
+        let mac = mk().span(span).semi_stmt(mac);
         stmts.push(mac);
 
         Ok(stmts)
     }
+
+    /// Report an inline assembly statement that we can't translate.
+    fn unsupported_asm(&self, src_loc: &Option<SrcLoc>, reason: &str) -> TranslationError {
+        match src_loc {
+            Some(loc) => diag!(
+                Diagnostic::Asm,
+                "{}: unsupported inline assembly: {}",
+                loc,
+                reason
+            ),
+            None => diag!(Diagnostic::Asm, "unsupported inline assembly: {}", reason),
+        }
+        format_translation_err!(src_loc, "Unsupported inline assembly: {}", reason)
+    }
 }
@@ -0,0 +1,548 @@
+//! This module provides translation of C11 `_Atomic` objects and of the C11 and GNU atomic
+//! builtins (`__c11_atomic_*` and `__atomic_*`). Both are translated into the atomic intrinsics
+//! in `core::intrinsics`, which operate on raw pointers, so `_Atomic T` itself translates to `T`.
+//! The `__sync_*` builtins are handled in `builtins.rs`.
+//!
+//! Plain accesses to `_Atomic` objects, such as `x = 1` or `x++`, are sequentially consistent,
+//! as they are in C.
+
+use super::*;
+
+/// C11 memory order, as passed to the atomic builtins
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum MemoryOrder {
+    Relaxed,
+    Acquire,
+    Release,
+    AcqRel,
+    SeqCst,
+}
+
+impl MemoryOrder {
+    /// Interpret the value of an `__ATOMIC_*` constant. `__ATOMIC_CONSUME` is strengthened to
+    /// acquire, like every C compiler does.
+    fn from_value(value: u64) -> Option<Self> {
+        match value {
+            0 => Some(MemoryOrder::Relaxed),
+            1 | 2 => Some(MemoryOrder::Acquire),
+            3 => Some(MemoryOrder::Release),
+            4 => Some(MemoryOrder::AcqRel),
+            5 => Some(MemoryOrder::SeqCst),
+            _ => None,
+        }
+    }
+
+    /// Suffix of the intrinsic implementing the atomic operation `op` with this ordering.
+    /// Orderings that C doesn't allow for `op` fall back to sequential consistency.
+    fn suffix(self, op: &str) -> &'static str {
+        match (op, self) {
+            (_, MemoryOrder::SeqCst) => "",
+            (_, MemoryOrder::Relaxed) => "_relaxed",
+            ("load", MemoryOrder::Acquire) => "_acq",
+            ("load", _) => "",
+            ("store", MemoryOrder::Release) => "_rel",
+            ("store", _) => "",
+            (_, MemoryOrder::Acquire) => "_acq",
+            (_, MemoryOrder::Release) => "_rel",
+            (_, MemoryOrder::AcqRel) => "_acqrel",
+        }
+    }
+
+    /// Suffix of the compare and exchange intrinsic with the `success` and `failure` orderings.
+    /// Combinations that don't have an intrinsic fall back to sequential consistency.
+    fn cxchg_suffix(success: Self, failure: Self) -> &'static str {
+        use self::MemoryOrder::*;
+        match (success, failure) {
+            (SeqCst, Acquire) => "_failacq",
+            (SeqCst, Relaxed) => "_failrelaxed",
+            (Acquire, Acquire) => "_acq",
+            (Acquire, Relaxed) => "_acq_failrelaxed",
+            (Release, Relaxed) => "_rel",
+            (AcqRel, Acquire) => "_acqrel",
+            (AcqRel, Relaxed) => "_acqrel_failrelaxed",
+            (Relaxed, Relaxed) => "_relaxed",
+            _ => "",
+        }
+    }
+}
+
+/// Compute the new value of an atomic read-modify-write from the `old` value and the operand.
+fn rmw_result(op: &str, old: P<Expr>, val: P<Expr>) -> P<Expr> {
+    match op {
+        "xadd" => mk().method_call_expr(old, "wrapping_add", vec![val]),
+        "xsub" => mk().method_call_expr(old, "wrapping_sub", vec![val]),
+        "and" => mk().binary_expr(BinOpKind::BitAnd, old, val),
+        "or" => mk().binary_expr(BinOpKind::BitOr, old, val),
+        "xor" => mk().binary_expr(BinOpKind::BitXor, old, val),
+        "nand" => mk().unary_expr(
+            ast::UnOp::Not,
+            mk().paren_expr(mk().binary_expr(BinOpKind::BitAnd, old, val)),
+        ),
+        _ => panic!("No result computation for atomic operation {}", op),
+    }
+}
+
+impl<'c> Translation<'c> {
+    fn atomic_intrinsic(&self, name: &str) -> P<Expr> {
+        self.use_feature("core_intrinsics");
+        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
+        mk().path_expr(vec!["", std_or_core, "intrinsics", name])
+    }
+
+    /// Evaluate an integer constant expression such as `__ATOMIC_ACQUIRE` or
+    /// `memory_order_acquire`.
+    fn atomic_constant(&self, expr: CExprId) -> Option<u64> {
+        match self.ast_context[expr].kind {
+            CExprKind::Literal(_, CLiteral::Integer(value, _)) => Some(value),
+            CExprKind::ImplicitCast(_, expr, _, _, _)
+            | CExprKind::ExplicitCast(_, expr, _, _, _)
+            | CExprKind::Paren(_, expr) => self.atomic_constant(expr),
+            CExprKind::DeclRef(_, decl, _) => match self.ast_context[decl].kind {
+                CDeclKind::EnumConstant {
+                    value: ConstIntExpr::U(value),
+                    ..
+                } => Some(value),
+                CDeclKind::EnumConstant {
+                    value: ConstIntExpr::I(value),
+                    ..
+                } => Some(value as u64),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Memory orders that aren't constant are treated as sequentially consistent, which is
+    /// always correct if possibly slower.
+    fn memory_order(&self, order: Option<CExprId>) -> MemoryOrder {
+        order
+            .and_then(|order| self.atomic_constant(order))
+            .and_then(MemoryOrder::from_value)
+            .unwrap_or(MemoryOrder::SeqCst)
+    }
+
+    /// Translate a call to one of the C11 or GNU atomic builtins represented by
+    /// `CExprKind::Atomic`.
+    pub fn convert_atomic(
+        &self,
+        ctx: ExprContext,
+        expr_id: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let Located { ref loc, ref kind } = self.ast_context[expr_id];
+        let (name, ptr, order, val1, order_fail, val2, weak) = match *kind {
+            CExprKind::Atomic {
+                ref name,
+                ptr,
+                order,
+                val1,
+                order_fail,
+                val2,
+                weak,
+                ..
+            } => (name, ptr, order, val1, order_fail, val2, weak),
+            _ => return Err(format_err!("Expected an atomic expression").into()),
+        };
+
+        if ctx.is_static {
+            return Err(format_translation_err!(
+                loc,
+                "Atomic builtins are not supported in static initializers",
+            ));
+        }
+
+        let pointee_ty = self
+            .ast_context
+            .get_pointee_qual_type(self.ast_context[ptr].kind.get_type().unwrap())
+            .ok_or_else(|| format_translation_err!(loc, "{} expects a pointer", name))?;
+        let value_kind = &self.ast_context.resolve_type(pointee_ty.ctype).kind;
+
+        // The generic GNU builtins pass values indirectly through pointers
+        let is_generic = match name.as_str() {
+            "__atomic_load"
+            | "__atomic_store"
+            | "__atomic_exchange"
+            | "__atomic_compare_exchange" => true,
+            _ => false,
+        };
+        let op = name
+            .trim_start_matches("__c11_atomic_")
+            .trim_start_matches("__atomic_");
+        let order = self.memory_order(order);
+
+        let mut operands = vec![ptr];
+        operands.extend(val1);
+        operands.extend(val2);
+        let operands = self.convert_exprs(ctx.used(), &operands)?;
+
+        let result = operands.and_then(|operands| {
+            let mut operands = operands.into_iter();
+            let ptr = operands.next().unwrap();
+            let mut next_operand = || {
+                operands
+                    .next()
+                    .ok_or_else(|| format_translation_err!(loc, "Missing operand to {}", name))
+            };
+            let deref = |e: P<Expr>| mk().unary_expr(ast::UnOp::Deref, e);
+
+            match op {
+                "init" => {
+                    let store = self.atomic_intrinsic("atomic_store_relaxed");
+                    let val = next_operand()?;
+                    Ok(WithStmts::new_val(mk().call_expr(store, vec![ptr, val])))
+                }
+
+                "load" | "load_n" => {
+                    let load =
+                        self.atomic_intrinsic(&format!("atomic_load{}", order.suffix("load")));
+                    let val = mk().call_expr(load, vec![ptr]);
+                    if is_generic {
+                        let ret = next_operand()?;
+                        Ok(WithStmts::new_val(mk().assign_expr(deref(ret), val)))
+                    } else {
+                        Ok(WithStmts::new_val(val))
+                    }
+                }
+
+                "store" | "store_n" => {
+                    let store =
+                        self.atomic_intrinsic(&format!("atomic_store{}", order.suffix("store")));
+                    let val = next_operand()?;
+                    let val = if is_generic { deref(val) } else { val };
+                    Ok(WithStmts::new_val(mk().call_expr(store, vec![ptr, val])))
+                }
+
+                "exchange" | "exchange_n" => {
+                    let xchg =
+                        self.atomic_intrinsic(&format!("atomic_xchg{}", order.suffix("xchg")));
+                    let val = next_operand()?;
+                    if is_generic {
+                        let ret = next_operand()?;
+                        let old = mk().call_expr(xchg, vec![ptr, deref(val)]);
+                        Ok(WithStmts::new_val(mk().assign_expr(deref(ret), old)))
+                    } else {
+                        Ok(WithStmts::new_val(mk().call_expr(xchg, vec![ptr, val])))
+                    }
+                }
+
+                "compare_exchange"
+                | "compare_exchange_n"
+                | "compare_exchange_strong"
+                | "compare_exchange_weak" => {
+                    let is_weak = op == "compare_exchange_weak"
+                        || weak
+                            .and_then(|weak| self.atomic_constant(weak))
+                            .map_or(false, |w| w != 0);
+                    let suffix = MemoryOrder::cxchg_suffix(order, self.memory_order(order_fail));
+                    let cxchg_name = if is_weak {
+                        "atomic_cxchgweak"
+                    } else {
+                        "atomic_cxchg"
+                    };
+                    let cxchg = self.atomic_intrinsic(&format!("{}{}", cxchg_name, suffix));
+
+                    let expected = next_operand()?;
+                    let desired = next_operand()?;
+                    let desired = if is_generic { deref(desired) } else { desired };
+
+                    // let expected = ...;
+                    // let fresh = atomic_cxchg(ptr, *expected, desired);
+                    // if !fresh.1 { *expected = fresh.0 }
+                    // fresh.1
+                    let expected_name = self.renamer.borrow_mut().fresh();
+                    let expected_let = mk().local_stmt(P(mk().local(
+                        mk().ident_pat(&expected_name),
+                        None as Option<P<Ty>>,
+                        Some(expected),
+                    )));
+                    let result_name = self.renamer.borrow_mut().fresh();
+                    let call = mk().call_expr(
+                        cxchg,
+                        vec![ptr, deref(mk().ident_expr(&expected_name)), desired],
+                    );
+                    let result_let = mk().local_stmt(P(mk().local(
+                        mk().ident_pat(&result_name),
+                        None as Option<P<Ty>>,
+                        Some(call),
+                    )));
+                    let update_expected = mk().ifte_expr(
+                        mk().unary_expr(
+                            ast::UnOp::Not,
+                            mk().field_expr(mk().ident_expr(&result_name), "1"),
+                        ),
+                        mk().block(vec![mk().semi_stmt(mk().assign_expr(
+                            deref(mk().ident_expr(&expected_name)),
+                            mk().field_expr(mk().ident_expr(&result_name), "0"),
+                        ))]),
+                        None as Option<P<Expr>>,
+                    );
+
+                    Ok(WithStmts::new(
+                        vec![expected_let, result_let, mk().semi_stmt(update_expected)],
+                        mk().field_expr(mk().ident_expr(&result_name), "1"),
+                    ))
+                }
+
+                _ if value_kind.is_pointer() => Err(format_translation_err!(
+                    loc,
+                    "Atomic arithmetic on pointers is not supported: {}",
+                    name,
+                )),
+
+                _ => {
+                    let (is_fetch_first, rmw_op) = if op.starts_with("fetch_") {
+                        (true, op.trim_start_matches("fetch_"))
+                    } else {
+                        (false, op.trim_end_matches("_fetch"))
+                    };
+                    let intrinsic_op = match rmw_op {
+                        "add" => "xadd",
+                        "sub" => "xsub",
+                        "and" | "or" | "xor" | "nand" => rmw_op,
+                        "min" | "max" if !is_fetch_first => {
+                            return Err(format_translation_err!(
+                                loc,
+                                "Unimplemented atomic {}",
+                                name
+                            ))
+                        }
+                        "min" | "max" if value_kind.is_unsigned_integral_type() => {
+                            if rmw_op == "min" {
+                                "umin"
+                            } else {
+                                "umax"
+                            }
+                        }
+                        "min" | "max" => rmw_op,
+                        _ => {
+                            return Err(format_translation_err!(
+                                loc,
+                                "Unimplemented atomic {}",
+                                name
+                            ))
+                        }
+                    };
+                    let rmw = self.atomic_intrinsic(&format!(
+                        "atomic_{}{}",
+                        intrinsic_op,
+                        order.suffix(intrinsic_op),
+                    ));
+                    let val = next_operand()?;
+
+                    if is_fetch_first {
+                        Ok(WithStmts::new_val(mk().call_expr(rmw, vec![ptr, val])))
+                    } else {
+                        // The operand is used twice, so we bind it to a temporary
+                        let val_name = self.renamer.borrow_mut().fresh();
+                        let val_let = mk().local_stmt(P(mk().local(
+                            mk().ident_pat(&val_name),
+                            None as Option<P<Ty>>,
+                            Some(val),
+                        )));
+                        let old = mk().call_expr(rmw, vec![ptr, mk().ident_expr(&val_name)]);
+                        Ok(WithStmts::new(
+                            vec![val_let],
+                            rmw_result(intrinsic_op, old, mk().ident_expr(&val_name)),
+                        ))
+                    }
+                }
+            }
+        })?;
+
+        self.convert_side_effects_expr(ctx, result, "Builtin is not supposed to be used")
+    }
+
+    /// Translate an `__atomic_thread_fence` or `__atomic_signal_fence` with the memory order
+    /// `order`. Signal fences only need to order accesses with respect to the current thread.
+    pub fn convert_atomic_fence(
+        &self,
+        ctx: ExprContext,
+        is_signal: bool,
+        order: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let order = self.memory_order(Some(order));
+        let fence = if is_signal {
+            "atomic_singlethreadfence"
+        } else {
+            "atomic_fence"
+        };
+        // There are no relaxed fences
+        let fence = match order {
+            MemoryOrder::Relaxed => {
+                return Ok(WithStmts::new_val(mk().tuple_expr(vec![] as Vec<P<Expr>>)))
+            }
+            MemoryOrder::SeqCst => fence.to_string(),
+            order => format!("{}{}", fence, order.suffix("fence")),
+        };
+        let call = mk().call_expr(self.atomic_intrinsic(&fence), vec![] as Vec<P<Expr>>);
+        self.convert_side_effects_expr(
+            ctx,
+            WithStmts::new_val(call),
+            "Builtin is not supposed to be used",
+        )
+    }
+
+    /// Is `expr` an lvalue of `_Atomic` type?
+    pub fn is_atomic_lvalue(&self, expr: CExprId) -> bool {
+        self.ast_context[expr]
+            .kind
+            .get_type()
+            .map_or(false, |ty| self.ast_context.is_atomic_type(ty))
+    }
+
+    /// Compute a raw pointer to the `_Atomic` lvalue `lval`.
+    fn atomic_place_address(
+        &self,
+        ctx: ExprContext,
+        lval: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let lval_ty = self.ast_context[lval]
+            .kind
+            .get_qual_type()
+            .ok_or_else(|| format_err!("bad atomic lvalue type"))?;
+        if ctx.is_static {
+            return Err(format_translation_err!(
+                self.ast_context[lval].loc,
+                "Atomic accesses are not supported in static initializers",
+            ));
+        }
+
+        // Like `volatile_write`, we only need to cast pointers to const objects
+        let ptr_ty = mk().mutbl().ptr_ty(self.convert_type(lval_ty.ctype)?);
+        Ok(self
+            .convert_expr(ctx.used(), lval)?
+            .map(|place| match place.node {
+                ExprKind::Unary(ast::UnOp::Deref, ref ptr) if !lval_ty.qualifiers.is_const => {
+                    ptr.clone()
+                }
+                ExprKind::Unary(ast::UnOp::Deref, ref ptr) => mk().cast_expr(ptr, ptr_ty),
+                _ => mk().cast_expr(mk().mutbl().addr_of_expr(&place), ptr_ty),
+            }))
+    }
+
+    /// Translate the operand of an `AtomicToNonAtomic` cast, which reads an `_Atomic` lvalue.
+    pub fn convert_atomic_read(
+        &self,
+        ctx: ExprContext,
+        expr: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        match self.ast_context[expr].kind {
+            CExprKind::ImplicitCast(_, lval, CastKind::LValueToRValue, _, _) => {
+                let load = self.atomic_intrinsic("atomic_load");
+                Ok(self
+                    .atomic_place_address(ctx, lval)?
+                    .map(|ptr| mk().call_expr(load, vec![ptr])))
+            }
+            _ => self.convert_expr(ctx, expr),
+        }
+    }
+
+    /// Translate an assignment or compound assignment to the `_Atomic` lvalue `lhs`.
+    pub fn convert_atomic_assignment(
+        &self,
+        ctx: ExprContext,
+        op: c_ast::BinOp,
+        lhs: CExprId,
+        rhs: WithStmts<P<Expr>>,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let lhs_ty = self.ast_context[lhs]
+            .kind
+            .get_qual_type()
+            .ok_or_else(|| format_err!("bad atomic lvalue type"))?;
+        let is_integral = self
+            .ast_context
+            .resolve_type(lhs_ty.ctype)
+            .kind
+            .is_integral_type();
+        let rmw_op = match op {
+            c_ast::BinOp::Assign => None,
+            c_ast::BinOp::AssignAdd if is_integral => Some("xadd"),
+            c_ast::BinOp::AssignSubtract if is_integral => Some("xsub"),
+            c_ast::BinOp::AssignBitAnd if is_integral => Some("and"),
+            c_ast::BinOp::AssignBitOr if is_integral => Some("or"),
+            c_ast::BinOp::AssignBitXor if is_integral => Some("xor"),
+            _ => {
+                return Err(format_translation_err!(
+                    self.ast_context[lhs].loc,
+                    "Unsupported compound assignment to an atomic lvalue",
+                ))
+            }
+        };
+
+        // The right-hand side may have been computed in a wider type
+        let ty = self.convert_type(lhs_ty.ctype)?;
+        let ptr = self.atomic_place_address(ctx, lhs)?;
+        let result = ptr.and_then(|ptr| {
+            rhs.and_then(|rhs| {
+                let rhs = mk().cast_expr(rhs, ty);
+                let val_name = self.renamer.borrow_mut().fresh();
+                let val_let = mk().local_stmt(P(mk().local(
+                    mk().ident_pat(&val_name),
+                    None as Option<P<Ty>>,
+                    Some(rhs.clone()),
+                )));
+                let val = mk().ident_expr(&val_name);
+
+                Ok(match rmw_op {
+                    None if ctx.is_used() => {
+                        let store = self.atomic_intrinsic("atomic_store");
+                        let store = mk().call_expr(store, vec![ptr, val.clone()]);
+                        WithStmts::new(vec![val_let, mk().semi_stmt(store)], val)
+                    }
+                    None => {
+                        let store = self.atomic_intrinsic("atomic_store");
+                        WithStmts::new_val(mk().call_expr(store, vec![ptr, rhs]))
+                    }
+                    Some(op) if ctx.is_used() => {
+                        let rmw = self.atomic_intrinsic(&format!("atomic_{}", op));
+                        let old = mk().call_expr(rmw, vec![ptr, val.clone()]);
+                        WithStmts::new(vec![val_let], rmw_result(op, old, val))
+                    }
+                    Some(op) => {
+                        let rmw = self.atomic_intrinsic(&format!("atomic_{}", op));
+                        WithStmts::new_val(mk().call_expr(rmw, vec![ptr, rhs]))
+                    }
+                })
+            })
+        })?;
+        self.convert_side_effects_expr(ctx, result, "Atomic assignment is not supposed to be used")
+    }
+
+    /// Translate an increment or decrement of the `_Atomic` lvalue `arg`. Postfix operators
+    /// evaluate to the old value, prefix operators to the new one.
+    pub fn convert_atomic_increment(
+        &self,
+        ctx: ExprContext,
+        up: bool,
+        is_prefix: bool,
+        arg: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let arg_ty = self.ast_context[arg]
+            .kind
+            .get_qual_type()
+            .ok_or_else(|| format_err!("bad atomic lvalue type"))?;
+        if !self
+            .ast_context
+            .resolve_type(arg_ty.ctype)
+            .kind
+            .is_integral_type()
+        {
+            return Err(format_translation_err!(
+                self.ast_context[arg].loc,
+                "Only atomic integers can be incremented or decremented",
+            ));
+        }
+
+        let op = if up { "xadd" } else { "xsub" };
+        let rmw = self.atomic_intrinsic(&format!("atomic_{}", op));
+        let one = || mk().lit_expr(mk().int_lit(1, LitIntType::Unsuffixed));
+        let result = self.atomic_place_address(ctx, arg)?.map(|ptr| {
+            let old = mk().call_expr(rmw, vec![ptr, one()]);
+            if is_prefix && ctx.is_used() {
+                rmw_result(op, old, one())
+            } else {
+                old
+            }
+        });
+        self.convert_side_effects_expr(ctx, result, "Atomic increment is not supposed to be used")
+    }
+}
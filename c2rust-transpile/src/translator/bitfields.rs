@@ -9,7 +9,7 @@ use super::TranslationError;
 use crate::c_ast::{
     BinOp, CDeclId, CDeclKind, CExprId, CExprKind, CQualTypeId, CTypeId, MemberKind, UnOp,
 };
-use crate::translator::{simple_metaitem, ConvertedDecl, ExprContext, Translation};
+use crate::translator::{ConvertedDecl, ExprContext, Translation};
 use crate::with_stmts::WithStmts;
 use c2rust_ast_builder::mk;
 use syntax::ast::{
@@ -225,8 +225,8 @@ impl<'a> Translation<'a> {
     /// ```
     pub fn convert_bitfield_struct_decl(
         &self,
+        decl_id: CDeclId,
         name: String,
-        manual_alignment: Option<u64>,
         platform_byte_size: u64,
         span: Span,
         field_info: Vec<FieldInfo>,
@@ -300,22 +300,13 @@ impl<'a> Translation<'a> {
             }
         }
 
-        let mut repr_items = vec![simple_metaitem("C")];
-
-        if let Some(align) = manual_alignment {
-            repr_items.push(simple_metaitem(&format!("align({})", align)));
-        }
-
-        let repr_attr = mk().meta_item("repr", MetaItemKind::List(repr_items));
-
-        let item = mk()
-            .span(span)
-            .pub_()
-            .call_attr("derive", vec!["BitfieldStruct", "Clone", "Copy"])
-            .meta_item_attr(AttrStyle::Outer, repr_attr)
-            .struct_item(name, field_entries);
-
-        Ok(ConvertedDecl::Item(item))
+        Ok(self.convert_record_layout(
+            decl_id,
+            name,
+            span,
+            vec!["BitfieldStruct", "Clone", "Copy"],
+            |builder, name| builder.struct_item(name, field_entries),
+        ))
     }
 
     /// Here we output a block to generate a struct literal initializer in.
@@ -93,6 +93,18 @@ impl<'c> Translation<'c> {
                 let val = self.convert_expr(ctx.used(), args[0])?;
                 Ok(val.map(|x| mk().method_call_expr(x, "abs", vec![] as Vec<P<Expr>>)))
             }
+            "creal" | "crealf" | "creall"
+            | "__builtin_creal" | "__builtin_crealf" | "__builtin_creall" => {
+                self.convert_complex_part(ctx, true, args[0])
+            }
+            "cimag" | "cimagf" | "cimagl"
+            | "__builtin_cimag" | "__builtin_cimagf" | "__builtin_cimagl" => {
+                self.convert_complex_part(ctx, false, args[0])
+            }
+            "conj" | "conjf" | "conjl"
+            | "__builtin_conj" | "__builtin_conjf" | "__builtin_conjl" => {
+                self.convert_complex_conj(ctx, args[0])
+            }
             "__builtin_flt_rounds" => {
                 // LLVM simply lowers this to the constant one which means
                 // that floats are rounded to the nearest number.
@@ -196,6 +208,32 @@ impl<'c> Translation<'c> {
                 Err(TranslationError::generic("Unsupported va_start"))
             }
             "__builtin_va_copy" => {
+                if self.is_va_list_runtime() {
+                    if ctx.is_unused() && args.len() == 2 {
+                        if let Some((dst_va_id, _src_va_id)) = self.match_vacopy(args[0], args[1]) {
+                            if self.is_copied_va_decl(dst_va_id) {
+                                let dst = self.convert_expr(ctx.used(), args[0])?;
+                                let src = self.convert_expr(ctx.used(), args[1])?;
+                                return dst.and_then(|dst| {
+                                    src.and_then(|src| {
+                                        let copy = mk().method_call_expr(
+                                            src,
+                                            "copy",
+                                            vec![] as Vec<P<Expr>>,
+                                        );
+                                        let stmt = mk().semi_stmt(mk().assign_expr(dst, copy));
+                                        Ok(WithStmts::new(
+                                            vec![stmt],
+                                            self.panic_or_err("va_copy stub"),
+                                        ))
+                                    })
+                                });
+                            }
+                        }
+                    }
+                    return Err(TranslationError::generic("Unsupported va_copy"));
+                }
+
                 // We are waiting on raw va_copy support to land in rustc:
                 // https://github.com/rust-lang/rust/pull/59625
                 Err(TranslationError::new(
@@ -235,6 +273,17 @@ impl<'c> Translation<'c> {
 
                             let val = self.convert_expr(ctx.used(), args[0])?;
 
+                            if self.is_va_list_runtime() {
+                                return val.and_then(|val| {
+                                    let call_expr =
+                                        mk().method_call_expr(val, "end", vec![] as Vec<P<Expr>>);
+                                    Ok(WithStmts::new(
+                                        vec![mk().semi_stmt(call_expr)],
+                                        self.panic_or_err("va_end stub"),
+                                    ))
+                                });
+                            }
+
                             let path = {
                                 let std_or_core =
                                     if self.tcfg.emit_no_std { "core" } else { "std" };
@@ -531,6 +580,13 @@ impl<'c> Translation<'c> {
                 )
             }
 
+            "__atomic_thread_fence" | "__c11_atomic_thread_fence" => {
+                self.convert_atomic_fence(ctx, false, args[0])
+            }
+            "__atomic_signal_fence" | "__c11_atomic_signal_fence" => {
+                self.convert_atomic_fence(ctx, true, args[0])
+            }
+
             "__sync_lock_test_and_set_1"
             | "__sync_lock_test_and_set_2"
             | "__sync_lock_test_and_set_4"
@@ -588,6 +644,14 @@ impl<'c> Translation<'c> {
                 ))
             }
 
+            // Intrinsics that `arm_neon.h` defines as macros expand to these, so there is no
+            // function to import from `core::arch::aarch64`
+            _ if builtin_name.starts_with("__builtin_neon_") => Err(format_translation_err!(
+                src_loc,
+                "Unimplemented NEON builtin {}, the intrinsic using it is a macro",
+                builtin_name
+            )),
+
             _ => Err(format_translation_err!(src_loc, "Unimplemented builtin {}", builtin_name)),
         }
     }
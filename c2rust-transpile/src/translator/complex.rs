@@ -0,0 +1,297 @@
+//! This module provides translations of C99 `_Complex` values.
+//!
+//! Complex types are represented using `num_complex::Complex`, which is `#[repr(C)]` and
+//! laid out like the corresponding C type, so complex values can be passed to and from C.
+
+use super::*;
+
+/// Functions from `<complex.h>` that we translate inline rather than calling into libm.
+const COMPLEX_LIBCALLS: [&str; 9] = [
+    "creal", "crealf", "creall", "cimag", "cimagf", "cimagl", "conj", "conjf", "conjl",
+];
+
+impl<'c> Translation<'c> {
+    /// Get the element type of a complex type, or `None` if `ctype` isn't complex.
+    pub fn complex_element_type(&self, ctype: CTypeId) -> Option<CTypeId> {
+        match self.ast_context.resolve_type(ctype).kind {
+            CTypeKind::Complex(elt) => Some(elt),
+            _ => None,
+        }
+    }
+
+    /// Build a complex value from its real and imaginary parts. We use a struct expression
+    /// rather than `Complex::new` so that this also works in static initializers.
+    pub fn mk_complex(&self, re: P<Expr>, im: P<Expr>) -> P<Expr> {
+        self.extern_crates.borrow_mut().insert("num_complex");
+        mk().struct_expr(
+            mk().path(vec!["num_complex", "Complex"]),
+            vec![mk().field("re", re), mk().field("im", im)],
+        )
+    }
+
+    /// Produce the complex zero value of the given complex type.
+    pub fn complex_zero(
+        &self,
+        ctype: CTypeId,
+        is_static: bool,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let elt = self
+            .complex_element_type(ctype)
+            .ok_or_else(|| format_err!("Expected complex type"))?;
+        let re = self.implicit_default_expr(elt, is_static)?.to_expr();
+        let im = self.implicit_default_expr(elt, is_static)?.to_expr();
+        Ok(WithStmts::new_val(self.mk_complex(re, im)))
+    }
+
+    /// Convert a GNU imaginary literal such as `2.0i`. This is how glibc defines `_Complex_I`.
+    pub fn convert_imaginary_literal(
+        &self,
+        ctx: ExprContext,
+        ty: CQualTypeId,
+        real: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let elt = self
+            .complex_element_type(ty.ctype)
+            .ok_or_else(|| format_err!("Imaginary literal with non-complex type"))?;
+        let zero = self.implicit_default_expr(elt, ctx.is_static)?.to_expr();
+        Ok(self
+            .convert_expr(ctx.used(), real)?
+            .map(|im| self.mk_complex(zero, im)))
+    }
+
+    /// Convert `__real__ arg`/`__imag__ arg`, and the equivalent `creal`/`cimag` calls.
+    ///
+    /// On a complex operand these name the corresponding field, so the result is still an
+    /// lvalue. GCC also allows these on real operands, in which case `__real__` is the identity
+    /// and `__imag__` is zero.
+    pub fn convert_complex_part(
+        &self,
+        ctx: ExprContext,
+        is_real: bool,
+        arg: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let arg_ty = self.ast_context[arg]
+            .kind
+            .get_type()
+            .ok_or_else(|| format_err!("bad complex part operand type"))?;
+
+        if self.complex_element_type(arg_ty).is_some() {
+            let field = if is_real { "re" } else { "im" };
+            Ok(self
+                .convert_expr(ctx.used(), arg)?
+                .map(|z| mk().field_expr(z, field)))
+        } else if is_real {
+            self.convert_expr(ctx.used(), arg)
+        } else {
+            let zero = self.implicit_default_expr(arg_ty, ctx.is_static)?;
+            self.convert_expr(ctx.unused(), arg)?
+                .and_then(|_| Ok(zero))
+        }
+    }
+
+    /// Convert the conjugate of a complex value, which is spelled `~z` in GNU C.
+    pub fn convert_complex_conj(
+        &self,
+        ctx: ExprContext,
+        arg: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        Ok(self
+            .convert_expr(ctx.used(), arg)?
+            .map(|z| mk().method_call_expr(z, "conj", vec![] as Vec<P<Expr>>)))
+    }
+
+    /// `creal`, `cimag` and `conj` are library builtins in Clang, so calls to them go through
+    /// a regular function pointer decay rather than `BuiltinFnToFnPtr`. As long as they are
+    /// only declared in this translation unit, we translate them like their `__builtin_`
+    /// counterparts.
+    pub fn is_complex_libcall(&self, fexp: CExprId) -> bool {
+        if let CExprKind::DeclRef(_, decl_id, _) = self.ast_context[fexp].kind {
+            if let CDeclKind::Function { ref name, body: None, .. } = self.ast_context[decl_id].kind {
+                return COMPLEX_LIBCALLS.contains(&name.as_str());
+            }
+        }
+        false
+    }
+
+    /// Convert a cast between complex types, or between complex and real types.
+    pub fn convert_complex_cast(
+        &self,
+        ctx: ExprContext,
+        source_ty: CQualTypeId,
+        ty: CQualTypeId,
+        val: WithStmts<P<Expr>>,
+        kind: CastKind,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let elt_kind = match kind {
+            CastKind::FloatingRealToComplex | CastKind::IntegralRealToComplex => {
+                // Clang has already converted the operand to the element type
+                let elt = self
+                    .complex_element_type(ty.ctype)
+                    .ok_or_else(|| format_err!("Cast to complex with non-complex type"))?;
+                let zero = self.implicit_default_expr(elt, ctx.is_static)?.to_expr();
+                return Ok(val.map(|re| self.mk_complex(re, zero)));
+            }
+
+            CastKind::FloatingComplexToReal | CastKind::IntegralComplexToReal => {
+                return Ok(val.map(|z| mk().field_expr(z, "re")));
+            }
+
+            CastKind::FloatingComplexToBoolean | CastKind::IntegralComplexToBoolean => {
+                return Ok(val.map(|z| self.match_bool(true, source_ty.ctype, z)));
+            }
+
+            CastKind::FloatingComplexCast => CastKind::FloatingCast,
+            CastKind::FloatingComplexToIntegralComplex => CastKind::FloatingToIntegral,
+            CastKind::IntegralComplexCast => CastKind::IntegralCast,
+            CastKind::IntegralComplexToFloatingComplex => CastKind::IntegralToFloating,
+
+            _ => return Err(format_err!("Unexpected complex cast kind {:?}", kind).into()),
+        };
+
+        let source_elt = self
+            .complex_element_type(source_ty.ctype)
+            .ok_or_else(|| format_err!("Complex cast from non-complex type"))?;
+        let target_elt = self
+            .complex_element_type(ty.ctype)
+            .ok_or_else(|| format_err!("Complex cast to non-complex type"))?;
+        let source_elt = CQualTypeId { qualifiers: Qualifiers::default(), ctype: source_elt };
+        let target_elt = CQualTypeId { qualifiers: Qualifiers::default(), ctype: target_elt };
+
+        val.and_then(|z| {
+            // Both parts of the operand are needed, so bind it to a local unless we're in a
+            // static initializer, which can't have side effects anyway.
+            let (stmts, z) = if ctx.is_static || ctx.is_const {
+                (vec![], z)
+            } else {
+                let name = self.renamer.borrow_mut().fresh();
+                let local = mk().local_stmt(P(mk().local(
+                    mk().ident_pat(&name),
+                    None as Option<P<Ty>>,
+                    Some(z),
+                )));
+                (vec![local], mk().ident_expr(&name))
+            };
+
+            let part = |field: &str| {
+                let val = WithStmts::new_val(mk().field_expr(z.clone(), field));
+                self.convert_cast(ctx, source_elt, target_elt, val, None, Some(elt_kind), None)
+            };
+            let re = part("re")?;
+            let im = part("im")?;
+
+            let mut res = re.and_then(|re| -> Result<_, TranslationError> {
+                Ok(im.map(|im| self.mk_complex(re, im)))
+            })?;
+            res.prepend_stmts(stmts);
+            Ok(res)
+        })
+    }
+
+    /// Convert a complex multiplication or division.
+    ///
+    /// C11 Annex G requires these operations to handle infinite and NaN operands in ways that
+    /// the `num_complex` operators don't. Like Clang, we call the `__mulXc3` and `__divXc3`
+    /// helpers that libgcc and compiler-rt provide for floating-point operands. Integer
+    /// complex values use the plain textbook formulas in GCC, which `num_complex` matches.
+    pub fn convert_complex_binary_operator(
+        &self,
+        ctx: ExprContext,
+        op: c_ast::BinOp,
+        ctype: CTypeId,
+        lhs: P<Expr>,
+        rhs: P<Expr>,
+    ) -> Result<P<Expr>, TranslationError> {
+        let elt = self
+            .complex_element_type(ctype)
+            .ok_or_else(|| format_err!("Complex operator with non-complex type"))?;
+
+        let helper = match (op, &self.ast_context.resolve_type(elt).kind) {
+            (c_ast::BinOp::Multiply, CTypeKind::Float) => "__mulsc3",
+            (c_ast::BinOp::Multiply, CTypeKind::Double) => "__muldc3",
+            (c_ast::BinOp::Divide, CTypeKind::Float) => "__divsc3",
+            (c_ast::BinOp::Divide, CTypeKind::Double) => "__divdc3",
+            // With `--long-double f64` the elements are doubles on the Rust side
+            (c_ast::BinOp::Multiply, CTypeKind::LongDouble)
+                if self.tcfg.long_double == LongDoubleMode::F64 =>
+            {
+                "__muldc3"
+            }
+            (c_ast::BinOp::Divide, CTypeKind::LongDouble)
+                if self.tcfg.long_double == LongDoubleMode::F64 =>
+            {
+                "__divdc3"
+            }
+            // `f128` and `f80` aren't ABI compatible with the C long double the helpers take
+            (_, CTypeKind::LongDouble) => {
+                return Err(format_err!("Unsupported complex long double {:?}", op).into())
+            }
+            _ => return Ok(mk().binary_expr(BinOpKind::from(op), lhs, rhs)),
+        };
+
+        if ctx.is_const {
+            return Err(format_err!("Cannot call {} in a const expression", helper).into());
+        }
+
+        let elt_ty = self.convert_type(elt)?;
+        let complex_ty = self.convert_type(ctype)?;
+        self.declare_complex_helper(helper, elt_ty, complex_ty);
+
+        // `{ let a = lhs; let b = rhs; __muldc3(a.re, a.im, b.re, b.im) }`
+        let lhs_name = self.renamer.borrow_mut().fresh();
+        let rhs_name = self.renamer.borrow_mut().fresh();
+        let bind = |name: &str, val: P<Expr>| {
+            mk().local_stmt(P(mk().local(
+                mk().ident_pat(name),
+                None as Option<P<Ty>>,
+                Some(val),
+            )))
+        };
+        let args = vec![
+            mk().field_expr(mk().ident_expr(&lhs_name), "re"),
+            mk().field_expr(mk().ident_expr(&lhs_name), "im"),
+            mk().field_expr(mk().ident_expr(&rhs_name), "re"),
+            mk().field_expr(mk().ident_expr(&rhs_name), "im"),
+        ];
+        let call = mk().call_expr(mk().path_expr(vec![helper]), args);
+
+        Ok(mk().block_expr(mk().block(vec![
+            bind(&lhs_name, lhs),
+            bind(&rhs_name, rhs),
+            mk().expr_stmt(call),
+        ])))
+    }
+
+    /// Declare one of the complex arithmetic runtime helpers in the extern block of the module
+    /// we are currently emitting into, unless it is already declared there.
+    fn declare_complex_helper(&self, name: &'static str, elt_ty: P<Ty>, complex_ty: P<Ty>) {
+        let cur_file = self.cur_file.borrow().clone();
+        let is_declared = |store: &ItemStore| {
+            store
+                .foreign_items
+                .iter()
+                .any(|item| &*item.ident.as_str() == name)
+        };
+        let already_declared = match cur_file {
+            Some(ref file) if self.tcfg.reorganize_definitions && file != &self.main_file => self
+                .mod_blocks
+                .borrow()
+                .get(file)
+                .map_or(false, is_declared),
+            _ => is_declared(&*self.item_store.borrow()),
+        };
+        if already_declared {
+            return;
+        }
+
+        let args = ["a", "b", "c", "d"]
+            .iter()
+            .map(|arg| mk().arg(elt_ty.clone(), mk().ident_pat(*arg)))
+            .collect();
+        let decl = mk().fn_decl(args, FunctionRetTy::Ty(complex_ty), false);
+        let item = mk().fn_foreign_item(name, decl);
+        match cur_file {
+            Some(ref file) => self.insert_foreign_item(item, Some(file)),
+            None => self.item_store.borrow_mut().foreign_items.push(item),
+        }
+    }
+}
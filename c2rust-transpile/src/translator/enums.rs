@@ -0,0 +1,428 @@
+//! This module translates closed C enums into Rust `enum`s, with `--closed-enums`.
+//!
+//! C enums are normally translated into a type alias of their underlying integer type and a
+//! constant for each enumerator, since C lets any value of that type be stored in an enum. An
+//! enum is closed if the translation unit only ever stores its enumerators in it, in which case
+//! it is translated into a Rust `enum` with a `TryFrom` conversion from the underlying type:
+//!
+//! ```ignore
+//! #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+//! #[repr(u32)]
+//! pub enum color { RED = 0, GREEN = 1 }
+//! impl ::std::convert::TryFrom<libc::c_uint> for color { ... }
+//! pub const RED: color = color::RED;
+//! pub const GREEN: color = color::GREEN;
+//! ```
+//!
+//! The constants keep the rest of the translation unchanged: enumerators still cast to integers
+//! with `as`, and the casts of enumerators back to the enum are elided by `enum_cast`. An enum is
+//! open, and keeps its alias, if its values are computed (e.g. `RED | GREEN` for bit flags or
+//! `c + 1`), incremented, converted from integers that aren't enumerators or read through
+//! pointers to other types, bitfields, unions or `va_arg`. Enums without an enumerator with the
+//! value 0 are open as well, since zero-initialized memory is common in C.
+//!
+//! Values that come from other translation units can't be checked, so enums that can cross its
+//! boundary are open too: those reachable from the signatures of functions with external linkage
+//! or without a definition, from the types of externally visible variables, and from the results
+//! of calls through function pointers. So are enums stored in memory that is accessed as another
+//! type, e.g. through the `void *` arguments of `memcpy` or `fread`.
+
+use super::*;
+
+impl<'c> Translation<'c> {
+    /// Find the enums of the translation unit that are only ever assigned their enumerators
+    pub fn find_closed_enums(&self) -> IndexSet<CEnumId> {
+        let mut closed: IndexSet<CEnumId> = self
+            .ast_context
+            .iter_decls()
+            .filter(|&(&decl_id, _)| self.may_be_closed_enum(decl_id))
+            .map(|(&decl_id, _)| decl_id)
+            .collect();
+
+        for (_, decl) in self.ast_context.iter_decls() {
+            let fields = match decl.kind {
+                CDeclKind::Struct {
+                    fields: Some(ref fields),
+                    ..
+                }
+                | CDeclKind::Union {
+                    fields: Some(ref fields),
+                    ..
+                } => fields,
+                _ => continue,
+            };
+            let is_union = match decl.kind {
+                CDeclKind::Union { .. } => true,
+                _ => false,
+            };
+            for &field_id in fields {
+                if let CDeclKind::Field {
+                    typ,
+                    bitfield_width,
+                    ..
+                } = self.ast_context[field_id].kind
+                {
+                    if is_union || bitfield_width.is_some() {
+                        if let Some(enum_id) = self.enum_of(typ.ctype) {
+                            closed.remove(&enum_id);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut external = IndexSet::new();
+        for (_, decl) in self.ast_context.iter_decls() {
+            match decl.kind {
+                CDeclKind::Function {
+                    is_global,
+                    typ,
+                    body,
+                    ..
+                } if is_global || body.is_none() => {
+                    self.reachable_enums(typ, true, &mut external, &mut IndexSet::new());
+                }
+                CDeclKind::Variable {
+                    is_externally_visible: true,
+                    typ,
+                    ..
+                } => {
+                    self.reachable_enums(typ.ctype, true, &mut external, &mut IndexSet::new());
+                }
+                _ => {}
+            }
+        }
+
+        for (_, expr) in self.ast_context.iter_exprs() {
+            if let Some(enum_id) = expr.kind.get_type().and_then(|ty| self.enum_of(ty)) {
+                if !self.keeps_enum_closed(&expr.kind, enum_id) {
+                    closed.remove(&enum_id);
+                }
+            }
+            if let Some((target, source)) = self.reinterpreted_types(&expr.kind) {
+                let mut reinterpreted = IndexSet::new();
+                self.reachable_enums(target, false, &mut reinterpreted, &mut IndexSet::new());
+                self.reachable_enums(source, false, &mut reinterpreted, &mut IndexSet::new());
+                external.extend(reinterpreted);
+            }
+        }
+
+        closed.retain(|enum_id| !external.contains(enum_id));
+        closed
+    }
+
+    /// Add the enums whose values are stored in values of `ctype`, directly or in the elements
+    /// of arrays and the fields of records, to `enums`. With `through_pointers`, those that can
+    /// be reached through pointers and function types are added as well.
+    fn reachable_enums(
+        &self,
+        ctype: CTypeId,
+        through_pointers: bool,
+        enums: &mut IndexSet<CEnumId>,
+        seen_records: &mut IndexSet<CRecordId>,
+    ) {
+        match self.ast_context.resolve_type(ctype).kind {
+            CTypeKind::Enum(enum_id) => {
+                enums.insert(enum_id);
+            }
+            CTypeKind::ConstantArray(elem, _)
+            | CTypeKind::IncompleteArray(elem)
+            | CTypeKind::VariableArray(elem, _)
+            | CTypeKind::Atomic(elem) => {
+                self.reachable_enums(elem, through_pointers, enums, seen_records)
+            }
+            CTypeKind::Struct(record_id) | CTypeKind::Union(record_id) => {
+                if !seen_records.insert(record_id) {
+                    return;
+                }
+                let fields = match self.ast_context[record_id].kind {
+                    CDeclKind::Struct {
+                        fields: Some(ref fields),
+                        ..
+                    }
+                    | CDeclKind::Union {
+                        fields: Some(ref fields),
+                        ..
+                    } => fields,
+                    _ => return,
+                };
+                for &field_id in fields {
+                    if let CDeclKind::Field { typ, .. } = self.ast_context[field_id].kind {
+                        self.reachable_enums(typ.ctype, through_pointers, enums, seen_records);
+                    }
+                }
+            }
+            CTypeKind::Pointer(pointee) | CTypeKind::BlockPointer(pointee) if through_pointers => {
+                self.reachable_enums(pointee.ctype, through_pointers, enums, seen_records)
+            }
+            CTypeKind::Function(ret, ref params, ..) if through_pointers => {
+                self.reachable_enums(ret.ctype, through_pointers, enums, seen_records);
+                for param in params {
+                    self.reachable_enums(param.ctype, through_pointers, enums, seen_records);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check the enum `decl_id` for the properties that don't depend on its uses: a Rust `enum`
+    /// needs a fixed size integer representation and distinct discriminants.
+    fn may_be_closed_enum(&self, decl_id: CEnumId) -> bool {
+        let (variants, integral_type) = match self.ast_context[decl_id].kind {
+            CDeclKind::Enum {
+                ref variants,
+                integral_type: Some(integral_type),
+                ..
+            } => (variants, integral_type),
+            _ => return false,
+        };
+        if variants.is_empty() || self.closed_enum_repr(integral_type.ctype).is_none() {
+            return false;
+        }
+
+        let mut values = IndexSet::new();
+        for &variant_id in variants {
+            match self.ast_context[variant_id].kind {
+                CDeclKind::EnumConstant { value, .. } => {
+                    if !values.insert(const_int_value(value)) {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+        values.contains(&0)
+    }
+
+    /// The primitive type of the `#[repr]` of a closed enum with underlying type `ctype`. Types
+    /// whose size depends on the platform are left out.
+    fn closed_enum_repr(&self, ctype: CTypeId) -> Option<&'static str> {
+        match self.ast_context.resolve_type(ctype).kind {
+            CTypeKind::SChar => Some("i8"),
+            CTypeKind::UChar => Some("u8"),
+            CTypeKind::Short => Some("i16"),
+            CTypeKind::UShort => Some("u16"),
+            CTypeKind::Int => Some("i32"),
+            CTypeKind::UInt => Some("u32"),
+            CTypeKind::LongLong => Some("i64"),
+            CTypeKind::ULongLong => Some("u64"),
+            _ => None,
+        }
+    }
+
+    /// The enum declaration of `ctype` if it is an enum type
+    fn enum_of(&self, ctype: CTypeId) -> Option<CEnumId> {
+        match self.ast_context.resolve_type(ctype).kind {
+            CTypeKind::Enum(enum_id) => Some(enum_id),
+            _ => None,
+        }
+    }
+
+    /// Whether the expression `kind` of type `enum_id` only yields enumerators of that enum,
+    /// provided that its operands do
+    fn keeps_enum_closed(&self, kind: &CExprKind, enum_id: CEnumId) -> bool {
+        match *kind {
+            CExprKind::DeclRef(..)
+            | CExprKind::Member(..)
+            | CExprKind::ArraySubscript(..)
+            | CExprKind::Paren(..)
+            | CExprKind::Conditional(..)
+            | CExprKind::BinaryConditional(..)
+            | CExprKind::CompoundLiteral(..)
+            | CExprKind::InitList(..)
+            | CExprKind::ImplicitValueInit(..)
+            | CExprKind::DesignatedInitExpr(..)
+            | CExprKind::Statements(..)
+            | CExprKind::Choose(..)
+            | CExprKind::GenericSelection(..)
+            | CExprKind::Unary(_, c_ast::UnOp::Deref, _, _)
+            | CExprKind::Unary(_, c_ast::UnOp::Extension, _, _)
+            | CExprKind::Binary(_, c_ast::BinOp::Assign, _, _, _, _)
+            | CExprKind::Binary(_, c_ast::BinOp::Comma, _, _, _, _) => true,
+
+            // Functions without a definition are handled with the other external declarations
+            CExprKind::Call(_, func, _) => match self.ast_context[func].kind {
+                CExprKind::ImplicitCast(_, func, CastKind::FunctionToPointerDecay, _, _) => {
+                    match self.ast_context[func].kind {
+                        CExprKind::DeclRef(..) => true,
+                        _ => false,
+                    }
+                }
+                _ => false,
+            },
+
+            CExprKind::ImplicitCast(_, expr, _, _, _)
+            | CExprKind::ExplicitCast(_, expr, _, _, _) => {
+                let source_ty = self.ast_context[expr].kind.get_type();
+                source_ty.and_then(|ty| self.enum_of(ty)) == Some(enum_id)
+                    || self.is_enumerator_of(enum_id, expr)
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Whether `expr_id` is an enumerator of `enum_id` that `enum_cast` translates into the
+    /// constant of the enumerator
+    fn is_enumerator_of(&self, enum_id: CEnumId, expr_id: CExprId) -> bool {
+        match self.ast_context[expr_id].kind {
+            CExprKind::DeclRef(_, decl_id, _) => {
+                self.ast_context.parents.get(&decl_id) == Some(&enum_id)
+            }
+            CExprKind::Literal(_, CLiteral::Integer(i, _)) => {
+                self.enum_variant_for_value(enum_id, i as i64).is_some()
+            }
+            CExprKind::Unary(_, c_ast::UnOp::Negate, subexpr_id, _) => {
+                match self.ast_context[subexpr_id].kind {
+                    CExprKind::Literal(_, CLiteral::Integer(i, _)) => {
+                        self.enum_variant_for_value(enum_id, -(i as i64)).is_some()
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// The types that the pointer cast `kind` reinterprets memory as and from, if they differ.
+    /// Memory cast to or from `void *` is reinterpreted as well, since it can be accessed as any
+    /// other type, or as bytes by functions like `memcpy`.
+    fn reinterpreted_types(&self, kind: &CExprKind) -> Option<(CTypeId, CTypeId)> {
+        let (target_ty, expr) = match *kind {
+            CExprKind::ImplicitCast(ty, expr, CastKind::BitCast, _, _)
+            | CExprKind::ExplicitCast(ty, expr, CastKind::BitCast, _, _) => (ty.ctype, expr),
+            _ => return None,
+        };
+        let source_ty = self.ast_context[expr].kind.get_type()?;
+        let pointee = |ctype| match self.ast_context.resolve_type(ctype).kind {
+            CTypeKind::Pointer(pointee) => Some(self.ast_context.resolve_type_id(pointee.ctype)),
+            _ => None,
+        };
+        let (target, source) = (pointee(target_ty)?, pointee(source_ty)?);
+        if target == source {
+            return None;
+        }
+        Some((target, source))
+    }
+
+    /// The enumerator of `enum_id` with the value `value`
+    pub fn enum_variant_for_value(&self, enum_id: CEnumId, value: i64) -> Option<CEnumConstantId> {
+        let variants = match self.ast_context[enum_id].kind {
+            CDeclKind::Enum { ref variants, .. } => variants,
+            _ => panic!("{:?} does not point to an `enum` declaration", enum_id),
+        };
+        variants
+            .iter()
+            .cloned()
+            .find(|&variant_id| match self.ast_context[variant_id].kind {
+                CDeclKind::EnumConstant { value: v, .. } => {
+                    v == ConstIntExpr::I(value) || v == ConstIntExpr::U(value as u64)
+                }
+                _ => panic!("{:?} does not point to an enum variant", variant_id),
+            })
+    }
+
+    /// Whether `enum_id` is translated into a Rust `enum`
+    pub fn is_closed_enum(&self, enum_id: CEnumId) -> bool {
+        self.closed_enums.contains(&enum_id)
+    }
+
+    /// Build the Rust `enum` for the closed enum `enum_id` along with its `TryFrom` conversion
+    /// from the underlying type `integral_type`
+    pub fn convert_closed_enum(
+        &self,
+        enum_id: CEnumId,
+        enum_name: &str,
+        span: Span,
+        integral_type: CTypeId,
+    ) -> Result<ConvertedDecl, TranslationError> {
+        let variants = match self.ast_context[enum_id].kind {
+            CDeclKind::Enum { ref variants, .. } => variants,
+            _ => return Err(TranslationError::generic("Expected an enum declaration")),
+        };
+        let repr = self
+            .closed_enum_repr(integral_type)
+            .ok_or_else(|| format_err!("No fixed size representation for enum {}", enum_name))?;
+        let int_ty = self.convert_type(integral_type)?;
+
+        let mut enum_variants = vec![];
+        let mut arms = vec![];
+        for &variant_id in variants {
+            let value = match self.ast_context[variant_id].kind {
+                CDeclKind::EnumConstant { value, .. } => value,
+                _ => return Err(format_err!("Missing enum constant {:?}", variant_id).into()),
+            };
+            let name = self
+                .renamer
+                .borrow_mut()
+                .get(&variant_id)
+                .expect("Enum constant not named");
+            enum_variants.push(mk().unit_variant(&name, Some(enum_constant_expr(value))));
+
+            let variant = mk().path_expr(vec![enum_name, name.as_str()]);
+            arms.push(mk().arm(
+                vec![mk().lit_pat(enum_constant_expr(value))],
+                None as Option<P<Expr>>,
+                mk().call_expr(mk().path_expr(vec!["Ok"]), vec![variant]),
+            ));
+        }
+        let value = || mk().path_expr(vec!["value"]);
+        arms.push(mk().arm(
+            vec![mk().wild_pat()],
+            None as Option<P<Expr>>,
+            mk().call_expr(mk().path_expr(vec!["Err"]), vec![value()]),
+        ));
+
+        let enum_item = mk()
+            .span(span)
+            .pub_()
+            .call_attr("derive", vec!["Copy", "Clone", "Debug", "PartialEq", "Eq"])
+            .call_attr("repr", vec![repr])
+            .enum_item(enum_name, enum_variants);
+
+        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
+        let try_from = mk().abs_path(vec![
+            mk().path_segment(std_or_core),
+            mk().path_segment("convert"),
+            mk().path_segment_with_args("TryFrom", mk().angle_bracketed_args(vec![int_ty.clone()])),
+        ]);
+        let result_ty = mk().path_ty(vec![mk().path_segment_with_args(
+            "Result",
+            mk().angle_bracketed_args(vec![mk().path_ty(vec![enum_name]), int_ty.clone()]),
+        )]);
+        let try_from_fn = mk().fn_impl_item(
+            "try_from",
+            mk().fn_decl(
+                vec![mk().arg(int_ty.clone(), mk().ident_pat("value"))],
+                FunctionRetTy::Ty(result_ty),
+                false,
+            ),
+            mk().block(vec![mk().expr_stmt(mk().match_expr(value(), arms))]),
+        );
+        let try_from_impl = mk().trait_impl_item(
+            try_from,
+            mk().path_ty(vec![enum_name]),
+            vec![mk().ty_impl_item("Error", int_ty), try_from_fn],
+        );
+
+        Ok(ConvertedDecl::Items(vec![enum_item, try_from_impl]))
+    }
+}
+
+/// The value of an enumerator, wide enough to compare signed and unsigned values
+fn const_int_value(value: ConstIntExpr) -> i128 {
+    match value {
+        ConstIntExpr::I(value) => value as i128,
+        ConstIntExpr::U(value) => value as i128,
+    }
+}
+
+/// The literal expression of the value of an enumerator
+fn enum_constant_expr(value: ConstIntExpr) -> P<Expr> {
+    match value {
+        ConstIntExpr::I(value) => signed_int_expr(value),
+        ConstIntExpr::U(value) => {
+            mk().lit_expr(mk().int_lit(value as u128, LitIntType::Unsuffixed))
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! This module provides accessors for flexible array members, the trailing array of a struct
+//! that a C program allocates more elements of than the struct declares, like `data` in:
+//!
+//! ```c
+//! struct msg {
+//!     size_t len;
+//!     char data[];
+//! };
+//! struct msg *m = malloc(sizeof(struct msg) + len * sizeof(char));
+//! ```
+//!
+//! Besides `T data[]`, the pre-C99 idioms `T data[0]` and `T data[1]` are recognized. The array
+//! is translated into a field of type `[T; 0]` (or `[T; 1]`), so we add methods that reach the
+//! elements past its end, and a helper to compute the size C allocates for them:
+//!
+//! ```ignore
+//! impl msg {
+//!     pub unsafe fn data(&self, len: usize) -> &[libc::c_char] { ... }
+//!     pub unsafe fn data_mut(&mut self, len: usize) -> &mut [libc::c_char] { ... }
+//!     pub fn data_ptr(&self) -> *const libc::c_char { ... }
+//!     pub fn data_mut_ptr(&mut self) -> *mut libc::c_char { ... }
+//!     pub fn alloc_size(len: usize) -> usize { ... }
+//! }
+//! let mut m = malloc(msg::alloc_size(len) as libc::c_ulong) as *mut msg;
+//! ```
+//!
+//! Packed structs and structs with bitfields don't get these methods, since their fields can't be
+//! borrowed or don't have fields of their own.
+
+use super::*;
+
+impl<'c> Translation<'c> {
+    /// The flexible array member of the struct `record_id`, if it has one and gets accessors for it
+    pub fn flexible_array_member(&self, record_id: CRecordId) -> Option<CFieldId> {
+        let fields = match self.ast_context[record_id].kind {
+            CDeclKind::Struct {
+                fields: Some(ref fields),
+                ..
+            } => fields,
+            _ => return None,
+        };
+        if self.ast_context.record_packing(record_id).is_some()
+            || self.ast_context.has_inner_struct_decl(record_id)
+        {
+            return None;
+        }
+
+        let mut last = None;
+        for &field_id in fields {
+            match self.ast_context[field_id].kind {
+                CDeclKind::Field {
+                    bitfield_width: Some(_),
+                    ..
+                } => return None,
+                CDeclKind::Field { typ, .. } => last = Some((field_id, typ.ctype)),
+                _ => return None,
+            }
+        }
+        let (field_id, typ) = last?;
+        if self.ast_context.maybe_flexible_array(typ) {
+            Some(field_id)
+        } else {
+            None
+        }
+    }
+
+    /// The element type of the flexible array member `field_id`
+    fn flexible_array_element(&self, field_id: CFieldId) -> Option<CTypeId> {
+        let typ = match self.ast_context[field_id].kind {
+            CDeclKind::Field { typ, .. } => typ.ctype,
+            _ => return None,
+        };
+        match self.ast_context.resolve_type(typ).kind {
+            CTypeKind::IncompleteArray(elt) | CTypeKind::ConstantArray(elt, _) => Some(elt),
+            _ => None,
+        }
+    }
+
+    /// Build the `impl` block with the accessors of the flexible array member `field_id` of the
+    /// struct `record_id`
+    pub fn convert_flexible_array_accessors(
+        &self,
+        record_id: CRecordId,
+        field_id: CFieldId,
+    ) -> Result<P<Item>, TranslationError> {
+        let record_name = self
+            .type_converter
+            .borrow()
+            .resolve_decl_name(record_id)
+            .unwrap();
+        let field_name = self
+            .type_converter
+            .borrow()
+            .resolve_field_name(Some(record_id), field_id)
+            .ok_or_else(|| format_err!("Missing field name {:?}", field_id))?;
+        let elt = self
+            .flexible_array_element(field_id)
+            .ok_or_else(|| format_err!("Expected a flexible array member"))?;
+        let elt_ty = self.convert_type(elt)?;
+        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
+
+        let self_arg = |mutbl| mk().self_arg(SelfKind::Region(None, mutbl));
+        let len_arg = || mk().arg(mk().path_ty(vec!["usize"]), mk().ident_pat("len"));
+        let field = || mk().field_expr(mk().path_expr(vec!["self"]), &field_name);
+        let ret = |ty| FunctionRetTy::Ty(ty);
+        let body = |expr| mk().block(vec![mk().expr_stmt(expr)]);
+
+        // The elements are only valid as far as C allocated them, which we can't check
+        let slice = mk().pub_().unsafe_().fn_impl_item(
+            &field_name,
+            mk().fn_decl(
+                vec![self_arg(Mutability::Immutable), len_arg()],
+                ret(mk().ref_ty(mk().slice_ty(elt_ty.clone()))),
+                false,
+            ),
+            body(mk().call_expr(
+                mk().path_expr(vec!["", std_or_core, "slice", "from_raw_parts"]),
+                vec![
+                    mk().method_call_expr(field(), "as_ptr", vec![] as Vec<P<Expr>>),
+                    mk().path_expr(vec!["len"]),
+                ],
+            )),
+        );
+        let slice_mut = mk().pub_().unsafe_().fn_impl_item(
+            format!("{}_mut", field_name),
+            mk().fn_decl(
+                vec![self_arg(Mutability::Mutable), len_arg()],
+                ret(mk().mutbl().ref_ty(mk().slice_ty(elt_ty.clone()))),
+                false,
+            ),
+            body(mk().call_expr(
+                mk().path_expr(vec!["", std_or_core, "slice", "from_raw_parts_mut"]),
+                vec![
+                    mk().method_call_expr(field(), "as_mut_ptr", vec![] as Vec<P<Expr>>),
+                    mk().path_expr(vec!["len"]),
+                ],
+            )),
+        );
+        let ptr = mk().pub_().fn_impl_item(
+            format!("{}_ptr", field_name),
+            mk().fn_decl(
+                vec![self_arg(Mutability::Immutable)],
+                ret(mk().ptr_ty(elt_ty.clone())),
+                false,
+            ),
+            body(mk().method_call_expr(field(), "as_ptr", vec![] as Vec<P<Expr>>)),
+        );
+        let ptr_mut = mk().pub_().fn_impl_item(
+            format!("{}_mut_ptr", field_name),
+            mk().fn_decl(
+                vec![self_arg(Mutability::Mutable)],
+                ret(mk().mutbl().ptr_ty(elt_ty.clone())),
+                false,
+            ),
+            body(mk().method_call_expr(field(), "as_mut_ptr", vec![] as Vec<P<Expr>>)),
+        );
+
+        // `sizeof(S) + len * sizeof(T)`, wrapping like the unsigned arithmetic in C
+        let size_of = |ty| {
+            let path = vec![
+                mk().path_segment(""),
+                mk().path_segment(std_or_core),
+                mk().path_segment("mem"),
+                mk().path_segment_with_args("size_of", mk().angle_bracketed_args(vec![ty])),
+            ];
+            mk().call_expr(mk().path_expr(path), vec![] as Vec<P<Expr>>)
+        };
+        let elts_size = mk().method_call_expr(
+            mk().path_expr(vec!["len"]),
+            "wrapping_mul",
+            vec![size_of(elt_ty)],
+        );
+        let alloc_size = mk().pub_().fn_impl_item(
+            "alloc_size",
+            mk().fn_decl(vec![len_arg()], ret(mk().path_ty(vec!["usize"])), false),
+            body(mk().method_call_expr(
+                size_of(mk().path_ty(vec!["Self"])),
+                "wrapping_add",
+                vec![elts_size],
+            )),
+        );
+
+        Ok(mk().impl_item(
+            mk().path_ty(vec![record_name]),
+            vec![slice, slice_mut, ptr, ptr_mut, alloc_size],
+        ))
+    }
+
+    /// Translate `expr_id` into a call to `alloc_size` of a struct with a flexible array member if
+    /// it computes the size of such a struct with `sizeof(S) + n * sizeof(T)`, where `T` is the
+    /// type of the elements of the array. `sizeof(T)` may be left out if `T` is a character type.
+    pub fn convert_flexible_array_alloc_size(
+        &self,
+        ctx: ExprContext,
+        expr_id: CExprId,
+    ) -> Result<Option<WithStmts<P<Expr>>>, TranslationError> {
+        let (ty, lhs, rhs) = match self.ast_context[expr_id].kind {
+            CExprKind::Binary(ty, c_ast::BinOp::Add, lhs, rhs, _, _) => (ty, lhs, rhs),
+            _ => return Ok(None),
+        };
+        let (record_id, field_id, len) = match self
+            .flexible_array_size_of(lhs)
+            .map(|record| (record, rhs))
+            .or_else(|| self.flexible_array_size_of(rhs).map(|record| (record, lhs)))
+        {
+            Some(((record_id, field_id), elts)) => match self.flexible_array_len(field_id, elts) {
+                Some(len) => (record_id, field_id, len),
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        trace!(
+            "Allocation size of flexible array member {:?} in {:?}",
+            field_id,
+            expr_id
+        );
+
+        let record_name = self
+            .type_converter
+            .borrow()
+            .resolve_decl_name(record_id)
+            .unwrap();
+        if let Some(cur_file) = self.cur_file.borrow().as_ref() {
+            self.add_import(cur_file, record_id, &record_name);
+        }
+
+        let ty = self.convert_type(ty.ctype)?;
+        let len = self.convert_expr(ctx.used(), len)?;
+        Ok(Some(len.map(|len| {
+            let alloc_size = mk().call_expr(
+                mk().path_expr(vec![record_name, "alloc_size".into()]),
+                vec![cast_int(len, "usize")],
+            );
+            mk().cast_expr(alloc_size, ty)
+        })))
+    }
+
+    /// The struct and flexible array member of `sizeof(S)` if `S` has a flexible array member
+    fn flexible_array_size_of(&self, expr_id: CExprId) -> Option<(CRecordId, CFieldId)> {
+        match self.ast_context[expr_id].kind {
+            CExprKind::Paren(_, expr_id) => self.flexible_array_size_of(expr_id),
+            CExprKind::UnaryType(_, UnTypeOp::SizeOf, _, arg_ty) => {
+                match self.ast_context.resolve_type(arg_ty.ctype).kind {
+                    CTypeKind::Struct(record_id) => {
+                        let field_id = self.flexible_array_member(record_id)?;
+                        Some((record_id, field_id))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The number of elements of the flexible array member `field_id` that `expr_id` computes
+    /// the size of, i.e. `n` in `n * sizeof(T)`
+    fn flexible_array_len(&self, field_id: CFieldId, expr_id: CExprId) -> Option<CExprId> {
+        let elt = self.flexible_array_element(field_id)?;
+        let elt = self.ast_context.resolve_type_id(elt);
+        let is_elt_size = |expr_id| match self.ast_context[expr_id].kind {
+            CExprKind::UnaryType(_, UnTypeOp::SizeOf, _, arg_ty) => {
+                self.ast_context.resolve_type_id(arg_ty.ctype) == elt
+            }
+            _ => false,
+        };
+
+        match self.ast_context[expr_id].kind {
+            CExprKind::Paren(_, inner) => self.flexible_array_len(field_id, inner),
+            CExprKind::Binary(_, c_ast::BinOp::Multiply, lhs, rhs, _, _) if is_elt_size(rhs) => {
+                Some(lhs)
+            }
+            CExprKind::Binary(_, c_ast::BinOp::Multiply, lhs, rhs, _, _) if is_elt_size(lhs) => {
+                Some(rhs)
+            }
+            _ => match self.ast_context.resolve_type(elt).kind {
+                CTypeKind::Char | CTypeKind::SChar | CTypeKind::UChar => Some(expr_id),
+                _ => None,
+            },
+        }
+    }
+}
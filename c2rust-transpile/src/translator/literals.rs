@@ -36,30 +36,19 @@ impl<'c> Translation<'c> {
             _ => panic!("{:?} does not point to an `enum` type"),
         };
 
-        let (variants, underlying_type_id) = match self.ast_context[def_id].kind {
-            CDeclKind::Enum {
-                ref variants,
-                integral_type,
-                ..
-            } => (variants, integral_type),
+        let underlying_type_id = match self.ast_context[def_id].kind {
+            CDeclKind::Enum { integral_type, .. } => integral_type,
             _ => panic!("{:?} does not point to an `enum` declaration"),
         };
 
-        for &variant_id in variants {
-            match self.ast_context[variant_id].kind {
-                CDeclKind::EnumConstant { value: v, .. } => {
-                    if v == ConstIntExpr::I(value) || v == ConstIntExpr::U(value as u64) {
-                        let name = self.renamer.borrow().get(&variant_id).unwrap();
+        if let Some(variant_id) = self.enum_variant_for_value(def_id, value) {
+            let name = self.renamer.borrow().get(&variant_id).unwrap();
 
-                        // Import the enum variant if needed
-                        if let Some(cur_file) = self.cur_file.borrow().as_ref() {
-                            self.add_import(cur_file, variant_id, &name);
-                        }
-                        return mk().path_expr(vec![name]);
-                    }
-                }
-                _ => panic!("{:?} does not point to an enum variant", variant_id),
+            // Import the enum variant if needed
+            if let Some(cur_file) = self.cur_file.borrow().as_ref() {
+                self.add_import(cur_file, variant_id, &name);
             }
+            return mk().path_expr(vec![name]);
         }
 
         let underlying_type_id =
@@ -123,14 +112,7 @@ impl<'c> Translation<'c> {
                     c_str.to_owned()
                 };
                 let val = match self.ast_context.resolve_type(ty.ctype).kind {
-                    CTypeKind::LongDouble => {
-                        self.extern_crates.borrow_mut().insert("f128");
-
-                        let fn_path = mk().path_expr(vec!["f128", "f128", "new"]);
-                        let args = vec![mk().ident_expr(str)];
-
-                        mk().call_expr(fn_path, args)
-                    }
+                    CTypeKind::LongDouble => self.convert_long_double_literal(is_static, val, &str),
                     CTypeKind::Double => mk().lit_expr(mk().float_lit(str, FloatTy::F64)),
                     CTypeKind::Float => mk().lit_expr(mk().float_lit(str, FloatTy::F32)),
                     ref k => panic!("Unsupported floating point literal type {:?}", k),
@@ -257,6 +239,9 @@ impl<'c> Translation<'c> {
                 let id = ids.first().unwrap();
                 self.convert_expr(ctx.used(), *id)
             }
+            CTypeKind::Vector(..) if self.portable_vector_type(ty.ctype).is_some() => {
+                self.portable_vector_initializer(ctx, ids, ty.ctype)
+            }
             CTypeKind::Vector(CQualTypeId { ctype, .. }, len) => {
                 self.vector_list_initializer(ctx, ids, ctype, len)
             }
@@ -276,11 +261,7 @@ impl<'c> Translation<'c> {
 
         match self.ast_context.index(union_id).kind {
             CDeclKind::Union { .. } => {
-                let union_name = self
-                    .type_converter
-                    .borrow()
-                    .resolve_decl_name(union_id)
-                    .unwrap();
+                let union_name = self.record_fields_name(union_id);
                 match self.ast_context.index(union_field_id).kind {
                     CDeclKind::Field { typ: field_ty, .. } => {
                         let val = if ids.is_empty() {
@@ -297,7 +278,7 @@ impl<'c> Translation<'c> {
                                 .resolve_field_name(Some(union_id), union_field_id)
                                 .unwrap();
                             let fields = vec![mk().field(field_name, v)];
-                            mk().struct_expr(name, fields)
+                            self.wrap_record_fields(union_id, mk().struct_expr(name, fields))
                         }))
                     }
                     _ => panic!("Union field decl mismatch"),
@@ -364,11 +345,7 @@ impl<'c> Translation<'c> {
             _ => panic!("Struct literal declaration mismatch"),
         };
 
-        let struct_name = self
-            .type_converter
-            .borrow()
-            .resolve_decl_name(struct_id)
-            .unwrap();
+        let struct_name = self.record_fields_name(struct_id);
 
         if has_bitfields {
             return self.convert_bitfield_struct_literal(
@@ -396,7 +373,8 @@ impl<'c> Translation<'c> {
            })
            .collect::<Result<WithStmts<Vec<ast::Field>>, TranslationError>>()?
            .map(|fields| {
-               mk().struct_expr(vec![mk().path_segment(struct_name)], fields)
+               let val = mk().struct_expr(vec![mk().path_segment(struct_name)], fields);
+               self.wrap_record_fields(struct_id, val)
            }))
     }
 }
@@ -0,0 +1,210 @@
+//! This module provides translations of `long double` values.
+//!
+//! Depending on `--long-double`, `long double` is represented with the `f128` crate, with the
+//! pure-Rust `c2rust_f80::f80` type, or with `f64`. In the latter case we emit a diagnostic
+//! wherever the lower precision may change the result.
+
+use super::*;
+use crate::diagnostics::Diagnostic;
+use c2rust_f80::f80;
+
+impl<'c> Translation<'c> {
+    /// Convert a `long double` literal, given its value rounded to a `double` and its digits.
+    pub fn convert_long_double_literal(&self, is_static: bool, val: f64, digits: &str) -> P<Expr> {
+        match self.tcfg.long_double {
+            LongDoubleMode::F128 => {
+                self.extern_crates.borrow_mut().insert("f128");
+
+                let fn_path = mk().path_expr(vec!["f128", "f128", "new"]);
+                mk().call_expr(fn_path, vec![mk().ident_expr(digits)])
+            }
+            LongDoubleMode::F64 => mk().lit_expr(mk().float_lit(digits, FloatTy::F64)),
+            LongDoubleMode::F80 => {
+                self.extern_crates.borrow_mut().insert("c2rust_f80");
+
+                let rounded = f80::from(val);
+                let exact = digits.parse().unwrap_or(rounded);
+                if exact.to_bits() == rounded.to_bits() && !is_static {
+                    let fn_path = mk().path_expr(vec!["c2rust_f80", "f80", "from"]);
+                    let lit = mk().lit_expr(mk().float_lit(digits, FloatTy::F64));
+                    mk().call_expr(fn_path, vec![lit])
+                } else {
+                    // `from_bits` is a `const fn`, so this also works in static initializers
+                    let fn_path = mk().path_expr(vec!["c2rust_f80", "f80", "from_bits"]);
+                    let bits = mk().float_unsuffixed_lit(format!("0x{:x}", exact.to_bits()));
+                    mk().call_expr(fn_path, vec![mk().lit_expr(bits)])
+                }
+            }
+        }
+    }
+
+    /// The `long double` with value zero
+    pub fn long_double_zero(&self) -> P<Expr> {
+        match self.tcfg.long_double {
+            LongDoubleMode::F128 => mk().path_expr(vec!["f128", "f128", "ZERO"]),
+            LongDoubleMode::F64 => mk().lit_expr(mk().float_unsuffixed_lit("0.")),
+            LongDoubleMode::F80 => mk().path_expr(vec!["c2rust_f80", "f80", "ZERO"]),
+        }
+    }
+
+    /// The `long double` with value one
+    pub fn long_double_one(&self) -> P<Expr> {
+        match self.tcfg.long_double {
+            LongDoubleMode::F128 => {
+                self.extern_crates.borrow_mut().insert("f128");
+
+                let fn_path = mk().path_expr(vec!["f128", "f128", "new"]);
+                mk().call_expr(fn_path, vec![mk().ident_expr("1.")])
+            }
+            LongDoubleMode::F64 => mk().lit_expr(mk().float_unsuffixed_lit("1.")),
+            LongDoubleMode::F80 => mk().path_expr(vec!["c2rust_f80", "f80", "ONE"]),
+        }
+    }
+
+    /// Convert the arithmetic value `val` to the `long double` type `target_ty`.
+    pub fn cast_to_long_double(&self, val: P<Expr>, target_ty: P<Ty>) -> P<Expr> {
+        let fn_path = match self.tcfg.long_double {
+            LongDoubleMode::F128 => {
+                self.extern_crates.borrow_mut().insert("f128");
+                mk().path_expr(vec!["f128", "f128", "new"])
+            }
+            LongDoubleMode::F64 => return mk().cast_expr(val, target_ty),
+            LongDoubleMode::F80 => mk().path_expr(vec!["c2rust_f80", "f80", "from"]),
+        };
+        mk().call_expr(fn_path, vec![val])
+    }
+
+    /// Convert the `long double` `val` to the arithmetic type `target_ty`.
+    pub fn cast_from_long_double(
+        &self,
+        val: P<Expr>,
+        target_ctype: &CTypeKind,
+        target_ty: P<Ty>,
+    ) -> Result<P<Expr>, TranslationError> {
+        match self.tcfg.long_double {
+            LongDoubleMode::F128 => {
+                self.extern_crates.borrow_mut().insert("num_traits");
+                self.item_store
+                    .borrow_mut()
+                    .uses
+                    .get_mut(vec!["num_traits".into()])
+                    .insert("ToPrimitive");
+
+                let to_method_name = match target_ctype {
+                    CTypeKind::Float => "to_f32",
+                    CTypeKind::Double => "to_f64",
+                    CTypeKind::Char => "to_i8",
+                    CTypeKind::UChar => "to_u8",
+                    CTypeKind::Short => "to_i16",
+                    CTypeKind::UShort => "to_u16",
+                    CTypeKind::Int => "to_i32",
+                    CTypeKind::UInt => "to_u32",
+                    CTypeKind::Long => "to_i64",
+                    CTypeKind::ULong => "to_u64",
+                    CTypeKind::LongLong => "to_i128",
+                    CTypeKind::ULongLong => "to_u128",
+                    _ => {
+                        return Err(format_err!(
+                            "Tried casting long double to unsupported type: {:?}",
+                            target_ctype
+                        )
+                        .into())
+                    }
+                };
+
+                let to_call = mk().method_call_expr(val, to_method_name, Vec::<P<Expr>>::new());
+                Ok(mk().method_call_expr(to_call, "unwrap", Vec::<P<Expr>>::new()))
+            }
+            LongDoubleMode::F64 => Ok(mk().cast_expr(val, target_ty)),
+            LongDoubleMode::F80 => {
+                // Go through the widest type of the right kind, out of range values are
+                // undefined behavior in C anyway
+                let to_method_name = match target_ctype {
+                    CTypeKind::Float => "to_f32",
+                    CTypeKind::Double => "to_f64",
+                    k if k.is_unsigned_integral_type() => "to_u128",
+                    k if k.is_signed_integral_type() => "to_i128",
+                    _ => {
+                        return Err(format_err!(
+                            "Tried casting long double to unsupported type: {:?}",
+                            target_ctype
+                        )
+                        .into())
+                    }
+                };
+
+                let to_call = mk().method_call_expr(val, to_method_name, Vec::<P<Expr>>::new());
+                Ok(mk().cast_expr(to_call, target_ty))
+            }
+        }
+    }
+
+    /// Warn about the C expression `expr_id` if lowering `long double` to `f64` changes its value.
+    /// Only literals, conversions from wide integers and arithmetic can lose precision; values
+    /// computed from these inherit the error.
+    pub fn check_long_double_precision(&self, expr_id: CExprId) {
+        let is_long_double = |ty: CQualTypeId| match self.ast_context.resolve_type(ty.ctype).kind {
+            CTypeKind::LongDouble => true,
+            _ => false,
+        };
+
+        let reason = match self.ast_context[expr_id].kind {
+            CExprKind::Literal(ty, CLiteral::Floating(val, ref digits)) if is_long_double(ty) => {
+                match digits.parse::<f80>() {
+                    Ok(exact) if exact.to_bits() != f80::from(val).to_bits() => {
+                        format!("long double literal {} is rounded to {}", digits, val)
+                    }
+                    _ => return,
+                }
+            }
+
+            CExprKind::ImplicitCast(ty, expr, CastKind::IntegralToFloating, _, _)
+            | CExprKind::ExplicitCast(ty, expr, CastKind::IntegralToFloating, _, _)
+                if is_long_double(ty) =>
+            {
+                let source_ty = match self.ast_context[expr].kind.get_type() {
+                    Some(source_ty) => source_ty,
+                    None => return,
+                };
+                match self.ast_context.resolve_type(source_ty).kind {
+                    CTypeKind::Long
+                    | CTypeKind::ULong
+                    | CTypeKind::LongLong
+                    | CTypeKind::ULongLong
+                    | CTypeKind::Int128
+                    | CTypeKind::UInt128 => {
+                        "conversion of a 64-bit integer to long double is rounded to a double"
+                            .to_string()
+                    }
+                    _ => return,
+                }
+            }
+
+            CExprKind::Binary(ty, op, _, _, compute_ty, _) => {
+                let compute_ty = compute_ty.unwrap_or(ty);
+                let arithmetic = match op {
+                    c_ast::BinOp::Add
+                    | c_ast::BinOp::Subtract
+                    | c_ast::BinOp::Multiply
+                    | c_ast::BinOp::Divide
+                    | c_ast::BinOp::AssignAdd
+                    | c_ast::BinOp::AssignSubtract
+                    | c_ast::BinOp::AssignMultiply
+                    | c_ast::BinOp::AssignDivide => true,
+                    _ => false,
+                };
+                if !arithmetic || !is_long_double(compute_ty) {
+                    return;
+                }
+                "long double arithmetic is computed with double precision".to_string()
+            }
+
+            _ => return,
+        };
+
+        match self.ast_context[expr_id].loc {
+            Some(ref loc) => diag!(Diagnostic::LongDouble, "{}: {}", loc, reason),
+            None => diag!(Diagnostic::LongDouble, "{}", reason),
+        }
+    }
+}
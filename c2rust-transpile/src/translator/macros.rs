@@ -0,0 +1,448 @@
+//! This module provides translations of C function-like macros.
+//!
+//! We translate a macro into an `#[inline]` function when every one of its expansions has the
+//! same argument and result types and translates to the same Rust code. Otherwise we fall back
+//! to a `macro_rules!` macro built from the most common translation, which is used at every
+//! expansion that translates to it. All other expansions are translated inline.
+
+use std::collections::HashSet;
+
+use super::*;
+use crate::c_ast::iterators::DFNodes;
+
+/// How the expansions of a function-like macro refer to its translation
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum MacroFunctionKind {
+    /// A call to an `#[inline]` function
+    Function,
+    /// An invocation of a `macro_rules!` macro
+    MacroRules,
+}
+
+/// A translated function-like macro
+#[derive(Clone, Debug)]
+pub struct TranslatedMacroFunction {
+    kind: MacroFunctionKind,
+    /// Expansions that we replace with a use of the translated macro, along with the argument
+    /// expressions to translate at each of them
+    uses: IndexMap<CExprId, Vec<CExprId>>,
+    /// Types in the signature of the translated function, which need to be imported wherever
+    /// the function is defined
+    types: Vec<CTypeId>,
+}
+
+impl<'c> Translation<'c> {
+    /// Translate the definition of a function-like macro and record which of its expansions
+    /// should refer to it.
+    pub fn convert_macro_function(
+        &self,
+        ctx: ExprContext,
+        span: Span,
+        decl_id: CDeclId,
+        params: &[String],
+        expansions: &[CMacroExpansion],
+    ) -> Result<ConvertedDecl, TranslationError> {
+        let name = self
+            .renamer
+            .borrow_mut()
+            .get(&decl_id)
+            .expect("Macro function not named");
+
+        trace!("Translating macro {:?}: {:?}", decl_id, self.ast_context[decl_id]);
+
+        let ctx = ctx
+            .not_static()
+            .set_const(false)
+            .set_expanding_macro(decl_id);
+
+        self.with_scope(|| {
+            let param_names: Vec<String> = params
+                .iter()
+                .map(|param| self.renamer.borrow_mut().pick_name(param))
+                .collect();
+            let param_exprs: Vec<P<Expr>> = param_names
+                .iter()
+                .map(|param| mk().ident_expr(param))
+                .collect();
+
+            // Translate each expansion with its arguments replaced by the macro parameters, so
+            // that we can tell which expansions translate identically.
+            let mut bodies = vec![];
+            for expansion in expansions {
+                if !self.is_macro_expansion_substitutable(expansion) {
+                    continue;
+                }
+                match self.convert_macro_body(ctx, expansion, &param_exprs) {
+                    Ok(body) => {
+                        let body_str = block_to_string(&body);
+                        bodies.push((expansion, body, body_str));
+                    }
+                    Err(e) => trace!("  failed to translate expansion: {}", e),
+                }
+            }
+
+            // Pick the first of the most common translations
+            let mut counts: IndexMap<&str, usize> = IndexMap::new();
+            for (_, _, body_str) in &bodies {
+                *counts.entry(body_str.as_str()).or_insert(0) += 1;
+            }
+            let template = match counts.iter().rev().max_by_key(|&(_, count)| count) {
+                Some((&body_str, _)) => body_str.to_owned(),
+                None => return Ok(ConvertedDecl::NoItem),
+            };
+            let mut bodies: Vec<_> = bodies
+                .into_iter()
+                .filter(|(_, _, body_str)| *body_str == template)
+                .map(|(expansion, body, _)| (expansion, body))
+                .collect();
+
+            let signatures = bodies
+                .iter()
+                .map(|(expansion, _)| self.macro_expansion_signature(expansion))
+                .collect::<Option<Vec<_>>>();
+            let signature = match signatures {
+                Some(ref signatures) if bodies.len() == expansions.len() => {
+                    let resolved = |signature: &Vec<CQualTypeId>| -> Vec<CTypeId> {
+                        signature
+                            .iter()
+                            .map(|ty| self.ast_context.resolve_type_id(ty.ctype))
+                            .collect()
+                    };
+                    let first = resolved(&signatures[0]);
+                    if signatures.iter().all(|signature| resolved(signature) == first) {
+                        Some(signatures[0].clone())
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+
+            if let Some(signature) = signature {
+                let (arg_tys, ret_ty) = signature.split_at(params.len());
+                let args = param_names
+                    .iter()
+                    .zip(arg_tys)
+                    .map(|(param, ty)| {
+                        Ok(mk().arg(self.convert_type(ty.ctype)?, mk().ident_pat(param)))
+                    })
+                    .collect::<Result<Vec<_>, TranslationError>>()?;
+                let ret = if self.is_void_type(ret_ty[0].ctype) {
+                    FunctionRetTy::Default(DUMMY_SP)
+                } else {
+                    FunctionRetTy::Ty(self.convert_type(ret_ty[0].ctype)?)
+                };
+                let decl = mk().fn_decl(args, ret, false);
+
+                // A function evaluates its arguments exactly once and before its body, so we
+                // can only call it where the arguments have no side effects.
+                let uses = bodies
+                    .iter()
+                    .filter(|(expansion, _)| {
+                        expansion
+                            .args
+                            .iter()
+                            .flatten()
+                            .all(|&arg| self.ast_context.is_expr_pure(arg))
+                    })
+                    .map(|(expansion, _)| (expansion.replacement, first_occurrences(expansion)))
+                    .collect();
+
+                self.macro_functions.borrow_mut().insert(
+                    decl_id,
+                    TranslatedMacroFunction {
+                        kind: MacroFunctionKind::Function,
+                        uses,
+                        types: signature.iter().map(|ty| ty.ctype).collect(),
+                    },
+                );
+
+                let block = bodies.swap_remove(0).1;
+                return Ok(ConvertedDecl::Item(
+                    mk().span(span)
+                        .pub_()
+                        .unsafe_()
+                        .single_attr("inline")
+                        .fn_item(name, decl, block),
+                ));
+            }
+
+            // Fall back to a `macro_rules!` macro, with the parameters as metavariables
+            let metavar_exprs: Vec<P<Expr>> = param_names
+                .iter()
+                .map(|param| mk().ident_expr(format!("${}", param)))
+                .collect();
+            let body = self.convert_macro_body(ctx, bodies[0].0, &metavar_exprs)?;
+
+            // Expand to a bare expression when possible, so that expansions which are lvalues
+            // in C can still be assigned to.
+            let bare_expr = match body.stmts.as_slice() {
+                [Stmt {
+                    node: StmtKind::Expr(ref expr),
+                    ..
+                }] => Some(expr.clone()),
+                _ => None,
+            };
+            let body = match bare_expr {
+                Some(expr) => Nonterminal::NtExpr(expr),
+                None => Nonterminal::NtBlock(body),
+            };
+
+            let mut rule = vec![TokenTree::Token(DUMMY_SP, Token::OpenDelim(DelimToken::Paren))];
+            for (i, param) in param_names.iter().enumerate() {
+                if i > 0 {
+                    rule.push(TokenTree::Token(DUMMY_SP, Token::Comma));
+                }
+                rule.push(TokenTree::Token(DUMMY_SP, Token::Dollar));
+                rule.push(TokenTree::Token(DUMMY_SP, Token::Ident(mk().ident(param), false)));
+                rule.push(TokenTree::Token(DUMMY_SP, Token::Colon));
+                rule.push(TokenTree::Token(DUMMY_SP, Token::Ident(mk().ident("expr"), false)));
+            }
+            rule.extend(vec![
+                TokenTree::Token(DUMMY_SP, Token::CloseDelim(DelimToken::Paren)),
+                TokenTree::Token(DUMMY_SP, Token::FatArrow),
+                TokenTree::Token(DUMMY_SP, Token::OpenDelim(DelimToken::Brace)),
+                TokenTree::Token(DUMMY_SP, Token::Interpolated(Lrc::new(body))),
+                TokenTree::Token(DUMMY_SP, Token::CloseDelim(DelimToken::Brace)),
+            ]);
+
+            let uses = bodies
+                .iter()
+                .map(|(expansion, _)| (expansion.replacement, first_occurrences(expansion)))
+                .collect();
+            self.macro_functions.borrow_mut().insert(
+                decl_id,
+                TranslatedMacroFunction {
+                    kind: MacroFunctionKind::MacroRules,
+                    uses,
+                    types: vec![],
+                },
+            );
+
+            // `macro_rules!` macros are only in scope textually after their definition, so we
+            // always emit them at the top of the main module, ahead of any functions.
+            let item = mk().span(span).macro_rules_item(name, rule);
+            self.item_store.borrow_mut().items.push(item);
+            Ok(ConvertedDecl::NoItem)
+        })
+    }
+
+    /// Translate an expansion of a function-like macro into a use of its translation, if we
+    /// decided that this expansion should use it.
+    pub fn convert_macro_function_use(
+        &self,
+        ctx: ExprContext,
+        macro_id: CDeclId,
+        expr_id: CExprId,
+    ) -> Result<Option<WithStmts<P<Expr>>>, TranslationError> {
+        let (kind, args) = match self.macro_functions.borrow().get(&macro_id) {
+            Some(mac) => match mac.uses.get(&expr_id) {
+                Some(args) => (mac.kind, args.clone()),
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+
+        // Static initializers can't call functions, and the macro bodies aren't constant
+        if ctx.is_static || ctx.is_const {
+            return Ok(None);
+        }
+
+        let in_submodule = self.tcfg.reorganize_definitions
+            && self
+                .cur_file
+                .borrow()
+                .as_ref()
+                .map_or(false, |file| file != &self.main_file);
+        if kind == MacroFunctionKind::MacroRules && in_submodule {
+            return Ok(None);
+        }
+
+        let rustname = self
+            .renamer
+            .borrow_mut()
+            .get(&macro_id)
+            .ok_or_else(|| format_err!("Macro name not declared"))?;
+
+        // The whole expansion may be an argument, as in `#define ID(x) x`, so don't look for
+        // this macro again when translating the arguments.
+        let arg_ctx = ctx.used().set_expanding_macro(macro_id);
+
+        let val = match kind {
+            MacroFunctionKind::Function => {
+                if let Some(cur_file) = self.cur_file.borrow().as_ref() {
+                    self.add_import(cur_file, macro_id, &rustname);
+                }
+
+                let mut call = self
+                    .convert_exprs(arg_ctx, &args)?
+                    .map(|args| mk().call_expr(mk().path_expr(vec![rustname]), args));
+                call.set_unsafe();
+                call
+            }
+
+            MacroFunctionKind::MacroRules => {
+                // Macro arguments are evaluated wherever they occur in the macro body, so any
+                // side effects have to stay inside the argument.
+                let mut tokens = vec![];
+                for (i, &arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        tokens.push(TokenTree::Token(DUMMY_SP, Token::Comma));
+                    }
+                    let arg = self.convert_expr(arg_ctx, arg)?.to_expr();
+                    tokens.push(TokenTree::Token(
+                        DUMMY_SP,
+                        Token::Interpolated(Lrc::new(Nonterminal::NtExpr(arg))),
+                    ));
+                }
+                let mac = mk().mac(vec![rustname], tokens, MacDelimiter::Parenthesis);
+                WithStmts::new_val(mk().mac_expr(mac))
+            }
+        };
+
+        self.convert_side_effects_expr(ctx, val, "Macro expansion is not supposed to be used")
+            .map(Some)
+    }
+
+    /// Get the types in the signature of a translated function-like macro.
+    pub fn macro_function_types(&self, macro_id: CDeclId) -> Vec<CTypeId> {
+        self.macro_functions
+            .borrow()
+            .get(&macro_id)
+            .map(|mac| mac.types.clone())
+            .unwrap_or_default()
+    }
+
+    /// Translate the body of a macro expansion, substituting the given expressions for each
+    /// occurrence of the corresponding argument.
+    fn convert_macro_body(
+        &self,
+        ctx: ExprContext,
+        expansion: &CMacroExpansion,
+        args: &[P<Expr>],
+    ) -> Result<P<Block>, TranslationError> {
+        {
+            let mut macro_args = self.macro_args.borrow_mut();
+            for (occurrences, arg) in expansion.args.iter().zip(args) {
+                for &occurrence in occurrences {
+                    macro_args.insert(occurrence, arg.clone());
+                }
+            }
+        }
+
+        let replacement_ty = self.ast_context[expansion.replacement].kind.get_type();
+        let body = if replacement_ty.map_or(true, |ty| self.is_void_type(ty)) {
+            self.convert_expr(ctx.unused(), expansion.replacement)
+                .map(|body| mk().block(body.into_stmts()))
+        } else {
+            self.convert_expr(ctx.used(), expansion.replacement)
+                .map(WithStmts::to_block)
+        };
+
+        self.macro_args.borrow_mut().clear();
+        body
+    }
+
+    /// Check that every argument of an expansion occurs in it and that the rest of the
+    /// expansion doesn't depend on where the macro was expanded.
+    fn is_macro_expansion_substitutable(&self, expansion: &CMacroExpansion) -> bool {
+        if expansion.args.iter().any(|occurrences| occurrences.is_empty()) {
+            return false;
+        }
+
+        let arg_nodes: HashSet<SomeId> = expansion
+            .args
+            .iter()
+            .flatten()
+            .flat_map(|&arg| DFNodes::new(&self.ast_context, SomeId::Expr(arg)))
+            .collect();
+        let body_nodes: Vec<SomeId> =
+            DFNodes::new(&self.ast_context, SomeId::Expr(expansion.replacement))
+                .filter(|node| !arg_nodes.contains(node))
+                .collect();
+        let declared: HashSet<CDeclId> = body_nodes.iter().filter_map(|node| node.decl()).collect();
+
+        body_nodes.iter().all(|&node| match node {
+            SomeId::Expr(expr_id) => match self.ast_context[expr_id].kind {
+                // Local variables of the function the macro was expanded in aren't in scope
+                CExprKind::DeclRef(_, decl_id, _) => match self.ast_context[decl_id].kind {
+                    CDeclKind::Variable { .. } => {
+                        declared.contains(&decl_id)
+                            || self.ast_context.c_decls_top.contains(&decl_id)
+                    }
+                    _ => true,
+                },
+                CExprKind::Predefined(..) | CExprKind::VAArg(..) | CExprKind::AddrLabel(..) => {
+                    false
+                }
+                _ => true,
+            },
+            // Control flow that leaves the expansion
+            SomeId::Stmt(stmt_id) => match self.ast_context[stmt_id].kind {
+                CStmtKind::Return(..)
+                | CStmtKind::Goto(..)
+                | CStmtKind::IndirectGoto(..)
+                | CStmtKind::Label(..)
+                | CStmtKind::Break
+                | CStmtKind::Continue => false,
+                _ => true,
+            },
+            _ => true,
+        })
+    }
+
+    /// Get the argument types followed by the result type of an expansion, or `None` if the
+    /// expansion can't be replaced with a function call.
+    fn macro_expansion_signature(&self, expansion: &CMacroExpansion) -> Option<Vec<CQualTypeId>> {
+        let is_value = |expr_id: CExprId| -> Option<CQualTypeId> {
+            // Functions can't assign through their parameters or result
+            if self.is_lvalue(expr_id) {
+                return None;
+            }
+            let ty = self.ast_context[expr_id].kind.get_qual_type()?;
+            match self.ast_context.resolve_type(ty.ctype).kind {
+                CTypeKind::ConstantArray(..)
+                | CTypeKind::IncompleteArray(..)
+                | CTypeKind::VariableArray(..)
+                | CTypeKind::Function(..) => None,
+                _ => Some(ty),
+            }
+        };
+
+        let mut signature = vec![];
+        for occurrences in &expansion.args {
+            let ty = is_value(occurrences[0])?;
+            let resolved = self.ast_context.resolve_type_id(ty.ctype);
+            for &occurrence in &occurrences[1..] {
+                let other = is_value(occurrence)?;
+                if self.ast_context.resolve_type_id(other.ctype) != resolved {
+                    return None;
+                }
+            }
+            signature.push(ty);
+        }
+        signature.push(is_value(expansion.replacement)?);
+        Some(signature)
+    }
+
+    fn is_lvalue(&self, expr_id: CExprId) -> bool {
+        match self.ast_context[expr_id].kind {
+            CExprKind::Paren(_, e) => self.is_lvalue(e),
+            CExprKind::CompoundLiteral(..) => true,
+            ref kind => kind.lrvalue() == LRValue::LValue,
+        }
+    }
+
+    fn is_void_type(&self, ty: CTypeId) -> bool {
+        self.ast_context.resolve_type(ty).kind == CTypeKind::Void
+    }
+}
+
+/// The expression to translate for each argument of an expansion
+fn first_occurrences(expansion: &CMacroExpansion) -> Vec<CExprId> {
+    expansion
+        .args
+        .iter()
+        .map(|occurrences| occurrences[0])
+        .collect()
+}
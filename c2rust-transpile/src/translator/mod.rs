@@ -27,21 +27,37 @@ use crate::c_ast::iterators::{DFExpr, SomeId};
 use crate::c_ast::*;
 use crate::cfg;
 use crate::convert_type::TypeConverter;
+use crate::diagnostics::Diagnostic;
+use crate::doc_comments;
 use crate::renamer::Renamer;
+use crate::report::{DeclReport, DeclStatus};
+use crate::source_map::{self, SourceMapping};
 use crate::with_stmts::WithStmts;
 use crate::TranspilerConfig;
 use c2rust_ast_exporter::clang_ast::LRValue;
 
 mod assembly;
+mod atomics;
 mod bitfields;
 mod builtins;
+mod complex;
+mod enums;
+mod flexible_arrays;
 mod literals;
+mod long_double;
+mod macros;
 mod main_function;
 mod named_references;
 mod operators;
+mod setjmp;
 mod simd;
+mod static_init;
+mod structs;
+mod targets;
 mod variadic;
 
+pub use self::setjmp::SetjmpIf;
+pub use self::targets::{check_targets, merge_target_translations, translate_target};
 pub use crate::diagnostics::{TranslationError, TranslationErrorKind};
 use crate::CrateSet;
 use crate::PragmaVec;
@@ -91,6 +107,48 @@ pub enum ReplaceMode {
     Extern,
 }
 
+/// How to translate the `long double` type
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LongDoubleMode {
+    /// `f128::f128`, which needs a C compiler to build
+    F128,
+    /// `f64`, losing precision
+    F64,
+    /// `c2rust_f80::f80`, a pure-Rust x87 extended precision float
+    F80,
+}
+
+/// How to translate `va_list` and variadic function definitions
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VaListMode {
+    /// `std::ffi::VaList` and Rust variadic functions, which need the unstable `c_variadic`
+    /// feature
+    Nightly,
+    /// `c2rust_va_list::VaList`, with a C trampoline for each variadic function definition
+    Runtime,
+}
+
+/// How to translate arithmetic on signed integers, whose overflow is undefined behavior in C.
+/// Arithmetic on unsigned integers always wraps around, like in C.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OverflowMode {
+    /// Plain operators, which panic on overflow in debug builds and wrap around in release builds
+    Plain,
+    /// `wrapping_*` methods, like compiling the C code with `-fwrapv`
+    Wrapping,
+    /// `checked_*` methods, which panic with the location of the C expression on overflow
+    Report,
+}
+
+/// How to initialize statics whose initializers Rust can't evaluate at compile time
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StaticInitMode {
+    /// In `run_static_initializers`, which is run before `main` from an `.init_array` section
+    Sections,
+    /// On first use, through an accessor function for each such static
+    Lazy,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct ExprContext {
     used: bool,
@@ -107,6 +165,10 @@ pub struct ExprContext {
 
     ternary_needs_parens: bool,
     expanding_macro: Option<CDeclId>,
+
+    // We are translating the initializer of a static that is initialized on first use, which
+    // must refer to other statics directly rather than through their accessors.
+    is_static_init: bool,
 }
 
 impl ExprContext {
@@ -146,6 +208,12 @@ impl ExprContext {
     pub fn set_static(self, is_static: bool) -> Self {
         ExprContext { is_static, ..self }
     }
+    pub fn static_init(self) -> Self {
+        ExprContext {
+            is_static_init: true,
+            ..self
+        }
+    }
     pub fn set_const(self, is_const: bool) -> Self {
         ExprContext { is_const, ..self }
     }
@@ -231,10 +299,15 @@ pub struct Translation<'c> {
     function_context: RefCell<FunContext>,
     potential_flexible_array_members: RefCell<IndexSet<CDeclId>>,
     macro_types: RefCell<IndexMap<CDeclId, CQualTypeId>>,
+    macro_functions: RefCell<IndexMap<CDeclId, macros::TranslatedMacroFunction>>,
+    // Expressions to substitute for macro arguments while translating a macro body
+    macro_args: RefCell<IndexMap<CExprId, P<Expr>>>,
 
     // Comment support
     pub comment_context: RefCell<CommentContext>, // Incoming comments
     pub comment_store: RefCell<CommentStore>,     // Outgoing comments
+    // Source locations of the marker comments for `--source-map`
+    source_locs: RefCell<Vec<SrcLoc>>,
 
     // Mod block defintion reorganization
     mod_blocks: RefCell<IndexMap<PathBuf, ItemStore>>,
@@ -245,6 +318,19 @@ pub struct Translation<'c> {
     // The file that the translator is operating on
     main_file: PathBuf,
 
+    // The outcome of each top-level declaration, for `--report`
+    decl_reports: RefCell<Vec<DeclReport>>,
+    // Definitions that were replaced by declarations, with the error that caused it
+    stubbed_decls: RefCell<IndexMap<CDeclId, TranslationError>>,
+    // C trampolines of the variadic functions translated with `VaListMode::Runtime`
+    va_trampolines: RefCell<Vec<String>>,
+    // Accessors of the statics initialized on first use with `StaticInitMode::Lazy`
+    lazy_static_accessors: RefCell<IndexMap<CDeclId, Option<String>>>,
+    // Statics whose address other static initializers take, which are never initialized lazily
+    statics_in_initializers: IndexSet<CDeclId>,
+    // Enums translated into Rust `enum`s with `--closed-enums`
+    closed_enums: IndexSet<CEnumId>,
+
     // While expanding an item, store the current file path that item is
     // expanded from. This is needed in order to note imports in mod_blocks when
     // encountering DeclRefs.
@@ -442,11 +528,117 @@ pub fn translate_failure(tcfg: &TranspilerConfig, msg: &str) {
     }
 }
 
+/// Translate a translation unit into the source of a Rust module. Along with the pragmas and
+/// crates it needs, the declaration reports and the trampolines of variadic functions, the
+/// source map of the module is returned when `tcfg.source_map` is set.
 pub fn translate(
     ast_context: TypedAstContext,
     tcfg: &TranspilerConfig,
     main_file: PathBuf,
-) -> (String, PragmaVec, CrateSet) {
+) -> (
+    String,
+    PragmaVec,
+    CrateSet,
+    Vec<DeclReport>,
+    Option<String>,
+    Option<Vec<SourceMapping>>,
+) {
+    let translation = translate_with(ast_context, tcfg, main_file, |t, pragmas, crates| {
+        let source_locs = t.source_locs.replace(vec![]);
+
+        // pass all converted items to the Rust pretty printer
+        let translation = to_string(|s| {
+            print_header(s, t.tcfg, pragmas, crates)?;
+
+            // Re-order comments
+            let mut traverser = t.comment_store.into_inner().into_comment_traverser();
+            let mut mod_items: Vec<P<Item>> = Vec::new();
+
+            // Header Reorganization: Submodule Item Stores
+            for (file_path, ref mut mod_item_store) in t.mod_blocks.borrow_mut().iter_mut() {
+                mod_items.push(make_submodule(
+                    mod_item_store,
+                    file_path,
+                    &t.item_store,
+                    &t.mod_names,
+                ));
+            }
+
+            // Global Item Store
+            let (items, foreign_items, uses) = t.item_store.borrow_mut().drain();
+
+            mod_items = mod_items
+                .into_iter()
+                .map(|p_i| p_i.map(|i| traverser.traverse_item(i)))
+                .collect();
+            let foreign_items: Vec<ForeignItem> = foreign_items
+                .into_iter()
+                .map(|fi| traverser.traverse_foreign_item(fi))
+                .collect();
+            let items: Vec<P<Item>> = items
+                .into_iter()
+                .map(|p_i| p_i.map(|i| traverser.traverse_item(i)))
+                .collect();
+
+            s.comments()
+                .get_or_insert(vec![])
+                .extend(traverser.into_comment_store().into_comments());
+
+            for mod_item in mod_items {
+                s.print_item(&*mod_item)?;
+            }
+
+            // This could have been merged in with items below; however, it's more idiomatic to have
+            // imports near the top of the file than randomly scattered about. Also, there is probably
+            // no reason to have comments associated with imports so it doesn't need to go through
+            // the above comment store process
+            for use_item in uses.into_items() {
+                s.print_item(&use_item)?;
+            }
+
+            if !foreign_items.is_empty() {
+                s.print_item(&mk().abi("C").foreign_items(foreign_items))?
+            }
+
+            // Add the items accumulated
+            for x in items {
+                s.print_item(&*x)?;
+            }
+
+            Ok(())
+        });
+
+        if tcfg.source_map {
+            let (translation, mappings) =
+                source_map::extract_source_map(&translation, &source_locs);
+            (translation, Some(mappings))
+        } else {
+            (translation, None)
+        }
+    });
+    let ((translation, source_map), pragmas, crates, reports, trampolines) = translation;
+    (
+        translation,
+        pragmas,
+        crates,
+        reports,
+        trampolines,
+        source_map,
+    )
+}
+
+/// Convert all declarations of a translation unit, then hand the translation to `print` along
+/// with the pragmas and crates it needs. The report of each top-level declaration and the C
+/// source of the trampolines of variadic functions are returned along with the result.
+fn translate_with<'c, R, F>(
+    ast_context: TypedAstContext,
+    tcfg: &'c TranspilerConfig,
+    main_file: PathBuf,
+    print: F,
+) -> (R, PragmaVec, CrateSet, Vec<DeclReport>, Option<String>)
+where
+    F: FnOnce(Translation<'c>, &PragmaVec, &CrateSet) -> R,
+{
     let mut t = Translation::new(ast_context, tcfg, main_file);
     let ctx = ExprContext {
         used: true,
@@ -457,6 +649,7 @@ pub fn translate(
         needs_address: false,
         ternary_needs_parens: false,
         expanding_macro: None,
+        is_static_init: false,
     };
 
     if t.tcfg.reorganize_definitions {
@@ -473,6 +666,13 @@ pub fn translate(
     // we simplify the translator output by omitting those.
     t.ast_context.prune_unused_decls();
 
+    if t.tcfg.closed_enums {
+        t.closed_enums = t.find_closed_enums();
+    }
+    if t.tcfg.static_init == StaticInitMode::Lazy {
+        t.statics_in_initializers = t.find_statics_in_initializers();
+    }
+
     enum Name<'a> {
         VarName(&'a str),
         TypeName(&'a str),
@@ -579,6 +779,7 @@ pub fn translate(
                     Name::VarName(ident)
                 }
                 CDeclKind::MacroObject { ref name, .. } => Name::VarName(name),
+                CDeclKind::MacroFunction { ref name, .. } => Name::VarName(name),
                 _ => Name::NoName,
             };
             match decl_name {
@@ -612,10 +813,15 @@ pub fn translate(
                 if t.tcfg.reorganize_definitions {
                     *t.cur_file.borrow_mut() = decl_file_path.cloned();
                 }
-                match t.convert_decl(ctx, decl_id) {
+                match t.convert_top_decl(ctx, decl_id) {
                     Ok(ConvertedDecl::Item(item)) => {
                         t.insert_item(item, decl_file_path);
                     }
+                    Ok(ConvertedDecl::Items(items)) => {
+                        for item in items {
+                            t.insert_item(item, decl_file_path);
+                        }
+                    }
                     Ok(ConvertedDecl::ForeignItem(item)) => {
                         t.insert_foreign_item(item, decl_file_path);
                     }
@@ -655,11 +861,14 @@ pub fn translate(
 
             // Export macros after the rest of the decls so we can reference all
             // types
-            if tcfg.translate_const_macros {
-                for (&decl_id, decl) in t.ast_context.iter_decls() {
-                    if let CDeclKind::MacroObject { .. } = decl.kind {
-                        convert_type(decl_id, decl);
-                    }
+            for (&decl_id, decl) in t.ast_context.iter_decls() {
+                let needs_export = match decl.kind {
+                    CDeclKind::MacroObject { .. } => tcfg.translate_const_macros,
+                    CDeclKind::MacroFunction { .. } => tcfg.translate_fn_macros,
+                    _ => false,
+                };
+                if needs_export {
+                    convert_type(decl_id, decl);
                 }
             }
         }
@@ -682,10 +891,15 @@ pub fn translate(
                 if t.tcfg.reorganize_definitions && decl_file_path != Some(&t.main_file) {
                     *t.cur_file.borrow_mut() = decl_file_path.cloned();
                 }
-                match t.convert_decl(ctx, *top_id) {
+                match t.convert_top_decl(ctx, *top_id) {
                     Ok(ConvertedDecl::Item(item)) => {
                         t.insert_item(item, decl_file_path);
                     }
+                    Ok(ConvertedDecl::Items(items)) => {
+                        for item in items {
+                            t.insert_item(item, decl_file_path);
+                        }
+                    }
                     Ok(ConvertedDecl::ForeignItem(item)) => {
                         t.insert_foreign_item(item, decl_file_path);
                     }
@@ -737,70 +951,17 @@ pub fn translate(
             items.push(initializer_static);
         }
 
+        // Pick up crates needed only for type translations, such as `num_complex`
+        t.extern_crates
+            .borrow_mut()
+            .extend(t.type_converter.borrow().crates_used());
+
         let pragmas = t.get_pragmas();
         let crates = t.extern_crates.borrow().clone();
-        // pass all converted items to the Rust pretty printer
-        let translation = to_string(|s| {
-            print_header(s, &t)?;
-
-            // Re-order comments
-            let mut traverser = t.comment_store.into_inner().into_comment_traverser();
-            let mut mod_items: Vec<P<Item>> = Vec::new();
-
-            // Header Reorganization: Submodule Item Stores
-            for (file_path, ref mut mod_item_store) in t.mod_blocks.borrow_mut().iter_mut() {
-                mod_items.push(make_submodule(
-                    mod_item_store,
-                    file_path,
-                    &t.item_store,
-                    &t.mod_names,
-                ));
-            }
-
-            // Global Item Store
-            let (items, foreign_items, uses) = t.item_store.borrow_mut().drain();
-
-            mod_items = mod_items
-                .into_iter()
-                .map(|p_i| p_i.map(|i| traverser.traverse_item(i)))
-                .collect();
-            let foreign_items: Vec<ForeignItem> = foreign_items
-                .into_iter()
-                .map(|fi| traverser.traverse_foreign_item(fi))
-                .collect();
-            let items: Vec<P<Item>> = items
-                .into_iter()
-                .map(|p_i| p_i.map(|i| traverser.traverse_item(i)))
-                .collect();
-
-            s.comments()
-                .get_or_insert(vec![])
-                .extend(traverser.into_comment_store().into_comments());
-
-            for mod_item in mod_items {
-                s.print_item(&*mod_item)?;
-            }
-
-            // This could have been merged in with items below; however, it's more idiomatic to have
-            // imports near the top of the file than randomly scattered about. Also, there is probably
-            // no reason to have comments associated with imports so it doesn't need to go through
-            // the above comment store process
-            for use_item in uses.into_items() {
-                s.print_item(&use_item)?;
-            }
-
-            if !foreign_items.is_empty() {
-                s.print_item(&mk().abi("C").foreign_items(foreign_items))?
-            }
-
-            // Add the items accumulated
-            for x in items {
-                s.print_item(&*x)?;
-            }
-
-            Ok(())
-        });
-        (translation, pragmas, crates)
+        let reports = t.decl_reports.replace(vec![]);
+        let trampolines = t.va_trampoline_source();
+        let translation = print(t, &pragmas, &crates);
+        (translation, pragmas, crates, reports, trampolines)
     })
 }
 
@@ -854,26 +1015,31 @@ fn make_submodule(
 }
 
 /// Pretty-print the leading pragmas and extern crate declarations
-fn print_header(s: &mut State, t: &Translation) -> io::Result<()> {
-    if t.tcfg.emit_modules {
+fn print_header(
+    s: &mut State,
+    tcfg: &TranspilerConfig,
+    pragmas: &PragmaVec,
+    crates: &CrateSet,
+) -> io::Result<()> {
+    if tcfg.emit_modules {
         s.print_item(&mk().use_item(vec!["libc"], None as Option<Ident>))?;
     } else {
-        let pragmas = t.get_pragmas();
-        for (key, mut values) in pragmas {
+        for (key, values) in pragmas {
+            let mut values = values.clone();
             values.sort();
             let value_attr_vec = values
                 .into_iter()
                 .map(|value| mk().nested_meta_item(mk().meta_item(vec![value], MetaItemKind::Word)))
                 .collect::<Vec<_>>();
-            let item = mk().meta_item(vec![key], MetaItemKind::List(value_attr_vec));
+            let item = mk().meta_item(vec![*key], MetaItemKind::List(value_attr_vec));
             for attr in mk().meta_item_attr(AttrStyle::Inner, item).as_inner_attrs() {
                 s.print_attribute(&attr)?;
             }
         }
 
-        if t.tcfg.cross_checks {
+        if tcfg.cross_checks {
             let mut xcheck_plugin_args: Vec<NestedMetaItem> = vec![];
-            for config_file in &t.tcfg.cross_check_configs {
+            for config_file in &tcfg.cross_check_configs {
                 let file_lit = mk().str_lit(config_file);
                 let file_item = mk().meta_item(vec!["config_file"], file_lit);
                 xcheck_plugin_args.push(mk().nested_meta_item(file_item));
@@ -892,16 +1058,16 @@ fn print_header(s: &mut State, t: &Translation) -> io::Result<()> {
             }
         }
 
-        if t.tcfg.emit_no_std {
+        if tcfg.emit_no_std {
             s.print_attribute(&mk().single_attr("no_std").as_inner_attrs()[0])?;
         }
 
         // Add `extern crate X;` to the top of the file
-        for crate_name in t.extern_crates.borrow().iter() {
+        for crate_name in crates.iter() {
             s.print_item(&mk().extern_crate_item(*crate_name, None))?;
         }
 
-        if t.tcfg.cross_checks {
+        if tcfg.cross_checks {
             s.print_item(
                 &mk()
                     .single_attr("macro_use")
@@ -961,9 +1127,32 @@ pub enum ExprUse {
 pub enum ConvertedDecl {
     ForeignItem(ForeignItem),
     Item(P<Item>),
+    Items(Vec<P<Item>>),
     NoItem,
 }
 
+impl ConvertedDecl {
+    /// Document the item, or the first of the items, with the doc comment `lines`
+    fn with_docs(mut self, lines: &[String]) -> Self {
+        let docs = lines.iter().map(|line| mk().doc_attr(line));
+        match self {
+            ConvertedDecl::ForeignItem(ref mut item) => {
+                item.attrs.splice(0..0, docs);
+            }
+            ConvertedDecl::Item(ref mut item) => {
+                item.attrs.splice(0..0, docs);
+            }
+            ConvertedDecl::Items(ref mut items) => {
+                if let Some(item) = items.first_mut() {
+                    item.attrs.splice(0..0, docs);
+                }
+            }
+            ConvertedDecl::NoItem => {}
+        }
+        self
+    }
+}
+
 impl<'c> Translation<'c> {
     pub fn new(
         mut ast_context: TypedAstContext,
@@ -976,6 +1165,8 @@ impl<'c> Translation<'c> {
         if tcfg.translate_valist {
             type_converter.translate_valist = true
         }
+        type_converter.long_double = tcfg.long_double;
+        type_converter.va_list = tcfg.va_list;
 
         Translation {
             features: RefCell::new(IndexSet::new()),
@@ -1000,15 +1191,175 @@ impl<'c> Translation<'c> {
             function_context: RefCell::new(FunContext::new()),
             potential_flexible_array_members: RefCell::new(IndexSet::new()),
             macro_types: RefCell::new(IndexMap::new()),
+            macro_functions: RefCell::new(IndexMap::new()),
+            macro_args: RefCell::new(IndexMap::new()),
             comment_context,
             comment_store: RefCell::new(CommentStore::new()),
+            source_locs: RefCell::new(vec![]),
             sectioned_static_initializers: RefCell::new(Vec::new()),
             mod_blocks: RefCell::new(IndexMap::new()),
             mod_names: RefCell::new(IndexMap::new()),
             main_file,
             extern_crates: RefCell::new(IndexSet::new()),
             cur_file: RefCell::new(None),
+            decl_reports: RefCell::new(Vec::new()),
+            stubbed_decls: RefCell::new(IndexMap::new()),
+            va_trampolines: RefCell::new(vec![]),
+            lazy_static_accessors: RefCell::new(IndexMap::new()),
+            statics_in_initializers: IndexSet::new(),
+            closed_enums: IndexSet::new(),
+        }
+    }
+
+    /// The marker comment that maps the next item or statement back to `loc` when writing a
+    /// source map
+    pub fn source_marker(&self, loc: &Option<SrcLoc>) -> Option<String> {
+        if !self.tcfg.source_map {
+            return None;
+        }
+        let mut source_locs = self.source_locs.borrow_mut();
+        source_locs.push(loc.as_ref()?.clone());
+        Some(source_map::marker(source_locs.len() - 1))
+    }
+
+    /// Take the comments that document `decl_id` in the C source and translate them into the
+    /// lines of a doc comment. A typedef that names an otherwise unnamed type isn't translated on
+    /// its own, so its comments document the type instead.
+    fn take_doc_comments(&self, decl_id: CDeclId) -> Vec<String> {
+        let mut comment_context = self.comment_context.borrow_mut();
+        let typedef_ids = self
+            .ast_context
+            .prenamed_decls
+            .iter()
+            .filter(|&(_, &named_id)| named_id == decl_id)
+            .map(|(&typedef_id, _)| typedef_id);
+
+        let mut comments = vec![];
+        for id in typedef_ids.chain(std::iter::once(decl_id)) {
+            if let Some(ref loc) = self.ast_context[id].loc {
+                comments.extend(comment_context.remove_decl_doc_comment(id, loc));
+            }
+        }
+        doc_comments::doc_comment_lines(&comments)
+    }
+
+    /// A builder for the translation of the field `field_id` with its doc comment. Rust fields
+    /// can't carry other comments, so those are reported instead.
+    fn field_builder(&self, field_id: CFieldId, span: Span) -> Builder {
+        let docs = self.take_doc_comments(field_id);
+        for comment in self
+            .comment_context
+            .borrow_mut()
+            .remove_decl_comment(field_id)
+        {
+            diag!(
+                Diagnostic::Comments,
+                "Could not place the comment '{}' on a field",
+                comment
+            );
+        }
+        docs.iter()
+            .fold(mk().span(span), |builder, line| builder.doc_comment(line))
+    }
+
+    /// Convert a top-level declaration like `convert_decl`, recording the outcome along with the
+    /// features and crates the declaration needs for the translation report.
+    fn convert_top_decl(
+        &self,
+        ctx: ExprContext,
+        decl_id: CDeclId,
+    ) -> Result<ConvertedDecl, TranslationError> {
+        // Collect the features and crates used by this declaration on their own, then merge
+        // them back in with those of the others
+        let features = self.features.replace(IndexSet::new());
+        let crates = self.extern_crates.replace(IndexSet::new());
+        let (type_features, type_crates) = self.type_converter.borrow_mut().take_usage();
+
+        // The doc comment has to be taken before `convert_decl` takes the other comments
+        let docs = self.take_doc_comments(decl_id);
+        let result = self
+            .convert_decl(ctx, decl_id)
+            .map(|converted| converted.with_docs(&docs));
+
+        let decl_features = self.features.replace(features);
+        let decl_crates = self.extern_crates.replace(crates);
+        let (decl_type_features, decl_type_crates) = self.type_converter.borrow_mut().take_usage();
+        self.features
+            .borrow_mut()
+            .extend(decl_features.iter().cloned());
+        self.extern_crates
+            .borrow_mut()
+            .extend(decl_crates.iter().cloned());
+        {
+            let mut type_converter = self.type_converter.borrow_mut();
+            type_converter.add_usage(&type_features, &type_crates);
+            type_converter.add_usage(&decl_type_features, &decl_type_crates);
+        }
+
+        let decl = &self.ast_context[decl_id];
+        let kind = match decl.kind {
+            CDeclKind::Function { .. } => "function",
+            CDeclKind::Variable { .. } => "variable",
+            CDeclKind::Enum { .. } => "enum",
+            CDeclKind::EnumConstant { .. } => "enum constant",
+            CDeclKind::Typedef { .. } => "typedef",
+            CDeclKind::Struct { .. } => "struct",
+            CDeclKind::Union { .. } => "union",
+            CDeclKind::Field { .. } => "field",
+            CDeclKind::MacroObject { .. } => "object-like macro",
+            CDeclKind::MacroFunction { .. } => "function-like macro",
+        };
+
+        let mut features: Vec<String> = decl_features
+            .iter()
+            .chain(&decl_type_features)
+            .map(|feature| feature.to_string())
+            .collect();
+        features.sort();
+        features.dedup();
+        let mut crates: Vec<String> = decl_crates
+            .iter()
+            .chain(&decl_type_crates)
+            .map(|krate| krate.to_string())
+            .collect();
+        crates.sort();
+        crates.dedup();
+
+        let is_definition = match decl.kind {
+            CDeclKind::Function { body, .. } => body.is_some(),
+            CDeclKind::Variable { is_defn, .. } => is_defn,
+            _ => false,
+        };
+        let decl_file_path = decl.loc.as_ref().and_then(|loc| loc.file_path.as_ref());
+
+        let mut report = DeclReport {
+            id: decl_id.0,
+            name: decl.kind.get_name().cloned(),
+            kind: kind.to_string(),
+            loc: decl.loc.as_ref().map(Into::into),
+            main_file_definition: is_definition && decl_file_path == Some(&self.main_file),
+            target: None,
+            status: DeclStatus::Translated,
+            errors: vec![],
+            error_locs: vec![],
+            features,
+            crates,
+        };
+        match result {
+            Ok(_) => {
+                if let Some(e) = self.stubbed_decls.borrow().get(&decl_id) {
+                    report.status = DeclStatus::Stubbed;
+                    report.set_error(e);
+                }
+            }
+            Err(ref e) => {
+                report.status = DeclStatus::Skipped;
+                report.set_error(e);
+            }
         }
+        self.decl_reports.borrow_mut().push(report);
+
+        result
     }
 
     /// Called when translation makes use of a language feature that will require a feature-gate.
@@ -1128,14 +1479,25 @@ impl<'c> Translation<'c> {
             None => return false,
         };
 
-        let iter = DFExpr::new(&self.ast_context, expr_id.into());
+        let mut iter = DFExpr::new(&self.ast_context, expr_id.into());
 
-        for i in iter {
+        while let Some(i) = iter.next() {
             let expr_id = match i {
                 SomeId::Expr(expr_id) => expr_id,
                 _ => unreachable!("Found static initializer type other than expr"),
             };
 
+            // Addresses in statics that `convert_static_address` computes at compile time
+            if self.tcfg.static_init == StaticInitMode::Lazy
+                && self.static_address(expr_id).is_some()
+            {
+                match self.ast_context[expr_id].kind {
+                    CExprKind::Binary(..) => iter.prune(2),
+                    _ => iter.prune(1),
+                }
+                continue;
+            }
+
             match self.ast_context[expr_id].kind {
                 // Technically we're being conservative here, but it's only the most
                 // contrived array indexing initializers that would be accepted
@@ -1290,19 +1652,20 @@ impl<'c> Translation<'c> {
         ctx: ExprContext,
         decl_id: CDeclId,
     ) -> Result<ConvertedDecl, TranslationError> {
+        let decl = self
+            .ast_context
+            .get_decl(&decl_id)
+            .ok_or_else(|| format_err!("Missing decl {:?}", decl_id))?;
+
         let mut s = {
-            let decl_cmt = self
+            let mut decl_cmt = self
                 .comment_context
                 .borrow_mut()
                 .remove_decl_comment(decl_id);
+            decl_cmt.extend(self.source_marker(&decl.loc));
             self.comment_store.borrow_mut().add_comment_lines(decl_cmt)
         };
 
-        let decl = self
-            .ast_context
-            .get_decl(&decl_id)
-            .ok_or_else(|| format_err!("Missing decl {:?}", decl_id))?;
-
         let _src_loc = &decl.loc;
 
         match decl.kind {
@@ -1324,9 +1687,6 @@ impl<'c> Translation<'c> {
 
             CDeclKind::Struct {
                 fields: Some(ref fields),
-                is_packed,
-                manual_alignment,
-                max_field_alignment,
                 platform_byte_size,
                 ..
             } => {
@@ -1335,6 +1695,9 @@ impl<'c> Translation<'c> {
                     .borrow()
                     .resolve_decl_name(decl_id)
                     .unwrap();
+                if self.import_neon_decl(decl_id, &name) {
+                    return Ok(ConvertedDecl::NoItem);
+                }
                 let mut has_bitfields = false;
 
                 // Check if the last field might be a flexible array member
@@ -1379,7 +1742,8 @@ impl<'c> Translation<'c> {
 
                             let typ = self.convert_type(typ.ctype)?;
 
-                            field_entries.push(mk().span(s).pub_().struct_field(name, typ));
+                            field_entries
+                                .push(self.field_builder(x, s).pub_().struct_field(name, typ));
                         }
                         _ => {
                             return Err(TranslationError::generic(
@@ -1390,41 +1754,41 @@ impl<'c> Translation<'c> {
                 }
 
                 if has_bitfields {
+                    if field_entries.iter().any(|field| !field.attrs.is_empty()) {
+                        diag!(
+                            Diagnostic::Comments,
+                            "Could not place the comments on the fields of {}",
+                            name
+                        );
+                    }
+                    if self.ast_context.has_inner_struct_decl(decl_id) {
+                        return Err(TranslationError::generic(
+                            "Packed and aligned structs with bitfields are not supported",
+                        ));
+                    }
                     return self.convert_bitfield_struct_decl(
+                        decl_id,
                         name,
-                        manual_alignment,
                         platform_byte_size,
                         s,
                         field_info,
                     );
                 }
 
-                let mut reprs = vec![simple_metaitem("C")];
-
-                if is_packed || max_field_alignment == Some(1) {
-                    reprs.push(simple_metaitem("packed"));
-                };
-                // https://github.com/rust-lang/rust/issues/33626
-                if let Some(alignment) = manual_alignment {
-                    let lit = mk().int_lit(alignment as u128, LitIntType::Unsuffixed);
-                    let inner = mk().meta_item(
-                        vec!["align"],
-                        MetaItemKind::List(vec![
-                            mk().nested_meta_item(NestedMetaItem::Literal(lit))
-                        ]),
-                    );
-                    reprs.push(mk().nested_meta_item(NestedMetaItem::MetaItem(inner)));
-                };
-
-                let repr_attr = mk().meta_item(vec!["repr"], MetaItemKind::List(reprs));
-
-                Ok(ConvertedDecl::Item(
-                    mk().span(s)
-                        .pub_()
-                        .call_attr("derive", vec!["Copy", "Clone"])
-                        .meta_item_attr(AttrStyle::Outer, repr_attr)
-                        .struct_item(name, field_entries),
-                ))
+                let record = self.convert_record_layout(
+                    decl_id,
+                    name,
+                    s,
+                    vec!["Copy", "Clone"],
+                    |builder, name| builder.struct_item(name, field_entries),
+                );
+                match (record, self.flexible_array_member(decl_id)) {
+                    (ConvertedDecl::Item(item), Some(field_id)) => {
+                        let accessors = self.convert_flexible_array_accessors(decl_id, field_id)?;
+                        Ok(ConvertedDecl::Items(vec![item, accessors]))
+                    }
+                    (record, _) => Ok(record),
+                }
             }
 
             CDeclKind::Union {
@@ -1447,7 +1811,7 @@ impl<'c> Translation<'c> {
                                 .borrow_mut()
                                 .declare_field_name(decl_id, x, name);
                             let typ = self.convert_type(typ.ctype)?;
-                            field_syns.push(mk().span(s).pub_().struct_field(name, typ))
+                            field_syns.push(self.field_builder(x, s).pub_().struct_field(name, typ))
                         }
                         _ => {
                             return Err(TranslationError::generic(
@@ -1457,24 +1821,21 @@ impl<'c> Translation<'c> {
                     }
                 }
 
-                Ok(if field_syns.is_empty() {
-                    // Empty unions are a GNU extension, but Rust doesn't allow empty unions.
-                    ConvertedDecl::Item(
-                        mk().span(s)
-                            .pub_()
-                            .call_attr("derive", vec!["Copy", "Clone"])
-                            .call_attr("repr", vec!["C"])
-                            .struct_item(name, vec![]),
-                    )
-                } else {
-                    ConvertedDecl::Item(
-                        mk().span(s)
-                            .pub_()
-                            .call_attr("derive", vec!["Copy", "Clone"])
-                            .call_attr("repr", vec!["C"])
-                            .union_item(name, field_syns),
-                    )
-                })
+                Ok(self.convert_record_layout(
+                    decl_id,
+                    name,
+                    s,
+                    vec!["Copy", "Clone"],
+                    |builder, name| {
+                        if field_syns.is_empty() {
+                            // Empty unions are a GNU extension, but Rust doesn't allow empty
+                            // unions.
+                            builder.struct_item(name, vec![])
+                        } else {
+                            builder.union_item(name, field_syns)
+                        }
+                    },
+                ))
             }
 
             CDeclKind::Field { .. } => Err(TranslationError::generic(
@@ -1490,6 +1851,9 @@ impl<'c> Translation<'c> {
                     .borrow()
                     .resolve_decl_name(decl_id)
                     .expect("Enums should already be renamed");
+                if self.is_closed_enum(decl_id) {
+                    return self.convert_closed_enum(decl_id, enum_name, s, integral_type.ctype);
+                }
                 let ty = self.convert_type(integral_type.ctype)?;
                 Ok(ConvertedDecl::Item(
                     mk().span(s).pub_().type_item(enum_name, ty),
@@ -1508,11 +1872,15 @@ impl<'c> Translation<'c> {
                     .borrow()
                     .resolve_decl_name(enum_id)
                     .expect("Enums should already be renamed");
-                let ty = mk().path_ty(mk().path(vec![enum_name]));
-                let val = match value {
-                    ConstIntExpr::I(value) => signed_int_expr(value),
-                    ConstIntExpr::U(value) => {
-                        mk().lit_expr(mk().int_lit(value as u128, LitIntType::Unsuffixed))
+                let ty = mk().path_ty(mk().path(vec![&enum_name]));
+                let val = if self.is_closed_enum(enum_id) {
+                    mk().path_expr(vec![&enum_name, &name])
+                } else {
+                    match value {
+                        ConstIntExpr::I(value) => signed_int_expr(value),
+                        ConstIntExpr::U(value) => {
+                            mk().lit_expr(mk().int_lit(value as u128, LitIntType::Unsuffixed))
+                        }
                     }
                 };
 
@@ -1542,7 +1910,8 @@ impl<'c> Translation<'c> {
                     .get(&decl_id)
                     .expect("Functions should already be renamed");
 
-                if self.import_simd_function(new_name)? {
+                if self.import_simd_function(new_name)? || self.import_neon_decl(decl_id, new_name)
+                {
                     return Ok(ConvertedDecl::NoItem);
                 }
 
@@ -1576,16 +1945,29 @@ impl<'c> Translation<'c> {
 
                 let is_main = self.ast_context.c_main == Some(decl_id);
 
-                let converted_function = self.convert_function(
-                    ctx, s, is_global, is_inline, is_main, is_var, is_extern, new_name, name,
-                    &args, ret, body, attrs,
-                );
+                let converted_function = match body {
+                    Some(body) if is_var && self.is_va_list_runtime() => self
+                        .convert_va_list_function(
+                            ctx, s, is_global && !is_inline, is_extern, new_name, name, &args,
+                            ret, body, attrs,
+                        ),
+                    _ => self.convert_function(
+                        ctx, s, is_global, is_inline, is_main, is_var, is_extern, new_name, name,
+                        &args, ret, body, attrs,
+                    ),
+                };
 
+                // A definition that can't be translated is declared instead, so that its
+                // callers still translate and it can be linked in from elsewhere
                 converted_function.or_else(|e| match self.tcfg.replace_unsupported_decls {
-                    ReplaceMode::Extern if body.is_none() => self.convert_function(
-                        ctx, s, is_global, false, is_main, is_var, is_extern, new_name, name,
-                        &args, ret, None, attrs,
-                    ),
+                    ReplaceMode::Extern if body.is_some() => {
+                        let stub = self.convert_function(
+                            ctx, s, is_global, false, is_main, is_var, is_extern, new_name, name,
+                            &args, ret, None, attrs,
+                        )?;
+                        self.stubbed_decls.borrow_mut().insert(decl_id, e);
+                        Ok(stub)
+                    }
                     _ => Err(e),
                 })
             }
@@ -1597,7 +1979,7 @@ impl<'c> Translation<'c> {
                     .resolve_decl_name(decl_id)
                     .unwrap();
 
-                if self.import_simd_typedef(new_name) {
+                if self.import_simd_typedef(new_name) || self.import_neon_decl(decl_id, new_name) {
                     return Ok(ConvertedDecl::NoItem);
                 }
 
@@ -1687,9 +2069,41 @@ impl<'c> Translation<'c> {
                     .get(&decl_id)
                     .expect("Variables should already be renamed");
 
-                // Collect problematic static initializers and offload them to sections for the linker
-                // to initialize for us
-                let (ty, init) = if self.static_initializer_is_uncompilable(initializer) {
+                let static_vis = if is_externally_visible {
+                    "pub"
+                } else if self.cur_file.borrow().is_some() {
+                    "pub(super)"
+                } else {
+                    ""
+                };
+
+                // Collect problematic static initializers and either initialize them on first use
+                // or offload them to sections for the linker to initialize for us
+                let mut accessor = None;
+                let (ty, init) = if let Some(accessor_name) = self.lazy_static_accessor(decl_id) {
+                    let ctx = ctx.not_static().static_init();
+                    let (ty, _, init) = self.convert_variable(ctx, initializer, typ)?;
+                    let mut init = init?;
+                    init.set_unsafe();
+                    let init = init.to_expr();
+
+                    let comment = format!("// Initialized on first use by {}", accessor_name);
+                    s = self
+                        .comment_store
+                        .borrow_mut()
+                        .add_comment_lines(vec![comment]);
+
+                    accessor = Some(self.convert_lazy_static(
+                        decl_id,
+                        &accessor_name,
+                        new_name,
+                        ty.clone(),
+                        init,
+                        static_vis,
+                    ));
+
+                    (ty, self.implicit_default_expr(typ.ctype, true)?.to_expr())
+                } else if self.static_initializer_is_uncompilable(initializer) {
                     // Note: We don't pass has_static_duration through here. Extracted initializers
                     // are run outside of the static initializer.
                     let (ty, _, init) =
@@ -1749,9 +2163,11 @@ impl<'c> Translation<'c> {
                     }
                 }
 
-                Ok(ConvertedDecl::Item(
-                    static_def.static_item(new_name, ty, init),
-                ))
+                let static_item = static_def.static_item(new_name, ty, init);
+                match accessor {
+                    Some(accessor) => Ok(ConvertedDecl::Items(vec![static_item, accessor])),
+                    None => Ok(ConvertedDecl::Item(static_item)),
+                }
             }
 
             CDeclKind::Variable { .. } => Err(TranslationError::generic(
@@ -1787,6 +2203,12 @@ impl<'c> Translation<'c> {
                     Ok(ConvertedDecl::NoItem)
                 }
             }
+
+            CDeclKind::MacroFunction {
+                ref params,
+                ref expansions,
+                ..
+            } => self.convert_macro_function(ctx, s, decl_id, params, expansions),
         }
     }
 
@@ -1898,7 +2320,14 @@ impl<'c> Translation<'c> {
             }
 
             // handle variadic arguments
+            let takes_va_list = is_variadic && body.is_some() && self.is_va_list_runtime();
             if is_variadic {
+                // Definitions called through a C trampoline take the arguments as a `VaList`
+                let ty = if takes_va_list {
+                    self.type_converter.borrow_mut().va_list_ty()
+                } else {
+                    mk().cvar_args_ty()
+                };
                 if let Some(va_decl_id) = self.get_promoted_va_decl() {
                     // `register_va_arg` succeeded
                     let var = self
@@ -1909,9 +2338,9 @@ impl<'c> Translation<'c> {
 
                     // FIXME: detect mutability requirements
                     let pat = mk().set_mutbl(Mutability::Mutable).ident_pat(var);
-                    args.push(mk().arg(mk().cvar_args_ty(), pat))
+                    args.push(mk().arg(ty, pat))
                 } else {
-                    args.push(mk().arg(mk().cvar_args_ty(), mk().wild_pat()))
+                    args.push(mk().arg(ty, mk().wild_pat()))
                 }
             }
 
@@ -1932,7 +2361,7 @@ impl<'c> Translation<'c> {
                 FunctionRetTy::Ty(ret)
             };
 
-            let decl = mk().fn_decl(args, ret, is_variadic);
+            let decl = mk().fn_decl(args, ret, is_variadic && !takes_va_list);
 
             if let Some(body) = body {
                 // Translating an actual function
@@ -1997,6 +2426,12 @@ impl<'c> Translation<'c> {
                     mk_ = mk_.single_attr("inline");
                 }
 
+                // `longjmp` unwinds through translated functions
+                if self.tcfg.translate_setjmp {
+                    self.use_feature("unwind_attributes");
+                    mk_ = mk_.single_attr("unwind(allowed)");
+                }
+
                 Ok(ConvertedDecl::Item(
                     mk_.span(span).unsafe_().fn_item(new_name, decl, block),
                 ))
@@ -2221,7 +2656,9 @@ impl<'c> Translation<'c> {
     ) -> Result<cfg::DeclStmtInfo, TranslationError> {
         if self.is_promoted_va_decl(decl_id) {
             // `va_list` decl was promoted to arg
-            self.use_feature("c_variadic");
+            if self.tcfg.va_list == VaListMode::Nightly {
+                self.use_feature("c_variadic");
+            }
             return Ok(cfg::DeclStmtInfo::empty());
         }
 
@@ -2245,6 +2682,38 @@ impl<'c> Translation<'c> {
                                 "Unable to rename function scoped static initializer",
                             )
                         })?;
+
+                    if let Some(accessor_name) = self.lazy_static_accessor(decl_id) {
+                        let ctx = ctx.not_static().static_init();
+                        let (ty, _, init) = self.convert_variable(ctx, initializer, typ)?;
+                        let default_init = self.implicit_default_expr(typ.ctype, true)?.to_expr();
+                        let comment = format!("// Initialized on first use by {}", accessor_name);
+                        let span = self
+                            .comment_store
+                            .borrow_mut()
+                            .add_comment_lines(vec![comment]);
+                        let static_item = mk()
+                            .span(span)
+                            .mutbl()
+                            .static_item(&ident2, ty.clone(), default_init);
+                        let mut init = init?;
+                        init.set_unsafe();
+                        let accessor = self.convert_lazy_static(
+                            decl_id,
+                            &accessor_name,
+                            &ident2,
+                            ty,
+                            init.to_expr(),
+                            "",
+                        );
+
+                        let mut item_store = self.item_store.borrow_mut();
+                        item_store.items.push(static_item);
+                        item_store.items.push(accessor);
+
+                        return Ok(cfg::DeclStmtInfo::empty());
+                    }
+
                     let (ty, _, init) = self.convert_variable(ctx.static_(), initializer, typ)?;
                     let default_init = self.implicit_default_expr(typ.ctype, true)?.to_expr();
                     let comment = String::from("// Initialized in run_static_initializers");
@@ -2295,11 +2764,7 @@ impl<'c> Translation<'c> {
                     // translate `va_list` declarations not promoted to an arg
                     // to `VaList` and do not emit an initializer.
                     let pat_mut = mk().set_mutbl("mut").ident_pat(rust_name.clone());
-                    let ty = {
-                        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
-                        let path = vec!["", std_or_core, "ffi", "VaList"];
-                        mk().path_ty(path)
-                    };
+                    let ty = self.type_converter.borrow_mut().va_list_ty();
                     let local_mut = mk().local::<_, _, P<Expr>>(pat_mut, Some(ty), None);
 
                     return Ok(cfg::DeclStmtInfo::new(
@@ -2396,17 +2861,18 @@ impl<'c> Translation<'c> {
                 if skip {
                     Ok(cfg::DeclStmtInfo::new(vec![], vec![], vec![]))
                 } else {
-                    let item = match self.convert_decl(ctx, decl_id)? {
-                        ConvertedDecl::Item(item) => item,
-                        ConvertedDecl::ForeignItem(item) => mk().abi("C").foreign_items(vec![item]),
+                    let items = match self.convert_decl(ctx, decl_id)? {
+                        ConvertedDecl::Item(item) => vec![item],
+                        ConvertedDecl::ForeignItem(item) => {
+                            vec![mk().abi("C").foreign_items(vec![item])]
+                        }
+                        ConvertedDecl::Items(items) => items,
                         ConvertedDecl::NoItem => return Ok(cfg::DeclStmtInfo::empty()),
                     };
+                    let stmts: Vec<Stmt> =
+                        items.into_iter().map(|item| mk().item_stmt(item)).collect();
 
-                    Ok(cfg::DeclStmtInfo::new(
-                        vec![mk().item_stmt(item.clone())],
-                        vec![],
-                        vec![mk().item_stmt(item)],
-                    ))
+                    Ok(cfg::DeclStmtInfo::new(stmts.clone(), vec![], stmts))
                 }
             }
         }
@@ -2828,12 +3294,33 @@ impl<'c> Translation<'c> {
 
         trace!("Converting expr {:?}: {:?}", expr_id, self.ast_context[expr_id]);
 
-        if self.tcfg.translate_const_macros {
+        if let Some(arg) = self.macro_args.borrow().get(&expr_id) {
+            let arg = WithStmts::new_val(arg.clone());
+            return self.convert_side_effects_expr(ctx, arg, "Macro argument is not supposed to be used");
+        }
+
+        if self.tcfg.translate_const_macros || self.tcfg.translate_fn_macros {
             if let Some(converted) = self.convert_macro_expansion(ctx, expr_id)? {
                 return Ok(converted);
             }
         }
 
+        if self.tcfg.long_double == LongDoubleMode::F64 {
+            self.check_long_double_precision(expr_id);
+        }
+
+        if !ctx.is_static && !ctx.is_const {
+            if let Some(converted) = self.convert_flexible_array_alloc_size(ctx, expr_id)? {
+                return Ok(converted);
+            }
+        }
+
+        if ctx.is_static && self.tcfg.static_init == StaticInitMode::Lazy {
+            if let Some(converted) = self.convert_static_address(ctx, expr_id)? {
+                return Ok(converted);
+            }
+        }
+
         match *expr_kind {
             CExprKind::DesignatedInitExpr(..) => {
                 Err(TranslationError::generic("Unexpected designated init expr"))
@@ -2841,11 +3328,21 @@ impl<'c> Translation<'c> {
             CExprKind::BadExpr => Err(TranslationError::generic(
                 "convert_expr: expression kind not supported",
             )),
+            CExprKind::ShuffleVector(ty, ref child_expr_ids)
+                if self.portable_vector_type(ty.ctype).is_some() =>
+            {
+                self.convert_portable_shuffle_vector(ctx, ty.ctype, child_expr_ids)
+            }
             CExprKind::ShuffleVector(_, ref child_expr_ids) => self
                 .convert_shuffle_vector(ctx, child_expr_ids)
                 .map_err(|e| {
                     TranslationError::new(src_loc, e.context(TranslationErrorKind::OldLLVMSimd))
                 }),
+            CExprKind::ConvertVector(ty, ref kids)
+                if kids.len() == 1 && self.portable_vector_type(ty.ctype).is_some() =>
+            {
+                self.convert_vector_conversion(ctx, ty.ctype, kids[0])
+            }
             CExprKind::ConvertVector(..) => {
                 Err(TranslationError::generic("convert vector not supported"))
             }
@@ -2908,6 +3405,21 @@ impl<'c> Translation<'c> {
 
                 let mut val = mk().path_expr(vec![rustname]);
 
+                // Statics that are initialized on first use are only accessed through their
+                // accessors, except by the initializers of statics, which only take their address
+                if !ctx.is_static && !ctx.is_static_init {
+                    if let Some(accessor) = self.lazy_static_accessor(decl_id) {
+                        if self.tcfg.reorganize_definitions {
+                            if let Some(cur_file) = self.cur_file.borrow().as_ref() {
+                                self.add_import(cur_file, decl_id, &accessor);
+                            }
+                        }
+                        let call =
+                            mk().call_expr(mk().path_expr(vec![accessor]), vec![] as Vec<P<Expr>>);
+                        val = mk().unary_expr(ast::UnOp::Deref, call);
+                    }
+                }
+
                 // If the variable is volatile and used as something that isn't an LValue, this
                 // constitutes a volatile read.
                 if lrvalue.is_rvalue() && qual_ty.qualifiers.is_volatile {
@@ -2962,11 +3474,9 @@ impl<'c> Translation<'c> {
                         kind.as_decl_or_typedef()
                             .expect("Did not find decl_id for offsetof struct")
                     };
-                    let name = self
-                        .type_converter
-                        .borrow()
-                        .resolve_decl_name(decl_id)
-                        .expect("Did not find name for offsetof struct");
+                    // The fields of packed and aligned records are at the same offsets in
+                    // their packed inner record
+                    let name = self.record_fields_name(decl_id);
                     let ty_ident = Nonterminal::NtIdent(mk().ident(name), false);
 
                     // Field name
@@ -3023,11 +3533,32 @@ impl<'c> Translation<'c> {
                     _ => {}
                 }
 
+                // Reading an `_Atomic` lvalue is an atomic load
+                if let CastKind::AtomicToNonAtomic = kind {
+                    return self.convert_atomic_read(ctx, expr);
+                }
+
                 let source_ty = self.ast_context[expr]
                     .kind
                     .get_qual_type()
                     .ok_or_else(|| format_err!("bad source type"))?;
 
+                // Decaying an array field of a packed record must not borrow the array, which
+                // might not be aligned
+                if let CastKind::ArrayToPointerDecay = kind {
+                    if !ctx.is_static && self.is_packed_place(expr) {
+                        let mutbl = if source_ty.qualifiers.is_const {
+                            Mutability::Immutable
+                        } else {
+                            Mutability::Mutable
+                        };
+                        let target_ty = self.convert_type(ty.ctype)?;
+                        return Ok(self
+                            .convert_packed_place_address(ctx, expr, mutbl)?
+                            .map(|ptr| mk().cast_expr(ptr, target_ty)));
+                    }
+                }
+
                 let val = if is_explicit {
                     let stmts = self.compute_variable_array_sizes(ctx, ty.ctype)?;
                     let mut val = self.convert_expr(ctx, expr)?;
@@ -3045,7 +3576,7 @@ impl<'c> Translation<'c> {
             }
 
             CExprKind::Unary(type_id, op, arg, lrvalue) => {
-                self.convert_unary_operator(ctx, op, type_id, arg, lrvalue)
+                self.convert_unary_operator(ctx, src_loc, op, type_id, arg, lrvalue)
             }
 
             CExprKind::Conditional(_, cond, lhs, rhs) => {
@@ -3122,7 +3653,16 @@ impl<'c> Translation<'c> {
             }
 
             CExprKind::Binary(type_id, op, lhs, rhs, opt_lhs_type_id, opt_res_type_id) => self
-                .convert_binary_expr(ctx, type_id, op, lhs, rhs, opt_lhs_type_id, opt_res_type_id)
+                .convert_binary_expr(
+                    ctx,
+                    src_loc,
+                    type_id,
+                    op,
+                    lhs,
+                    rhs,
+                    opt_lhs_type_id,
+                    opt_res_type_id,
+                )
                 .map_err(|e| e.add_loc(src_loc)),
 
             CExprKind::ArraySubscript(_, ref lhs, ref rhs, _) => {
@@ -3145,6 +3685,16 @@ impl<'c> Translation<'c> {
                 let lhs_node_type = lhs_node
                     .get_type()
                     .ok_or_else(|| format_err!("lhs node bad type"))?;
+
+                // Generic vectors are arrays, so they can be indexed directly
+                if self.portable_vector_type(lhs_node_type).is_some() {
+                    let lhs = self.convert_expr(ctx.used(), *lhs)?;
+                    let rhs = self.convert_expr(ctx.used(), *rhs)?;
+                    return lhs.and_then(|lhs| {
+                        Ok(rhs.map(|rhs| mk().index_expr(lhs, cast_int(rhs, "usize"))))
+                    });
+                }
+
                 if self
                     .ast_context
                     .resolve_type(lhs_node_type)
@@ -3265,6 +3815,27 @@ impl<'c> Translation<'c> {
                     _ => false,
                 };
                 let func = match self.ast_context[func].kind {
+                    // Calls to `creal` and friends are translated inline
+                    CExprKind::ImplicitCast(_, fexp, CastKind::FunctionToPointerDecay, _, _)
+                        if self.is_complex_libcall(fexp) => {
+                        return self.convert_builtin(ctx, fexp, args)
+                    }
+
+                    // Calls to `setjmp` are only translated as conditions of `if` statements,
+                    // see `cfg::CfgBuilder::convert_setjmp_if`
+                    CExprKind::ImplicitCast(_, fexp, CastKind::FunctionToPointerDecay, _, _)
+                        if self.tcfg.translate_setjmp && self.is_setjmp_libcall(fexp) => {
+                        return Err(self.unsupported_setjmp(
+                            expr_id,
+                            "setjmp must be the condition of an if statement",
+                        ))
+                    }
+
+                    CExprKind::ImplicitCast(_, fexp, CastKind::FunctionToPointerDecay, _, _)
+                        if self.tcfg.translate_setjmp && self.is_longjmp_libcall(fexp) => {
+                        return self.convert_longjmp(ctx, args)
+                    }
+
                     // Direct function call
                     CExprKind::ImplicitCast(_, fexp, CastKind::FunctionToPointerDecay, _, _) => {
                         self.convert_expr(ctx.used(), fexp)?
@@ -3364,21 +3935,23 @@ impl<'c> Translation<'c> {
                         .borrow()
                         .resolve_field_name(None, decl)
                         .unwrap();
+                    let record_id = self.ast_context.parents[&decl];
                     match kind {
                         MemberKind::Dot => {
                             let val = self.convert_expr(ctx, expr)?;
-                            Ok(val.map(|v| mk().field_expr(v, field_name)))
+                            Ok(val.map(|v| self.record_field_expr(record_id, v, field_name)))
                         }
                         MemberKind::Arrow => {
                             if let CExprKind::Unary(_, c_ast::UnOp::AddressOf, subexpr_id, _) =
                                 self.ast_context[expr].kind
                             {
                                 let val = self.convert_expr(ctx, subexpr_id)?;
-                                Ok(val.map(|v| mk().field_expr(v, field_name)))
+                                Ok(val.map(|v| self.record_field_expr(record_id, v, field_name)))
                             } else {
                                 let val = self.convert_expr(ctx, expr)?;
                                 Ok(val.map(|v| {
-                                    mk().field_expr(
+                                    self.record_field_expr(
+                                        record_id,
                                         mk().unary_expr(ast::UnOp::Deref, v),
                                         field_name,
                                     )
@@ -3393,6 +3966,8 @@ impl<'c> Translation<'c> {
 
             CExprKind::CompoundLiteral(_, val) => self.convert_expr(ctx, val),
 
+            CExprKind::ImaginaryLiteral(ty, val) => self.convert_imaginary_literal(ctx, ty, val),
+
             CExprKind::InitList(ty, ref ids, opt_union_field_id, _) => {
                 self.convert_init_list(ctx, ty, ids, opt_union_field_id)
             }
@@ -3409,6 +3984,28 @@ impl<'c> Translation<'c> {
 
             CExprKind::VAArg(ty, val_id) => self.convert_vaarg(ctx, ty, val_id),
 
+            CExprKind::Atomic { .. } => self.convert_atomic(ctx, expr_id),
+
+            // Clang has already resolved the selection, and the controlling expression is never
+            // evaluated
+            CExprKind::GenericSelection(_, _, _, selected) => self.convert_expr(ctx, selected),
+
+            CExprKind::TypesCompatible(ty, _, _, compatible) => self.convert_literal(
+                ctx.is_static,
+                ty,
+                &CLiteral::Integer(compatible as u64, IntBase::Dec),
+            ),
+
+            CExprKind::AddrLabel(ty, label_id) => {
+                let tag = self
+                    .ast_context
+                    .label_address_tag(label_id)
+                    .ok_or_else(|| format_err!("Label {:?} has no address", label_id))?;
+                let ty = self.convert_type(ty.ctype)?;
+                let val = mk().cast_expr(mk().lit_expr(mk().int_lit(tag as u128, "usize")), ty);
+                Ok(WithStmts::new_val(val))
+            }
+
             CExprKind::Choose(_, _cond, lhs, rhs, is_cond_true) => {
                 let chosen_expr = if is_cond_true {
                     self.convert_expr(ctx, lhs)?
@@ -3447,6 +4044,13 @@ impl<'c> Translation<'c> {
                 .first()
             {
                 trace!("  found macro expansion: {:?}", macro_id);
+                match self.ast_context[*macro_id].kind {
+                    CDeclKind::MacroFunction { .. } if self.tcfg.translate_fn_macros => {
+                        return self.convert_macro_function_use(ctx, *macro_id, expr_id);
+                    }
+                    CDeclKind::MacroObject { .. } if self.tcfg.translate_const_macros => {}
+                    _ => return Ok(None),
+                }
                 // Ensure that we've converted this macro and that it has a
                 // valid definition
                 if let ConvertedDecl::NoItem = self.convert_decl(ctx, *macro_id)? {
@@ -3661,8 +4265,11 @@ impl<'c> Translation<'c> {
         match kind {
             CastKind::BitCast | CastKind::NoOp => {
                 val.and_then(|x| {
+                    // Generic vectors are arrays, which can't be reinterpreted with `as` either
                     if self.ast_context.is_function_pointer(ty.ctype)
                         || self.ast_context.is_function_pointer(source_ty.ctype)
+                        || self.portable_vector_type(ty.ctype).is_some()
+                        || self.portable_vector_type(source_ty.ctype).is_some()
                     {
                         if ctx.is_static {
                             self.use_feature("const_transmute");
@@ -3710,50 +4317,9 @@ impl<'c> Translation<'c> {
 
                 let source_ty = self.convert_type(source_ty_ctype_id)?;
                 if let CTypeKind::LongDouble = target_ty_ctype {
-                    self.extern_crates.borrow_mut().insert("f128");
-
-                    let fn_path = mk().path_expr(vec!["f128", "f128", "new"]);
-                    Ok(val.map(|val| mk().call_expr(fn_path, vec![val])))
+                    Ok(val.map(|val| self.cast_to_long_double(val, target_ty)))
                 } else if let CTypeKind::LongDouble = self.ast_context[source_ty_ctype_id].kind {
-                    self.extern_crates.borrow_mut().insert("num_traits");
-                    self.item_store
-                        .borrow_mut()
-                        .uses
-                        .get_mut(vec!["num_traits".into()])
-                        .insert("ToPrimitive");
-
-                    let to_method_name = match target_ty_ctype {
-                        CTypeKind::Float => "to_f32",
-                        CTypeKind::Double => "to_f64",
-                        CTypeKind::Char => "to_i8",
-                        CTypeKind::UChar => "to_u8",
-                        CTypeKind::Short => "to_i16",
-                        CTypeKind::UShort => "to_u16",
-                        CTypeKind::Int => "to_i32",
-                        CTypeKind::UInt => "to_u32",
-                        CTypeKind::Long => "to_i64",
-                        CTypeKind::ULong => "to_u64",
-                        CTypeKind::LongLong => "to_i128",
-                        CTypeKind::ULongLong => "to_u128",
-                        _ => {
-                            return Err(format_err!(
-                                "Tried casting long double to unsupported type: {:?}",
-                                target_ty_ctype
-                            )
-                            .into())
-                        }
-                    };
-
-                    Ok(val.map(|val| {
-                        let to_call =
-                            mk().method_call_expr(val, to_method_name, Vec::<P<Expr>>::new());
-
-                        mk().method_call_expr(
-                            to_call,
-                            "unwrap",
-                            Vec::<P<Expr>>::new(),
-                        )
-                    }))
+                    val.result_map(|val| self.cast_from_long_double(val, target_ty_ctype, target_ty))
                 } else if let &CTypeKind::Enum(enum_decl_id) = target_ty_ctype {
                     // Casts targeting `enum` types...
                     let expr = expr.ok_or_else(|| format_err!("Casts to enums require a C ExprId"))?;
@@ -3774,7 +4340,11 @@ impl<'c> Translation<'c> {
                 }
             }
 
-            CastKind::LValueToRValue | CastKind::ToVoid | CastKind::ConstCast => Ok(val),
+            CastKind::LValueToRValue
+            | CastKind::ToVoid
+            | CastKind::ConstCast
+            | CastKind::AtomicToNonAtomic
+            | CastKind::NonAtomicToAtomic => Ok(val),
 
             CastKind::FunctionToPointerDecay | CastKind::BuiltinFnToFnPtr => {
                 Ok(val.map(|x| mk().call_expr(mk().ident_expr("Some"), vec![x])))
@@ -3860,11 +4430,7 @@ impl<'c> Translation<'c> {
                 let field_id = opt_field_id.expect("Missing field ID in union cast");
                 let union_id = self.ast_context.parents[&field_id];
 
-                let union_name = self
-                    .type_converter
-                    .borrow()
-                    .resolve_decl_name(union_id)
-                    .expect("required union name");
+                let union_name = self.record_fields_name(union_id);
                 let field_name = self
                     .type_converter
                     .borrow()
@@ -3872,7 +4438,9 @@ impl<'c> Translation<'c> {
                     .expect("field name required");
 
                 Ok(val.map(|x| {
-                    mk().struct_expr(mk().path(vec![union_name]), vec![mk().field(field_name, x)])
+                    let fields = vec![mk().field(field_name, x)];
+                    let union = mk().struct_expr(mk().path(vec![union_name]), fields);
+                    self.wrap_record_fields(union_id, union)
                 }))
             }
 
@@ -3895,17 +4463,24 @@ impl<'c> Translation<'c> {
             | CastKind::FloatingComplexToIntegralComplex
             | CastKind::FloatingComplexCast
             | CastKind::FloatingComplexToReal
+            | CastKind::FloatingComplexToBoolean
             | CastKind::IntegralComplexToReal
             | CastKind::IntegralRealToComplex
             | CastKind::IntegralComplexCast
             | CastKind::IntegralComplexToFloatingComplex
-            | CastKind::IntegralComplexToBoolean => Err(TranslationError::generic(
-                "TODO casts with complex numbers not supported",
-            )),
+            | CastKind::IntegralComplexToBoolean => {
+                self.convert_complex_cast(ctx, source_ty, ty, val, kind)
+            }
 
-            CastKind::VectorSplat => Err(TranslationError::generic(
-                "TODO vector splat casts not supported",
-            )),
+            CastKind::VectorSplat => match self.portable_vector_type(ty.ctype) {
+                Some((_, len)) => {
+                    let len = mk().lit_expr(mk().int_lit(len as u128, LitIntType::Unsuffixed));
+                    Ok(val.map(|x| mk().repeat_expr(x, len)))
+                }
+                None => Err(TranslationError::generic(
+                    "TODO vector splat casts not supported",
+                )),
+            },
         }
     }
 
@@ -3978,7 +4553,7 @@ impl<'c> Translation<'c> {
             Ok(WithStmts::new_val(mk().lit_expr(mk().int_lit(0, LitIntType::Unsuffixed))))
         } else if resolved_ty.is_floating_type() {
             match self.ast_context[ty_id].kind {
-                CTypeKind::LongDouble => Ok(WithStmts::new_val(mk().path_expr(vec!["f128", "f128", "ZERO"]))),
+                CTypeKind::LongDouble => Ok(WithStmts::new_val(self.long_double_zero())),
                 _ => Ok(WithStmts::new_val(mk().lit_expr(mk().float_unsuffixed_lit("0.")))),
             }
         } else if let &CTypeKind::Pointer(_) = resolved_ty {
@@ -4002,8 +4577,14 @@ impl<'c> Translation<'c> {
             let count = self.compute_size_of_expr(ty_id).unwrap();
             Ok(self.implicit_default_expr(inner, is_static)?
                .map(|val| vec_expr(val, count)))
+        } else if let Some((elt, len)) = self.portable_vector_type(ty_id) {
+            let len = mk().lit_expr(mk().int_lit(len as u128, LitIntType::Unsuffixed));
+            Ok(self.implicit_default_expr(elt.ctype, is_static)?
+                .map(|elt| mk().repeat_expr(elt, len)))
         } else if let &CTypeKind::Vector(CQualTypeId { ctype, .. }, len) = resolved_ty {
             self.implicit_vector_default(ctype, len, is_static)
+        } else if let CTypeKind::Complex(_) = resolved_ty {
+            self.complex_zero(resolved_ty_id, is_static)
         } else {
             Err(format_err!("Unsupported default initializer: {:?}", resolved_ty).into())
         }
@@ -4029,11 +4610,7 @@ impl<'c> Translation<'c> {
                 platform_byte_size,
                 ..
             } => {
-                let name = self
-                    .type_converter
-                    .borrow()
-                    .resolve_decl_name(decl_id)
-                    .unwrap();
+                let name = self.record_fields_name(decl_id);
 
                 let fields = match *fields {
                     Some(ref fields) => fields,
@@ -4076,17 +4653,15 @@ impl<'c> Translation<'c> {
                         })
                         .collect::<Result<_, TranslationError>>()?;
 
-                    fields.map(|fields| mk().struct_expr(vec![name], fields))
+                    fields.map(|fields| {
+                        self.wrap_record_fields(decl_id, mk().struct_expr(vec![name], fields))
+                    })
                 }
             }
 
             // Zero initialize the first field
             CDeclKind::Union { ref fields, .. } => {
-                let name = self
-                    .type_converter
-                    .borrow()
-                    .resolve_decl_name(decl_id)
-                    .unwrap();
+                let name = self.record_fields_name(decl_id);
 
                 let fields = match *fields {
                     Some(ref fields) => fields,
@@ -4119,7 +4694,9 @@ impl<'c> Translation<'c> {
                     )),
                 };
 
-                field.map(|field| mk().struct_expr(vec![name], vec![field]))
+                field.map(|field| {
+                    self.wrap_record_fields(decl_id, mk().struct_expr(vec![name], vec![field]))
+                })
             }
 
             // Transmute the number `0` into the enum type
@@ -4161,6 +4738,21 @@ impl<'c> Translation<'c> {
             } else {
                 mk().unary_expr(ast::UnOp::Not, val)
             }
+        } else if let &CTypeKind::Complex(elt) = ty {
+            // A complex value is false only when both of its parts are zero
+            let elt_zero = || match self.ast_context.resolve_type(elt).kind {
+                CTypeKind::LongDouble => self.long_double_zero(),
+                CTypeKind::Float | CTypeKind::Double => {
+                    mk().lit_expr(mk().float_unsuffixed_lit("0."))
+                }
+                _ => mk().lit_expr(mk().int_lit(0, LitIntType::Unsuffixed)),
+            };
+            let zero = self.mk_complex(elt_zero(), elt_zero());
+            if target {
+                mk().binary_expr(BinOpKind::Ne, val, zero)
+            } else {
+                mk().binary_expr(BinOpKind::Eq, val, zero)
+            }
         } else {
             let zero = if ty.is_floating_type() {
                 mk().lit_expr(mk().float_unsuffixed_lit("0."))
@@ -4337,13 +4929,20 @@ impl<'c> Translation<'c> {
             | ULongLong | Int128 | UInt128 | Half | Float | Double => {
                 self.add_lib_import(decl_file_path, "libc", false);
             }
-            LongDouble => {
-                self.add_lib_import(decl_file_path, "f128", false);
+            LongDouble => match self.tcfg.long_double {
+                LongDoubleMode::F128 => self.add_lib_import(decl_file_path, "f128", false),
+                LongDoubleMode::F64 => {}
+                LongDoubleMode::F80 => self.add_lib_import(decl_file_path, "c2rust_f80", false),
+            },
+            Complex(ctype) => {
+                self.add_lib_import(decl_file_path, "num_complex", false);
+                self.import_type(ctype, decl_file_path)
             }
             // Bool uses the bool type, so no dependency on libc
             Bool => {}
             Paren(ctype)
             | Decayed(ctype)
+            | Atomic(ctype)
             | IncompleteArray(ctype)
             | ConstantArray(ctype, _)
             | Elaborated(ctype)
@@ -4378,6 +4977,11 @@ impl<'c> Translation<'c> {
                     self.import_type(param_id.ctype, decl_file_path);
                 }
             }
+            Vector(CQualTypeId { ctype: elt, .. }, _)
+                if self.portable_vector_type(ctype).is_some() =>
+            {
+                self.import_type(elt, decl_file_path)
+            }
             Vector(CQualTypeId { ctype, .. }, len) => {
                 // Since vector imports are global, we can find the correct type name in the parent scope
                 let type_name = match (&self.ast_context[ctype].kind, len) {
@@ -4454,6 +5058,12 @@ impl<'c> Translation<'c> {
                 }
             }
 
+            CDeclKind::MacroFunction { .. } => {
+                for ty in self.macro_function_types(decl_id) {
+                    self.import_type(ty, decl_file_path)
+                }
+            }
+
             CDeclKind::Function { .. } => {
                 // TODO: We may need to explicitly skip SIMD functions here when getting types for
                 // a fn definition in a header since SIMD headers define functions but we're using imports
@@ -52,6 +52,7 @@ impl<'c> Translation<'c> {
             .kind
             .get_qual_type()
             .ok_or_else(|| format_err!("bad reference type"))?;
+        let is_packed_place = self.is_packed_place(reference);
         let reference = self.convert_expr(ctx.used(), reference)?;
         reference.and_then(|reference| {
             /// Check if something is a valid Rust lvalue. Inspired by `librustc::ty::expr_is_lval`.
@@ -92,6 +93,21 @@ impl<'c> Translation<'c> {
             } else {
                 // This is the case where we explicitly need to factor out possible side-effects.
 
+                // Fields of packed records might not be aligned, so rather than borrowing the
+                // field itself, we borrow the outermost record and access the field through it.
+                let mut reference = reference;
+                let mut fields = vec![];
+                if is_packed_place {
+                    loop {
+                        let (base, field) = match reference.node {
+                            ExprKind::Field(ref base, field) => (base.clone(), field),
+                            _ => break,
+                        };
+                        fields.push(field);
+                        reference = base;
+                    }
+                }
+
                 let ptr_name = self.renamer.borrow_mut().fresh();
 
                 // let ref mut p = lhs;
@@ -102,6 +118,10 @@ impl<'c> Translation<'c> {
                 )));
 
                 let write = mk().unary_expr(ast::UnOp::Deref, mk().ident_expr(&ptr_name));
+                let write = fields
+                    .into_iter()
+                    .rev()
+                    .fold(write, |write, field| mk().field_expr(write, field));
 
                 Ok(WithStmts::new(
                     vec![compute_ref],
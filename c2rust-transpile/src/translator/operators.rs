@@ -10,6 +10,16 @@ fn wrapping_neg_expr(arg: P<Expr>) -> P<Expr> {
     mk().method_call_expr(arg, "wrapping_neg", vec![] as Vec<P<Expr>>)
 }
 
+/// Unwrap the result of a `checked_*` method, panicking with the location of the C expression if
+/// it overflowed
+fn expect_no_overflow(checked: P<Expr>, src_loc: &Option<SrcLoc>) -> P<Expr> {
+    let msg = match src_loc {
+        Some(loc) => format!("{}: arithmetic overflow", loc),
+        None => "arithmetic overflow".to_string(),
+    };
+    mk().method_call_expr(checked, "expect", vec![mk().lit_expr(mk().str_lit(msg))])
+}
+
 impl From<c_ast::BinOp> for BinOpKind {
     fn from(op: c_ast::BinOp) -> Self {
         match op {
@@ -42,6 +52,7 @@ impl<'c> Translation<'c> {
     pub fn convert_binary_expr(
         &self,
         mut ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         type_id: CQualTypeId,
         op: c_ast::BinOp,
         lhs: CExprId,
@@ -94,6 +105,7 @@ impl<'c> Translation<'c> {
             | c_ast::BinOp::AssignBitAnd
             | c_ast::BinOp::Assign => self.convert_assignment_operator(
                 ctx,
+                src_loc,
                 op,
                 type_id,
                 lhs,
@@ -142,6 +154,7 @@ impl<'c> Translation<'c> {
                                    let expr_ids = Some((lhs, rhs));
                                    self.convert_binary_operator(
                                        ctx,
+                                       src_loc,
                                        op,
                                        ty,
                                        type_id.ctype,
@@ -161,6 +174,7 @@ impl<'c> Translation<'c> {
     fn covert_assignment_operator_aux(
         &self,
         ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         bin_op_kind: BinOpKind,
         bin_op: c_ast::BinOp,
         read: P<Expr>,
@@ -184,6 +198,7 @@ impl<'c> Translation<'c> {
             let ty = self.convert_type(compute_res_ty.ctype)?;
             let val = self.convert_binary_operator(
                 ctx,
+                src_loc,
                 bin_op,
                 ty,
                 compute_res_ty.ctype,
@@ -210,6 +225,7 @@ impl<'c> Translation<'c> {
     fn convert_assignment_operator(
         &self,
         ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         op: c_ast::BinOp,
         qtype: CQualTypeId,
         lhs: CExprId,
@@ -226,6 +242,7 @@ impl<'c> Translation<'c> {
         let rhs_translation = self.convert_expr(ctx.used(), rhs)?;
         self.convert_assignment_operator_with_rhs(
             ctx,
+            src_loc,
             op,
             qtype,
             lhs,
@@ -240,6 +257,7 @@ impl<'c> Translation<'c> {
     fn convert_assignment_operator_with_rhs(
         &self,
         ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         op: c_ast::BinOp,
         qtype: CQualTypeId,
         lhs: CExprId,
@@ -257,6 +275,10 @@ impl<'c> Translation<'c> {
             .get_qual_type()
             .ok_or_else(|| format_err!("bad initial lhs type"))?;
 
+        if self.ast_context.is_atomic_type(initial_lhs_type_id.ctype) {
+            return self.convert_atomic_assignment(ctx, op, lhs, rhs_translation);
+        }
+
         let bitfield_id = match initial_lhs {
             CExprKind::Member(_, _, decl_id, _, _) => {
                 let kind = &self.ast_context[*decl_id].kind;
@@ -288,30 +310,46 @@ impl<'c> Translation<'c> {
         let is_volatile_compound_assign = op.underlying_assignment().is_some() && is_volatile;
 
         let qtype_kind = &self.ast_context.resolve_type(qtype.ctype).kind;
-        let compute_type_kind = &self
-            .ast_context
-            .resolve_type(compute_lhs_type_id.ctype)
-            .kind;
 
         let pointer_lhs = match qtype_kind {
             &CTypeKind::Pointer(pointee) => Some(pointee),
             _ => None,
         };
 
-        let is_unsigned_arith = match op {
+        // Arithmetic that uses methods rather than operators doesn't map onto `+=` and friends
+        let is_method_arith = match op {
             c_ast::BinOp::AssignAdd
             | c_ast::BinOp::AssignSubtract
             | c_ast::BinOp::AssignMultiply
             | c_ast::BinOp::AssignDivide
-            | c_ast::BinOp::AssignModulus => compute_type_kind.is_unsigned_integral_type(),
+            | c_ast::BinOp::AssignModulus => {
+                self.overflow_mode(ctx, compute_lhs_type_id.ctype) != OverflowMode::Plain
+            }
+            _ => false,
+        };
+
+        // Complex multiplication and division don't map onto `*=` and `/=`, see
+        // `convert_complex_binary_operator`
+        let is_complex_arith = match op {
+            c_ast::BinOp::AssignMultiply | c_ast::BinOp::AssignDivide => {
+                self.complex_element_type(compute_lhs_type_id.ctype).is_some()
+            }
             _ => false,
         };
 
+        // Neither do compound assignments to generic vectors, which are arrays in Rust
+        let is_vector_arith = op.underlying_assignment().is_some()
+            && self
+                .portable_vector_type(compute_lhs_type_id.ctype)
+                .is_some();
+
         let lhs_translation = if initial_lhs_type_id.ctype != compute_lhs_type_id.ctype
             || ctx.is_used()
             || pointer_lhs.is_some()
             || is_volatile_compound_assign
-            || is_unsigned_arith
+            || is_method_arith
+            || is_complex_arith
+            || is_vector_arith
         {
             self.name_reference_write_read(ctx, lhs)?
         } else {
@@ -335,7 +373,7 @@ impl<'c> Translation<'c> {
                     }
 
                     // Anything volatile needs to be desugared into explicit reads and writes
-                    op if is_volatile || is_unsigned_arith => {
+                    op if is_volatile || is_method_arith || is_complex_arith || is_vector_arith => {
                         let mut is_unsafe = false;
                         let op = op
                             .underlying_assignment()
@@ -344,6 +382,7 @@ impl<'c> Translation<'c> {
                         let val = if compute_lhs_type_id.ctype == initial_lhs_type_id.ctype {
                             self.convert_binary_operator(
                                 ctx,
+                                src_loc,
                                 op,
                                 ty,
                                 qtype.ctype,
@@ -360,6 +399,7 @@ impl<'c> Translation<'c> {
                             let ty = self.convert_type(result_type_id.ctype)?;
                             let val = self.convert_binary_operator(
                                 ctx,
+                                src_loc,
                                 op,
                                 ty,
                                 result_type_id.ctype,
@@ -428,6 +468,7 @@ impl<'c> Translation<'c> {
 
                     c_ast::BinOp::AssignAdd => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::Add,
                         c_ast::BinOp::Add,
                         read.clone(),
@@ -440,6 +481,7 @@ impl<'c> Translation<'c> {
                     )?,
                     c_ast::BinOp::AssignSubtract => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::Sub,
                         c_ast::BinOp::Subtract,
                         read.clone(),
@@ -452,6 +494,7 @@ impl<'c> Translation<'c> {
                     )?,
                     c_ast::BinOp::AssignMultiply => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::Mul,
                         c_ast::BinOp::Multiply,
                         read.clone(),
@@ -464,6 +507,7 @@ impl<'c> Translation<'c> {
                     )?,
                     c_ast::BinOp::AssignDivide => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::Div,
                         c_ast::BinOp::Divide,
                         read.clone(),
@@ -476,6 +520,7 @@ impl<'c> Translation<'c> {
                     )?,
                     c_ast::BinOp::AssignModulus => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::Rem,
                         c_ast::BinOp::Modulus,
                         read.clone(),
@@ -488,6 +533,7 @@ impl<'c> Translation<'c> {
                     )?,
                     c_ast::BinOp::AssignBitXor => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::BitXor,
                         c_ast::BinOp::BitXor,
                         read.clone(),
@@ -500,6 +546,7 @@ impl<'c> Translation<'c> {
                     )?,
                     c_ast::BinOp::AssignShiftLeft => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::Shl,
                         c_ast::BinOp::ShiftLeft,
                         read.clone(),
@@ -512,6 +559,7 @@ impl<'c> Translation<'c> {
                     )?,
                     c_ast::BinOp::AssignShiftRight => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::Shr,
                         c_ast::BinOp::ShiftRight,
                         read.clone(),
@@ -524,6 +572,7 @@ impl<'c> Translation<'c> {
                     )?,
                     c_ast::BinOp::AssignBitOr => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::BitOr,
                         c_ast::BinOp::BitOr,
                         read.clone(),
@@ -536,6 +585,7 @@ impl<'c> Translation<'c> {
                     )?,
                     c_ast::BinOp::AssignBitAnd => self.covert_assignment_operator_aux(
                         ctx,
+                        src_loc,
                         BinOpKind::BitAnd,
                         c_ast::BinOp::BitAnd,
                         read.clone(),
@@ -565,6 +615,7 @@ impl<'c> Translation<'c> {
     fn convert_binary_operator(
         &self,
         ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         op: c_ast::BinOp,
         ty: P<Ty>,
         ctype: CTypeId,
@@ -574,45 +625,26 @@ impl<'c> Translation<'c> {
         rhs: P<Expr>,
         lhs_rhs_ids: Option<(CExprId, CExprId)>,
     ) -> Result<P<Expr>, TranslationError> {
-        let is_unsigned_integral_type = self
-            .ast_context
-            .index(ctype)
-            .kind
-            .is_unsigned_integral_type();
+        if self.portable_vector_type(ctype).is_some() {
+            return self.convert_vector_binary_operator(
+                ctx, src_loc, op, ctype, lhs_type, rhs_type, lhs, rhs,
+            );
+        }
 
-        match op {
-            c_ast::BinOp::Add => self.convert_addition(ctx, lhs_type, rhs_type, lhs, rhs),
-            c_ast::BinOp::Subtract => self.convert_subtraction(ctx, ty, lhs_type, rhs_type, lhs, rhs),
+        let is_complex = self.complex_element_type(ctype).is_some();
 
-            c_ast::BinOp::Multiply if is_unsigned_integral_type => {
-                if ctx.is_const {
-                    return Err(TranslationError::generic(
-                        "Cannot use wrapping multiply in a const expression",
-                    ));
-                }
-                Ok(mk().method_call_expr(lhs, mk().path_segment("wrapping_mul"), vec![rhs]))
+        match op {
+            c_ast::BinOp::Add => self.convert_addition(ctx, src_loc, lhs_type, rhs_type, lhs, rhs),
+            c_ast::BinOp::Subtract => {
+                self.convert_subtraction(ctx, src_loc, ty, lhs_type, rhs_type, lhs, rhs)
             }
-            c_ast::BinOp::Multiply => Ok(mk().binary_expr(BinOpKind::Mul, lhs, rhs)),
 
-            c_ast::BinOp::Divide if is_unsigned_integral_type => {
-                if ctx.is_const {
-                    return Err(TranslationError::generic(
-                        "Cannot use wrapping division in a const expression",
-                    ));
-                }
-                Ok(mk().method_call_expr(lhs, mk().path_segment("wrapping_div"), vec![rhs]))
+            c_ast::BinOp::Multiply | c_ast::BinOp::Divide if is_complex => {
+                self.convert_complex_binary_operator(ctx, op, ctype, lhs, rhs)
             }
-            c_ast::BinOp::Divide => Ok(mk().binary_expr(BinOpKind::Div, lhs, rhs)),
-
-            c_ast::BinOp::Modulus if is_unsigned_integral_type => {
-                if ctx.is_const {
-                    return Err(TranslationError::generic(
-                        "Cannot use wrapping remainder in a const expression",
-                    ));
-                }
-                Ok(mk().method_call_expr(lhs, mk().path_segment("wrapping_rem"), vec![rhs]))
+            c_ast::BinOp::Multiply | c_ast::BinOp::Divide | c_ast::BinOp::Modulus => {
+                self.convert_arithmetic(ctx, src_loc, op, ctype, lhs, rhs)
             }
-            c_ast::BinOp::Modulus => Ok(mk().binary_expr(BinOpKind::Rem, lhs, rhs)),
 
             c_ast::BinOp::BitXor => Ok(mk().binary_expr(BinOpKind::BitXor, lhs, rhs)),
 
@@ -675,9 +707,61 @@ impl<'c> Translation<'c> {
         }
     }
 
+    /// Translate the arithmetic operator `op` on values of type `ctype`. Unsigned integers wrap
+    /// around like in C, signed integers overflow as selected by `OverflowMode`, and other types
+    /// use the plain Rust operator.
+    fn convert_arithmetic(
+        &self,
+        ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
+        op: c_ast::BinOp,
+        ctype: CTypeId,
+        lhs: P<Expr>,
+        rhs: P<Expr>,
+    ) -> Result<P<Expr>, TranslationError> {
+        let (method, const_err) = match op {
+            c_ast::BinOp::Add => ("add", "Cannot use wrapping add in a const expression"),
+            c_ast::BinOp::Subtract => ("sub", "Cannot use wrapping subtract in a const expression"),
+            c_ast::BinOp::Multiply => ("mul", "Cannot use wrapping multiply in a const expression"),
+            c_ast::BinOp::Divide => ("div", "Cannot use wrapping division in a const expression"),
+            c_ast::BinOp::Modulus => ("rem", "Cannot use wrapping remainder in a const expression"),
+            _ => panic!("C BinOp {:?} is not an arithmetic operator", op),
+        };
+
+        match self.overflow_mode(ctx, ctype) {
+            OverflowMode::Plain => Ok(mk().binary_expr(BinOpKind::from(op), lhs, rhs)),
+            OverflowMode::Wrapping => {
+                if ctx.is_const {
+                    return Err(TranslationError::generic(const_err));
+                }
+                let method = format!("wrapping_{}", method);
+                Ok(mk().method_call_expr(lhs, method, vec![rhs]))
+            }
+            OverflowMode::Report => {
+                let checked = mk().method_call_expr(lhs, format!("checked_{}", method), vec![rhs]);
+                Ok(expect_no_overflow(checked, src_loc))
+            }
+        }
+    }
+
+    /// How arithmetic on values of type `ctype` overflows
+    fn overflow_mode(&self, ctx: ExprContext, ctype: CTypeId) -> OverflowMode {
+        let kind = &self.ast_context.resolve_type(ctype).kind;
+        if kind.is_unsigned_integral_type() {
+            OverflowMode::Wrapping
+        } else if kind.is_signed_integral_type() && !ctx.is_const && !ctx.is_static {
+            // Constants and static initializers don't need methods, since overflow while
+            // evaluating them is a compile-time error in Rust
+            self.tcfg.overflow
+        } else {
+            OverflowMode::Plain
+        }
+    }
+
     fn convert_addition(
         &self,
         ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         lhs_type_id: CQualTypeId,
         rhs_type_id: CQualTypeId,
         lhs: P<Expr>,
@@ -710,21 +794,15 @@ impl<'c> Translation<'c> {
                 }
                 None => Ok(pointer_offset(rhs, lhs)),
             }
-        } else if lhs_type.is_unsigned_integral_type() {
-            if ctx.is_const {
-                return Err(TranslationError::generic(
-                    "Cannot use wrapping add in a const expression",
-                ));
-            }
-            Ok(mk().method_call_expr(lhs, mk().path_segment("wrapping_add"), vec![rhs]))
         } else {
-            Ok(mk().binary_expr(BinOpKind::Add, lhs, rhs))
+            self.convert_arithmetic(ctx, src_loc, c_ast::BinOp::Add, lhs_type_id.ctype, lhs, rhs)
         }
     }
 
     fn convert_subtraction(
         &self,
         ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         ty: P<Ty>,
         lhs_type_id: CQualTypeId,
         rhs_type_id: CQualTypeId,
@@ -764,25 +842,24 @@ impl<'c> Translation<'c> {
                     ),
                 )),
             }
-        } else if lhs_type.is_unsigned_integral_type() {
-            if ctx.is_const {
-                return Err(TranslationError::generic(
-                    "Cannot use wrapping subtract in a const expression",
-                ));
-            }
-            Ok(mk().method_call_expr(lhs, mk().path_segment("wrapping_sub"), vec![rhs]))
         } else {
-            Ok(mk().binary_expr(BinOpKind::Sub, lhs, rhs))
+            let op = c_ast::BinOp::Subtract;
+            self.convert_arithmetic(ctx, src_loc, op, lhs_type_id.ctype, lhs, rhs)
         }
     }
 
     fn convert_pre_increment(
         &self,
         ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         ty: CQualTypeId,
         up: bool,
         arg: CExprId,
     ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        if self.is_atomic_lvalue(arg) {
+            return self.convert_atomic_increment(ctx, up, true, arg);
+        }
+
         let op = if up {
             c_ast::BinOp::AssignAdd
         } else {
@@ -792,14 +869,7 @@ impl<'c> Translation<'c> {
             // TODO: If rust gets f16 support:
             // CTypeKind::Half |
             CTypeKind::Float | CTypeKind::Double => mk().lit_expr(mk().float_unsuffixed_lit("1.")),
-            CTypeKind::LongDouble => {
-                self.extern_crates.borrow_mut().insert("f128");
-
-                let fn_path = mk().path_expr(vec!["f128", "f128", "new"]);
-                let args = vec![mk().ident_expr("1.")];
-
-                mk().call_expr(fn_path, args)
-            }
+            CTypeKind::LongDouble => self.long_double_one(),
             _ => mk().lit_expr(mk().int_lit(1, LitIntType::Unsuffixed)),
         };
         let arg_type = self.ast_context[arg]
@@ -808,6 +878,7 @@ impl<'c> Translation<'c> {
             .ok_or_else(|| format_err!("bad arg type"))?;
         self.convert_assignment_operator_with_rhs(
             ctx.used(),
+            src_loc,
             op,
             arg_type,
             arg,
@@ -821,13 +892,18 @@ impl<'c> Translation<'c> {
     fn convert_post_increment(
         &self,
         ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         ty: CQualTypeId,
         up: bool,
         arg: CExprId,
     ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        if self.is_atomic_lvalue(arg) {
+            return self.convert_atomic_increment(ctx, up, false, arg);
+        }
+
         // If we aren't going to be using the result, may as well do a simple pre-increment
         if ctx.is_unused() {
-            return self.convert_pre_increment(ctx, ty, up, arg);
+            return self.convert_pre_increment(ctx, src_loc, ty, up, arg);
         }
 
         let ty = self
@@ -850,14 +926,7 @@ impl<'c> Translation<'c> {
                     // TODO: If rust gets f16 support:
                     // CTypeKind::Half |
                     CTypeKind::Float | CTypeKind::Double => mk().lit_expr(mk().float_unsuffixed_lit("1.")),
-                    CTypeKind::LongDouble => {
-                        self.extern_crates.borrow_mut().insert("f128");
-
-                        let fn_path = mk().path_expr(vec!["f128", "f128", "new"]);
-                        let args = vec![mk().ident_expr("1.")];
-
-                        mk().call_expr(fn_path, args)
-                    }
+                    CTypeKind::LongDouble => self.long_double_one(),
                     _ => mk().lit_expr(mk().int_lit(1, LitIntType::Unsuffixed)),
                 };
 
@@ -875,23 +944,12 @@ impl<'c> Translation<'c> {
                         };
                         mk().method_call_expr(read.clone(), "offset", vec![n])
                     } else {
-                        if self
-                            .ast_context
-                            .resolve_type(ty.ctype)
-                            .kind
-                            .is_unsigned_integral_type()
-                        {
-                            if ctx.is_const {
-                                return Err(TranslationError::generic(
-                                    "Cannot use wrapping add or sub in a const expression",
-                                ));
-                            }
-                            let m = if up { "wrapping_add" } else { "wrapping_sub" };
-                            mk().method_call_expr(read.clone(), m, vec![one])
+                        let op = if up {
+                            c_ast::BinOp::Add
                         } else {
-                            let k = if up { BinOpKind::Add } else { BinOpKind::Sub };
-                            mk().binary_expr(k, read.clone(), one)
-                        }
+                            c_ast::BinOp::Subtract
+                        };
+                        self.convert_arithmetic(ctx, src_loc, op, ty.ctype, read.clone(), one)?
                     };
 
                 // *p = *p + rhs
@@ -911,6 +969,7 @@ impl<'c> Translation<'c> {
     pub fn convert_unary_operator(
         &self,
         mut ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
         name: c_ast::UnOp,
         cqual_type: CQualTypeId,
         arg: CExprId,
@@ -918,7 +977,6 @@ impl<'c> Translation<'c> {
     ) -> Result<WithStmts<P<Expr>>, TranslationError> {
         let CQualTypeId { ctype, .. } = cqual_type;
         let ty = self.convert_type(ctype)?;
-        let resolved_ctype = self.ast_context.resolve_type(ctype);
 
         match name {
             c_ast::UnOp::AddressOf => {
@@ -937,6 +995,20 @@ impl<'c> Translation<'c> {
                     _ => (),
                 };
 
+                // Fields of packed records might not be aligned, so we must not borrow them
+                if !ctx.is_static && self.is_packed_place(arg) {
+                    let is_const = self
+                        .ast_context
+                        .get_pointee_qual_type(ctype)
+                        .map_or(false, |pointee_ty| pointee_ty.qualifiers.is_const);
+                    let mutbl = if is_const {
+                        Mutability::Immutable
+                    } else {
+                        Mutability::Mutable
+                    };
+                    return self.convert_packed_place_address(ctx, arg, mutbl);
+                }
+
                 // In this translation, there are only pointers to functions and
                 // & becomes a no-op when applied to a function.
 
@@ -987,10 +1059,18 @@ impl<'c> Translation<'c> {
                     })
                 }
             }
-            c_ast::UnOp::PreIncrement => self.convert_pre_increment(ctx, cqual_type, true, arg),
-            c_ast::UnOp::PreDecrement => self.convert_pre_increment(ctx, cqual_type, false, arg),
-            c_ast::UnOp::PostIncrement => self.convert_post_increment(ctx, cqual_type, true, arg),
-            c_ast::UnOp::PostDecrement => self.convert_post_increment(ctx, cqual_type, false, arg),
+            c_ast::UnOp::PreIncrement => {
+                self.convert_pre_increment(ctx, src_loc, cqual_type, true, arg)
+            }
+            c_ast::UnOp::PreDecrement => {
+                self.convert_pre_increment(ctx, src_loc, cqual_type, false, arg)
+            }
+            c_ast::UnOp::PostIncrement => {
+                self.convert_post_increment(ctx, src_loc, cqual_type, true, arg)
+            }
+            c_ast::UnOp::PostDecrement => {
+                self.convert_post_increment(ctx, src_loc, cqual_type, false, arg)
+            }
             c_ast::UnOp::Deref => {
                 match self.ast_context[arg].kind {
                     CExprKind::Unary(_, c_ast::UnOp::AddressOf, arg_, _) => {
@@ -1021,20 +1101,36 @@ impl<'c> Translation<'c> {
             }
             c_ast::UnOp::Plus => self.convert_expr(ctx.used(), arg), // promotion is explicit in the clang AST
 
+            c_ast::UnOp::Negate | c_ast::UnOp::Complement
+                if self.portable_vector_type(ctype).is_some() =>
+            {
+                self.convert_vector_unary_operator(ctx, name, ctype, arg)
+            }
+
             c_ast::UnOp::Negate => {
                 let val = self.convert_expr(ctx.used(), arg)?;
 
-                if resolved_ctype.kind.is_unsigned_integral_type() {
-                    if ctx.is_const {
-                        return Err(TranslationError::generic(
-                            "Cannot use wrapping negate in a const expression",
-                        ));
+                match self.overflow_mode(ctx, ctype) {
+                    OverflowMode::Plain => Ok(val.map(neg_expr)),
+                    OverflowMode::Wrapping => {
+                        if ctx.is_const {
+                            return Err(TranslationError::generic(
+                                "Cannot use wrapping negate in a const expression",
+                            ));
+                        }
+                        Ok(val.map(wrapping_neg_expr))
                     }
-                    Ok(val.map(wrapping_neg_expr))
-                } else {
-                    Ok(val.map(neg_expr))
+                    OverflowMode::Report => Ok(val.map(|val| {
+                        let checked =
+                            mk().method_call_expr(val, "checked_neg", vec![] as Vec<P<Expr>>);
+                        expect_no_overflow(checked, src_loc)
+                    })),
                 }
             }
+            // GNU C uses `~` for the complex conjugate
+            c_ast::UnOp::Complement if self.complex_element_type(ctype).is_some() => {
+                self.convert_complex_conj(ctx, arg)
+            }
             c_ast::UnOp::Complement => Ok(self
                 .convert_expr(ctx.used(), arg)?
                 .map(|a| mk().unary_expr(ast::UnOp::Not, a))),
@@ -1047,9 +1143,94 @@ impl<'c> Translation<'c> {
                 let arg = self.convert_expr(ctx, arg)?;
                 Ok(arg)
             }
-            c_ast::UnOp::Real | c_ast::UnOp::Imag | c_ast::UnOp::Coawait => {
-                panic!("Unsupported extension operator")
-            }
+            c_ast::UnOp::Real => self.convert_complex_part(ctx, true, arg),
+            c_ast::UnOp::Imag => self.convert_complex_part(ctx, false, arg),
+            c_ast::UnOp::Coawait => panic!("Unsupported extension operator"),
         }
     }
+
+    /// Generic vector operators apply the scalar operator to each pair of elements. Comparisons
+    /// produce -1 for the elements where they hold and 0 elsewhere.
+    fn convert_vector_binary_operator(
+        &self,
+        ctx: ExprContext,
+        src_loc: &Option<SrcLoc>,
+        op: c_ast::BinOp,
+        ctype: CTypeId,
+        lhs_type: CQualTypeId,
+        rhs_type: CQualTypeId,
+        lhs: P<Expr>,
+        rhs: P<Expr>,
+    ) -> Result<P<Expr>, TranslationError> {
+        let (res_elt, _) = self
+            .portable_vector_type(ctype)
+            .ok_or_else(|| format_err!("Expected a generic vector type"))?;
+        let element_type = |qty: CQualTypeId| {
+            self.portable_vector_type(qty.ctype)
+                .map_or(qty, |(elt, _)| elt)
+        };
+        let lhs_elt = element_type(lhs_type);
+        let rhs_elt = element_type(rhs_type);
+        let res_elt_ty = self.convert_type(res_elt.ctype)?;
+
+        let operands = vec![(lhs, lhs_type.ctype), (rhs, rhs_type.ctype)];
+        self.elementwise_vector(ctx, ctype, operands, |elements| {
+            let mut elements = elements.into_iter();
+            let lhs = elements.next().expect("Missing vector lhs");
+            let rhs = elements.next().expect("Missing vector rhs");
+
+            match op {
+                c_ast::BinOp::Less
+                | c_ast::BinOp::Greater
+                | c_ast::BinOp::LessEqual
+                | c_ast::BinOp::GreaterEqual
+                | c_ast::BinOp::EqualEqual
+                | c_ast::BinOp::NotEqual => {
+                    let cmp = mk().binary_expr(BinOpKind::from(op), lhs, rhs);
+                    Ok(neg_expr(mk().cast_expr(cmp, res_elt_ty)))
+                }
+                _ => self.convert_binary_operator(
+                    ctx,
+                    src_loc,
+                    op,
+                    res_elt_ty,
+                    res_elt.ctype,
+                    lhs_elt,
+                    rhs_elt,
+                    lhs,
+                    rhs,
+                    None,
+                ),
+            }
+        })
+    }
+
+    /// Negation and complement of a generic vector, element by element
+    fn convert_vector_unary_operator(
+        &self,
+        ctx: ExprContext,
+        name: c_ast::UnOp,
+        ctype: CTypeId,
+        arg: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (elt, _) = self
+            .portable_vector_type(ctype)
+            .ok_or_else(|| format_err!("Expected a generic vector type"))?;
+        let is_unsigned = self
+            .ast_context
+            .resolve_type(elt.ctype)
+            .kind
+            .is_unsigned_integral_type();
+
+        self.convert_expr(ctx.used(), arg)?.result_map(|val| {
+            self.elementwise_vector(ctx, ctype, vec![(val, ctype)], |mut elements| {
+                let element = elements.pop().expect("Missing vector operand");
+                Ok(match name {
+                    c_ast::UnOp::Negate if is_unsigned => wrapping_neg_expr(element),
+                    c_ast::UnOp::Negate => neg_expr(element),
+                    _ => mk().unary_expr(ast::UnOp::Not, element),
+                })
+            })
+        })
+    }
 }
@@ -0,0 +1,443 @@
+//! This module provides translation of `setjmp` and `longjmp` on top of the `c2rust-setjmp`
+//! runtime crate (enabled by `--translate-setjmp`).
+//!
+//! Rust can't return twice from a function, so we only support `setjmp` calls used as the
+//! condition of an `if` statement. The code that a `longjmp` can jump out of (the "protected"
+//! code) is moved into a closure run by `c2rust_setjmp::setjmp`, and the branch taken after a
+//! `longjmp` runs when that returns an error:
+//!
+//! ```c
+//! if (setjmp(env)) {
+//!     handle_error();
+//!     return -1;
+//! }
+//! return parse();
+//! ```
+//!
+//! becomes
+//!
+//! ```no_run
+//! match c2rust_setjmp::setjmp(env.as_mut_ptr() as *mut libc::c_void, || {
+//!     return parse();
+//! }) {
+//!     Ok(ret) => return ret,
+//!     Err(_) => {}
+//! }
+//! handle_error();
+//! return -1;
+//! ```
+//!
+//! When the `if` statement has an `else` branch taken when `setjmp` returns 0, that branch is the
+//! protected code. Otherwise the protected code is the rest of the enclosing block. Control may
+//! only leave the protected code by falling off its end, except that the rest of a function body
+//! may also return from the function. Every other use of `setjmp` is reported with a `-Wsetjmp`
+//! warning and fails the translation of the enclosing function.
+//!
+//! `longjmp` unwinds the stack, so translated functions are marked with `#[unwind(allowed)]`.
+
+use super::*;
+use crate::diagnostics::Diagnostic;
+
+/// C library functions that save the calling environment
+const SETJMP_FUNCTIONS: &[&str] = &["setjmp", "_setjmp", "sigsetjmp", "__sigsetjmp"];
+
+/// C library functions that jump back to a saved environment
+const LONGJMP_FUNCTIONS: &[&str] = &["longjmp", "_longjmp", "siglongjmp"];
+
+/// An `if` statement whose condition calls `setjmp`
+#[derive(Copy, Clone, Debug)]
+pub struct SetjmpIf {
+    /// The `if` statement
+    pub stmt: CStmtId,
+    /// The `setjmp` call
+    pub call: CExprId,
+    /// The `jmp_buf` passed to `setjmp`
+    pub env: CExprId,
+    /// Lvalue the result of `setjmp` is assigned to, as in `if ((ret = setjmp(env)))`
+    pub result: Option<CExprId>,
+    /// Branch taken when `setjmp` returns 0
+    pub direct: Option<CStmtId>,
+    /// Branch taken after a `longjmp`
+    pub jumped: Option<CStmtId>,
+}
+
+impl<'c> Translation<'c> {
+    fn is_libcall_in(&self, fexp: CExprId, names: &[&str]) -> bool {
+        if let CExprKind::DeclRef(_, decl_id, _) = self.ast_context[fexp].kind {
+            if let CDeclKind::Function {
+                ref name,
+                body: None,
+                ..
+            } = self.ast_context[decl_id].kind
+            {
+                return names.contains(&name.as_str());
+            }
+        }
+        false
+    }
+
+    pub fn is_setjmp_libcall(&self, fexp: CExprId) -> bool {
+        self.is_libcall_in(fexp, SETJMP_FUNCTIONS)
+    }
+
+    pub fn is_longjmp_libcall(&self, fexp: CExprId) -> bool {
+        self.is_libcall_in(fexp, LONGJMP_FUNCTIONS)
+    }
+
+    /// Get the call and the `jmp_buf` argument of `expr` if it is a `setjmp` call.
+    fn setjmp_call(&self, expr: CExprId) -> Option<(CExprId, CExprId)> {
+        match self.ast_context[expr].kind {
+            CExprKind::Paren(_, expr) | CExprKind::ImplicitCast(_, expr, _, _, _) => {
+                self.setjmp_call(expr)
+            }
+            CExprKind::Call(_, func, ref args) if !args.is_empty() => {
+                match self.ast_context[func].kind {
+                    CExprKind::ImplicitCast(_, fexp, CastKind::FunctionToPointerDecay, _, _)
+                        if self.is_setjmp_libcall(fexp) =>
+                    {
+                        Some((expr, args[0]))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn is_zero_literal(&self, expr: CExprId) -> bool {
+        match self.ast_context[expr].kind {
+            CExprKind::Paren(_, expr) | CExprKind::ImplicitCast(_, expr, _, _, _) => {
+                self.is_zero_literal(expr)
+            }
+            CExprKind::Literal(_, CLiteral::Integer(0, _)) => true,
+            _ => false,
+        }
+    }
+
+    /// Match a condition that tests the result of a `setjmp` call. Returns the call, its
+    /// `jmp_buf` argument, the lvalue its result is assigned to, if any, and whether the
+    /// condition holds after a `longjmp`.
+    fn setjmp_condition(&self, cond: CExprId) -> Option<(CExprId, CExprId, Option<CExprId>, bool)> {
+        if let Some((call, env)) = self.setjmp_call(cond) {
+            return Some((call, env, None, true));
+        }
+
+        let negate = |(call, env, result, jumped): (CExprId, CExprId, Option<CExprId>, bool)| {
+            (call, env, result, !jumped)
+        };
+        match self.ast_context[cond].kind {
+            CExprKind::Paren(_, expr) | CExprKind::ImplicitCast(_, expr, _, _, _) => {
+                self.setjmp_condition(expr)
+            }
+            CExprKind::Unary(_, c_ast::UnOp::Not, expr, _) => {
+                self.setjmp_condition(expr).map(negate)
+            }
+            CExprKind::Binary(_, c_ast::BinOp::EqualEqual, lhs, rhs, _, _)
+                if self.is_zero_literal(rhs) =>
+            {
+                self.setjmp_condition(lhs).map(negate)
+            }
+            CExprKind::Binary(_, c_ast::BinOp::EqualEqual, lhs, rhs, _, _)
+                if self.is_zero_literal(lhs) =>
+            {
+                self.setjmp_condition(rhs).map(negate)
+            }
+            CExprKind::Binary(_, c_ast::BinOp::NotEqual, lhs, rhs, _, _)
+                if self.is_zero_literal(rhs) =>
+            {
+                self.setjmp_condition(lhs)
+            }
+            CExprKind::Binary(_, c_ast::BinOp::NotEqual, lhs, rhs, _, _)
+                if self.is_zero_literal(lhs) =>
+            {
+                self.setjmp_condition(rhs)
+            }
+            CExprKind::Binary(_, c_ast::BinOp::Assign, lhs, rhs, _, _) => self
+                .setjmp_call(rhs)
+                .map(|(call, env)| (call, env, Some(lhs), true)),
+            _ => None,
+        }
+    }
+
+    /// Recognize an `if` statement whose condition tests the result of a `setjmp` call.
+    pub fn setjmp_if(&self, stmt: CStmtId) -> Option<SetjmpIf> {
+        if !self.tcfg.translate_setjmp {
+            return None;
+        }
+
+        match self.ast_context[stmt].kind {
+            CStmtKind::If {
+                scrutinee,
+                true_variant,
+                false_variant,
+            } => {
+                let (call, env, result, jumped) = self.setjmp_condition(scrutinee)?;
+                let (direct, jumped) = if jumped {
+                    (false_variant, Some(true_variant))
+                } else {
+                    (Some(true_variant), false_variant)
+                };
+                Some(SetjmpIf {
+                    stmt,
+                    call,
+                    env,
+                    result,
+                    direct,
+                    jumped,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Find a way for control to leave the protected statements `region` other than falling off
+    /// their end, and describe it. `c_label_to_goto` maps all labels of the function to the
+    /// `goto` statements that target them.
+    pub fn setjmp_region_escape(
+        &self,
+        region: &[CStmtId],
+        allow_return: bool,
+        c_label_to_goto: &IndexMap<CLabelId, IndexSet<CStmtId>>,
+    ) -> Option<&'static str> {
+        let stmts: IndexSet<CStmtId> = region
+            .iter()
+            .flat_map(|&stmt_id| DFExpr::new(&self.ast_context, stmt_id.into()))
+            .flat_map(SomeId::stmt)
+            .collect();
+
+        for &stmt_id in &stmts {
+            match self.ast_context[stmt_id].kind {
+                CStmtKind::Return(_) if !allow_return => {
+                    return Some("the protected code returns from the function")
+                }
+                CStmtKind::Goto(label_id) if !stmts.contains(&label_id) => {
+                    return Some("a goto jumps out of the protected code")
+                }
+                CStmtKind::IndirectGoto(_)
+                    if c_label_to_goto.iter().any(|(label_id, gotos)| {
+                        gotos.contains(&stmt_id) && !stmts.contains(label_id)
+                    }) =>
+                {
+                    return Some("a computed goto jumps out of the protected code")
+                }
+                CStmtKind::Label(_) => {
+                    let mut gotos = c_label_to_goto.get(&stmt_id).into_iter().flatten();
+                    if gotos.any(|goto| !stmts.contains(goto)) {
+                        return Some("a goto jumps into the protected code");
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if region
+            .iter()
+            .any(|&stmt_id| self.has_unenclosed_jump(stmt_id, false, false))
+        {
+            return Some("a break or continue leaves the protected code");
+        }
+
+        None
+    }
+
+    /// Does `stmt_id` contain a `break` or `continue` that isn't enclosed in a loop or `switch`
+    /// inside `stmt_id`?
+    fn has_unenclosed_jump(&self, stmt_id: CStmtId, in_loop: bool, in_switch: bool) -> bool {
+        match self.ast_context[stmt_id].kind {
+            CStmtKind::Break => !in_loop && !in_switch,
+            CStmtKind::Continue => !in_loop,
+
+            CStmtKind::Label(sub) | CStmtKind::Case(_, sub, _) | CStmtKind::Default(sub) => {
+                self.has_unenclosed_jump(sub, in_loop, in_switch)
+            }
+            CStmtKind::Compound(ref stmts) => stmts
+                .iter()
+                .any(|&stmt| self.has_unenclosed_jump(stmt, in_loop, in_switch)),
+            CStmtKind::If {
+                true_variant,
+                false_variant,
+                ..
+            } => {
+                self.has_unenclosed_jump(true_variant, in_loop, in_switch)
+                    || false_variant.map_or(false, |stmt| {
+                        self.has_unenclosed_jump(stmt, in_loop, in_switch)
+                    })
+            }
+            CStmtKind::Switch { body, .. } => self.has_unenclosed_jump(body, in_loop, true),
+            CStmtKind::While { body, .. }
+            | CStmtKind::DoWhile { body, .. }
+            | CStmtKind::ForLoop { body, .. } => self.has_unenclosed_jump(body, true, in_switch),
+
+            _ => false,
+        }
+    }
+
+    /// Report a `setjmp` call that we can't translate.
+    pub fn unsupported_setjmp(&self, call: CExprId, reason: &str) -> TranslationError {
+        let loc = &self.ast_context[call].loc;
+        match loc {
+            Some(loc) => diag!(
+                Diagnostic::Setjmp,
+                "{}: unsupported setjmp: {}",
+                loc,
+                reason
+            ),
+            None => diag!(Diagnostic::Setjmp, "unsupported setjmp: {}", reason),
+        }
+        format_translation_err!(loc, "Unsupported setjmp: {}", reason)
+    }
+
+    fn convert_jmp_buf(
+        &self,
+        ctx: ExprContext,
+        env: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        self.extern_crates.borrow_mut().insert("c2rust_setjmp");
+        let void_ptr = mk().mutbl().ptr_ty(mk().path_ty(vec!["libc", "c_void"]));
+        Ok(self
+            .convert_expr(ctx.used(), env)?
+            .map(|env| mk().cast_expr(env, void_ptr)))
+    }
+
+    /// Translate the protected statements `region` of a `setjmp` into the body of a closure.
+    pub fn convert_setjmp_region(
+        &self,
+        ctx: ExprContext,
+        region: &[CStmtId],
+        ret: cfg::ImplicitReturnType,
+    ) -> Result<Vec<Stmt>, TranslationError> {
+        let name = format!("{}_setjmp", self.function_context.borrow().get_name());
+        self.convert_function_body(ctx, &name, region, ret)
+    }
+
+    /// Build the call `c2rust_setjmp::setjmp(env, || { body })` for the `setjmp` in `setjmp_if`.
+    pub fn convert_setjmp_call(
+        &self,
+        ctx: ExprContext,
+        setjmp_if: &SetjmpIf,
+        body: Vec<Stmt>,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let closure = mk().closure_expr(
+            CaptureBy::Ref,
+            Movability::Movable,
+            mk().fn_decl(vec![], FunctionRetTy::Default(DUMMY_SP), false),
+            mk().block_expr(stmts_block(body)),
+        );
+        let setjmp = mk().path_expr(vec!["c2rust_setjmp", "setjmp"]);
+        Ok(self
+            .convert_jmp_buf(ctx, setjmp_if.env)?
+            .map(|env| mk().call_expr(setjmp, vec![env, closure])))
+    }
+
+    /// Build the statements that assign `val` to the lvalue the result of `setjmp` is assigned
+    /// to, if there is one.
+    fn convert_setjmp_result(
+        &self,
+        ctx: ExprContext,
+        setjmp_if: &SetjmpIf,
+        val: P<Expr>,
+    ) -> Result<Vec<Stmt>, TranslationError> {
+        let lhs = match setjmp_if.result {
+            Some(lhs) => lhs,
+            None => return Ok(vec![]),
+        };
+        let lhs_ty = self.ast_context[lhs]
+            .kind
+            .get_type()
+            .ok_or_else(|| format_err!("bad setjmp result type"))?;
+        let lhs_ty = self.convert_type(lhs_ty)?;
+
+        let (mut stmts, lhs) = self.convert_expr(ctx.used(), lhs)?.discard_unsafe();
+        let val = mk().cast_expr(val, lhs_ty);
+        stmts.push(mk().semi_stmt(mk().assign_expr(lhs, val)));
+        Ok(stmts)
+    }
+
+    /// Build the statements that run before the protected code, when `setjmp` returns 0.
+    pub fn convert_setjmp_init(
+        &self,
+        ctx: ExprContext,
+        setjmp_if: &SetjmpIf,
+    ) -> Result<Vec<Stmt>, TranslationError> {
+        let zero = mk().lit_expr(mk().int_lit(0, ""));
+        self.convert_setjmp_result(ctx, setjmp_if, zero)
+    }
+
+    /// Inspect the result of `c2rust_setjmp::setjmp`. When `returns` is set, the protected code
+    /// returns from the function, so we build the statement
+    ///
+    /// ```no_run
+    /// match call { Ok(ret) => return ret, Err(val) => { lhs = val } }
+    /// ```
+    ///
+    /// and otherwise the condition
+    ///
+    /// ```no_run
+    /// match call { Ok(_) => false, Err(val) => { lhs = val; true } }
+    /// ```
+    ///
+    /// which holds after a `longjmp`.
+    pub fn convert_setjmp_match(
+        &self,
+        ctx: ExprContext,
+        setjmp_if: &SetjmpIf,
+        call: P<Expr>,
+        returns: bool,
+    ) -> Result<P<Expr>, TranslationError> {
+        if !returns && setjmp_if.result.is_none() {
+            return Ok(mk().method_call_expr(call, "is_err", vec![] as Vec<P<Expr>>));
+        }
+
+        let val_name = self.renamer.borrow_mut().pick_name("jmp_val");
+        let mut jumped_stmts =
+            self.convert_setjmp_result(ctx, setjmp_if, mk().ident_expr(&val_name))?;
+        let val_pat = if setjmp_if.result.is_some() {
+            mk().ident_pat(&val_name)
+        } else {
+            mk().wild_pat()
+        };
+
+        let ok_arm = if returns {
+            let ret_name = self.renamer.borrow_mut().pick_name("ret");
+            mk().arm(
+                vec![mk().tuple_struct_pat(vec!["Ok"], vec![mk().ident_pat(&ret_name)])],
+                None as Option<P<Expr>>,
+                mk().return_expr(Some(mk().ident_expr(&ret_name))),
+            )
+        } else {
+            jumped_stmts.push(mk().expr_stmt(mk().lit_expr(mk().bool_lit(true))));
+            mk().arm(
+                vec![mk().tuple_struct_pat(vec!["Ok"], vec![mk().wild_pat()])],
+                None as Option<P<Expr>>,
+                mk().lit_expr(mk().bool_lit(false)),
+            )
+        };
+        let err_arm = mk().arm(
+            vec![mk().tuple_struct_pat(vec!["Err"], vec![val_pat])],
+            None as Option<P<Expr>>,
+            mk().block_expr(mk().block(jumped_stmts)),
+        );
+
+        Ok(mk().match_expr(call, vec![ok_arm, err_arm]))
+    }
+
+    /// Translate a call to `longjmp(env, val)`.
+    pub fn convert_longjmp(
+        &self,
+        ctx: ExprContext,
+        args: &[CExprId],
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (env, val) = match *args {
+            [env, val] => (env, val),
+            _ => return Err(format_err!("longjmp expects 2 arguments").into()),
+        };
+        let longjmp = mk().path_expr(vec!["c2rust_setjmp", "longjmp"]);
+        let (mut stmts, env) = self.convert_jmp_buf(ctx, env)?.discard_unsafe();
+        let (val_stmts, val) = self.convert_expr(ctx.used(), val)?.discard_unsafe();
+        stmts.extend(val_stmts);
+        Ok(WithStmts::new(
+            stmts,
+            mk().call_expr(longjmp, vec![env, val]),
+        ))
+    }
+}
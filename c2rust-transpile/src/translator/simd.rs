@@ -67,6 +67,72 @@ static SIMD_X86_64_ONLY: &[&str] = &[
     "_mm_crc32_u64",
 ];
 
+/// The public x86 SIMD typedefs, all of which have a Rust counterpart.
+static SIMD_X86_TYPES: &[&str] = &[
+    "__m128i", "__m128", "__m128d", "__m64", "__m256", "__m256d", "__m256i",
+];
+
+/// These seem to be C internal types only, and shouldn't need any explicit support.
+/// See https://internals.rust-lang.org/t/getting-explicit-simd-on-stable-rust/4380/115
+static SIMD_X86_INTERNAL_TYPES: &[&str] = &[
+    "__v1di",
+    "__v2si",
+    "__v4hi",
+    "__v8qi",
+    "__v4si",
+    "__v4sf",
+    "__v4su",
+    "__v2df",
+    "__v2di",
+    "__v8hi",
+    "__v16qi",
+    "__v2du",
+    "__v8hu",
+    "__v16qu",
+    "__v4df",
+    "__v8sf",
+    "__v4di",
+    "__v8si",
+    "__v16hi",
+    "__v32qi",
+    "__v4du",
+    "__v8di_aligned",
+    "__v8df_aligned",
+    "__v16sf_aligned",
+    "__v8sf_aligned",
+    "__v4df_aligned",
+    "__v4di_aligned",
+    "__v16qs",
+    "__v8su",
+    "__v16hu",
+    "__mm_loadh_pi_v2f32",
+    "__mm_loadl_pi_v2f32",
+];
+
+/// NEON vector types are named `<base><bits>x<lanes>_t`, and the structs holding several of them
+/// `<base><bits>x<lanes>x<count>_t`, e.g. `uint8x16_t` or `float32x4x2_t`.
+fn is_neon_vector_type(name: &str) -> bool {
+    if !name.ends_with("_t") {
+        return false;
+    }
+    let name = &name[..name.len() - 2];
+
+    let sizes = ["poly", "uint", "int", "float"]
+        .iter()
+        .find(|base| name.starts_with(*base))
+        .map(|base| &name[base.len()..]);
+    match sizes {
+        Some(sizes) => {
+            let sizes: Vec<&str> = sizes.split('x').collect();
+            (sizes.len() == 2 || sizes.len() == 3)
+                && sizes
+                    .iter()
+                    .all(|size| !size.is_empty() && size.bytes().all(|b| b.is_ascii_digit()))
+        }
+        None => false,
+    }
+}
+
 impl<'c> Translation<'c> {
     /// Given the name of a typedef check if its one of the SIMD types.
     /// This function returns `true` when the name of the type is one that
@@ -74,7 +140,7 @@ impl<'c> Translation<'c> {
     pub fn import_simd_typedef(&self, name: &str) -> bool {
         match name {
             // Public API SIMD typedefs:
-            "__m128i" | "__m128" | "__m128d" | "__m64" | "__m256" | "__m256d" | "__m256i" => {
+            _ if SIMD_X86_TYPES.contains(&name) => {
                 // __m64 is still behind a feature gate
                 if name == "__m64" {
                     self.use_feature("stdsimd");
@@ -99,41 +165,7 @@ impl<'c> Translation<'c> {
 
                 true
             }
-            // These seem to be C internal types only, and shouldn't need any explicit support.
-            // See https://internals.rust-lang.org/t/getting-explicit-simd-on-stable-rust/4380/115
-            "__v1di"
-            | "__v2si"
-            | "__v4hi"
-            | "__v8qi"
-            | "__v4si"
-            | "__v4sf"
-            | "__v4su"
-            | "__v2df"
-            | "__v2di"
-            | "__v8hi"
-            | "__v16qi"
-            | "__v2du"
-            | "__v8hu"
-            | "__v16qu"
-            | "__v4df"
-            | "__v8sf"
-            | "__v4di"
-            | "__v8si"
-            | "__v16hi"
-            | "__v32qi"
-            | "__v4du"
-            | "__v8di_aligned"
-            | "__v8df_aligned"
-            | "__v16sf_aligned"
-            | "__v8sf_aligned"
-            | "__v4df_aligned"
-            | "__v4di_aligned"
-            | "__v16qs"
-            | "__v8su"
-            | "__v16hu"
-            | "__mm_loadh_pi_v2f32"
-            | "__mm_loadl_pi_v2f32" => true,
-            _ => false,
+            _ => SIMD_X86_INTERNAL_TYPES.contains(&name),
         }
     }
 
@@ -181,6 +213,50 @@ impl<'c> Translation<'c> {
         Ok(false)
     }
 
+    /// Determine if a declaration is one of the NEON vector types or intrinsics from Clang's
+    /// `arm_neon.h`. If so it is imported from `core::arch::aarch64` instead, `true` is returned,
+    /// and no further translation should be done.
+    pub fn import_neon_decl(&self, decl_id: CDeclId, name: &str) -> bool {
+        if !self.is_arm_neon_decl(decl_id) {
+            return false;
+        }
+
+        let is_neon_item = match self.ast_context[decl_id].kind {
+            // The helpers that the intrinsics are implemented with start with `__`
+            CDeclKind::Function { .. } => name.starts_with('v'),
+            CDeclKind::Typedef { .. } | CDeclKind::Struct { .. } => is_neon_vector_type(name),
+            _ => false,
+        };
+        if !is_neon_item {
+            return false;
+        }
+
+        // Most of the aarch64 intrinsics are still behind a feature gate
+        self.use_feature("stdsimd");
+
+        let aarch64_attr = mk()
+            .call_attr("cfg", vec!["target_arch = \"aarch64\""])
+            .pub_();
+        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" }.to_string();
+
+        self.item_store
+            .borrow_mut()
+            .uses
+            .get_mut(vec![std_or_core, "arch".into(), "aarch64".into()])
+            .insert_with_attr(name, aarch64_attr);
+
+        true
+    }
+
+    fn is_arm_neon_decl(&self, decl_id: CDeclId) -> bool {
+        self.ast_context[decl_id]
+            .loc
+            .as_ref()
+            .and_then(|loc| loc.file_path.as_ref())
+            .and_then(|path| path.file_name())
+            .map_or(false, |file_name| file_name == "arm_neon.h")
+    }
+
     /// This function will strip either an implicitly casted int or explicitly casted
     /// vector as both casts are unnecessary (and problematic) for our purposes
     fn clean_int_or_vector_param(&self, expr_id: CExprId) -> CExprId {
@@ -591,4 +667,258 @@ impl<'c> Translation<'c> {
             _ => false,
         }
     }
+
+    /// Generic vectors are the ones declared with `__attribute__((vector_size(N)))` or
+    /// `__attribute__((ext_vector_type(N)))` rather than through one of the x86 or NEON vector
+    /// types. They are translated to fixed-size arrays, and their operations element by element.
+    /// This returns the element type and length of such a vector type.
+    pub fn portable_vector_type(&self, ctype: CTypeId) -> Option<(CQualTypeId, usize)> {
+        match self.ast_context[ctype].kind {
+            CTypeKind::Typedef(decl_id) => match self.ast_context[decl_id].kind {
+                CDeclKind::Typedef { ref name, typ, .. } => {
+                    if SIMD_X86_TYPES.contains(&name.as_str())
+                        || SIMD_X86_INTERNAL_TYPES.contains(&name.as_str())
+                        || self.is_arm_neon_decl(decl_id)
+                    {
+                        None
+                    } else {
+                        self.portable_vector_type(typ.ctype)
+                    }
+                }
+                _ => None,
+            },
+            CTypeKind::Attributed(ty, _) => self.portable_vector_type(ty.ctype),
+            CTypeKind::Elaborated(ty)
+            | CTypeKind::Decayed(ty)
+            | CTypeKind::TypeOf(ty)
+            | CTypeKind::Paren(ty) => self.portable_vector_type(ty),
+            CTypeKind::Vector(elt, len) => Some((elt, len)),
+            _ => None,
+        }
+    }
+
+    /// Build a generic vector of type `ctype` element by element:
+    ///
+    /// ```ignore
+    /// {
+    ///     let operand = ...;
+    ///     let operand_0 = ...;
+    ///     let mut result: [T; N] = [0; N];
+    ///     for i in 0..N {
+    ///         result[i] = operand[i] + operand_0[i];
+    ///     }
+    ///     result
+    /// }
+    /// ```
+    ///
+    /// Each of the `operands` is bound to a variable first, and passed to `element` indexed by the
+    /// loop variable if it is a vector, or as is otherwise.
+    pub fn elementwise_vector<F>(
+        &self,
+        ctx: ExprContext,
+        ctype: CTypeId,
+        operands: Vec<(P<Expr>, CTypeId)>,
+        element: F,
+    ) -> Result<P<Expr>, TranslationError>
+    where
+        F: FnOnce(Vec<P<Expr>>) -> Result<P<Expr>, TranslationError>,
+    {
+        let (elt, len) = self
+            .portable_vector_type(ctype)
+            .ok_or_else(|| format_err!("Expected a generic vector type"))?;
+        if ctx.is_static {
+            return Err(TranslationError::generic(
+                "Cannot compute a generic vector element by element in a static",
+            ));
+        }
+        let ty = self.convert_type(ctype)?;
+        let len = mk().lit_expr(mk().int_lit(len as u128, LitIntType::Unsuffixed));
+
+        let index = self.renamer.borrow_mut().pick_name("i");
+        let mut stmts = vec![];
+        let mut elements = vec![];
+        for (operand, operand_ty) in operands {
+            let name = self.renamer.borrow_mut().pick_name("operand");
+            stmts.push(mk().local_stmt(P(mk().local(
+                mk().ident_pat(&name),
+                None as Option<P<Ty>>,
+                Some(operand),
+            ))));
+            elements.push(if self.portable_vector_type(operand_ty).is_some() {
+                mk().index_expr(mk().ident_expr(&name), mk().ident_expr(&index))
+            } else {
+                mk().ident_expr(&name)
+            });
+        }
+
+        let result = self.renamer.borrow_mut().pick_name("result");
+        let zero = self
+            .implicit_default_expr(elt.ctype, ctx.is_static)?
+            .to_expr();
+        stmts.push(mk().local_stmt(P(mk().local(
+            mk().mutbl().ident_pat(&result),
+            Some(ty),
+            Some(mk().repeat_expr(zero, len.clone())),
+        ))));
+
+        let assign = mk().assign_expr(
+            mk().index_expr(mk().ident_expr(&result), mk().ident_expr(&index)),
+            element(elements)?,
+        );
+        let range = mk().range_expr(mk().lit_expr(mk().int_lit(0, LitIntType::Unsuffixed)), len);
+        stmts.push(mk().semi_stmt(mk().for_expr(
+            mk().ident_pat(&index),
+            range,
+            mk().block(vec![mk().semi_stmt(assign)]),
+            None as Option<Ident>,
+        )));
+        stmts.push(mk().expr_stmt(mk().ident_expr(&result)));
+
+        Ok(mk().block_expr(mk().block(stmts)))
+    }
+
+    /// Translate a list initializer of a generic vector to an array, filling in the elements
+    /// that aren't given with zeros.
+    pub fn portable_vector_initializer(
+        &self,
+        ctx: ExprContext,
+        ids: &[CExprId],
+        ctype: CTypeId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (elt, len) = self
+            .portable_vector_type(ctype)
+            .ok_or_else(|| format_err!("Expected a generic vector type"))?;
+        let zero = self
+            .implicit_default_expr(elt.ctype, ctx.is_static)?
+            .to_expr();
+
+        Ok(self.convert_exprs(ctx, ids)?.map(|mut elements| {
+            elements.resize(len, zero);
+            mk().array_expr(elements)
+        }))
+    }
+
+    /// Translate `__builtin_convertvector` between generic vectors, converting each element
+    /// with an `as` cast.
+    pub fn convert_vector_conversion(
+        &self,
+        ctx: ExprContext,
+        ctype: CTypeId,
+        src: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (elt, _) = self
+            .portable_vector_type(ctype)
+            .ok_or_else(|| format_err!("Expected a generic vector type"))?;
+        let src_ty = self.ast_context[src]
+            .kind
+            .get_qual_type()
+            .ok_or_else(|| format_err!("bad convert vector source type"))?
+            .ctype;
+        if self.portable_vector_type(src_ty).is_none() {
+            return Err(TranslationError::generic(
+                "convert vector from non-generic vectors not supported",
+            ));
+        }
+        let elt_ty = self.convert_type(elt.ctype)?;
+
+        self.convert_expr(ctx.used(), src)?.result_map(|src| {
+            self.elementwise_vector(ctx, ctype, vec![(src, src_ty)], |mut elements| {
+                let element = elements.pop().expect("Missing convert vector operand");
+                Ok(mk().cast_expr(element, elt_ty))
+            })
+        })
+    }
+
+    /// Translate `__builtin_shufflevector` on generic vectors to an array of the elements picked
+    /// out of the two operands. Only constant indices are supported.
+    pub fn convert_portable_shuffle_vector(
+        &self,
+        ctx: ExprContext,
+        ctype: CTypeId,
+        child_expr_ids: &[CExprId],
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (elt, _) = self
+            .portable_vector_type(ctype)
+            .ok_or_else(|| format_err!("Expected a generic vector type"))?;
+        if child_expr_ids.len() < 2 {
+            return Err(TranslationError::generic(
+                "Shuffle vector without two vector operands",
+            ));
+        }
+        let first_len = self.ast_context[child_expr_ids[0]]
+            .kind
+            .get_qual_type()
+            .and_then(|qty| self.portable_vector_type(qty.ctype))
+            .map(|(_, len)| len)
+            .ok_or_else(|| format_err!("Shuffle vector operand is not a generic vector"))?;
+        let indices = child_expr_ids[2..]
+            .iter()
+            .map(|&id| {
+                self.shuffle_vector_index(id).ok_or_else(|| {
+                    TranslationError::generic("Shuffle vector with a non-constant index")
+                })
+            })
+            .collect::<Result<Vec<i64>, TranslationError>>()?;
+        let zero = self
+            .implicit_default_expr(elt.ctype, ctx.is_static)?
+            .to_expr();
+
+        let first = self.renamer.borrow_mut().pick_name("first");
+        let second = self.renamer.borrow_mut().pick_name("second");
+        let operands = self.convert_exprs(ctx.used(), &child_expr_ids[..2])?;
+        Ok(operands.map(|operands| {
+            let mut stmts: Vec<Stmt> = operands
+                .into_iter()
+                .zip(&[&first, &second])
+                .map(|(operand, name)| {
+                    mk().local_stmt(P(mk().local(
+                        mk().ident_pat(*name),
+                        None as Option<P<Ty>>,
+                        Some(operand),
+                    )))
+                })
+                .collect();
+
+            let elements: Vec<P<Expr>> = indices
+                .iter()
+                .map(|&index| {
+                    // An index of -1 leaves the element undefined
+                    if index < 0 {
+                        return zero.clone();
+                    }
+                    let (name, index) = if (index as usize) < first_len {
+                        (&first, index as usize)
+                    } else {
+                        (&second, index as usize - first_len)
+                    };
+                    mk().index_expr(
+                        mk().ident_expr(name),
+                        mk().lit_expr(mk().int_lit(index as u128, LitIntType::Unsuffixed)),
+                    )
+                })
+                .collect();
+            stmts.push(mk().expr_stmt(mk().array_expr(elements)));
+
+            mk().block_expr(mk().block(stmts))
+        }))
+    }
+
+    /// Evaluate a constant shuffle vector index.
+    fn shuffle_vector_index(&self, expr_id: CExprId) -> Option<i64> {
+        match self.ast_context[expr_id].kind {
+            Literal(_, Integer(value, _)) => Some(value as i64),
+            CExprKind::Unary(_, c_ast::UnOp::Negate, expr_id, _) => {
+                self.shuffle_vector_index(expr_id).map(|value| -value)
+            }
+            ImplicitCast(_, expr_id, _, _, _)
+            | ExplicitCast(_, expr_id, _, _, _)
+            | CExprKind::Paren(_, expr_id) => self.shuffle_vector_index(expr_id),
+            // Clang wraps constant expressions in a `ConstantExpr`, which we import as a
+            // `ConvertVector` with a single child
+            CExprKind::ConvertVector(_, ref kids) if kids.len() == 1 => {
+                self.shuffle_vector_index(kids[0])
+            }
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,360 @@
+//! Statics whose C initializers can't be evaluated by Rust at compile time.
+//!
+//! With `StaticInitMode::Sections`, such statics start out zeroed and are assigned by
+//! `run_static_initializers`, which the linker runs before `main` through an `.init_array` (or
+//! equivalent) section. With `StaticInitMode::Lazy`, each of them gets an accessor that
+//! initializes it on first use instead:
+//!
+//! ```ignore
+//! unsafe fn init_table() -> *mut [*mut libc::c_char; 2] {
+//!     static INIT: ::std::sync::Once = ::std::sync::Once::new();
+//!     INIT.call_once(|| { table = [...]; });
+//!     &mut table as *mut [*mut libc::c_char; 2]
+//! }
+//! ```
+//!
+//! and uses of `table` in functions become `*init_table()`. C static initializers can only take
+//! the address of other statics, never read them, so initializers keep referring to statics
+//! directly. This also keeps initializers that refer to their own static from recursing. Reads
+//! through such an address don't go through the accessor, though, so statics whose address other
+//! static initializers take are still initialized in a section.
+//!
+//! To need fewer accessors, this mode also evaluates addresses of parts of statics, such as
+//! `&s.field`, `&array[2]` and `array + 2`, at compile time where possible.
+
+use super::*;
+use crate::diagnostics::Diagnostic;
+
+impl<'c> Translation<'c> {
+    /// The name of the accessor of `decl_id` if it is a static that is initialized on first use
+    pub fn lazy_static_accessor(&self, decl_id: CDeclId) -> Option<String> {
+        if self.tcfg.static_init != StaticInitMode::Lazy {
+            return None;
+        }
+        if let Some(accessor) = self.lazy_static_accessors.borrow().get(&decl_id) {
+            return accessor.clone();
+        }
+
+        // Thread-locals would need an accessor per thread, so they are still initialized in a
+        // section
+        let accessor = match self.ast_context[decl_id].kind {
+            CDeclKind::Variable {
+                has_static_duration: true,
+                has_thread_duration: false,
+                is_defn: true,
+                initializer,
+                ..
+            } if self.static_initializer_is_uncompilable(initializer)
+                && !self.statics_in_initializers.contains(&decl_id) =>
+            {
+                let name = self.renamer.borrow().get(&decl_id)?;
+                Some(
+                    self.renamer
+                        .borrow_mut()
+                        .pick_name_root(&format!("init_{}", name)),
+                )
+            }
+            _ => None,
+        };
+        self.lazy_static_accessors
+            .borrow_mut()
+            .insert(decl_id, accessor.clone());
+        accessor
+    }
+
+    /// Find the statics that the initializers of other statics refer to
+    pub fn find_statics_in_initializers(&self) -> IndexSet<CDeclId> {
+        let mut statics = IndexSet::new();
+        for (&decl_id, decl) in self.ast_context.iter_decls() {
+            let initializer = match decl.kind {
+                CDeclKind::Variable {
+                    has_static_duration: true,
+                    initializer: Some(initializer),
+                    ..
+                } => initializer,
+                _ => continue,
+            };
+            let mut iter = DFExpr::new(&self.ast_context, initializer.into());
+            while let Some(node) = iter.next() {
+                if let SomeId::Expr(expr_id) = node {
+                    if let CExprKind::DeclRef(_, referenced, _) = self.ast_context[expr_id].kind {
+                        if referenced != decl_id {
+                            statics.insert(referenced);
+                        }
+                    }
+                }
+            }
+        }
+        statics
+    }
+
+    /// Build the accessor `accessor` that assigns `init` to the static `name` of type `ty` the
+    /// first time it is called, and returns a pointer to the static.
+    pub fn convert_lazy_static(
+        &self,
+        decl_id: CDeclId,
+        accessor: &str,
+        name: &str,
+        ty: P<Ty>,
+        init: P<Expr>,
+        vis: &str,
+    ) -> P<Item> {
+        let decl = &self.ast_context[decl_id];
+        if let CDeclKind::Variable {
+            is_externally_visible: true,
+            ..
+        } = decl.kind
+        {
+            let reason = format!(
+                "{} is visible to other translation units, but is only initialized once it is used \
+                 in this one",
+                name,
+            );
+            match decl.loc {
+                Some(ref loc) => diag!(Diagnostic::StaticInit, "{}: {}", loc, reason),
+                None => diag!(Diagnostic::StaticInit, "{}", reason),
+            }
+        }
+
+        let assign = mk().semi_stmt(mk().assign_expr(mk().path_expr(vec![name]), init));
+        let mut stmts = if self.tcfg.emit_no_std {
+            // `Once` needs `std`, and translated statics aren't thread-safe anyway
+            let initialized = mk().mutbl().static_item(
+                "INITIALIZED",
+                mk().path_ty(vec!["bool"]),
+                mk().lit_expr(mk().bool_lit(false)),
+            );
+            let set_initialized = mk().semi_stmt(mk().assign_expr(
+                mk().path_expr(vec!["INITIALIZED"]),
+                mk().lit_expr(mk().bool_lit(true)),
+            ));
+            let not_initialized =
+                mk().unary_expr(ast::UnOp::Not, mk().path_expr(vec!["INITIALIZED"]));
+            vec![
+                mk().item_stmt(initialized),
+                mk().expr_stmt(mk().ifte_expr(
+                    not_initialized,
+                    mk().block(vec![set_initialized, assign]),
+                    None as Option<P<Expr>>,
+                )),
+            ]
+        } else {
+            let once_ty = mk().path_ty(vec!["", "std", "sync", "Once"]);
+            let once_new = mk().call_expr(
+                mk().path_expr(vec!["", "std", "sync", "Once", "new"]),
+                vec![] as Vec<P<Expr>>,
+            );
+            let init_closure = mk().closure_expr(
+                CaptureBy::Ref,
+                Movability::Movable,
+                mk().fn_decl(vec![], FunctionRetTy::Default(DUMMY_SP), false),
+                mk().block_expr(mk().block(vec![assign])),
+            );
+            vec![
+                mk().item_stmt(mk().static_item("INIT", once_ty, once_new)),
+                mk().semi_stmt(mk().method_call_expr(
+                    mk().path_expr(vec!["INIT"]),
+                    "call_once",
+                    vec![init_closure],
+                )),
+            ]
+        };
+
+        let ptr_ty = mk().mutbl().ptr_ty(ty);
+        let static_ptr = mk().cast_expr(
+            mk().mutbl().addr_of_expr(mk().path_expr(vec![name])),
+            ptr_ty.clone(),
+        );
+        stmts.push(mk().expr_stmt(static_ptr));
+
+        let decl = mk().fn_decl(vec![], FunctionRetTy::Ty(ptr_ty), false);
+        self.mk_cross_check(mk(), vec!["none"])
+            .vis(vis)
+            .unsafe_()
+            .fn_item(accessor, decl, mk().block(stmts))
+    }
+
+    /// Convert `expr_id` if it is an address in a static variable that Rust can compute at
+    /// compile time (see `static_address`).
+    pub fn convert_static_address(
+        &self,
+        ctx: ExprContext,
+        expr_id: CExprId,
+    ) -> Result<Option<WithStmts<P<Expr>>>, TranslationError> {
+        let (place, index) = match self.static_address(expr_id) {
+            Some(address) => address,
+            None => return Ok(None),
+        };
+
+        let mut place = self.convert_static_place(ctx, place)?;
+        if let Some(index) = index {
+            place = mk().index_expr(place, mk().lit_expr(mk().int_lit(index.into(), "usize")));
+        }
+
+        let ty = self.ast_context[expr_id]
+            .kind
+            .get_qual_type()
+            .ok_or_else(|| format_err!("bad static address type"))?;
+        let pointee_ty = self
+            .ast_context
+            .get_pointee_qual_type(ty.ctype)
+            .ok_or_else(|| TranslationError::generic("Static address should be a pointer"))?;
+
+        // Static initializers can't use &mut, so we go through & and *const like `&x` does
+        let mut addr = mk().addr_of_expr(place);
+        if !pointee_ty.qualifiers.is_const {
+            let mut const_pointee_ty = pointee_ty;
+            const_pointee_ty.qualifiers.is_const = true;
+            let const_ty = self
+                .type_converter
+                .borrow_mut()
+                .convert_pointer(&self.ast_context, const_pointee_ty)?;
+            addr = mk().cast_expr(addr, const_ty);
+        }
+        let ty = self.convert_type(ty.ctype)?;
+        Ok(Some(WithStmts::new_val(mk().cast_expr(addr, ty))))
+    }
+
+    /// If `expr_id` computes the address of a part of a static variable, return that part and,
+    /// for pointer arithmetic on an array, the index of the element it points to. These addresses
+    /// are `&place` where `place` is a `static_place`, and `array + N` and `array - N` where
+    /// `array` is a `static_place` with an element `N` or `-N`.
+    pub fn static_address(&self, expr_id: CExprId) -> Option<(CExprId, Option<u64>)> {
+        match self.ast_context[expr_id].kind {
+            CExprKind::Unary(_, c_ast::UnOp::AddressOf, place, _)
+                if self.is_static_place(place) =>
+            {
+                Some((place, None))
+            }
+            CExprKind::Binary(_, c_ast::BinOp::Add, lhs, rhs, _, _) => {
+                let element = self
+                    .static_index(rhs)
+                    .and_then(|index| self.static_element(lhs, index))
+                    .or_else(|| {
+                        self.static_index(lhs)
+                            .and_then(|index| self.static_element(rhs, index))
+                    })?;
+                Some((element.0, Some(element.1)))
+            }
+            CExprKind::Binary(_, c_ast::BinOp::Subtract, lhs, rhs, _, _) => {
+                let index = self.static_index(rhs)?.checked_neg()?;
+                let element = self.static_element(lhs, index)?;
+                Some((element.0, Some(element.1)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Is `expr_id` a place in a static variable that can be borrowed in a static initializer?
+    /// These are static variables, their fields, except for bitfields and fields of packed
+    /// records, and the elements of their constant size arrays at constant indices.
+    fn is_static_place(&self, expr_id: CExprId) -> bool {
+        match self.ast_context[expr_id].kind {
+            CExprKind::DeclRef(_, decl_id, _) => match self.ast_context[decl_id].kind {
+                CDeclKind::Variable {
+                    has_static_duration: true,
+                    has_thread_duration: false,
+                    ..
+                } => true,
+                _ => false,
+            },
+            CExprKind::Paren(_, expr_id) => self.is_static_place(expr_id),
+            CExprKind::Member(_, base, field, MemberKind::Dot, _) => {
+                let is_bitfield = match self.ast_context[field].kind {
+                    CDeclKind::Field { bitfield_width, .. } => bitfield_width.is_some(),
+                    _ => true,
+                };
+                !is_bitfield && !self.is_packed_place(expr_id) && self.is_static_place(base)
+            }
+            CExprKind::ArraySubscript(_, lhs, rhs, _) => self.static_subscript(lhs, rhs).is_some(),
+            _ => false,
+        }
+    }
+
+    /// The array and index of `lhs[rhs]` if it is an element of a `static_place`
+    fn static_subscript(&self, lhs: CExprId, rhs: CExprId) -> Option<(CExprId, u64)> {
+        self.static_index(rhs)
+            .and_then(|index| self.static_element(lhs, index))
+            .or_else(|| {
+                self.static_index(lhs)
+                    .and_then(|index| self.static_element(rhs, index))
+            })
+    }
+
+    /// The array and index of the element `index` of the array that `ptr` decays from, if that
+    /// array is a `static_place` of constant size and has such an element
+    fn static_element(&self, ptr: CExprId, index: i64) -> Option<(CExprId, u64)> {
+        let array = match self.ast_context[ptr].kind {
+            CExprKind::ImplicitCast(_, array, CastKind::ArrayToPointerDecay, _, _) => array,
+            _ => return None,
+        };
+        let array_ty = self.ast_context[array].kind.get_type()?;
+        let len = match self.ast_context.resolve_type(array_ty).kind {
+            CTypeKind::ConstantArray(_, len) => len as u64,
+            _ => return None,
+        };
+        if index < 0 || index as u64 >= len || !self.is_static_place(array) {
+            return None;
+        }
+        Some((array, index as u64))
+    }
+
+    /// The value of `expr_id` if it is an integer constant simple enough to use as an index
+    fn static_index(&self, expr_id: CExprId) -> Option<i64> {
+        match self.ast_context[expr_id].kind {
+            CExprKind::Literal(_, CLiteral::Integer(value, _))
+                if value <= i64::max_value() as u64 =>
+            {
+                Some(value as i64)
+            }
+            CExprKind::ImplicitCast(_, expr_id, CastKind::IntegralCast, _, _)
+            | CExprKind::ExplicitCast(_, expr_id, CastKind::IntegralCast, _, _)
+            | CExprKind::Paren(_, expr_id) => self.static_index(expr_id),
+            CExprKind::Unary(_, c_ast::UnOp::Negate, expr_id, _) => {
+                self.static_index(expr_id)?.checked_neg()
+            }
+            CExprKind::DeclRef(_, decl_id, _) => match self.ast_context[decl_id].kind {
+                CDeclKind::EnumConstant {
+                    value: ConstIntExpr::I(value),
+                    ..
+                } => Some(value),
+                CDeclKind::EnumConstant {
+                    value: ConstIntExpr::U(value),
+                    ..
+                } if value <= i64::max_value() as u64 => Some(value as i64),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Convert the `static_place` `expr_id`
+    fn convert_static_place(
+        &self,
+        ctx: ExprContext,
+        expr_id: CExprId,
+    ) -> Result<P<Expr>, TranslationError> {
+        match self.ast_context[expr_id].kind {
+            CExprKind::Paren(_, expr_id) => self.convert_static_place(ctx, expr_id),
+            CExprKind::Member(_, base, field, _, _) => {
+                let base = self.convert_static_place(ctx, base)?;
+                let field_name = self
+                    .type_converter
+                    .borrow()
+                    .resolve_field_name(None, field)
+                    .ok_or_else(|| format_err!("Missing field name {:?}", field))?;
+                let record_id = self.ast_context.parents[&field];
+                Ok(self.record_field_expr(record_id, base, field_name))
+            }
+            CExprKind::ArraySubscript(_, lhs, rhs, _) => {
+                let (array, index) = self
+                    .static_subscript(lhs, rhs)
+                    .ok_or_else(|| format_err!("Expected an element of a static array"))?;
+                let array = self.convert_static_place(ctx, array)?;
+                let index = mk().lit_expr(mk().int_lit(index.into(), "usize"));
+                Ok(mk().index_expr(array, index))
+            }
+            _ => Ok(self.convert_expr(ctx.used(), expr_id)?.to_expr()),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! This module provides translation of the layout of structs and unions that are packed, either
+//! with `__attribute__((packed))` or `#pragma pack(N)`, or that have a manually specified
+//! alignment.
+//!
+//! Rust doesn't allow `#[repr(packed)]` and `#[repr(align)]` on the same type, so records that
+//! need both are translated into an aligned tuple struct wrapping the packed record:
+//!
+//! ```no_run
+//! #[repr(C, align(4))]
+//! pub struct foo(pub foo_Inner);
+//! #[repr(C, packed)]
+//! pub struct foo_Inner {
+//!     pub a: libc::c_char,
+//!     pub b: libc::c_int,
+//! }
+//! ```
+//!
+//! Fields of packed records might not be aligned, so we also avoid creating Rust references to
+//! them. Reading and writing such fields by value is fine, since Rust emits unaligned accesses.
+
+use super::*;
+
+/// Suffix of the packed inner record of a record that is also over-aligned
+const INNER_SUFFIX: &str = "_Inner";
+
+/// Build an argument of a `#[repr]` attribute that takes a value, e.g. `align(8)`.
+fn repr_arg_metaitem(name: &str, value: u64) -> NestedMetaItem {
+    let lit = mk().int_lit(value as u128, LitIntType::Unsuffixed);
+    let meta_item = mk().meta_item(
+        vec![name],
+        MetaItemKind::List(vec![mk().nested_meta_item(NestedMetaItem::Literal(lit))]),
+    );
+    mk().nested_meta_item(NestedMetaItem::MetaItem(meta_item))
+}
+
+fn packed_metaitem(packing: u64) -> NestedMetaItem {
+    if packing == 1 {
+        simple_metaitem("packed")
+    } else {
+        repr_arg_metaitem("packed", packing)
+    }
+}
+
+impl<'c> Translation<'c> {
+    /// Emit the items for the struct or union `decl_id` with the `#[repr]` attributes needed to
+    /// reproduce its C layout. `mk_item` builds the record itself from a builder that already
+    /// carries the record's attributes.
+    pub fn convert_record_layout<F>(
+        &self,
+        decl_id: CRecordId,
+        name: String,
+        span: Span,
+        derives: Vec<&str>,
+        mk_item: F,
+    ) -> ConvertedDecl
+    where
+        F: FnOnce(Builder, String) -> P<Item>,
+    {
+        let manual_alignment = match self.ast_context[decl_id].kind {
+            CDeclKind::Struct {
+                manual_alignment, ..
+            }
+            | CDeclKind::Union {
+                manual_alignment, ..
+            } => manual_alignment,
+            _ => None,
+        };
+        let packing = self.ast_context.record_packing(decl_id);
+
+        let attrs = |reprs: Vec<NestedMetaItem>| {
+            let repr_attr = mk().meta_item(vec!["repr"], MetaItemKind::List(reprs));
+            mk().span(span)
+                .pub_()
+                .call_attr("derive", derives.clone())
+                .meta_item_attr(AttrStyle::Outer, repr_attr)
+        };
+
+        if let (true, Some(packing), Some(alignment)) = (
+            self.ast_context.has_inner_struct_decl(decl_id),
+            packing,
+            manual_alignment,
+        ) {
+            let inner_name = self.record_fields_name(decl_id);
+            let inner = mk_item(
+                attrs(vec![simple_metaitem("C"), packed_metaitem(packing)]),
+                inner_name.clone(),
+            );
+            let outer_field = mk().pub_().enum_field(mk().path_ty(vec![inner_name]));
+            let outer = attrs(vec![simple_metaitem("C"), repr_arg_metaitem("align", alignment)])
+                .tuple_struct_item(name, vec![outer_field]);
+            return ConvertedDecl::Items(vec![outer, inner]);
+        }
+
+        let mut reprs = vec![simple_metaitem("C")];
+        if let Some(packing) = packing {
+            // A manual alignment no larger than the packing doesn't change the layout
+            reprs.push(packed_metaitem(packing));
+        } else if let Some(alignment) = manual_alignment {
+            // https://github.com/rust-lang/rust/issues/33626
+            reprs.push(repr_arg_metaitem("align", alignment));
+        }
+        ConvertedDecl::Item(mk_item(attrs(reprs), name))
+    }
+
+    /// Get the name of the Rust record that declares the fields of the record `record_id`. This is
+    /// the record itself unless it has a packed inner record.
+    pub fn record_fields_name(&self, record_id: CRecordId) -> String {
+        if self.ast_context.has_inner_struct_decl(record_id) {
+            self.type_converter
+                .borrow_mut()
+                .resolve_decl_suffix_name(record_id, INNER_SUFFIX)
+        } else {
+            self.type_converter
+                .borrow()
+                .resolve_decl_name(record_id)
+                .unwrap()
+        }
+    }
+
+    /// Turn a value of the Rust record named by `record_fields_name` into a value of the record
+    /// `record_id`.
+    pub fn wrap_record_fields(&self, record_id: CRecordId, val: P<Expr>) -> P<Expr> {
+        if self.ast_context.has_inner_struct_decl(record_id) {
+            let name = self
+                .type_converter
+                .borrow()
+                .resolve_decl_name(record_id)
+                .unwrap();
+            mk().call_expr(mk().path_expr(vec![name]), vec![val])
+        } else {
+            val
+        }
+    }
+
+    /// Access the field `field_name` of `val`, which is a value of the record `record_id`.
+    pub fn record_field_expr(
+        &self,
+        record_id: CRecordId,
+        val: P<Expr>,
+        field_name: String,
+    ) -> P<Expr> {
+        let val = if self.ast_context.has_inner_struct_decl(record_id) {
+            mk().field_expr(val, "0")
+        } else {
+            val
+        };
+        mk().field_expr(val, field_name)
+    }
+
+    /// Does the C lvalue `expr` name a field of a packed record, or something inside such a
+    /// field? Rust must not create references to these places since they might not be aligned.
+    pub fn is_packed_place(&self, expr: CExprId) -> bool {
+        match self.ast_context[expr].kind {
+            CExprKind::Member(_, base, field, kind, _) => {
+                let is_dot = match kind {
+                    MemberKind::Dot => true,
+                    MemberKind::Arrow => false,
+                };
+                self.ast_context.is_packed_field(field) || (is_dot && self.is_packed_place(base))
+            }
+            CExprKind::Paren(_, expr) => self.is_packed_place(expr),
+            _ => false,
+        }
+    }
+
+    /// Compute a raw pointer to the packed place `expr` (see `is_packed_place`) without creating
+    /// a reference to it. `&s.f` translates to
+    ///
+    /// ```no_run
+    /// (&mut s as *mut S as *mut u8).offset(OFFSET) as *mut F
+    /// ```
+    ///
+    /// where `OFFSET` is the byte offset of `f` in `S`. Borrowing the whole record is fine since
+    /// records are always aligned to their own alignment, packed or not.
+    pub fn convert_packed_place_address(
+        &self,
+        ctx: ExprContext,
+        expr: CExprId,
+        mutbl: Mutability,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (ty, base, field, kind) = match self.ast_context[expr].kind {
+            CExprKind::Paren(_, expr) => return self.convert_packed_place_address(ctx, expr, mutbl),
+            CExprKind::Member(ty, base, field, kind, _) => (ty, base, field, kind),
+            _ => return Err(format_err!("Expected a field of a packed record").into()),
+        };
+
+        let byte_offset = match self.ast_context[field].kind {
+            CDeclKind::Field {
+                bitfield_width: None,
+                platform_bit_offset,
+                ..
+            } => platform_bit_offset / 8,
+            _ => return Err(format_err!("Cannot take the address of a bitfield").into()),
+        };
+
+        let record_ptr = match kind {
+            MemberKind::Arrow => self.convert_expr(ctx.used(), base)?,
+            MemberKind::Dot if self.is_packed_place(base) => {
+                self.convert_packed_place_address(ctx, base, mutbl)?
+            }
+            MemberKind::Dot => {
+                let base_ty = self.ast_context[base]
+                    .kind
+                    .get_type()
+                    .ok_or_else(|| format_err!("bad member base type"))?;
+                let base_ty = self.convert_type(base_ty)?;
+                self.convert_expr(ctx.used(), base)?.map(|base| {
+                    let addr = mk().set_mutbl(mutbl).addr_of_expr(base);
+                    mk().cast_expr(addr, mk().set_mutbl(mutbl).ptr_ty(base_ty))
+                })
+            }
+        };
+
+        let field_ty = self.convert_type(ty.ctype)?;
+        Ok(record_ptr.map(|ptr| {
+            let byte_ty = mk().set_mutbl(mutbl).ptr_ty(mk().path_ty(vec!["u8"]));
+            let byte_ptr = mk().cast_expr(ptr, byte_ty);
+            let offset = mk().lit_expr(mk().int_lit(byte_offset as u128, LitIntType::Unsuffixed));
+            let field_ptr = mk().method_call_expr(byte_ptr, "offset", vec![offset]);
+            mk().cast_expr(field_ptr, mk().set_mutbl(mutbl).ptr_ty(field_ty))
+        }))
+    }
+}
@@ -0,0 +1,510 @@
+//! This module merges the translations of a translation unit for several targets into a single
+//! Rust file.
+//!
+//! Each target is translated separately and printed item by item. Items that come out the same
+//! for every target are emitted once, while the others get a copy per distinct translation,
+//! guarded by a `#[cfg]` attribute that selects the targets it belongs to.
+
+use super::*;
+
+/// The parts of a Clang target triple that translations can differ by
+struct TargetTriple {
+    arch: &'static str,
+    pointer_width: u32,
+    os: Option<&'static str>,
+    env: Option<&'static str>,
+}
+
+/// Parse the Clang target triple `target` into the Rust names of its parts. Operating systems
+/// and environments we don't know are left out.
+fn parse_target(target: &str) -> Result<TargetTriple, TranslationError> {
+    let mut parts = target.split('-');
+    let arch = parts.next().unwrap_or_default();
+    let (arch, pointer_width) = match arch {
+        "x86_64" | "amd64" if target.ends_with("gnux32") => ("x86_64", 32),
+        "x86_64" | "amd64" => ("x86_64", 64),
+        "i386" | "i486" | "i586" | "i686" | "x86" => ("x86", 32),
+        "aarch64" | "arm64" => ("aarch64", 64),
+        _ if arch.starts_with("arm") || arch.starts_with("thumb") => ("arm", 32),
+        "powerpc" | "ppc" => ("powerpc", 32),
+        "powerpc64" | "powerpc64le" | "ppc64" | "ppc64le" => ("powerpc64", 64),
+        "mips" | "mipsel" => ("mips", 32),
+        "mips64" | "mips64el" => ("mips64", 64),
+        "riscv32" => ("riscv32", 32),
+        "riscv64" => ("riscv64", 64),
+        "s390x" => ("s390x", 64),
+        "sparc64" => ("sparc64", 64),
+        "wasm32" => ("wasm32", 32),
+        _ => return Err(format_err!("Unsupported target triple {}", target).into()),
+    };
+
+    let mut os = None;
+    let mut env = None;
+    // The vendor is optional, so look for the parts we know anywhere after the architecture.
+    // Versions, as in `macosx10.14`, are ignored.
+    for part in parts {
+        let starts_with = |prefix: &str| part.starts_with(prefix);
+        if starts_with("linux") {
+            os = os.or(Some("linux"));
+        } else if starts_with("android") {
+            os = Some("android");
+        } else if starts_with("windows") || starts_with("win32") {
+            os = Some("windows");
+        } else if starts_with("mingw") {
+            os = Some("windows");
+            env = Some("gnu");
+        } else if starts_with("darwin") || starts_with("macos") {
+            os = Some("macos");
+        } else if starts_with("ios") {
+            os = Some("ios");
+        } else if starts_with("freebsd") {
+            os = Some("freebsd");
+        } else if starts_with("netbsd") {
+            os = Some("netbsd");
+        } else if starts_with("openbsd") {
+            os = Some("openbsd");
+        } else if starts_with("dragonfly") {
+            os = Some("dragonfly");
+        } else if starts_with("solaris") {
+            os = Some("solaris");
+        } else if starts_with("fuchsia") {
+            os = Some("fuchsia");
+        } else if starts_with("gnu") {
+            env = Some("gnu");
+        } else if starts_with("musl") {
+            env = Some("musl");
+        } else if starts_with("msvc") {
+            env = Some("msvc");
+        }
+    }
+
+    Ok(TargetTriple {
+        arch,
+        pointer_width,
+        os,
+        env,
+    })
+}
+
+/// The `cfg` predicate that selects the Rust target matching the Clang target triple `target`.
+fn target_cfg(target: &str) -> Result<String, TranslationError> {
+    let triple = parse_target(target)?;
+
+    let mut predicates = vec![
+        format!("target_arch = \"{}\"", triple.arch),
+        format!("target_pointer_width = \"{}\"", triple.pointer_width),
+    ];
+    if let Some(os) = triple.os {
+        predicates.push(format!("target_os = \"{}\"", os));
+    }
+    if let Some(env) = triple.env {
+        predicates.push(format!("target_env = \"{}\"", env));
+    }
+    Ok(format!("all({})", predicates.join(", ")))
+}
+
+/// The C preprocessor condition that holds when compiling for the Clang target triple `target`,
+/// using the macros GCC and Clang predefine. It can't tell apart the environments of Unix
+/// targets, like `gnu` and `musl`, or Apple's operating systems.
+fn target_c_condition(target: &str) -> Result<String, TranslationError> {
+    let triple = parse_target(target)?;
+
+    let arch = match triple.arch {
+        "x86_64" => "defined(__x86_64__)",
+        "x86" => "defined(__i386__)",
+        "aarch64" => "defined(__aarch64__)",
+        "arm" => "defined(__arm__)",
+        "powerpc" | "powerpc64" => "defined(__powerpc__)",
+        "mips" | "mips64" => "defined(__mips__)",
+        "riscv32" | "riscv64" => "defined(__riscv)",
+        "s390x" => "defined(__s390x__)",
+        "sparc64" => "defined(__sparc__)",
+        "wasm32" => "defined(__wasm32__)",
+        arch => unreachable!("No C condition for {}", arch),
+    };
+    let mut conditions = vec![
+        arch.to_string(),
+        format!("__SIZEOF_POINTER__ == {}", triple.pointer_width / 8),
+    ];
+    let os = match triple.os {
+        Some("linux") => Some("defined(__linux__) && !defined(__ANDROID__)"),
+        Some("android") => Some("defined(__ANDROID__)"),
+        Some("windows") => Some("defined(_WIN32)"),
+        Some("macos") | Some("ios") => Some("defined(__APPLE__)"),
+        Some("freebsd") => Some("defined(__FreeBSD__)"),
+        Some("netbsd") => Some("defined(__NetBSD__)"),
+        Some("openbsd") => Some("defined(__OpenBSD__)"),
+        Some("dragonfly") => Some("defined(__DragonFly__)"),
+        Some("solaris") => Some("defined(__sun)"),
+        Some("fuchsia") => Some("defined(__Fuchsia__)"),
+        _ => None,
+    };
+    conditions.extend(os.map(String::from));
+    let env = match (triple.os, triple.env) {
+        (Some("windows"), Some("msvc")) => Some("defined(_MSC_VER)"),
+        (Some("windows"), Some("gnu")) => Some("defined(__MINGW32__)"),
+        _ => None,
+    };
+    conditions.extend(env.map(String::from));
+    Ok(conditions.join(" && "))
+}
+
+/// Check that every target in `targets` is supported, and that no two of them would be guarded
+/// by the same `cfg` predicate, which would define their differing items twice.
+pub fn check_targets(targets: &[String]) -> Result<(), TranslationError> {
+    let mut cfgs: IndexMap<String, &str> = IndexMap::new();
+    for target in targets {
+        if let Some(other) = cfgs.insert(target_cfg(target)?, target) {
+            return Err(format_err!(
+                "Targets {} and {} can't be told apart with #[cfg]",
+                other,
+                target
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// The translation of a translation unit for one target, with every top-level item printed
+/// separately and keyed by what it defines.
+pub struct TargetTranslation {
+    cfg: String,
+    c_condition: String,
+    items: Vec<(String, String)>,
+    pragmas: PragmaVec,
+    crates: CrateSet,
+    reports: Vec<DeclReport>,
+    trampolines: Option<String>,
+}
+
+/// Translate `ast_context`, which was exported for `target`, keeping its items apart.
+pub fn translate_target(
+    target: &str,
+    ast_context: TypedAstContext,
+    tcfg: &TranspilerConfig,
+    main_file: PathBuf,
+) -> Result<TargetTranslation, TranslationError> {
+    let cfg = target_cfg(target)?;
+    let c_condition = target_c_condition(target)?;
+
+    let translation = translate_with(ast_context, tcfg, main_file, |t, _, _| {
+        let mut traverser = t.comment_store.into_inner().into_comment_traverser();
+        let mut items = vec![];
+        // Each item is printed with the comments that go inside or before it
+        let mut push_item = |item: P<Item>| {
+            let item = item.map(|i| traverser.traverse_item(i));
+            let comments = traverser.take_comments();
+            let text = to_string(|s| {
+                s.comments().get_or_insert(vec![]).extend(comments);
+                s.print_item(&item)
+            });
+            items.push((item_key(&item), text));
+        };
+
+        for (file_path, ref mut mod_item_store) in t.mod_blocks.borrow_mut().iter_mut() {
+            push_item(make_submodule(
+                mod_item_store,
+                file_path,
+                &t.item_store,
+                &t.mod_names,
+            ));
+        }
+
+        let (global_items, foreign_items, uses) = t.item_store.borrow_mut().drain();
+        for use_item in uses.into_items() {
+            push_item(use_item);
+        }
+        // Give each foreign item an `extern` block of its own, so it can be guarded separately
+        for foreign_item in foreign_items {
+            push_item(mk().abi("C").foreign_items(vec![foreign_item]));
+        }
+        for item in global_items {
+            push_item(item);
+        }
+
+        items
+    });
+    let (items, pragmas, crates, mut reports, trampolines) = translation;
+
+    for report in &mut reports {
+        report.target = Some(target.to_string());
+    }
+
+    Ok(TargetTranslation {
+        cfg,
+        c_condition,
+        items,
+        pragmas,
+        crates,
+        reports,
+        trampolines,
+    })
+}
+
+/// Identifies the definition made by `item`, so the translations of the same C declaration for
+/// different targets can be matched up.
+fn item_key(item: &Item) -> String {
+    match item.node {
+        ItemKind::Impl(_, _, _, _, Some(ref trait_ref), ref ty, _) => format!(
+            "impl {} for {}",
+            path_to_string(&trait_ref.path),
+            ty_to_string(ty)
+        ),
+        ItemKind::Impl(_, _, _, _, None, ref ty, _) => format!("impl {}", ty_to_string(ty)),
+        ItemKind::ForeignMod(ref foreign_mod) => {
+            let names: Vec<String> = foreign_mod
+                .items
+                .iter()
+                .map(|item| item.ident.to_string())
+                .collect();
+            format!("extern {}", names.join(", "))
+        }
+        // Differing imports don't conflict, so key them on their full text
+        ItemKind::Use(..) => item_to_string(item),
+        ref node => format!("{} {}", node.descriptive_variant(), item.ident),
+    }
+}
+
+/// Merge the translations of a translation unit for several targets into the contents of a
+/// single Rust file. The declaration reports of all targets are kept, and the trampolines of
+/// variadic functions are merged into a single C file.
+pub fn merge_target_translations(
+    tcfg: &TranspilerConfig,
+    translations: Vec<TargetTranslation>,
+) -> Result<(String, PragmaVec, CrateSet, Vec<DeclReport>, Option<String>), TranslationError> {
+    let mut pragmas: IndexMap<&'static str, IndexSet<&'static str>> = IndexMap::new();
+    let mut crates = CrateSet::new();
+    let mut reports = vec![];
+    let mut target_items = vec![];
+    let mut target_trampolines = vec![];
+    for translation in translations {
+        for (key, values) in translation.pragmas {
+            pragmas.entry(key).or_default().extend(values);
+        }
+        crates.extend(translation.crates);
+        reports.extend(translation.reports);
+        target_items.push((translation.cfg, translation.items));
+        target_trampolines.push((translation.c_condition, translation.trampolines));
+    }
+    let trampolines = merge_trampolines(target_trampolines)?;
+    let pragmas: PragmaVec = pragmas
+        .into_iter()
+        .map(|(key, values)| (key, values.into_iter().collect()))
+        .collect();
+
+    let mut translation = with_globals(|| to_string(|s| print_header(s, tcfg, &pragmas, &crates)));
+    translation.push_str(&merge_items(target_items));
+
+    Ok((translation, pragmas, crates, reports, trampolines))
+}
+
+/// Merge the keyed items of each target, given along with its `cfg` predicate, into Rust source.
+/// Items are emitted in the order they are first seen, and each distinct translation of an item
+/// is guarded by the `cfg`s of the targets it belongs to, unless all targets share it.
+fn merge_items(target_items: Vec<(String, Vec<(String, String)>)>) -> String {
+    let num_targets = target_items.len();
+
+    // Item key -> item text -> `cfg`s of the targets with that translation
+    let mut items: IndexMap<String, IndexMap<String, IndexSet<String>>> = IndexMap::new();
+    for (cfg, target_items) in target_items {
+        for (key, text) in target_items {
+            items
+                .entry(key)
+                .or_default()
+                .entry(text)
+                .or_default()
+                .insert(cfg.clone());
+        }
+    }
+
+    let mut source = String::new();
+    for (_, variants) in items {
+        for (text, cfgs) in variants {
+            source.push('\n');
+            if cfgs.len() < num_targets {
+                let cfgs: Vec<String> = cfgs.into_iter().collect();
+                let predicate = match cfgs.as_slice() {
+                    [cfg] => cfg.clone(),
+                    _ => format!("any({})", cfgs.join(", ")),
+                };
+                source.push_str(&format!("#[cfg({})]\n", predicate));
+            }
+            source.push_str(&text);
+            source.push('\n');
+        }
+    }
+    source
+}
+
+/// Merge the trampolines of variadic functions of each target, given along with its C
+/// preprocessor condition. The C file is compiled for each target, so when the trampolines
+/// differ between targets, each version is only compiled for the targets it belongs to.
+fn merge_trampolines(
+    target_trampolines: Vec<(String, Option<String>)>,
+) -> Result<Option<String>, TranslationError> {
+    let first = target_trampolines
+        .first()
+        .and_then(|(_, trampolines)| trampolines.clone());
+    if target_trampolines
+        .iter()
+        .all(|(_, trampolines)| *trampolines == first)
+    {
+        return Ok(first);
+    }
+
+    // Trampolines -> conditions of the targets with those trampolines
+    let mut variants: IndexMap<Option<String>, IndexSet<String>> = IndexMap::new();
+    for (condition, trampolines) in target_trampolines {
+        variants.entry(trampolines).or_default().insert(condition);
+    }
+    let mut conditions = IndexSet::new();
+    for condition in variants.values().flatten() {
+        if !conditions.insert(condition) {
+            return Err(format_err!(
+                "Variadic functions differ between targets that C can't tell apart: {}",
+                condition
+            )
+            .into());
+        }
+    }
+
+    let mut source = String::new();
+    for (i, (trampolines, conditions)) in variants.iter().enumerate() {
+        let conditions: Vec<String> = conditions
+            .iter()
+            .map(|condition| format!("({})", condition))
+            .collect();
+        let directive = if i == 0 { "#if" } else { "#elif" };
+        source.push_str(&format!("{} {}\n", directive, conditions.join(" || ")));
+        if let Some(trampolines) = trampolines {
+            source.push_str(trampolines);
+        }
+    }
+    source.push_str("#else\n");
+    source.push_str("#error \"The variadic functions were not translated for this target\"\n");
+    source.push_str("#endif\n");
+    Ok(Some(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_cfgs() {
+        assert_eq!(
+            target_cfg("x86_64-unknown-linux-gnu").unwrap(),
+            "all(target_arch = \"x86_64\", target_pointer_width = \"64\", \
+             target_os = \"linux\", target_env = \"gnu\")"
+        );
+        assert_eq!(
+            target_cfg("x86_64-pc-windows-msvc").unwrap(),
+            "all(target_arch = \"x86_64\", target_pointer_width = \"64\", \
+             target_os = \"windows\", target_env = \"msvc\")"
+        );
+        assert_eq!(
+            target_cfg("x86_64-linux-gnux32").unwrap(),
+            "all(target_arch = \"x86_64\", target_pointer_width = \"32\", \
+             target_os = \"linux\", target_env = \"gnu\")"
+        );
+        assert_eq!(
+            target_cfg("arm64-apple-macosx10.14").unwrap(),
+            "all(target_arch = \"aarch64\", target_pointer_width = \"64\", target_os = \"macos\")"
+        );
+        assert_eq!(
+            target_cfg("armv7-none-eabi").unwrap(),
+            "all(target_arch = \"arm\", target_pointer_width = \"32\")"
+        );
+        assert!(target_cfg("avr-unknown-unknown").is_err());
+    }
+
+    #[test]
+    fn colliding_targets() {
+        let targets = |targets: &[&str]| -> Vec<String> {
+            targets.iter().map(|target| target.to_string()).collect()
+        };
+        assert!(check_targets(&targets(&["x86_64-linux-gnu", "x86_64-pc-windows-msvc"])).is_ok());
+        assert!(
+            check_targets(&targets(&["x86_64-linux-gnu", "x86_64-unknown-linux-gnu"])).is_err()
+        );
+    }
+
+    #[test]
+    fn target_c_conditions() {
+        assert_eq!(
+            target_c_condition("x86_64-pc-windows-msvc").unwrap(),
+            "defined(__x86_64__) && __SIZEOF_POINTER__ == 8 && defined(_WIN32) && \
+             defined(_MSC_VER)"
+        );
+        assert_eq!(
+            target_c_condition("i686-linux-gnu").unwrap(),
+            "defined(__i386__) && __SIZEOF_POINTER__ == 4 && \
+             defined(__linux__) && !defined(__ANDROID__)"
+        );
+    }
+
+    #[test]
+    fn merged_trampolines() {
+        let trampolines = |text: &str| Some(text.to_string());
+        assert_eq!(
+            merge_trampolines(vec![
+                ("a".to_string(), trampolines("int f();\n")),
+                ("b".to_string(), trampolines("int f();\n")),
+            ])
+            .unwrap(),
+            trampolines("int f();\n")
+        );
+        assert_eq!(
+            merge_trampolines(vec![
+                ("a".to_string(), trampolines("long f();\n")),
+                ("b".to_string(), trampolines("int f();\n")),
+                ("c".to_string(), None),
+                ("d".to_string(), trampolines("int f();\n")),
+            ])
+            .unwrap(),
+            trampolines(
+                "#if (a)\nlong f();\n\
+                 #elif (b) || (d)\nint f();\n\
+                 #elif (c)\n\
+                 #else\n#error \"The variadic functions were not translated for this target\"\n\
+                 #endif\n"
+            )
+        );
+        assert!(merge_trampolines(vec![
+            ("a".to_string(), trampolines("long f();\n")),
+            ("a".to_string(), trampolines("int f();\n")),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn merged_items() {
+        let item = |key: &str, text: &str| (key.to_string(), text.to_string());
+        let merged = merge_items(vec![
+            (
+                "a".to_string(),
+                vec![
+                    item("fn f", "fn f() {}"),
+                    item("type T", "type T = i64;"),
+                    item("fn g", "fn g() {}"),
+                ],
+            ),
+            (
+                "b".to_string(),
+                vec![item("fn f", "fn f() {}"), item("type T", "type T = i32;")],
+            ),
+            (
+                "c".to_string(),
+                vec![item("fn f", "fn f() {}"), item("type T", "type T = i32;")],
+            ),
+        ]);
+        assert_eq!(
+            merged,
+            "\nfn f() {}\n\
+             \n#[cfg(a)]\ntype T = i64;\n\
+             \n#[cfg(any(b, c))]\ntype T = i32;\n\
+             \n#[cfg(a)]\nfn g() {}\n"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use super::*;
 use std::collections::HashMap;
+use std::fmt::Write;
 
 #[derive(Copy, Clone, Debug)]
 pub enum VaPart {
@@ -192,6 +193,224 @@ impl<'c> Translation<'c> {
         }
     }
 
+    pub fn is_va_list_runtime(&self) -> bool {
+        self.tcfg.translate_valist && self.tcfg.va_list == VaListMode::Runtime
+    }
+
+    /// Convert a variadic function definition into a Rust function that takes its variable
+    /// arguments as a `c2rust_va_list::VaList`, and a declaration of the C trampoline that
+    /// passes them to it. Callers on both sides go through the trampoline, which is added to
+    /// `va_trampolines`.
+    pub fn convert_va_list_function(
+        &self,
+        ctx: ExprContext,
+        span: Span,
+        is_exported: bool,
+        is_extern: bool,
+        new_name: &str,
+        name: &str,
+        arguments: &[(CDeclId, String, CQualTypeId)],
+        return_type: Option<CQualTypeId>,
+        body: CStmtId,
+        attrs: &IndexSet<c_ast::Attribute>,
+    ) -> Result<ConvertedDecl, TranslationError> {
+        let (trampoline_name, impl_name) = self.va_trampoline_names(name, is_exported);
+
+        let mut decl = match self.convert_function(
+            ctx,
+            span,
+            is_exported,
+            false,
+            false,
+            true,
+            is_extern,
+            new_name,
+            &trampoline_name,
+            arguments,
+            return_type,
+            None,
+            attrs,
+        )? {
+            ConvertedDecl::ForeignItem(decl) => decl,
+            _ => panic!("Expected a foreign item for the trampoline of {}", name),
+        };
+        // The declaration stands in for the definition, so it is just as visible
+        if is_exported {
+            decl.vis.node = VisibilityKind::Public;
+        }
+
+        // The trampoline calls the definition, so it is always exported
+        let defn = match self.convert_function(
+            ctx,
+            span,
+            true,
+            false,
+            false,
+            true,
+            true,
+            &impl_name,
+            &impl_name,
+            arguments,
+            return_type,
+            Some(body),
+            attrs,
+        )? {
+            ConvertedDecl::Item(defn) => defn,
+            _ => panic!("Expected an item for the definition of {}", name),
+        };
+
+        let trampoline =
+            self.va_trampoline(&trampoline_name, &impl_name, arguments, return_type)?;
+        self.va_trampolines.borrow_mut().push(trampoline);
+
+        Ok(ConvertedDecl::Items(vec![
+            defn,
+            mk().abi("C").foreign_items(vec![decl]),
+        ]))
+    }
+
+    /// The names of the C trampoline of the variadic function `name` and of the Rust function
+    /// it calls. Trampolines of functions that are not exported are visible to the whole
+    /// program, so their names are made unique with the name of the translation unit.
+    fn va_trampoline_names(&self, name: &str, is_exported: bool) -> (String, String) {
+        if is_exported {
+            return (name.to_string(), format!("c2rust_va_impl_{}", name));
+        }
+
+        let stem: String = self
+            .main_file
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        (
+            format!("c2rust_va_{}_{}", stem, name),
+            format!("c2rust_va_impl_{}_{}", stem, name),
+        )
+    }
+
+    /// The C definition of `trampoline_name`, which starts the variable arguments of a call and
+    /// passes them to `impl_name` as a `va_list`. Parameters are declared with types that are
+    /// passed the same way as the original ones, so no other declarations are needed.
+    fn va_trampoline(
+        &self,
+        trampoline_name: &str,
+        impl_name: &str,
+        arguments: &[(CDeclId, String, CQualTypeId)],
+        return_type: Option<CQualTypeId>,
+    ) -> Result<String, TranslationError> {
+        let ret = match return_type {
+            Some(ty) => self.c_abi_type_name(ty.ctype)?,
+            None => "void",
+        };
+        let params = arguments
+            .iter()
+            .map(|&(_, _, ty)| self.c_abi_type_name(ty.ctype))
+            .collect::<Result<Vec<_>, _>>()?;
+        let names: Vec<String> = (0..params.len()).map(|i| format!("a{}", i)).collect();
+        let last = names.last().ok_or_else(|| {
+            TranslationError::generic("Variadic function has no parameter to start va_list from")
+        })?;
+
+        let mut defn = String::new();
+        let impl_params: Vec<&str> = params.iter().cloned().chain(Some("va_list")).collect();
+        writeln!(defn, "{} {}({});", ret, impl_name, impl_params.join(", ")).unwrap();
+        let params: Vec<String> = params
+            .iter()
+            .zip(&names)
+            .map(|(ty, name)| format!("{} {}", ty, name))
+            .chain(Some("...".to_string()))
+            .collect();
+        writeln!(
+            defn,
+            "{} {}({}) {{",
+            ret,
+            trampoline_name,
+            params.join(", ")
+        )
+        .unwrap();
+        writeln!(defn, "    va_list ap;").unwrap();
+        if ret != "void" {
+            writeln!(defn, "    {} ret;", ret).unwrap();
+        }
+        writeln!(defn, "    va_start(ap, {});", last).unwrap();
+        let call = format!("{}({}, ap)", impl_name, names.join(", "));
+        if ret == "void" {
+            writeln!(defn, "    {};", call).unwrap();
+        } else {
+            writeln!(defn, "    ret = {};", call).unwrap();
+        }
+        writeln!(defn, "    va_end(ap);").unwrap();
+        if ret != "void" {
+            writeln!(defn, "    return ret;").unwrap();
+        }
+        writeln!(defn, "}}").unwrap();
+        Ok(defn)
+    }
+
+    /// A C type that is passed and returned the same way as `ctype`
+    fn c_abi_type_name(&self, ctype: CTypeId) -> Result<&'static str, TranslationError> {
+        Ok(match self.ast_context.resolve_type(ctype).kind {
+            CTypeKind::Void => "void",
+            CTypeKind::Bool => "_Bool",
+            CTypeKind::Char => "char",
+            CTypeKind::SChar => "signed char",
+            CTypeKind::UChar => "unsigned char",
+            CTypeKind::Short => "short",
+            CTypeKind::UShort => "unsigned short",
+            CTypeKind::Int => "int",
+            CTypeKind::UInt => "unsigned int",
+            CTypeKind::Long => "long",
+            CTypeKind::ULong => "unsigned long",
+            CTypeKind::LongLong => "long long",
+            CTypeKind::ULongLong => "unsigned long long",
+            CTypeKind::Int128 => "__int128",
+            CTypeKind::UInt128 => "unsigned __int128",
+            CTypeKind::Float => "float",
+            CTypeKind::Double => "double",
+            CTypeKind::LongDouble => "long double",
+            CTypeKind::Pointer(_)
+            | CTypeKind::ConstantArray(..)
+            | CTypeKind::IncompleteArray(_)
+            | CTypeKind::VariableArray(..) => "void *",
+            CTypeKind::Enum(enum_id) => match self.ast_context[enum_id].kind {
+                CDeclKind::Enum {
+                    integral_type: Some(ty),
+                    ..
+                } => self.c_abi_type_name(ty.ctype)?,
+                _ => return Err(format_err!("Enum {:?} has no integral type", enum_id).into()),
+            },
+            ref kind => {
+                return Err(format_err!(
+                    "Variadic functions with {:?} parameters or results can't be called through a trampoline",
+                    kind
+                )
+                .into())
+            }
+        })
+    }
+
+    /// The C source of the trampolines of the variadic functions translated with
+    /// `VaListMode::Runtime`, or `None` if there are none.
+    pub fn va_trampoline_source(&self) -> Option<String> {
+        let trampolines = self.va_trampolines.borrow();
+        if trampolines.is_empty() {
+            return None;
+        }
+
+        let mut source = format!(
+            "/* Trampolines for the variadic functions translated from {} */\n\n#include <stdarg.h>\n",
+            self.main_file.file_name().unwrap().to_string_lossy()
+        );
+        for trampoline in trampolines.iter() {
+            source.push('\n');
+            source.push_str(trampoline);
+        }
+        Some(source)
+    }
+
     /// Determine if a variadic function body declares a va_list argument
     /// and contains a va_start and va_end call for that argument list.
     /// If it does, the declaration ID for that variable is registered so that
@@ -0,0 +1,81 @@
+//! A C project on disk for the integration tests to translate
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use c2rust_transpile::TranspilerConfig;
+
+/// C sources and their `compile_commands.json` in a temporary directory, which is removed when
+/// the project is dropped
+pub struct Project {
+    pub dir: PathBuf,
+    sources: Vec<PathBuf>,
+}
+
+impl Project {
+    pub fn new(name: &str) -> Self {
+        let dir = env::temp_dir().join(format!("c2rust-transpile-{}-{}", name, process::id()));
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(&dir).unwrap();
+        // The transpiler canonicalizes the paths of translation units
+        let dir = dir.canonicalize().unwrap();
+        Project {
+            dir,
+            sources: vec![],
+        }
+    }
+
+    /// Write `contents` to `name`. Files ending in `.c` are compiled by the project.
+    pub fn file(&mut self, name: &str, contents: &str) -> &mut Self {
+        let path = self.dir.join(name);
+        fs::write(&path, contents).unwrap();
+        if name.ends_with(".c") {
+            self.sources.push(path);
+        }
+        self
+    }
+
+    /// Write the compile database and return its path
+    pub fn compile_commands(&self) -> PathBuf {
+        let entries: Vec<_> = self
+            .sources
+            .iter()
+            .map(|path| {
+                let path = path.to_str().unwrap();
+                serde_json::json!({
+                    "directory": self.dir,
+                    "arguments": ["cc", "-c", path],
+                    "file": path,
+                })
+            })
+            .collect();
+        let cc_db = self.dir.join("compile_commands.json");
+        fs::write(&cc_db, serde_json::to_string_pretty(&entries).unwrap()).unwrap();
+        cc_db
+    }
+
+    pub fn transpile(&self, tcfg: TranspilerConfig) {
+        c2rust_transpile::transpile(tcfg, &self.compile_commands(), &[]);
+    }
+
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub fn read(&self, name: impl AsRef<Path>) -> String {
+        let path = self.dir.join(name);
+        fs::read_to_string(&path).expect(&format!("Could not read {}", path.display()))
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
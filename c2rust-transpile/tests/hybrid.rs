@@ -0,0 +1,58 @@
+//! A hybrid build compiles from C the translation units that are filtered out, kept in C, or
+//! whose definitions could not be translated, and builds the rest from Rust
+
+mod common;
+
+use c2rust_transpile::TranspilerConfig;
+use regex::Regex;
+
+use common::Project;
+
+#[test]
+fn hybrid_build_files() {
+    let mut project = Project::new("hybrid");
+    project
+        .file("util.h", "struct record { __fp16 value; };\n")
+        // Only the header's struct can't be translated, so the unit stays in Rust
+        .file(
+            "a.c",
+            "#include \"util.h\"\nint a_has_record(struct record *r) { return r != 0; }\n",
+        )
+        .file(
+            "b.c",
+            "void *b_frame(void) { return __builtin_frame_address(0); }\n",
+        )
+        .file("c.c", "int c_kept(void) { return 1; }\n")
+        .file("d.c", "int d_filtered(void) { return 2; }\n");
+
+    let out = project.path("out");
+    project.transpile(TranspilerConfig {
+        emit_modules: true,
+        emit_build_files: true,
+        hybrid_build: true,
+        keep_c: Some(Regex::new(r"c\.c$").unwrap()),
+        filter: Some(Regex::new(r"[abc]\.c$").unwrap()),
+        output_dir: Some(out.clone()),
+        ..TranspilerConfig::default()
+    });
+
+    let build_rs = project.read(out.join("build.rs"));
+    let compiles = |name: &str| {
+        let file = project.path(name).display().to_string();
+        build_rs.contains(&format!(".file({:?})", file))
+    };
+    assert!(!compiles("a.c"), "{}", build_rs);
+    assert!(compiles("b.c"), "{}", build_rs);
+    assert!(compiles("c.c"), "{}", build_rs);
+    assert!(compiles("d.c"), "{}", build_rs);
+
+    let lib_rs = project.read(out.join("lib.rs"));
+    assert!(lib_rs.contains("pub mod a;"), "{}", lib_rs);
+    for name in &["b", "c", "d"] {
+        assert!(
+            !lib_rs.contains(&format!("pub mod {};", name)),
+            "{}",
+            lib_rs
+        );
+    }
+}
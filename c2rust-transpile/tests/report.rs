@@ -0,0 +1,83 @@
+//! `--report` lists what became of each declaration, why, and what its translation needs
+
+mod common;
+
+use std::path::PathBuf;
+
+use c2rust_transpile::TranspilerConfig;
+use serde_json::Value;
+
+use common::Project;
+
+#[test]
+fn report_decls() {
+    let mut project = Project::new("report");
+    project.file(
+        "decls.c",
+        "extern _Thread_local int counter;\n\
+         long double scaled(long double x) { return x * 2.5L + counter; }\n\
+         void *frame(void) { return __builtin_frame_address(0); }\n\
+         void halves(__fp16 *h) {}\n",
+    );
+
+    let report_path = project.path("report.json");
+    project.transpile(TranspilerConfig {
+        report: Some(report_path.clone()),
+        ..TranspilerConfig::default()
+    });
+
+    let report: Value = serde_json::from_str(&project.read(&report_path)).unwrap();
+    let files = report["files"].as_array().unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(
+        PathBuf::from(files[0]["input"].as_str().unwrap()),
+        project.path("decls.c")
+    );
+    let decls = files[0]["decls"].as_array().unwrap();
+    let decl = |name: &str| {
+        decls
+            .iter()
+            .find(|decl| decl["name"] == name)
+            .expect(&format!("{} is not in the report", name))
+    };
+    let errors = |decl: &Value| -> String {
+        let errors = decl["errors"].as_array().unwrap();
+        errors.iter().map(|e| e.as_str().unwrap()).collect()
+    };
+
+    let counter = decl("counter");
+    assert_eq!(counter["kind"], "variable");
+    assert_eq!(counter["status"], "translated");
+    assert_eq!(counter["main_file_definition"], false);
+    assert_eq!(counter["features"], serde_json::json!(["thread_local"]));
+
+    let scaled = decl("scaled");
+    assert_eq!(scaled["kind"], "function");
+    assert_eq!(scaled["status"], "translated");
+    assert_eq!(scaled["main_file_definition"], true);
+    assert_eq!(scaled["errors"], serde_json::json!([]));
+    assert!(
+        scaled["crates"]
+            .as_array()
+            .unwrap()
+            .contains(&"f128".into()),
+        "{}",
+        scaled
+    );
+    assert_eq!(scaled["loc"]["line"], 2);
+
+    let frame = decl("frame");
+    assert_eq!(frame["status"], "stubbed");
+    assert!(
+        errors(frame).contains("Unimplemented builtin __builtin_frame_address"),
+        "{}",
+        frame
+    );
+
+    let halves = decl("halves");
+    assert_eq!(halves["status"], "skipped");
+    assert!(errors(halves).contains("Unsupported type"), "{}", halves);
+
+    assert_eq!(report["summary"]["stubbed"], 1);
+    assert_eq!(report["summary"]["skipped"], 1);
+}
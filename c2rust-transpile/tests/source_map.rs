@@ -0,0 +1,67 @@
+//! `--source-map` maps the translated code back to the C it came from, leaving no markers behind
+
+mod common;
+
+use c2rust_transpile::TranspilerConfig;
+use serde_json::Value;
+
+use common::Project;
+
+#[test]
+fn source_map() {
+    let mut project = Project::new("source-map");
+    project.file(
+        "clamp.c",
+        "int clamp(int x) {\n\
+         \x20   if (x > 10) x = 10; else x = x + 1;\n\
+         \x20   for (int i = 0; i < 3; i++) { x -= i; }\n\
+         \x20   return x * 2;\n\
+         }\n",
+    );
+
+    project.transpile(TranspilerConfig {
+        source_map: true,
+        ..TranspilerConfig::default()
+    });
+
+    let rs = project.read("clamp.rs");
+    assert!(!rs.contains("c2rust-source-map"), "{}", rs);
+
+    let source_map: Value = serde_json::from_str(&project.read("clamp.source_map.json")).unwrap();
+    assert_eq!(
+        source_map["file"].as_str(),
+        Some(project.path("clamp.rs").to_str().unwrap())
+    );
+    let mappings = source_map["mappings"].as_array().unwrap();
+    assert!(!mappings.is_empty());
+
+    let line_of = |offset: usize| rs[..offset].matches('\n').count() + 1;
+    let mut code_of_line = vec![];
+    for mapping in mappings {
+        let start = mapping["start"].as_u64().unwrap() as usize;
+        let end = mapping["end"].as_u64().unwrap() as usize;
+        // The line numbers are those of the mapped code
+        assert_eq!(mapping["line"], line_of(start) as u64, "{}", mapping);
+        assert_eq!(mapping["end_line"], line_of(end) as u64, "{}", mapping);
+        assert!(!rs[start..end].trim().is_empty(), "{}", mapping);
+        assert!(
+            mapping["source"]["file"]
+                .as_str()
+                .unwrap()
+                .ends_with("clamp.c"),
+            "{}",
+            mapping
+        );
+        let c_line = mapping["source"]["line"].as_u64().unwrap();
+        code_of_line.push((c_line, &rs[start..end]));
+    }
+
+    let mapped = |c_line: u64, code: &str| {
+        code_of_line
+            .iter()
+            .any(|&(line, text)| line == c_line && text.contains(code))
+    };
+    assert!(mapped(1, "fn clamp("), "{:?}", code_of_line);
+    assert!(mapped(2, "x = 10"), "{:?}", code_of_line);
+    assert!(mapped(4, "return x * 2"), "{:?}", code_of_line);
+}
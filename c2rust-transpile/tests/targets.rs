@@ -0,0 +1,43 @@
+//! Translating for several targets keeps the comments of each target's items
+
+mod common;
+
+use c2rust_transpile::TranspilerConfig;
+
+use common::Project;
+
+#[test]
+fn target_comments() {
+    let mut project = Project::new("targets");
+    project.file(
+        "width.c",
+        "/// The width of pointers in bits\n\
+         unsigned width(void) {\n\
+         \x20   // Known at compile time\n\
+         #if __SIZEOF_POINTER__ == 8\n\
+         \x20   return 64;\n\
+         #else\n\
+         \x20   return 32;\n\
+         #endif\n\
+         }\n",
+    );
+
+    project.transpile(TranspilerConfig {
+        targets: vec![
+            "x86_64-unknown-linux-gnu".to_string(),
+            "i686-unknown-linux-gnu".to_string(),
+        ],
+        ..TranspilerConfig::default()
+    });
+
+    let rs = project.read("width.rs");
+    // The function differs between the targets, so each variant has the comments
+    assert_eq!(rs.matches("#[cfg(").count(), 2, "{}", rs);
+    assert_eq!(
+        rs.matches("/// The width of pointers in bits").count(),
+        2,
+        "{}",
+        rs
+    );
+    assert_eq!(rs.matches("// Known at compile time").count(), 2, "{}", rs);
+}
@@ -0,0 +1,9 @@
+extern crate cc;
+
+fn main() {
+    // `va_arg` and `va_copy` can only be written in C on stable Rust
+    cc::Build::new()
+        .file("src/va_list.c")
+        .compile("c2rust_va_list");
+    println!("cargo:rerun-if-changed=src/va_list.c");
+}
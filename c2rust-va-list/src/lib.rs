@@ -0,0 +1,109 @@
+//! Runtime support for variadic functions translated by C2Rust without the unstable
+//! `c_variadic` feature.
+//!
+//! A variadic C function is translated into a Rust function that takes its variable arguments
+//! as a [`VaList`], along with a C trampoline that starts the `va_list` and calls it. Since a
+//! [`VaList`] is passed the same way as a C `va_list`, it can also be passed to and received
+//! from C functions such as `vprintf`.
+
+#![no_std]
+
+use core::ffi::c_void;
+use core::ptr;
+
+/// A C `va_list`, as it is passed to a function.
+///
+/// A `VaList` is a handle: copying it with `Clone` or `Copy` yields another handle to the same
+/// arguments, just like passing a `va_list` to a function in C. Use [`VaList::copy`] to get
+/// arguments that can be read independently.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug)]
+pub struct VaList(*mut c_void);
+
+extern "C" {
+    fn c2rust_va_arg_int(slot: *mut *mut c_void) -> i32;
+    fn c2rust_va_arg_uint(slot: *mut *mut c_void) -> u32;
+    fn c2rust_va_arg_longlong(slot: *mut *mut c_void) -> i64;
+    fn c2rust_va_arg_ulonglong(slot: *mut *mut c_void) -> u64;
+    fn c2rust_va_arg_intptr(slot: *mut *mut c_void) -> isize;
+    fn c2rust_va_arg_uintptr(slot: *mut *mut c_void) -> usize;
+    fn c2rust_va_arg_double(slot: *mut *mut c_void) -> f64;
+    fn c2rust_va_arg_ptr(slot: *mut *mut c_void) -> *mut c_void;
+    fn c2rust_va_copy(dst: *mut *mut c_void, src: *mut *mut c_void);
+    fn c2rust_va_end(slot: *mut *mut c_void);
+}
+
+impl VaList {
+    /// Read the next argument as a `T`, like `va_arg(ap, T)`.
+    ///
+    /// # Safety
+    ///
+    /// There must be a next argument, and it must have been passed as a `T`.
+    pub unsafe fn arg<T: VaArg>(&mut self) -> T {
+        T::va_arg(&mut self.0)
+    }
+
+    /// Copy the arguments that are left, like `va_copy`. The copy must be released with
+    /// [`VaList::end`].
+    ///
+    /// # Safety
+    ///
+    /// `self` must not have been released with [`VaList::end`].
+    pub unsafe fn copy(&self) -> VaList {
+        let mut src = self.0;
+        let mut dst = VaList(ptr::null_mut());
+        c2rust_va_copy(&mut dst.0, &mut src);
+        dst
+    }
+
+    /// Release a copy made by [`VaList::copy`], like `va_end`.
+    ///
+    /// # Safety
+    ///
+    /// `self` must come from [`VaList::copy`] and must not be used afterwards. Variable
+    /// arguments received from C are released by the caller.
+    pub unsafe fn end(&mut self) {
+        c2rust_va_end(&mut self.0)
+    }
+}
+
+/// Types that can be read from a [`VaList`]. These are the types that arguments are promoted
+/// to, so `f32` and integers narrower than `i32` are not included.
+pub unsafe trait VaArg: Sized {
+    #[doc(hidden)]
+    unsafe fn va_arg(slot: *mut *mut c_void) -> Self;
+}
+
+macro_rules! impl_va_arg {
+    ($($ty:ty => $fn:ident),*) => {
+        $(
+            unsafe impl VaArg for $ty {
+                unsafe fn va_arg(slot: *mut *mut c_void) -> $ty {
+                    $fn(slot)
+                }
+            }
+        )*
+    };
+}
+
+impl_va_arg! {
+    i32 => c2rust_va_arg_int,
+    u32 => c2rust_va_arg_uint,
+    i64 => c2rust_va_arg_longlong,
+    u64 => c2rust_va_arg_ulonglong,
+    isize => c2rust_va_arg_intptr,
+    usize => c2rust_va_arg_uintptr,
+    f64 => c2rust_va_arg_double
+}
+
+unsafe impl<T> VaArg for *mut T {
+    unsafe fn va_arg(slot: *mut *mut c_void) -> *mut T {
+        c2rust_va_arg_ptr(slot) as *mut T
+    }
+}
+
+unsafe impl<T> VaArg for *const T {
+    unsafe fn va_arg(slot: *mut *mut c_void) -> *const T {
+        c2rust_va_arg_ptr(slot) as *const T
+    }
+}
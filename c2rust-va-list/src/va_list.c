@@ -0,0 +1,48 @@
+/* Accessors for the `va_list`s wrapped by `VaList`.
+ *
+ * A `VaList` is a single pointer, since that is how a `va_list` argument is passed on every
+ * supported platform. Where `va_list` is itself a pointer (e.g. on x86), the `VaList` holds the
+ * `va_list`. Where it is a larger structure (e.g. on x86_64), the `VaList` points to it. Each
+ * function takes the address of that pointer, so `va_arg` advances the `VaList` in both cases. */
+
+#include <stdarg.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define VA_LIST_IS_POINTER (sizeof(va_list) <= sizeof(void *))
+
+/* The `va_list` of the `VaList` at `slot` */
+#define VA_PTR(slot) (VA_LIST_IS_POINTER ? (va_list *)(slot) : (va_list *)*(slot))
+
+#define VA_ARG(name, type)                           \
+    type c2rust_va_arg_##name(void **slot) {         \
+        return va_arg(*VA_PTR(slot), type);          \
+    }
+
+VA_ARG(int, int)
+VA_ARG(uint, unsigned int)
+VA_ARG(longlong, long long)
+VA_ARG(ulonglong, unsigned long long)
+VA_ARG(intptr, intptr_t)
+VA_ARG(uintptr, uintptr_t)
+VA_ARG(double, double)
+VA_ARG(ptr, void *)
+
+void c2rust_va_copy(void **dst, void **src) {
+    if (VA_LIST_IS_POINTER) {
+        va_copy(*VA_PTR(dst), *VA_PTR(src));
+    } else {
+        /* The copy outlives this function, so it can't live on the stack */
+        va_list *copy = malloc(sizeof(va_list));
+        if (!copy)
+            abort();
+        va_copy(*copy, *VA_PTR(src));
+        *dst = copy;
+    }
+}
+
+void c2rust_va_end(void **slot) {
+    va_end(*VA_PTR(slot));
+    if (!VA_LIST_IS_POINTER)
+        free(*slot);
+}
@@ -0,0 +1,810 @@
+//! Records the compile and link commands of a build, so projects that aren't built with cmake
+//! can be translated without installing `bear` or `intercept-build`.
+//!
+//! The build runs with a directory of wrappers first on `PATH`: each wrapper is a link to this
+//! executable named after the compiler or archiver it stands in for, including the versioned and
+//! cross-compiling variants found on `PATH`, like `clang-9` or `x86_64-linux-gnu-gcc`. When
+//! invoked under such a name, this program logs its arguments and executes the real tool from the
+//! rest of `PATH`.
+//! Once the build has finished, the log is turned into `compile_commands.json` and
+//! `link_commands.json`, which lists the translation units that went into each executable and
+//! library the build produced.
+
+#[macro_use]
+extern crate clap;
+#[macro_use]
+extern crate serde_derive;
+
+use clap::App;
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::symlink;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+/// C compilers that are wrapped during the build
+const COMPILERS: &[&str] = &["cc", "c89", "c99", "gcc", "clang"];
+
+/// Archivers that are wrapped during the build
+const ARCHIVERS: &[&str] = &["ar"];
+
+/// Path of the log of wrapped invocations, which holds one JSON object per line
+const LOG_VAR: &str = "C2RUST_INTERCEPT_LOG";
+
+/// Directory of the wrappers, which is skipped when looking for the real tools
+const WRAPPER_DIR_VAR: &str = "C2RUST_INTERCEPT_DIR";
+
+/// Compiler options whose value is the next argument
+const OPTIONS_WITH_VALUE: &[&str] = &[
+    "-D",
+    "-U",
+    "-I",
+    "-x",
+    "-include",
+    "-imacros",
+    "-isystem",
+    "-iquote",
+    "-idirafter",
+    "-isysroot",
+    "-MF",
+    "-MT",
+    "-MQ",
+    "-Xlinker",
+    "-Xassembler",
+    "-Xpreprocessor",
+    "-T",
+    "-u",
+    "-z",
+    "-e",
+    "-arch",
+    "-target",
+];
+
+/// What a wrapped tool does
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ToolKind {
+    Compiler,
+    Archiver,
+}
+
+/// The kind of tool `name` refers to, if it is a compiler or archiver that is wrapped: one of
+/// `COMPILERS` or `ARCHIVERS`, optionally with a prefix like `x86_64-linux-gnu-` or `llvm-` and a
+/// version suffix like `-9` or `-4.8`
+fn tool_kind(name: &str) -> Option<ToolKind> {
+    let is_version = |s: &str| {
+        s.starts_with(|c: char| c.is_ascii_digit())
+            && s.chars().all(|c| c.is_ascii_digit() || c == '.')
+    };
+    let name = match name.rfind('-') {
+        Some(i) if is_version(&name[i + 1..]) => &name[..i],
+        _ => name,
+    };
+    let base = name.rsplit('-').next().unwrap();
+    if COMPILERS.contains(&base) {
+        Some(ToolKind::Compiler)
+    } else if ARCHIVERS.contains(&base) {
+        Some(ToolKind::Archiver)
+    } else {
+        None
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Invocation {
+    directory: PathBuf,
+    tool: String,
+    arguments: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct CompileCommand {
+    directory: PathBuf,
+    arguments: Vec<String>,
+    file: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum LinkKind {
+    Executable,
+    SharedLibrary,
+    StaticLibrary,
+}
+
+#[derive(Serialize, Clone)]
+struct LinkCommand {
+    directory: PathBuf,
+    arguments: Vec<String>,
+    output: PathBuf,
+    kind: LinkKind,
+    /// Translation units linked into the output, directly or through static libraries
+    files: Vec<PathBuf>,
+    /// Shared libraries built by the project that the output links against
+    libraries: Vec<PathBuf>,
+}
+
+fn main() {
+    let tool = env::args_os().next().map(PathBuf::from).and_then(|path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map(String::from)
+    });
+    if let (Some(tool), Some(log)) = (tool, env::var_os(LOG_VAR)) {
+        if tool_kind(&tool).is_some() {
+            process::exit(wrap(&tool, Path::new(&log)));
+        }
+    }
+
+    let yaml = load_yaml!("../intercept.yaml");
+    let matches = App::from_yaml(yaml).get_matches();
+
+    let build_cmd: Vec<&str> = matches.values_of("BUILD_COMMAND").unwrap().collect();
+    let output_dir = Path::new(matches.value_of("output-dir").unwrap_or("."));
+    match intercept(&build_cmd, output_dir) {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("c2rust-intercept: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Run the build with the wrappers on `PATH` and write the databases from what they recorded.
+/// Returns the exit code of the build.
+fn intercept(build_cmd: &[&str], output_dir: &Path) -> io::Result<i32> {
+    let wrapper_dir = env::temp_dir().join(format!("c2rust-intercept-{}", process::id()));
+    fs::create_dir_all(&wrapper_dir)?;
+    let result = run_build(build_cmd, &wrapper_dir);
+    fs::remove_dir_all(&wrapper_dir)?;
+    let (code, invocations) = result?;
+
+    let (compile_cmds, link_cmds) = process_invocations(&invocations);
+    if compile_cmds.is_empty() {
+        eprintln!(
+            "c2rust-intercept: warning: no compile commands were recorded. Compilers are only \
+             intercepted when the build looks them up on PATH, not when it runs them by path."
+        );
+    }
+    fs::create_dir_all(output_dir)?;
+    let compile_cmds_path = output_dir.join("compile_commands.json");
+    serde_json::to_writer_pretty(File::create(&compile_cmds_path)?, &compile_cmds)?;
+    let link_cmds_path = output_dir.join("link_commands.json");
+    serde_json::to_writer_pretty(File::create(&link_cmds_path)?, &link_cmds)?;
+    eprintln!(
+        "Recorded {} compile commands in {} and {} link commands in {}",
+        compile_cmds.len(),
+        compile_cmds_path.display(),
+        link_cmds.len(),
+        link_cmds_path.display(),
+    );
+
+    Ok(code)
+}
+
+fn run_build(build_cmd: &[&str], wrapper_dir: &Path) -> io::Result<(i32, Vec<Invocation>)> {
+    let old_path: Vec<PathBuf> = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
+
+    // Wrap the tools of the usual names, and their variants that are installed
+    let mut tools: Vec<String> = COMPILERS
+        .iter()
+        .chain(ARCHIVERS)
+        .map(|&tool| tool.to_owned())
+        .collect();
+    for dir in &old_path {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(Result::ok) {
+            if let Ok(name) = entry.file_name().into_string() {
+                if tool_kind(&name).is_some() && !tools.contains(&name) {
+                    tools.push(name);
+                }
+            }
+        }
+    }
+    let exe = env::current_exe()?;
+    for tool in &tools {
+        symlink(&exe, wrapper_dir.join(tool))?;
+    }
+    let log = wrapper_dir.join("invocations.jsonl");
+    File::create(&log)?;
+
+    let path = Some(wrapper_dir.to_owned()).into_iter().chain(old_path);
+    let path = env::join_paths(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let (program, args) = build_cmd.split_first().unwrap();
+    let status = Command::new(program)
+        .args(args)
+        .env("PATH", path)
+        .env(LOG_VAR, &log)
+        .env(WRAPPER_DIR_VAR, wrapper_dir)
+        .status()?;
+
+    let mut invocations = vec![];
+    for line in BufReader::new(File::open(&log)?).lines() {
+        invocations.push(serde_json::from_str(&line?)?);
+    }
+    Ok((status.code().unwrap_or(1), invocations))
+}
+
+/// Log an invocation of `tool` and replace this process with the real tool
+fn wrap(tool: &str, log: &Path) -> i32 {
+    let args: Vec<OsString> = env::args_os().skip(1).collect();
+    if let Err(e) = record(tool, &args, log) {
+        eprintln!("c2rust-intercept: could not record {} command: {}", tool, e);
+    }
+
+    let wrapper_dir = env::var_os(WRAPPER_DIR_VAR).map(PathBuf::from);
+    let path: Vec<PathBuf> = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
+    let path: Vec<PathBuf> = path
+        .into_iter()
+        .filter(|dir| Some(dir) != wrapper_dir.as_ref())
+        .collect();
+    let real_tool = match path.iter().map(|dir| dir.join(tool)).find(|p| p.is_file()) {
+        Some(real_tool) => real_tool,
+        None => {
+            eprintln!("c2rust-intercept: could not find {} on PATH", tool);
+            return 127;
+        }
+    };
+
+    // Tools started by the real tool are not part of the build proper, so they run unwrapped
+    let mut cmd = Command::new(&real_tool);
+    cmd.args(&args);
+    if let Ok(path) = env::join_paths(path) {
+        cmd.env("PATH", path);
+    }
+    let e = cmd.exec();
+    eprintln!(
+        "c2rust-intercept: could not run {}: {}",
+        real_tool.display(),
+        e
+    );
+    126
+}
+
+fn record(tool: &str, args: &[OsString], log: &Path) -> io::Result<()> {
+    let invocation = Invocation {
+        directory: env::current_dir()?,
+        tool: tool.to_owned(),
+        arguments: args
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect(),
+    };
+    let mut line = serde_json::to_string(&invocation)?;
+    line.push('\n');
+    // Appends of a single write don't interleave with those of parallel jobs
+    OpenOptions::new()
+        .append(true)
+        .open(log)?
+        .write_all(line.as_bytes())
+}
+
+/// The absolute path of `path`, relative to `dir`, with symbolic links resolved if it exists
+fn absolute(dir: &Path, path: &str) -> PathBuf {
+    let path = dir.join(path);
+    path.canonicalize().unwrap_or(path)
+}
+
+fn is_c_source(arg: &str) -> bool {
+    arg.ends_with(".c")
+}
+
+/// A compiler invocation, split up by what its arguments do
+#[derive(Default)]
+struct CompilerArgs<'a> {
+    /// Options other than the output file
+    flags: Vec<&'a str>,
+    sources: Vec<&'a str>,
+    /// Objects and libraries given by path
+    inputs: Vec<&'a str>,
+    /// Libraries given with `-l`
+    libs: Vec<&'a str>,
+    lib_dirs: Vec<&'a str>,
+    output: Option<&'a str>,
+    /// Compiles without linking, with `-c`
+    compile_only: bool,
+    /// Writes no object file, with `-E`, `-S`, `-M` or `-MM`
+    no_object: bool,
+    shared: bool,
+}
+
+impl<'a> CompilerArgs<'a> {
+    fn parse(args: &'a [String]) -> Self {
+        let mut parsed = CompilerArgs::default();
+        let mut args = args.iter().map(String::as_str);
+        while let Some(arg) = args.next() {
+            match arg {
+                "-o" => {
+                    parsed.output = args.next();
+                    continue;
+                }
+                "-c" => parsed.compile_only = true,
+                "-E" | "-S" | "-M" | "-MM" => parsed.no_object = true,
+                "-shared" => parsed.shared = true,
+                // Libraries are left out of the flags, which are also used to compile
+                "-l" => {
+                    parsed.libs.extend(args.next());
+                    continue;
+                }
+                "-L" => {
+                    parsed.lib_dirs.extend(args.next());
+                    continue;
+                }
+                _ if arg.starts_with("-o") => {
+                    parsed.output = Some(&arg[2..]);
+                    continue;
+                }
+                _ if arg.starts_with("-l") => {
+                    parsed.libs.push(&arg[2..]);
+                    continue;
+                }
+                _ if arg.starts_with("-L") => {
+                    parsed.lib_dirs.push(&arg[2..]);
+                    continue;
+                }
+                _ if OPTIONS_WITH_VALUE.contains(&arg) => {
+                    parsed.flags.push(arg);
+                    parsed.flags.extend(args.next());
+                    continue;
+                }
+                _ if arg.starts_with('-') => {}
+                _ if is_c_source(arg) => {
+                    parsed.sources.push(arg);
+                    continue;
+                }
+                _ => {
+                    parsed.inputs.push(arg);
+                    continue;
+                }
+            }
+            parsed.flags.push(arg);
+        }
+        parsed
+    }
+}
+
+/// Outputs of the build so far, used to trace the inputs of a link back to the translation units
+#[derive(Default)]
+struct Outputs {
+    /// Source file each object file was compiled from
+    objects: HashMap<PathBuf, PathBuf>,
+    link_cmds: Vec<LinkCommand>,
+    /// Index into `link_cmds` of the last command writing each output
+    linked: HashMap<PathBuf, usize>,
+}
+
+impl Outputs {
+    fn add_link_cmd(&mut self, cmd: LinkCommand) {
+        match self.linked.get(&cmd.output) {
+            Some(&idx) => self.link_cmds[idx] = cmd,
+            None => {
+                self.linked.insert(cmd.output.clone(), self.link_cmds.len());
+                self.link_cmds.push(cmd);
+            }
+        }
+    }
+
+    /// Add the object or library at `path` to the inputs of `cmd`. Returns false if the build
+    /// did not produce it.
+    fn add_input(&self, cmd: &mut LinkCommand, path: &Path) -> bool {
+        if let Some(source) = self.objects.get(path) {
+            push_unique(&mut cmd.files, source);
+            return true;
+        }
+        let lib = match self.linked.get(path) {
+            Some(&idx) => &self.link_cmds[idx],
+            None => return false,
+        };
+        match lib.kind {
+            LinkKind::StaticLibrary => {
+                for file in &lib.files {
+                    push_unique(&mut cmd.files, file);
+                }
+                for library in &lib.libraries {
+                    push_unique(&mut cmd.libraries, library);
+                }
+            }
+            LinkKind::SharedLibrary => push_unique(&mut cmd.libraries, &lib.output),
+            LinkKind::Executable => return false,
+        }
+        true
+    }
+
+    fn compile(&mut self, compile_cmds: &mut Vec<CompileCommand>, inv: &Invocation) {
+        let args = CompilerArgs::parse(&inv.arguments);
+        if args.no_object || (args.sources.is_empty() && args.inputs.is_empty()) {
+            return;
+        }
+
+        let single_output = args.compile_only && args.sources.len() == 1;
+        for source in &args.sources {
+            let mut arguments = vec![inv.tool.clone()];
+            arguments.extend(args.flags.iter().map(|&arg| arg.to_owned()));
+            if !args.compile_only {
+                arguments.push("-c".to_owned());
+            }
+            arguments.push(source.to_string());
+
+            let file = absolute(&inv.directory, source);
+            let output = match args.output {
+                Some(output) if single_output => Some(output.to_owned()),
+                _ if args.compile_only => {
+                    let stem = Path::new(source).file_stem().unwrap().to_string_lossy();
+                    Some(format!("{}.o", stem))
+                }
+                _ => None,
+            };
+            if let Some(output) = &output {
+                arguments.push("-o".to_owned());
+                arguments.push(output.clone());
+                self.objects
+                    .insert(absolute(&inv.directory, output), file.clone());
+            }
+
+            let cmd = CompileCommand {
+                directory: inv.directory.clone(),
+                arguments,
+                file,
+                output: output.map(PathBuf::from),
+            };
+            // A file compiled again, e.g. with `-fPIC` for a shared library, keeps the entry of
+            // its first compilation, updated with the last command
+            let existing = compile_cmds
+                .iter_mut()
+                .find(|c| c.directory == cmd.directory && c.file == cmd.file);
+            match existing {
+                Some(existing) => *existing = cmd,
+                None => compile_cmds.push(cmd),
+            }
+        }
+
+        if args.compile_only {
+            return;
+        }
+
+        let mut cmd = LinkCommand {
+            directory: inv.directory.clone(),
+            arguments: Some(inv.tool.clone())
+                .into_iter()
+                .chain(inv.arguments.iter().cloned())
+                .collect(),
+            output: absolute(&inv.directory, args.output.unwrap_or("a.out")),
+            kind: if args.shared {
+                LinkKind::SharedLibrary
+            } else {
+                LinkKind::Executable
+            },
+            files: vec![],
+            libraries: vec![],
+        };
+        for source in &args.sources {
+            push_unique(&mut cmd.files, &absolute(&inv.directory, source));
+        }
+        for input in &args.inputs {
+            self.add_input(&mut cmd, &absolute(&inv.directory, input));
+        }
+        for lib in &args.libs {
+            // The linker prefers shared libraries to static ones in each directory it searches
+            for dir in &args.lib_dirs {
+                let dir = absolute(&inv.directory, dir);
+                if self.add_input(&mut cmd, &dir.join(format!("lib{}.so", lib)))
+                    || self.add_input(&mut cmd, &dir.join(format!("lib{}.a", lib)))
+                {
+                    break;
+                }
+            }
+        }
+        self.add_link_cmd(cmd);
+    }
+
+    fn archive(&mut self, inv: &Invocation) {
+        let (operation, args) = match inv.arguments.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+        // Only operations that add members are of interest, e.g. `ar rcs libfoo.a foo.o`
+        if !operation
+            .trim_start_matches('-')
+            .contains(|c| c == 'r' || c == 'q')
+        {
+            return;
+        }
+        let (archive, members) = match args.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+
+        let output = absolute(&inv.directory, archive);
+        let mut cmd = match self.linked.get(&output) {
+            // Archives may be built up by several commands
+            Some(&idx) if self.link_cmds[idx].kind == LinkKind::StaticLibrary => {
+                self.link_cmds[idx].clone()
+            }
+            _ => LinkCommand {
+                directory: inv.directory.clone(),
+                arguments: Some(inv.tool.clone())
+                    .into_iter()
+                    .chain(inv.arguments.iter().cloned())
+                    .collect(),
+                output,
+                kind: LinkKind::StaticLibrary,
+                files: vec![],
+                libraries: vec![],
+            },
+        };
+        for member in members {
+            self.add_input(&mut cmd, &absolute(&inv.directory, member));
+        }
+        self.add_link_cmd(cmd);
+    }
+}
+
+fn push_unique(paths: &mut Vec<PathBuf>, path: &Path) {
+    if !paths.iter().any(|p| p == path) {
+        paths.push(path.to_owned());
+    }
+}
+
+/// Turn the logged invocations into compile and link commands. Commands for files that no longer
+/// exist once the build has finished, like the test programs of configure scripts, are dropped.
+fn process_invocations(invocations: &[Invocation]) -> (Vec<CompileCommand>, Vec<LinkCommand>) {
+    let mut compile_cmds = vec![];
+    let mut outputs = Outputs::default();
+    for inv in invocations {
+        if tool_kind(&inv.tool) == Some(ToolKind::Archiver) {
+            outputs.archive(inv);
+        } else {
+            outputs.compile(&mut compile_cmds, inv);
+        }
+    }
+
+    compile_cmds.retain(|cmd| cmd.file.exists());
+    let mut link_cmds = outputs.link_cmds;
+    link_cmds.retain(|cmd| cmd.output.exists());
+    (compile_cmds, link_cmds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inv(tool: &str, args: &str) -> Invocation {
+        Invocation {
+            directory: PathBuf::from("/build"),
+            tool: tool.to_owned(),
+            arguments: args.split_whitespace().map(String::from).collect(),
+        }
+    }
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    /// The compile and link commands of `invocations`, whose files need not exist
+    fn run(invocations: &[Invocation]) -> (Vec<CompileCommand>, Vec<LinkCommand>) {
+        let mut compile_cmds = vec![];
+        let mut outputs = Outputs::default();
+        for inv in invocations {
+            if tool_kind(&inv.tool) == Some(ToolKind::Archiver) {
+                outputs.archive(inv);
+            } else {
+                outputs.compile(&mut compile_cmds, inv);
+            }
+        }
+        (compile_cmds, outputs.link_cmds)
+    }
+
+    #[test]
+    fn parse_args() {
+        let args: Vec<String> = "-c -O2 -I include -DNDEBUG foo.c -o out/foo.o -lm -L lib bar.o"
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        let parsed = CompilerArgs::parse(&args);
+        assert_eq!(parsed.flags, vec!["-c", "-O2", "-I", "include", "-DNDEBUG"]);
+        assert_eq!(parsed.sources, vec!["foo.c"]);
+        assert_eq!(parsed.inputs, vec!["bar.o"]);
+        assert_eq!(parsed.libs, vec!["m"]);
+        assert_eq!(parsed.lib_dirs, vec!["lib"]);
+        assert_eq!(parsed.output, Some("out/foo.o"));
+        assert!(parsed.compile_only);
+
+        let args = vec!["-ofoo.o".to_owned(), "-c".to_owned(), "foo.c".to_owned()];
+        let parsed = CompilerArgs::parse(&args);
+        assert_eq!(parsed.flags, vec!["-c"]);
+        assert_eq!(parsed.output, Some("foo.o"));
+    }
+
+    #[test]
+    fn tool_names() {
+        for name in &[
+            "cc",
+            "gcc",
+            "clang-9",
+            "gcc-4.8",
+            "x86_64-linux-gnu-gcc",
+            "arm-none-eabi-gcc-8",
+        ] {
+            assert_eq!(tool_kind(name), Some(ToolKind::Compiler), "{}", name);
+        }
+        for name in &[
+            "ar",
+            "llvm-ar",
+            "gcc-ar",
+            "x86_64-linux-gnu-ar",
+            "llvm-ar-9",
+        ] {
+            assert_eq!(tool_kind(name), Some(ToolKind::Archiver), "{}", name);
+        }
+        for name in &[
+            "c++",
+            "clang++",
+            "clang-format",
+            "ccache",
+            "gcc-nm",
+            "make",
+            "arm-9",
+        ] {
+            assert_eq!(tool_kind(name), None, "{}", name);
+        }
+
+        let (_, link_cmds) = run(&[
+            inv("x86_64-linux-gnu-gcc", "-c foo.c"),
+            inv("gcc-ar", "rcs libfoo.a foo.o"),
+        ]);
+        assert_eq!(link_cmds.len(), 1);
+        assert!(link_cmds[0].kind == LinkKind::StaticLibrary);
+        assert_eq!(link_cmds[0].files, paths(&["/build/foo.c"]));
+    }
+
+    #[test]
+    fn recompiled_sources() {
+        // Objects for a static and a shared library
+        let (compile_cmds, link_cmds) = run(&[
+            inv("cc", "-c foo.c -o foo.o"),
+            inv("cc", "-c bar.c -o bar.o"),
+            inv("cc", "-c -fPIC foo.c -o foo.pic.o"),
+            inv("cc", "-shared foo.pic.o -o libfoo.so"),
+        ]);
+        assert_eq!(compile_cmds.len(), 2);
+        assert_eq!(compile_cmds[0].file, PathBuf::from("/build/foo.c"));
+        assert_eq!(
+            compile_cmds[0].arguments,
+            vec!["cc", "-c", "-fPIC", "foo.c", "-o", "foo.pic.o"]
+        );
+        assert_eq!(compile_cmds[1].file, PathBuf::from("/build/bar.c"));
+        // Both objects still lead back to the source
+        assert_eq!(link_cmds[0].files, paths(&["/build/foo.c"]));
+
+        // The same file name in another directory is another entry
+        let mut other = inv("cc", "-c foo.c");
+        other.directory = PathBuf::from("/build/sub");
+        let (compile_cmds, _) = run(&[inv("cc", "-c foo.c"), other]);
+        assert_eq!(compile_cmds.len(), 2);
+    }
+
+    #[test]
+    fn compile_only() {
+        let (compile_cmds, link_cmds) = run(&[
+            inv("cc", "-c -O2 foo.c -o out/foo.o"),
+            inv("cc", "-c bar.c -oout/bar.o"),
+        ]);
+        assert!(link_cmds.is_empty());
+        assert_eq!(compile_cmds.len(), 2);
+        assert_eq!(
+            compile_cmds[0].arguments,
+            vec!["cc", "-c", "-O2", "foo.c", "-o", "out/foo.o"]
+        );
+        assert_eq!(compile_cmds[0].file, PathBuf::from("/build/foo.c"));
+        assert_eq!(compile_cmds[0].output, Some(PathBuf::from("out/foo.o")));
+        assert_eq!(
+            compile_cmds[1].arguments,
+            vec!["cc", "-c", "bar.c", "-o", "out/bar.o"]
+        );
+    }
+
+    #[test]
+    fn multiple_sources() {
+        // With `-c`, each source is compiled to an object file of the same name
+        let (compile_cmds, _) = run(&[inv("cc", "-c a.c src/b.c")]);
+        let outputs: Vec<_> = compile_cmds.iter().map(|cmd| cmd.output.clone()).collect();
+        assert_eq!(
+            outputs,
+            vec![Some(PathBuf::from("a.o")), Some(PathBuf::from("b.o"))]
+        );
+        assert_eq!(
+            compile_cmds[1].arguments,
+            vec!["cc", "-c", "src/b.c", "-o", "b.o"]
+        );
+
+        // Otherwise, the sources are compiled and linked together
+        let (compile_cmds, link_cmds) = run(&[inv("cc", "a.c b.c -o prog")]);
+        assert_eq!(compile_cmds.len(), 2);
+        assert_eq!(compile_cmds[0].arguments, vec!["cc", "-c", "a.c"]);
+        assert_eq!(compile_cmds[0].output, None);
+        assert_eq!(link_cmds.len(), 1);
+        assert!(link_cmds[0].kind == LinkKind::Executable);
+        assert_eq!(link_cmds[0].output, PathBuf::from("/build/prog"));
+        assert_eq!(link_cmds[0].files, paths(&["/build/a.c", "/build/b.c"]));
+    }
+
+    #[test]
+    fn static_library() {
+        let (_, link_cmds) = run(&[
+            inv("cc", "-c foo.c"),
+            inv("cc", "-c bar.c"),
+            inv("cc", "-c main.c"),
+            inv("ar", "rcs libfoo.a foo.o"),
+            inv("ar", "rcs libfoo.a bar.o"),
+            inv("cc", "main.o -L. -lfoo -o main"),
+        ]);
+        assert_eq!(link_cmds.len(), 2);
+        assert!(link_cmds[0].kind == LinkKind::StaticLibrary);
+        assert_eq!(link_cmds[0].output, PathBuf::from("/build/libfoo.a"));
+        assert_eq!(link_cmds[0].files, paths(&["/build/foo.c", "/build/bar.c"]));
+        assert_eq!(
+            link_cmds[1].files,
+            paths(&["/build/main.c", "/build/foo.c", "/build/bar.c"])
+        );
+        assert!(link_cmds[1].libraries.is_empty());
+    }
+
+    #[test]
+    fn library_search() {
+        let (_, link_cmds) = run(&[
+            inv("cc", "-c foo.c -o lib/foo.o"),
+            inv("ar", "rcs lib/libfoo.a lib/foo.o"),
+            inv("cc", "-shared lib/foo.o -o lib/libfoo.so"),
+            inv("cc", "-c main.c"),
+            inv("cc", "main.o -L other -Llib -lfoo -lm -o main"),
+            inv("cc", "-L lib main.o lib/libfoo.a -o main_static"),
+        ]);
+        assert_eq!(link_cmds.len(), 4);
+        assert!(link_cmds[1].kind == LinkKind::SharedLibrary);
+
+        // The shared library is found first
+        assert_eq!(link_cmds[2].files, paths(&["/build/main.c"]));
+        assert_eq!(link_cmds[2].libraries, paths(&["/build/lib/libfoo.so"]));
+
+        assert_eq!(
+            link_cmds[3].files,
+            paths(&["/build/main.c", "/build/foo.c"])
+        );
+        assert!(link_cmds[3].libraries.is_empty());
+    }
+
+    #[test]
+    fn missing_files() {
+        let dir = env::temp_dir().join(format!("c2rust-intercept-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        File::create(dir.join("main.c")).unwrap();
+        File::create(dir.join("main")).unwrap();
+
+        // Like configure scripts, which remove the programs they test
+        let mut invocations = vec![inv("cc", "main.c -o main"), inv("cc", "conftest.c")];
+        for invocation in &mut invocations {
+            invocation.directory = dir.clone();
+        }
+        let (compile_cmds, link_cmds) = process_invocations(&invocations);
+        let dir = dir.canonicalize().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(compile_cmds.len(), 1);
+        assert_eq!(compile_cmds[0].file, dir.join("main.c"));
+        assert_eq!(link_cmds.len(), 1);
+        assert_eq!(link_cmds[0].output, dir.join("main"));
+    }
+}
@@ -8,7 +8,10 @@ use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use c2rust_transpile::{Diagnostic, ReplaceMode, TranspilerConfig};
+use c2rust_transpile::{
+    Diagnostic, LongDoubleMode, OverflowMode, ReplaceMode, StaticInitMode, TranspilerConfig,
+    VaListMode,
+};
 
 fn main() {
     let yaml = load_yaml!("../transpile.yaml");
@@ -75,18 +78,55 @@ fn main() {
         // support landed. We may still want to disable this option to target
         // stable rust output.
         translate_valist: true,
+        va_list: match matches.value_of("va-list") {
+            Some("nightly") => VaListMode::Nightly,
+            Some("runtime") => VaListMode::Runtime,
+            _ => panic!("Invalid option"),
+        },
+        static_init: match matches.value_of("static-init") {
+            Some("sections") => StaticInitMode::Sections,
+            Some("lazy") => StaticInitMode::Lazy,
+            _ => panic!("Invalid option"),
+        },
+        overflow: match matches.value_of("overflow") {
+            Some("plain") => OverflowMode::Plain,
+            Some("wrapping") => OverflowMode::Wrapping,
+            Some("report") => OverflowMode::Report,
+            _ => panic!("Invalid option"),
+        },
 
         translate_const_macros: matches.is_present("translate-const-macros"),
+        translate_fn_macros: matches.is_present("translate-fn-macros"),
+        translate_setjmp: matches.is_present("translate-setjmp"),
+        closed_enums: matches.is_present("closed-enums"),
+        long_double: match matches.value_of("long-double") {
+            Some("f128") => LongDoubleMode::F128,
+            Some("f64") => LongDoubleMode::F64,
+            Some("f80") => LongDoubleMode::F80,
+            _ => panic!("Invalid option"),
+        },
+        targets: matches
+            .values_of("target")
+            .map(|targets| targets.map(String::from).collect())
+            .unwrap_or_default(),
 
         use_c_loop_info: !matches.is_present("ignore-c-loop-info"),
         use_c_multiple_info: !matches.is_present("ignore-c-multiple-info"),
         simplify_structures: !matches.is_present("no-simplify-structures"),
         overwrite_existing: matches.is_present("overwrite-existing"),
+        cache_dir: matches.value_of("cache-dir").map(PathBuf::from),
+        save_asts: matches.value_of("save-asts").map(PathBuf::from),
+        load_asts: matches.value_of("load-asts").map(PathBuf::from),
         reduce_type_annotations: matches.is_present("reduce-type-annotations"),
         reorganize_definitions: matches.is_present("reorganize-definitions"),
         emit_modules: matches.is_present("emit-modules"),
         emit_build_files: matches.is_present("emit-build-files"),
         output_dir: matches.value_of("output-dir").map(PathBuf::from),
+        jobs: matches
+            .value_of("jobs")
+            .map(|jobs| jobs.parse().expect("--jobs expects a number")),
+        report: matches.value_of("report").map(PathBuf::from),
+        source_map: matches.is_present("source-map"),
         main: {
             if matches.is_present("main") {
                 Some(String::from(matches.value_of("main").unwrap()))
@@ -94,6 +134,11 @@ fn main() {
                 None
             }
         },
+        hybrid_build: matches.is_present("hybrid") || matches.is_present("keep-c"),
+        keep_c: matches
+            .value_of("keep-c")
+            .map(|keep_c| Regex::new(keep_c).unwrap()),
+        link_commands: matches.value_of("link-commands").map(PathBuf::from),
         panic_on_translator_failure: {
             match matches.value_of("invalid-code") {
                 Some("panic") => true,
@@ -109,6 +154,14 @@ fn main() {
     if tcfg.main != None {
         tcfg.emit_build_files = true
     };
+    // hybrid implies emit-build-files
+    if tcfg.hybrid_build {
+        tcfg.emit_build_files = true
+    };
+    // link-commands implies emit-build-files
+    if tcfg.link_commands.is_some() {
+        tcfg.emit_build_files = true
+    };
     // emit-build-files implies emit-modules
     if tcfg.emit_build_files {
         tcfg.emit_modules = true
@@ -42,7 +42,7 @@ cargo = get_cmd_or_die("cargo")
 
 # Intermediate files
 intermediate_files = [
-    'cc_db', 'cbor', 'c_obj', 'c_lib', 'rust_src',
+    'cc_db', 'cbor', 'c_obj', 'c_lib', 'rust_src', 'va_trampolines',
 ]
 
 
@@ -70,6 +70,15 @@ class CFile:
         self.disable_incremental_relooper = "disable_incremental_relooper" in flags
         self.disallow_current_block = "disallow_current_block" in flags
         self.translate_const_macros = "translate_const_macros" in flags
+        self.translate_fn_macros = "translate_fn_macros" in flags
+        self.translate_setjmp = "translate_setjmp" in flags
+        self.long_double_f64 = "long_double_f64" in flags
+        self.long_double_f80 = "long_double_f80" in flags
+        self.va_list_runtime = "va_list_runtime" in flags
+        self.static_init_lazy = "static_init_lazy" in flags
+        self.overflow_report = "overflow_report" in flags
+        self.overflow_wrapping = "overflow_wrapping" in flags
+        self.closed_enums = "closed_enums" in flags
         self.reorganize_definitions = "reorganize_definitions" in flags
 
     def translate(self, cc_db, extra_args: List[str] = []) -> RustFile:
@@ -96,6 +105,24 @@ class CFile:
             args.append("--fail-on-multiple")
         if self.translate_const_macros:
             args.append("--translate-const-macros")
+        if self.translate_fn_macros:
+            args.append("--translate-fn-macros")
+        if self.translate_setjmp:
+            args.append("--translate-setjmp")
+        if self.long_double_f64:
+            args.extend(["--long-double", "f64"])
+        if self.long_double_f80:
+            args.extend(["--long-double", "f80"])
+        if self.va_list_runtime:
+            args.extend(["--va-list", "runtime"])
+        if self.static_init_lazy:
+            args.extend(["--static-init", "lazy"])
+        if self.overflow_report:
+            args.extend(["--overflow", "report"])
+        if self.overflow_wrapping:
+            args.extend(["--overflow", "wrapping"])
+        if self.closed_enums:
+            args.append("--closed-enums")
         if self.reorganize_definitions:
             args.append("--reorganize-definitions")
 
@@ -208,6 +235,7 @@ class TestDirectory:
             "c_obj": [],
             "c_lib": [],
             "cc_db": [],
+            "va_trampolines": [],
         }
 
         for entry in os.listdir(self.full_path_src):
@@ -217,7 +245,8 @@ class TestDirectory:
                 _, ext = os.path.splitext(path)
                 filename = os.path.splitext(os.path.basename(path))[0]
 
-                if ext == ".c":
+                # Trampolines kept from an earlier run are generated, not tests
+                if ext == ".c" and not path.endswith(".va_trampolines.c"):
                     c_file = self._read_c_file(path)
 
                     if c_file:
@@ -324,29 +353,11 @@ class TestDirectory:
 
         sys.stdout.write("{}:\n".format(self.name))
 
-        # .c -> .a
-        description = "libtest.a: creating a static C library..."
-
-        self.print_status(Colors.WARNING, "RUNNING", description)
-
-        try:
-            static_library = build_static_library(self.c_files, self.full_path)
-        except NonZeroReturn as exception:
-            self.print_status(Colors.FAIL, "FAILED", "create libtest.a")
-            sys.stdout.write('\n')
-            sys.stdout.write(str(exception))
-
-            outcomes.append(TestOutcome.UnexpectedFailure)
-
-            return outcomes
-
-        self.generated_files["c_lib"].append(static_library)
-        self.generated_files["c_obj"].extend(static_library.obj_files)
-
         rust_file_builder = RustFileBuilder()
         rust_file_builder.add_features(["libc", "extern_types", "simd_ffi", "stdsimd", "const_transmute", "nll"])
 
         # .c -> .rs
+        trampoline_files = []
         for c_file in self.c_files:
             _, c_file_short = os.path.split(c_file.path)
             description = "{}: translating the C file into Rust...".format(
@@ -371,12 +382,40 @@ class TestDirectory:
 
             self.generated_files["rust_src"].append(translated_rust_file)
 
+            # Variadic functions translated with `--va-list runtime` are called
+            # through C trampolines, which are linked in with the C code
+            extensionless_rust_file, _ = os.path.splitext(translated_rust_file.path)
+            trampolines = extensionless_rust_file + ".va_trampolines.c"
+            if os.path.exists(trampolines):
+                self.generated_files["va_trampolines"].append(trampolines)
+                trampoline_files.append(CFile(trampolines))
+
             _, rust_file_short = os.path.split(translated_rust_file.path)
             extensionless_rust_file, _ = os.path.splitext(rust_file_short)
 
             rust_file_builder.add_mod(RustMod(extensionless_rust_file,
                                               RustVisibility.Public))
 
+        # .c -> .a, along with the trampolines of variadic functions
+        description = "libtest.a: creating a static C library..."
+
+        self.print_status(Colors.WARNING, "RUNNING", description)
+
+        try:
+            static_library = build_static_library(self.c_files + trampoline_files,
+                                                  self.full_path)
+        except NonZeroReturn as exception:
+            self.print_status(Colors.FAIL, "FAILED", "create libtest.a")
+            sys.stdout.write('\n')
+            sys.stdout.write(str(exception))
+
+            outcomes.append(TestOutcome.UnexpectedFailure)
+
+            return outcomes
+
+        self.generated_files["c_lib"].append(static_library)
+        self.generated_files["c_obj"].extend(static_library.obj_files)
+
         match_arms = []
         rustc_extra_args = ["-C", "target-cpu=native"]
 
@@ -0,0 +1,36 @@
+// GNU inline assembly, translated to `asm!` (x86 and x86_64 only)
+
+void asm_operands(unsigned int a, unsigned int b, unsigned int *out) {
+    unsigned int sum, doubled = a, shifted;
+    unsigned char low;
+
+    // Positional operands, with the result register sized by its C type
+    __asm__("movl %1, %0\n\taddl %2, %0" : "=&r"(sum) : "r"(a), "r"(b) : "cc");
+
+    // Read-write operand and an immediate
+    __asm__("addl %1, %0" : "+r"(doubled) : "i"(3) : "cc");
+
+    // Named operands, an input tied to the output and an explicit register
+    __asm__("shll %%cl, %[value]"
+            : [value] "=r"(shifted)
+            : "0"(b), "c"(4)
+            : "cc");
+
+    // Byte register
+    __asm__("movb %b1, %0" : "=q"(low) : "q"(a));
+
+    out[0] = sum;
+    out[1] = doubled;
+    out[2] = shifted;
+    out[3] = low;
+}
+
+void asm_memory(int *out) {
+    int x = 5;
+
+    // Memory operands, which are passed to `asm!` by address
+    __asm__ volatile("addl %1, %0" : "+m"(x) : "r"(10) : "cc", "memory");
+    __asm__ volatile("movl %1, %%eax\n\tmovl %%eax, %0" : "=m"(out[1]) : "m"(x) : "eax");
+
+    out[0] = x;
+}
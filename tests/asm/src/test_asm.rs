@@ -0,0 +1,41 @@
+//! feature_asm
+
+extern crate libc;
+
+use asm::{rust_asm_memory, rust_asm_operands};
+use self::libc::{c_int, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn asm_operands(a: c_uint, b: c_uint, out: *mut c_uint);
+
+    #[no_mangle]
+    fn asm_memory(out: *mut c_int);
+}
+
+pub fn test_asm_operands() {
+    let mut out = [0; 4];
+    let mut rust_out = [0; 4];
+
+    unsafe {
+        asm_operands(0x1234, 7, out.as_mut_ptr());
+        rust_asm_operands(0x1234, 7, rust_out.as_mut_ptr());
+    }
+
+    assert_eq!(out, [4667, 4663, 112, 52]);
+    assert_eq!(out, rust_out);
+}
+
+pub fn test_asm_memory() {
+    let mut out = [0; 2];
+    let mut rust_out = [0; 2];
+
+    unsafe {
+        asm_memory(out.as_mut_ptr());
+        rust_asm_memory(rust_out.as_mut_ptr());
+    }
+
+    assert_eq!(out, [15, 15]);
+    assert_eq!(out, rust_out);
+}
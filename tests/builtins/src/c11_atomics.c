@@ -0,0 +1,73 @@
+#include <stdatomic.h>
+
+static _Atomic int counter = 3;
+
+struct shared {
+    _Atomic unsigned flags;
+    _Atomic long total;
+};
+
+void c11_atomics_entry(const unsigned buffer_size, int buffer[const])
+{
+    int i = 0, x = 34, expected, desired;
+    struct shared s = { 0, 0 };
+
+    __atomic_store_n(&x, 12, __ATOMIC_RELEASE);
+    buffer[i++] = __atomic_load_n(&x, __ATOMIC_ACQUIRE);
+    buffer[i++] = __atomic_exchange_n(&x, 40, __ATOMIC_ACQ_REL); buffer[i++] = x;
+    buffer[i++] = __atomic_fetch_add(&x, 55, __ATOMIC_RELAXED);  buffer[i++] = x;
+    buffer[i++] = __atomic_fetch_sub(&x, 17, __ATOMIC_SEQ_CST);  buffer[i++] = x;
+    buffer[i++] = __atomic_fetch_nand(&x, 0xA0, __ATOMIC_SEQ_CST); buffer[i++] = x;
+    buffer[i++] = __atomic_add_fetch(&x, 55, __ATOMIC_SEQ_CST);  buffer[i++] = x;
+    buffer[i++] = __atomic_xor_fetch(&x, 0xA5, __ATOMIC_SEQ_CST); buffer[i++] = x;
+    buffer[i++] = __atomic_nand_fetch(&x, 0xA0, __ATOMIC_SEQ_CST); buffer[i++] = x;
+
+    __atomic_load(&x, &expected, __ATOMIC_SEQ_CST);
+    buffer[i++] = expected;
+    desired = 99;
+    __atomic_store(&x, &desired, __ATOMIC_SEQ_CST);
+    buffer[i++] = x;
+    __atomic_exchange(&x, &expected, &desired, __ATOMIC_SEQ_CST);
+    buffer[i++] = x; buffer[i++] = desired;
+
+    for (int j = 0; j < 4; j++) {
+        expected = j;
+        buffer[i++] = __atomic_compare_exchange_n(&x, &expected, 2, 0,
+                                                  __ATOMIC_ACQUIRE, __ATOMIC_RELAXED);
+        buffer[i++] = expected; buffer[i++] = x;
+        desired = 100 + j;
+        buffer[i++] = __atomic_compare_exchange(&x, &expected, &desired, 0,
+                                                __ATOMIC_SEQ_CST, __ATOMIC_SEQ_CST);
+        buffer[i++] = expected; buffer[i++] = x;
+    }
+    __atomic_thread_fence(__ATOMIC_SEQ_CST);
+    __atomic_signal_fence(__ATOMIC_ACQUIRE);
+
+    atomic_init(&s.flags, 5);
+    atomic_store_explicit(&s.total, 7, memory_order_release);
+    buffer[i++] = atomic_load_explicit(&s.total, memory_order_acquire);
+    buffer[i++] = atomic_fetch_or(&s.flags, 8); buffer[i++] = s.flags;
+    buffer[i++] = atomic_fetch_and_explicit(&s.flags, 6, memory_order_relaxed);
+    buffer[i++] = atomic_exchange(&s.total, 30); buffer[i++] = s.total;
+    long old_total = 31;
+    buffer[i++] = atomic_compare_exchange_strong(&s.total, &old_total, 32);
+    buffer[i++] = old_total;
+    buffer[i++] = atomic_compare_exchange_weak_explicit(&s.total, &old_total, 33,
+                                                         memory_order_acq_rel,
+                                                         memory_order_acquire);
+    buffer[i++] = s.total;
+    atomic_thread_fence(memory_order_acquire);
+
+    buffer[i++] = counter;
+    buffer[i++] = counter++;
+    buffer[i++] = ++counter;
+    buffer[i++] = counter--;
+    buffer[i++] = counter = 20;
+    buffer[i++] = counter += 5;
+    counter -= 2;
+    buffer[i++] = counter;
+    buffer[i++] = counter |= 64;
+    buffer[i++] = (s.flags ^= 3);
+    s.total = s.total + counter;
+    buffer[i++] = s.total;
+}
@@ -2,6 +2,7 @@
 extern crate libc;
 
 use atomics::rust_atomics_entry;
+use c11_atomics::rust_c11_atomics_entry;
 use mem_x_fns::rust_mem_x;
 use self::libc::{c_int, c_uint, c_char};
 
@@ -10,6 +11,8 @@ extern "C" {
     #[no_mangle]
     fn atomics_entry(_: c_uint, _: *mut c_int);
     #[no_mangle]
+    fn c11_atomics_entry(_: c_uint, _: *mut c_int);
+    #[no_mangle]
     fn mem_x(_: *const c_char, _: *mut c_char);
 }
 
@@ -30,6 +33,20 @@ pub fn test_atomics() {
     }
 }
 
+pub fn test_c11_atomics() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+
+    unsafe {
+       c11_atomics_entry(BUFFER_SIZE as u32, buffer.as_mut_ptr());
+       rust_c11_atomics_entry(BUFFER_SIZE as u32, rust_buffer.as_mut_ptr());
+    }
+
+    for index in 0..BUFFER_SIZE {
+        assert_eq!(buffer[index], rust_buffer[index]);
+    }
+}
+
 pub fn test_mem_fns() {
     let const_string = "I am ten!\0";
     let mut buffer = [0; BUFFER_SIZE2];
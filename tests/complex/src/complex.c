@@ -0,0 +1,46 @@
+#include <complex.h>
+
+static double complex scale(double complex z, double k) {
+    return z * k;
+}
+
+void complex_arith(unsigned buffer_size, double buffer[]) {
+    double complex a = 1.5 + 2.0 * I;
+    double complex b = -0.5 + 4.0 * I;
+    float complex f = 3.0f - 1.0f * I;
+    int i = 0;
+
+    if (buffer_size < 20) {
+        return;
+    }
+
+    double complex sum = a + b;
+    double complex diff = a - b;
+    double complex prod = a * b;
+    double complex quot = a / b;
+
+    f *= f;
+    a /= 2;
+    __real__ b = 0.25;
+
+    buffer[i++] = creal(sum);
+    buffer[i++] = cimag(sum);
+    buffer[i++] = creal(diff);
+    buffer[i++] = cimag(diff);
+    buffer[i++] = creal(prod);
+    buffer[i++] = cimag(prod);
+    buffer[i++] = creal(quot);
+    buffer[i++] = cimag(quot);
+    buffer[i++] = crealf(f);
+    buffer[i++] = cimagf(f);
+    buffer[i++] = __real__ a;
+    buffer[i++] = __imag__ a;
+    buffer[i++] = creal(scale(b, 3.0));
+    buffer[i++] = cimag(scale(b, 3.0));
+    buffer[i++] = cimag(~b);
+    buffer[i++] = cimag(conj(b));
+    buffer[i++] = (int)creal(a);
+    buffer[i++] = __imag__ buffer[0];
+    buffer[i++] = b ? 1 : 0;
+    buffer[i++] = (b - b) ? 1 : 0;
+}
@@ -0,0 +1,31 @@
+//! extern_crate_num_complex
+
+extern crate libc;
+
+use complex::rust_complex_arith;
+use self::libc::{c_double, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn complex_arith(_: c_uint, _: *mut c_double);
+}
+
+const BUFFER_SIZE: usize = 20;
+
+pub fn test_complex_arith() {
+    let mut buffer = [0.0; BUFFER_SIZE];
+    let mut rust_buffer = [0.0; BUFFER_SIZE];
+    let expected_buffer = [
+        1.0, 6.0, 2.0, -2.0, -8.75, 5.0, 0.44615384615384618, -0.43076923076923079, 8.0, -6.0,
+        0.75, 1.0, 0.75, 12.0, -4.0, -4.0, 0.0, 0.0, 1.0, 0.0,
+    ];
+
+    unsafe {
+        complex_arith(BUFFER_SIZE as u32, buffer.as_mut_ptr());
+        rust_complex_arith(BUFFER_SIZE as u32, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
@@ -0,0 +1,84 @@
+//! closed_enums
+
+#include <string.h>
+
+enum color { RED, GREEN, BLUE };
+
+/* Bit flags aren't closed */
+enum flags { FLAG_A = 1, FLAG_B = 2, FLAG_C = 4 };
+
+/* Neither are enums with arithmetic on their values */
+enum level { LOW, MID, HIGH };
+
+/* Nor enums that other translation units can pass in */
+enum shade { DARK, LIGHT };
+
+/* Nor enums stored in memory that is copied as bytes */
+enum mode { MODE_OFF, MODE_ON };
+
+struct pixel {
+    enum color color;
+    int x;
+};
+
+static enum color next_color(enum color c) {
+    switch (c) {
+    case RED:
+        return GREEN;
+    case GREEN:
+        return (BLUE);
+    default:
+        return 0;
+    }
+}
+
+int color_cycle_length(void) {
+    enum color c = RED;
+    int n = 0;
+    do {
+        c = next_color(c);
+        n++;
+    } while (c != RED);
+    return n;
+}
+
+static int pixel_value(const struct pixel *p) {
+    return (int)p->color * 100 + p->x;
+}
+
+static void paint(struct pixel *p, int n) {
+    static enum color last;
+    struct pixel fresh = { 0 };
+    if (n > 0)
+        fresh.color = BLUE;
+    else
+        fresh.color = GREEN;
+    fresh.x = n;
+    *p = fresh;
+    if (p->color == BLUE)
+        last = RED;
+}
+
+int painted_value(int n) {
+    struct pixel p = { RED, 0 };
+    paint(&p, n);
+    return pixel_value(&p);
+}
+
+enum flags combine(void) {
+    return FLAG_A | FLAG_C;
+}
+
+enum level raise(enum level l) {
+    return l == HIGH ? l : l + 1;
+}
+
+enum shade invert(enum shade s) {
+    return s == DARK ? LIGHT : DARK;
+}
+
+int copied_mode(int on) {
+    enum mode m = on ? MODE_ON : MODE_OFF, copy;
+    memcpy(&copy, &m, sizeof m);
+    return copy == MODE_ON;
+}
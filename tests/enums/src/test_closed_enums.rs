@@ -0,0 +1,42 @@
+extern crate libc;
+
+use closed_enums::{
+    color, flags, level, mode, pixel, rust_color_cycle_length, rust_combine, rust_copied_mode,
+    rust_invert, rust_painted_value, rust_raise, shade, BLUE, DARK, GREEN, HIGH, LIGHT, MID,
+    MODE_ON, RED,
+};
+use std::convert::TryFrom;
+
+pub fn test_closed_enum() {
+    unsafe {
+        assert_eq!(rust_color_cycle_length(), 3);
+    }
+    assert_eq!(color::try_from(2), Ok(BLUE));
+    assert_eq!(color::try_from(3), Err(3));
+    assert_eq!(GREEN, color::GREEN);
+}
+
+pub fn test_closed_enum_fields() {
+    let p = pixel { color: RED, x: 0 };
+    assert_eq!(p.color, color::RED);
+    unsafe {
+        assert_eq!(rust_painted_value(7), 207);
+        assert_eq!(rust_painted_value(-1), 99);
+    }
+}
+
+pub fn test_open_enums() {
+    unsafe {
+        let f: flags = rust_combine();
+        assert_eq!(f, 5);
+        let l: level = rust_raise(MID);
+        assert_eq!(l, HIGH);
+        assert_eq!(rust_raise(HIGH), 2);
+        let s: shade = rust_invert(DARK);
+        assert_eq!(s, LIGHT);
+        assert_eq!(rust_invert(7), DARK);
+        let m: mode = MODE_ON;
+        assert_eq!(m, 1);
+        assert_eq!(rust_copied_mode(1), 1);
+    }
+}
@@ -0,0 +1,33 @@
+//! long_double_f80
+
+static long double two_pow_60 = 1152921504606846976.0L;
+static const long double tenth = 0.1L;
+
+// Only nonzero with the 64-bit significand of x87 extended precision
+double long_double_epsilon(void) {
+    long double one = 1;
+    long double x = one + one / two_pow_60;
+
+    return (double)((x - one) * two_pow_60);
+}
+
+// The error in summing ten 0.1L, in units of 2^-64
+double long_double_sum(void) {
+    long double sum = 0;
+    int i;
+
+    for (i = 0; i < 10; i++) {
+        sum += tenth;
+    }
+
+    return (double)((sum - 1) * two_pow_60 * 16);
+}
+
+unsigned long long long_double_roundtrip(unsigned long long x) {
+    long double y = x;
+
+    y++;
+    y--;
+
+    return (unsigned long long)y;
+}
@@ -0,0 +1,37 @@
+extern crate libc;
+
+use long_double_f80::{rust_long_double_epsilon, rust_long_double_roundtrip, rust_long_double_sum};
+use self::libc::{c_double, c_ulonglong};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn long_double_epsilon() -> c_double;
+    #[no_mangle]
+    fn long_double_sum() -> c_double;
+    #[no_mangle]
+    fn long_double_roundtrip(x: c_ulonglong) -> c_ulonglong;
+}
+
+pub fn test_epsilon() {
+    unsafe {
+        assert_eq!(long_double_epsilon(), 1.);
+        assert_eq!(rust_long_double_epsilon(), 1.);
+    }
+}
+
+pub fn test_sum() {
+    unsafe {
+        assert_eq!(long_double_sum(), 2.);
+        assert_eq!(rust_long_double_sum(), 2.);
+    }
+}
+
+pub fn test_roundtrip() {
+    let max = c_ulonglong::max_value();
+
+    unsafe {
+        assert_eq!(long_double_roundtrip(max), max);
+        assert_eq!(rust_long_double_roundtrip(max), max);
+    }
+}
@@ -0,0 +1,73 @@
+#include <math.h>
+
+#define kind_of(x) _Generic((x), \
+    char: 1, \
+    int: 2, \
+    long: 3, \
+    float: 4, \
+    double: 5, \
+    default: 0)
+
+#define generic_abs(x) _Generic((x), \
+    int: abs_int, \
+    long: abs_long, \
+    double: fabs)(x)
+
+#define is_int(x) __builtin_types_compatible_p(__typeof__(x), int)
+
+#define max_or_first(x, y) __builtin_choose_expr(is_int(x), (x) > (y) ? (x) : (y), (x))
+
+struct point { int x, y; };
+typedef int my_int;
+
+static int abs_int(int x) { return x < 0 ? -x : x; }
+static long abs_long(long x) { return x < 0 ? -x : x; }
+
+static int selected = _Generic(1.0f, float: 10, default: 20);
+
+void generics(const unsigned buffer_size, int buffer[const])
+{
+    int i = 0;
+    char c = 'a';
+    long l = -7;
+    double d = -2.5;
+    struct point p = { 1, 2 };
+    my_int m = 3;
+
+    buffer[i++] = kind_of(c);
+    buffer[i++] = kind_of(i);
+    buffer[i++] = kind_of(l);
+    buffer[i++] = kind_of(1.0f);
+    buffer[i++] = kind_of(d);
+    buffer[i++] = kind_of(p);
+    buffer[i++] = kind_of(m);
+    buffer[i++] = kind_of(c + c);
+    buffer[i++] = selected;
+
+    buffer[i++] = generic_abs(-4);
+    buffer[i++] = generic_abs(l);
+    buffer[i++] = generic_abs(d) * 2;
+
+    /* The controlling expression is never evaluated */
+    int j = 5;
+    buffer[i++] = _Generic(j++, int: j, default: -1);
+
+    /* A generic selection of an lvalue is an lvalue */
+    _Generic(p.x, int: p.y, default: p.x) = 42;
+    buffer[i++] = p.y;
+
+    buffer[i++] = __builtin_types_compatible_p(int, my_int);
+    buffer[i++] = __builtin_types_compatible_p(int, const int);
+    buffer[i++] = __builtin_types_compatible_p(int, unsigned);
+    buffer[i++] = __builtin_types_compatible_p(int[], int[4]);
+    buffer[i++] = __builtin_types_compatible_p(struct point, struct point);
+    buffer[i++] = __builtin_types_compatible_p(char *, const char *);
+    buffer[i++] = is_int(m);
+    buffer[i++] = is_int(l);
+
+    buffer[i++] = __builtin_choose_expr(1, 11, 12);
+    buffer[i++] = __builtin_choose_expr(0, 11, 12);
+    buffer[i++] = max_or_first(m, 8);
+    buffer[i++] = max_or_first(l, 8L);
+    buffer[i++] = __builtin_choose_expr(is_int(d), 1, (int)d);
+}
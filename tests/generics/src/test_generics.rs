@@ -0,0 +1,29 @@
+extern crate libc;
+
+use generics::rust_generics;
+use self::libc::{c_int, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn generics(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 30;
+
+pub fn test_generics() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [
+        1, 2, 3, 4, 5, 0, 2, 2, 10, 4, 7, 5, 5, 42, 1, 1, 0, 1, 1, 0, 1, 0, 11, 12, 8, -7, -2, 0,
+        0, 0,
+    ];
+
+    unsafe {
+        generics(BUFFER_SIZE as u32, buffer.as_mut_ptr());
+        rust_generics(BUFFER_SIZE as u32, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
@@ -0,0 +1,54 @@
+enum { OP_PUSH, OP_ADD, OP_MUL, OP_JNZ, OP_DEC, OP_HALT };
+
+// A small stack machine in the style of threaded interpreters: the dispatch
+// table holds label addresses and every instruction ends in a computed goto
+int computed_goto(const int *code) {
+    static const void *dispatch[] = {
+        &&do_push, &&do_add, &&do_mul, &&do_jnz, &&do_dec, &&do_halt,
+    };
+    int stack[16];
+    int sp = 0;
+    int pc = 0;
+
+    goto *dispatch[code[pc]];
+
+do_push:
+    stack[sp++] = code[pc + 1];
+    pc += 2;
+    goto *dispatch[code[pc]];
+do_add:
+    sp--;
+    stack[sp - 1] += stack[sp];
+    pc += 1;
+    goto *dispatch[code[pc]];
+do_mul:
+    sp--;
+    stack[sp - 1] *= stack[sp];
+    pc += 1;
+    goto *dispatch[code[pc]];
+do_jnz:
+    pc = stack[sp - 1] ? code[pc + 1] : pc + 2;
+    goto *dispatch[code[pc]];
+do_dec:
+    stack[sp - 1] -= 1;
+    pc += 1;
+    goto *dispatch[code[pc]];
+do_halt:
+    return stack[sp - 1];
+}
+
+// Label addresses can also live in local variables and be compared
+int label_values(int n) {
+    void *next = n > 0 ? &&positive : &&not_positive;
+    int result = 0;
+
+    if (next == &&positive) {
+        result += 100;
+    }
+    goto *next;
+
+positive:
+    return result + n;
+not_positive:
+    return result - n;
+}
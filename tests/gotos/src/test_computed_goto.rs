@@ -0,0 +1,23 @@
+extern crate libc;
+
+use computed_goto::{rust_computed_goto, rust_label_values};
+
+pub fn test_computed_goto() {
+    let arithmetic = [0, 2, 0, 3, 1, 0, 4, 2, 5];
+    let countdown = [0, 5, 4, 3, 2, 5];
+    let constant = [0, 7, 5];
+
+    unsafe {
+        assert_eq!(rust_computed_goto(arithmetic.as_ptr()), 20);
+        assert_eq!(rust_computed_goto(countdown.as_ptr()), 0);
+        assert_eq!(rust_computed_goto(constant.as_ptr()), 7);
+    }
+}
+
+pub fn test_label_values() {
+    unsafe {
+        assert_eq!(rust_label_values(7), 107);
+        assert_eq!(rust_label_values(-3), 3);
+        assert_eq!(rust_label_values(0), 0);
+    }
+}
@@ -0,0 +1,43 @@
+//! overflow_report
+
+#include <limits.h>
+
+int checked_arithmetic(int a, int b) {
+    int x = a + b;
+    x = x * 3 - b;
+    x /= 2;
+    x %= 1000;
+    x += a;
+    x *= -1;
+    return -x;
+}
+
+int checked_increments(int n) {
+    int i = INT_MAX - n;
+    int count = 0;
+    while (i < INT_MAX) {
+        i++;
+        ++count;
+    }
+    return count;
+}
+
+unsigned wrapping_unsigned(unsigned a) {
+    unsigned x = a + 1;
+    x *= 2;
+    return x - 3;
+}
+
+long checked_long(long a) {
+    static long scale = 4 * 5;
+    return a * scale + LONG_MIN / 2;
+}
+
+int overflowing_add(int a) {
+    return a + 1;
+}
+
+int overflowing_mul_assign(int a, int b) {
+    a *= b;
+    return a;
+}
@@ -0,0 +1,14 @@
+//! overflow_wrapping
+
+int wrapped_add(int a) {
+    return a + 1;
+}
+
+int wrapped_mul_assign(int a, int b) {
+    a *= b;
+    return a;
+}
+
+int wrapped_neg(int a) {
+    return -a;
+}
@@ -0,0 +1,75 @@
+extern crate libc;
+
+use overflow_report::{
+    rust_checked_arithmetic, rust_checked_increments, rust_checked_long, rust_overflowing_add,
+    rust_overflowing_mul_assign, rust_wrapping_unsigned,
+};
+use self::libc::{c_int, c_long, c_uint};
+use std::panic::{self, UnwindSafe};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn checked_arithmetic(a: c_int, b: c_int) -> c_int;
+    #[no_mangle]
+    fn checked_increments(n: c_int) -> c_int;
+    #[no_mangle]
+    fn wrapping_unsigned(a: c_uint) -> c_uint;
+    #[no_mangle]
+    fn checked_long(a: c_long) -> c_long;
+}
+
+pub fn test_checked_arithmetic() {
+    for &(a, b) in &[(0, 0), (1, 2), (-7, 3), (12345, -678), (100000, 99999)] {
+        unsafe {
+            assert_eq!(rust_checked_arithmetic(a, b), checked_arithmetic(a, b));
+        }
+    }
+}
+
+pub fn test_checked_increments() {
+    unsafe {
+        assert_eq!(rust_checked_increments(10), checked_increments(10));
+        assert_eq!(rust_checked_increments(10), 10);
+    }
+}
+
+pub fn test_unsigned_still_wraps() {
+    unsafe {
+        assert_eq!(rust_wrapping_unsigned(c_uint::max_value()), wrapping_unsigned(c_uint::max_value()));
+        assert_eq!(rust_wrapping_unsigned(0), c_uint::max_value());
+    }
+}
+
+pub fn test_checked_long() {
+    unsafe {
+        assert_eq!(rust_checked_long(-3), checked_long(-3));
+    }
+}
+
+/// The message of the panic that `f` ends in
+fn panic_message<F: FnOnce() -> c_int + UnwindSafe>(f: F) -> String {
+    let payload = panic::catch_unwind(f).expect_err("arithmetic didn't overflow");
+    match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => payload.downcast::<&str>().unwrap().to_string(),
+    }
+}
+
+pub fn test_overflow_panics() {
+    let msg = panic_message(|| unsafe { rust_overflowing_add(c_int::max_value()) });
+    assert!(msg.contains("overflow_report.c:37:"), "{}", msg);
+    assert!(msg.ends_with(": arithmetic overflow"), "{}", msg);
+
+    let msg = panic_message(|| unsafe { rust_overflowing_mul_assign(c_int::max_value(), 2) });
+    assert!(msg.contains("overflow_report.c:41:"), "{}", msg);
+    assert!(msg.ends_with(": arithmetic overflow"), "{}", msg);
+
+    unsafe {
+        assert_eq!(
+            rust_overflowing_add(c_int::max_value() - 1),
+            c_int::max_value()
+        );
+        assert_eq!(rust_overflowing_mul_assign(-3, 7), -21);
+    }
+}
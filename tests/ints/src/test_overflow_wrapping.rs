@@ -0,0 +1,14 @@
+extern crate libc;
+
+use overflow_wrapping::{rust_wrapped_add, rust_wrapped_mul_assign, rust_wrapped_neg};
+use self::libc::c_int;
+
+pub fn test_overflow_wraps() {
+    unsafe {
+        assert_eq!(rust_wrapped_add(c_int::max_value()), c_int::min_value());
+        assert_eq!(rust_wrapped_add(-1), 0);
+        assert_eq!(rust_wrapped_mul_assign(c_int::max_value(), 2), -2);
+        assert_eq!(rust_wrapped_mul_assign(0x4000_0000, 4), 0);
+        assert_eq!(rust_wrapped_neg(c_int::min_value()), c_int::min_value());
+    }
+}
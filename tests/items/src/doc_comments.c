@@ -0,0 +1,38 @@
+#include <stddef.h>
+
+/** A point on the plane */
+struct point {
+    int x; ///< The horizontal coordinate
+    /// The vertical coordinate
+    int y;
+};
+
+/**
+ * @brief Add up the coordinates of points.
+ *
+ * Usage:
+ *
+ *     int sum = coordinate_sum(points, len);
+ *
+ * @param points the points to add up
+ * @param len the number of points
+ * @return the sum of all their coordinates
+ */
+int coordinate_sum(const struct point *points, size_t len) {
+    int sum = 0;
+    for (size_t i = 0; i < len; i++) {
+        sum += points[i].x + points[i].y;
+    }
+    return sum;
+}
+
+/*
+ * Count the points, like so:
+ * ```
+ * point_count(points, 2) == 2
+ * ```
+ */
+size_t point_count(const struct point *points, size_t len) {
+    (void)points;
+    return len;
+}
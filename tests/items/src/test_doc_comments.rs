@@ -0,0 +1,51 @@
+extern crate libc;
+
+use self::libc::size_t;
+
+use doc_comments::{point, rust_coordinate_sum, rust_point_count};
+
+pub fn test_doc_comments() {
+    let points = [point { x: 1, y: 2 }, point { x: 3, y: 4 }];
+    unsafe {
+        assert_eq!(
+            rust_coordinate_sum(points.as_ptr(), points.len() as size_t),
+            10
+        );
+        assert_eq!(rust_point_count(points.as_ptr(), points.len() as size_t), 2);
+    }
+
+    // Doc comments can't be inspected at run time, so we check the source itself
+    let src = include_str!("doc_comments.rs");
+
+    assert!(src.contains("/// A point on the plane\n"));
+    assert!(src.contains("    /// The horizontal coordinate\n    pub x: "));
+    assert!(src.contains("    /// The vertical coordinate\n    pub y: "));
+
+    // Code blocks are marked as text, so that rustdoc doesn't compile them as doctests
+    assert!(src.contains(
+        "/// Add up the coordinates of points.
+///
+/// Usage:
+///
+/// ```text
+///     int sum = coordinate_sum(points, len);
+/// ```
+///
+/// # Arguments
+///
+/// * `points` - the points to add up
+/// * `len` - the number of points
+///
+/// # Returns
+///
+/// the sum of all their coordinates
+"
+    ));
+    assert!(src.contains(
+        "/// Count the points, like so:
+/// ```text
+/// point_count(points, 2) == 2
+/// ```
+"
+    ));
+}
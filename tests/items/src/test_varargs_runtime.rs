@@ -0,0 +1,82 @@
+//! extern_crate_c2rust_va_list
+
+extern crate libc;
+
+use varargs_runtime::{
+    rust_call_count_args, rust_format, rust_format_twice, rust_sum_ints, rust_sum_mixed,
+};
+
+use self::libc::{c_char, c_double, c_int, c_long, size_t};
+use std::ffi::{CStr, CString};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn sum_ints(_: c_int, ...) -> c_int;
+
+    #[no_mangle]
+    fn sum_mixed(_: *const c_char, ...) -> c_double;
+
+    #[no_mangle]
+    fn format(_: *mut c_char, _: size_t, _: *const c_char, ...) -> c_int;
+
+    #[no_mangle]
+    fn format_twice(_: *mut c_char, _: size_t, _: *const c_char, ...) -> c_int;
+
+    #[no_mangle]
+    fn call_count_args() -> c_int;
+}
+
+const BUFFER_SIZE: usize = 64;
+
+pub fn test_sum_ints() {
+    unsafe {
+        assert_eq!(sum_ints(3, 1, 2, 3), rust_sum_ints(3, 1, 2, 3));
+        assert_eq!(rust_sum_ints(0), 0);
+    }
+}
+
+pub fn test_sum_mixed() {
+    let types = CString::new("ildp").unwrap();
+    let mut value: c_int = 5;
+    unsafe {
+        let expected = sum_mixed(types.as_ptr(), 1, 2 as c_long, 3.5, &mut value as *mut c_int);
+        let actual = rust_sum_mixed(types.as_ptr(), 1, 2 as c_long, 3.5, &mut value as *mut c_int);
+        assert_eq!(expected, actual);
+        assert_eq!(actual, 11.5);
+    }
+}
+
+// `va_list`s are passed on through several functions, ending in `vsnprintf`
+pub fn test_format() {
+    let fmt = CString::new("%d, %.1f, %s").unwrap();
+    let s = CString::new("test").unwrap();
+    let mut buffer = [0 as c_char; BUFFER_SIZE];
+    let mut rust_buffer = [0 as c_char; BUFFER_SIZE];
+    unsafe {
+        format(buffer.as_mut_ptr(), BUFFER_SIZE, fmt.as_ptr(), 10, 1.5, s.as_ptr());
+        rust_format(rust_buffer.as_mut_ptr(), BUFFER_SIZE, fmt.as_ptr(), 10, 1.5, s.as_ptr());
+        assert_eq!(CStr::from_ptr(buffer.as_ptr()), CStr::from_ptr(rust_buffer.as_ptr()));
+    }
+}
+
+pub fn test_format_twice() {
+    let fmt = CString::new("%d;%s;").unwrap();
+    let s = CString::new("test").unwrap();
+    let mut buffer = [0 as c_char; BUFFER_SIZE];
+    let mut rust_buffer = [0 as c_char; BUFFER_SIZE];
+    unsafe {
+        let len = format_twice(buffer.as_mut_ptr(), BUFFER_SIZE, fmt.as_ptr(), 7, s.as_ptr());
+        let rust_len =
+            rust_format_twice(rust_buffer.as_mut_ptr(), BUFFER_SIZE, fmt.as_ptr(), 7, s.as_ptr());
+        assert_eq!(len, rust_len);
+        assert_eq!(CStr::from_ptr(rust_buffer.as_ptr()).to_str().unwrap(), "7;test;7;test;");
+    }
+}
+
+pub fn test_static_variadic() {
+    unsafe {
+        assert_eq!(call_count_args(), rust_call_count_args());
+        assert_eq!(rust_call_count_args(), 3);
+    }
+}
@@ -0,0 +1,95 @@
+//! va_list_runtime
+
+#include <stdarg.h>
+#include <stdio.h>
+
+int sum_ints(int count, ...) {
+  va_list ap;
+  int total = 0;
+
+  va_start(ap, count);
+  for (int i = 0; i < count; i++)
+    total += va_arg(ap, int);
+  va_end(ap);
+
+  return total;
+}
+
+// Read arguments of different types, as described by `types`
+double sum_mixed(const char *types, ...) {
+  va_list ap;
+  double total = 0;
+
+  va_start(ap, types);
+  for (; *types; types++) {
+    switch (*types) {
+    case 'i':
+      total += va_arg(ap, int);
+      break;
+    case 'l':
+      total += va_arg(ap, long);
+      break;
+    case 'd':
+      total += va_arg(ap, double);
+      break;
+    case 'p':
+      total += *va_arg(ap, int *);
+      break;
+    }
+  }
+  va_end(ap);
+
+  return total;
+}
+
+// A va_list passed through several frames before it is consumed
+static int format_inner(char *buf, size_t len, const char *fmt, va_list ap) {
+  return vsnprintf(buf, len, fmt, ap);
+}
+
+int format_va(char *buf, size_t len, const char *fmt, va_list ap) {
+  return format_inner(buf, len, fmt, ap);
+}
+
+int format(char *buf, size_t len, const char *fmt, ...) {
+  va_list ap;
+  int ret;
+
+  va_start(ap, fmt);
+  ret = format_va(buf, len, fmt, ap);
+  va_end(ap);
+
+  return ret;
+}
+
+// Format the same arguments twice
+int format_twice(char *buf, size_t len, const char *fmt, ...) {
+  va_list ap, aq;
+  int n;
+
+  va_start(ap, fmt);
+  va_copy(aq, ap);
+  n = vsnprintf(buf, len, fmt, ap);
+  n += vsnprintf(buf + n, len - n, fmt, aq);
+  va_end(aq);
+  va_end(ap);
+
+  return n;
+}
+
+// Variadic functions with internal linkage are called through a trampoline too
+static int count_args(int sentinel, ...) {
+  va_list ap;
+  int count = 0;
+
+  va_start(ap, sentinel);
+  while (va_arg(ap, int) != sentinel)
+    count++;
+  va_end(ap);
+
+  return count;
+}
+
+int call_count_args(void) {
+  return count_args(-1, 1, 2, 3, -1);
+}
@@ -0,0 +1,41 @@
+//! translate_fn_macros
+
+#define SQUARE(x) ((x) * (x))
+#define MAX(a, b) ((a) > (b) ? (a) : (b))
+#define VALUE(p) ((p)->value)
+#define ID(x) x
+
+struct fn_macros_s {
+    int value;
+};
+
+static int counter = 0;
+
+static int bump(void) {
+    return ++counter;
+}
+
+int fn_macros(void) {
+    int a = 3, b = 4;
+    long c = 5;
+    struct fn_macros_s s = { 1 };
+    struct fn_macros_s *p = &s;
+
+    // Every expansion has the same types, so this becomes a function
+    int total = SQUARE(a) + SQUARE(b);
+
+    // Expanded with both `int` and `long` arguments, so this becomes a macro
+    total += MAX(a, b);
+    total += (int)MAX(c, 2L);
+
+    // Expands to an lvalue, so this becomes a macro
+    VALUE(p) = 10;
+    total += VALUE(p);
+
+    // The argument has side effects and is evaluated twice, so this is
+    // translated inline
+    total += SQUARE(bump());
+
+    total += ID(a);
+    return total;
+}
@@ -0,0 +1,21 @@
+extern crate libc;
+
+use fn_macros::{rust_fn_macros, SQUARE};
+use self::libc::c_int;
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn fn_macros() -> c_int;
+}
+
+pub fn test_fn_macros() {
+    let x = unsafe { fn_macros() };
+    let rust_x = unsafe { rust_fn_macros() };
+
+    assert_eq!(x, rust_x);
+}
+
+pub fn test_fn_macro_function() {
+    assert_eq!(unsafe { SQUARE(7) }, 49);
+}
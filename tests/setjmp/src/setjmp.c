@@ -0,0 +1,59 @@
+//! translate_setjmp
+
+#include <setjmp.h>
+
+static jmp_buf env;
+
+static int checked_div(int a, int b) {
+    if (b == 0) {
+        longjmp(env, 2);
+    }
+    return a / b;
+}
+
+static int try_div(int a, int b) {
+    if (setjmp(env)) {
+        return -1;
+    }
+    return checked_div(a, b);
+}
+
+static int retry(void) {
+    jmp_buf retry_env;
+    volatile int attempts = 0;
+    int ret;
+
+    if ((ret = setjmp(retry_env)) != 0) {
+        attempts += ret;
+    }
+
+    if (attempts < 5) {
+        longjmp(retry_env, 1);
+    }
+    return attempts;
+}
+
+static int guarded(int x) {
+    int result = 0;
+
+    if (!setjmp(env)) {
+        result = checked_div(100, x);
+    } else {
+        result = -2;
+    }
+
+    return result;
+}
+
+void setjmp_test(unsigned buffer_size, int buffer[]) {
+    if (buffer_size < 6) {
+        return;
+    }
+
+    buffer[0] = try_div(10, 2);
+    buffer[1] = try_div(10, 0);
+    buffer[2] = retry();
+    buffer[3] = guarded(5);
+    buffer[4] = guarded(0);
+    buffer[5] = try_div(7, 7);
+}
@@ -0,0 +1,28 @@
+//! extern_crate_c2rust_setjmp, feature_unwind_attributes
+
+extern crate libc;
+
+use self::libc::{c_int, c_uint};
+use setjmp::rust_setjmp_test;
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn setjmp_test(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 6;
+
+pub fn test_setjmp() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [5, -1, 5, 20, -2, 1];
+
+    unsafe {
+        setjmp_test(BUFFER_SIZE as u32, buffer.as_mut_ptr());
+        rust_setjmp_test(BUFFER_SIZE as u32, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
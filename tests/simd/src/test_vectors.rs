@@ -0,0 +1,58 @@
+extern crate libc;
+
+use vectors::{rust_vector_arith, rust_vector_compare, rust_vector_misc};
+use self::libc::c_int;
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn vector_arith(a: *const c_int, b: *const c_int, out: *mut c_int);
+
+    #[no_mangle]
+    fn vector_compare(a: *const c_int, b: *const c_int, out: *mut c_int);
+
+    #[no_mangle]
+    fn vector_misc(out: *mut c_int);
+}
+
+const A: [c_int; 4] = [1, -2, 3, 100];
+const B: [c_int; 4] = [4, -2, -6, 7];
+
+pub fn test_vector_arith() {
+    let mut out = [0; 4];
+    let mut rust_out = [0; 4];
+
+    unsafe {
+        vector_arith(A.as_ptr(), B.as_ptr(), out.as_mut_ptr());
+        rust_vector_arith(A.as_ptr(), B.as_ptr(), rust_out.as_mut_ptr());
+    }
+
+    assert_eq!(out, [26, 6, 30, 20]);
+    assert_eq!(out, rust_out);
+}
+
+pub fn test_vector_compare() {
+    let mut out = [0; 4];
+    let mut rust_out = [0; 4];
+
+    unsafe {
+        vector_compare(A.as_ptr(), B.as_ptr(), out.as_mut_ptr());
+        rust_vector_compare(A.as_ptr(), B.as_ptr(), rust_out.as_mut_ptr());
+    }
+
+    assert_eq!(out, [-1, -2, 0, 0]);
+    assert_eq!(out, rust_out);
+}
+
+pub fn test_vector_misc() {
+    let mut out = [0; 8];
+    let mut rust_out = [0; 8];
+
+    unsafe {
+        vector_misc(out.as_mut_ptr());
+        rust_vector_misc(rust_out.as_mut_ptr());
+    }
+
+    assert_eq!(out, [7, 0, 2, 3, 14, 0, 4, 6]);
+    assert_eq!(out, rust_out);
+}
@@ -0,0 +1,52 @@
+// Generic vectors, which are translated to arrays
+
+#define N 4
+
+typedef int v4si __attribute__((vector_size(16)));
+typedef unsigned int v4su __attribute__((vector_size(16)));
+typedef float v4sf __attribute__((vector_size(16)));
+
+void vector_arith(const int *a, const int *b, int *out) {
+    v4si x = {a[0], a[1], a[2], a[3]};
+    v4si y = {b[0], b[1], b[2], b[3]};
+
+    v4si r = x + y * 2;
+    r -= x;
+    r = r ^ ~y;
+    r = -r;
+    r = r << 1;
+
+    for (int i = 0; i < N; i++) {
+        out[i] = r[i];
+    }
+}
+
+void vector_compare(const int *a, const int *b, int *out) {
+    v4si x = {a[0], a[1], a[2], a[3]};
+    v4si y = {b[0], b[1], b[2], b[3]};
+
+    v4si lt = x < y;
+    v4si eq = x == y;
+
+    for (int i = 0; i < N; i++) {
+        out[i] = lt[i] + eq[i] * 2;
+    }
+}
+
+void vector_misc(int *out) {
+    // Missing elements are zero
+    v4si partial = {1, 2};
+    v4su wrapping = {0xffffffff, 1, 2, 3};
+    v4si shuffled;
+    v4sf floats;
+
+    wrapping += 1;
+    partial[3] = 7;
+    shuffled = __builtin_shufflevector(partial, (v4si)wrapping, 3, 4, 1, 6);
+    floats = __builtin_convertvector(shuffled, v4sf) * 0.5f;
+
+    for (int i = 0; i < N; i++) {
+        out[i] = shuffled[i];
+        out[N + i] = (int)(floats[i] * 4);
+    }
+}
@@ -0,0 +1,49 @@
+//! static_init_lazy
+
+#include <stddef.h>
+
+typedef struct {
+    int a;
+    int b[4];
+} Inner;
+
+typedef struct {
+    Inner inner;
+    int *p;
+} Outer;
+
+Outer outer = { { 1, { 2, 3, 4, 5 } }, NULL };
+int table[3][2] = { { 1, 2 }, { 3, 4 }, { 5, 6 } };
+
+// These addresses are computed at compile time
+int *field_addr = &outer.inner.a;
+int *element_addr = &outer.inner.b[2];
+int *array_plus = outer.inner.b + 3;
+int *nested_element = &table[2][1];
+int *row_plus = table[1] + 1;
+
+// These are initialized on first use
+unsigned lazy_unsigned = 1U + 2U;
+static unsigned lazy_private = -1U;
+const size_t lazy_cond = sizeof(size_t) == 4 ? 30 : 31;
+
+// Reads through the address of this one wouldn't initialize it, so it is initialized before main
+unsigned lazy_pointee = 40U + 2U;
+unsigned *lazy_pointee_addr = &lazy_pointee;
+
+unsigned read_lazy_pointee_addr(void) {
+    return *lazy_pointee_addr;
+}
+
+unsigned read_lazy_private(void) {
+    return lazy_private;
+}
+
+unsigned bump_fn_scoped(void) {
+    static unsigned count = 10U + 1U;
+    return count++;
+}
+
+int read_field_addr(void) {
+    return *field_addr + *element_addr + *array_plus + *nested_element + *row_plus;
+}
@@ -0,0 +1,34 @@
+extern crate libc;
+
+use lazy_init::{
+    init_rust_lazy_cond, init_rust_lazy_unsigned, rust_array_plus, rust_bump_fn_scoped,
+    rust_element_addr, rust_field_addr, rust_lazy_pointee, rust_nested_element, rust_outer,
+    rust_read_field_addr, rust_read_lazy_pointee_addr, rust_read_lazy_private, rust_row_plus,
+    rust_table,
+};
+
+pub fn test_compile_time_addresses() {
+    unsafe {
+        assert_eq!(rust_field_addr, &mut rust_outer.inner.a as *mut _);
+        assert_eq!(rust_element_addr, &mut rust_outer.inner.b[2] as *mut _);
+        assert_eq!(rust_array_plus, &mut rust_outer.inner.b[3] as *mut _);
+        assert_eq!(rust_nested_element, &mut rust_table[2][1] as *mut _);
+        assert_eq!(rust_row_plus, &mut rust_table[1][1] as *mut _);
+        assert_eq!(rust_read_field_addr(), 1 + 4 + 5 + 6 + 4);
+    }
+}
+
+pub fn test_lazy_statics() {
+    unsafe {
+        // Read through the address before anything else refers to the static
+        assert_eq!(rust_read_lazy_pointee_addr(), 42);
+        assert_eq!(rust_lazy_pointee, 42);
+
+        assert_eq!(*init_rust_lazy_unsigned(), 3);
+        assert!(*init_rust_lazy_cond() == 30 || *init_rust_lazy_cond() == 31);
+        assert_eq!(rust_read_lazy_private(), libc::c_uint::max_value());
+
+        assert_eq!(rust_bump_fn_scoped(), 11);
+        assert_eq!(rust_bump_fn_scoped(), 12);
+    }
+}
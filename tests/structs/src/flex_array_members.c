@@ -47,3 +47,24 @@ void exercise_flex_arrays(const unsigned sz, int buf[const]) {
   u->flex[5] = 15;
   buf[i++] = u->flex[5];
 }
+
+struct msg {
+  unsigned len;
+  char data[];
+};
+
+struct msg *new_msg(unsigned len) {
+  struct msg *m = malloc(sizeof(struct msg) + len * sizeof(char));
+  m->len = len;
+  for (unsigned i = 0; i < len; i++)
+    m->data[i] = 'a' + i;
+  return m;
+}
+
+struct c89_flex *new_c89_flex(unsigned len) {
+  struct c89_flex *f = malloc(sizeof(struct c89_flex) + (len - 1) * sizeof(int));
+  f->x = len;
+  for (unsigned i = 0; i < len; i++)
+    f->flex[i] = i * i;
+  return f;
+}
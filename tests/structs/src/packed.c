@@ -0,0 +1,88 @@
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+struct __attribute__((packed)) eth_hdr {
+    uint8_t dst[6];
+    uint8_t src[6];
+    uint16_t type;
+};
+
+struct __attribute__((packed)) ip_opt {
+    uint8_t kind;
+    uint32_t value;
+    uint16_t len;
+};
+
+#pragma pack(push, 2)
+struct pack2 {
+    char c;
+    int i;
+    short s;
+};
+#pragma pack(pop)
+
+struct __attribute__((packed, aligned(4))) packed_aligned {
+    char c;
+    int i;
+};
+
+union __attribute__((packed)) packed_union {
+    char c;
+    int i;
+};
+
+void packed_layout(unsigned buffer[]) {
+    int i = 0;
+
+    buffer[i++] = sizeof(struct eth_hdr);
+    buffer[i++] = sizeof(struct ip_opt);
+    buffer[i++] = _Alignof(struct ip_opt);
+    buffer[i++] = sizeof(struct pack2);
+    buffer[i++] = _Alignof(struct pack2);
+    buffer[i++] = sizeof(struct packed_aligned);
+    buffer[i++] = _Alignof(struct packed_aligned);
+    buffer[i++] = sizeof(union packed_union);
+    buffer[i++] = _Alignof(union packed_union);
+}
+
+static void bump(uint32_t *p) {
+    uint32_t v;
+    memcpy(&v, p, sizeof(v));
+    v++;
+    memcpy(p, &v, sizeof(v));
+}
+
+static struct ip_opt *id(struct ip_opt *p) {
+    return p;
+}
+
+void packed_fields(unsigned buffer[]) {
+    const uint8_t mac[6] = { 1, 2, 3, 4, 5, 6 };
+    struct eth_hdr hdr = { { 0 }, { 0 }, 0x0800 };
+    struct ip_opt opt = { 1, 0x01020304, 5 };
+    struct ip_opt *p = &opt;
+    struct pack2 p2 = { 'x', 40, 2 };
+    struct packed_aligned pa = { 'a', 7 };
+    union packed_union pu = { 0 };
+    int i = 0;
+
+    memcpy(hdr.src, mac, sizeof(mac));
+    p->value += 1;
+    opt.len++;
+    id(&opt)->len += 2;
+    bump(&opt.value);
+    p2.i += p2.s;
+    pa.i *= 3;
+    pu.i = 0x10203;
+
+    buffer[i++] = hdr.src[5];
+    buffer[i++] = hdr.type;
+    buffer[i++] = opt.kind;
+    buffer[i++] = opt.value;
+    buffer[i++] = opt.len;
+    buffer[i++] = p2.i;
+    buffer[i++] = pa.c;
+    buffer[i++] = pa.i;
+    buffer[i++] = pu.i;
+}
@@ -1,7 +1,10 @@
 extern crate libc;
 
-use flex_array_members::{rust_exercise_flex_arrays};
+use flex_array_members::{
+    c89_flex, msg, rust_exercise_flex_arrays, rust_new_c89_flex, rust_new_msg,
+};
 use self::libc::{c_int, c_uint, size_t};
+use std::mem::size_of;
 
 #[link(name = "test")]
 extern "C" {
@@ -24,3 +27,22 @@ pub fn test_flex_array_members() {
     assert_eq!(buffer, rust_buffer);
     assert_eq!(buffer, expected_buffer);
 }
+
+pub fn test_flex_array_accessors() {
+    unsafe {
+        let m = rust_new_msg(3);
+        assert_eq!((*m).data(3), &[b'a' as libc::c_char, b'b' as _, b'c' as _]);
+        (*m).data_mut(3)[1] = b'z' as _;
+        assert_eq!(*(*m).data_ptr().offset(1), b'z' as libc::c_char);
+        libc::free(m as *mut libc::c_void);
+
+        let f = rust_new_c89_flex(4);
+        assert_eq!((*f).flex(4), &[0, 1, 4, 9]);
+        *(*f).flex_mut_ptr().offset(3) = 16;
+        assert_eq!((*f).flex(4)[3], 16);
+        libc::free(f as *mut libc::c_void);
+    }
+
+    assert_eq!(msg::alloc_size(5), size_of::<msg>() + 5);
+    assert_eq!(c89_flex::alloc_size(3), size_of::<c89_flex>() + 3 * size_of::<c_int>());
+}
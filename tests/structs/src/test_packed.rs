@@ -0,0 +1,43 @@
+extern crate libc;
+
+use packed::{rust_packed_fields, rust_packed_layout};
+use self::libc::c_uint;
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn packed_layout(_: *mut c_uint);
+    #[no_mangle]
+    fn packed_fields(_: *mut c_uint);
+}
+
+const LAYOUT_BUFFER_SIZE: usize = 9;
+const FIELDS_BUFFER_SIZE: usize = 9;
+
+pub fn test_packed_layout() {
+    let mut buffer = [0; LAYOUT_BUFFER_SIZE];
+    let mut rust_buffer = [0; LAYOUT_BUFFER_SIZE];
+    let expected_buffer = [14, 7, 1, 8, 2, 8, 4, 4, 1];
+
+    unsafe {
+        packed_layout(buffer.as_mut_ptr());
+        rust_packed_layout(rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
+
+pub fn test_packed_fields() {
+    let mut buffer = [0; FIELDS_BUFFER_SIZE];
+    let mut rust_buffer = [0; FIELDS_BUFFER_SIZE];
+    let expected_buffer = [6, 0x0800, 1, 0x01020306, 8, 42, 97, 21, 0x10203];
+
+    unsafe {
+        packed_fields(buffer.as_mut_ptr());
+        rust_packed_fields(rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}